@@ -0,0 +1,106 @@
+//! Scan latency, against recorded window lists and the live one.
+//!
+//! The `fixture/` benchmarks replay the window lists in `benches/fixtures`
+//! (recorded with `cargo run --example record_fixture`), so they measure
+//! detection logic alone and give the same numbers on any machine. The
+//! `live/` benchmarks need a Mac with a GUI session and include the time the
+//! window server takes to produce the list.
+//!
+//! Compare against an earlier build with `cargo bench -- --save-baseline
+//! before` there and `cargo bench -- --baseline before` here. Add
+//! `--features parallel` to compare sequential and parallel window parsing.
+
+use std::fs;
+use std::path::Path;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use no_cluely_driver::{
+    detect_cluely_in_rust, detect_cluely_rust, scan_all_rust, scan_windows_rust,
+    IncrementalDetector, ScannedWindow, WindowBounds,
+};
+
+/// Parse a fixture written by the `record_fixture` example
+fn load_fixture(name: &str) -> Vec<ScannedWindow> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("benches/fixtures")
+        .join(name);
+    let contents = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", path.display(), e));
+
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            assert_eq!(fields.len(), 14, "malformed fixture line: {line}");
+            let int = |i: usize| fields[i].parse::<i32>().unwrap();
+            let float = |i: usize| fields[i].parse::<f64>().ok();
+
+            let layer = int(2);
+            let is_onscreen = fields[6] == "1";
+            let alpha = float(7).unwrap();
+            let bounds = float(8).map(|x| WindowBounds {
+                x,
+                y: float(9).unwrap(),
+                width: float(10).unwrap(),
+                height: float(11).unwrap(),
+            });
+            ScannedWindow {
+                name: if fields[13].is_empty() {
+                    "<No Title>".to_string()
+                } else {
+                    fields[13].to_string()
+                },
+                owner: fields[12].to_string(),
+                owner_pid: int(1),
+                window_id: int(0),
+                layer,
+                is_onscreen,
+                alpha,
+                is_hidden: !is_onscreen || alpha < 0.1 || layer < 0,
+                sharing_state: int(3),
+                store_type: int(4),
+                backing_type: int(5),
+                bounds,
+                target: None,
+            }
+        })
+        .collect()
+}
+
+fn fixture_scans(c: &mut Criterion) {
+    let windows = load_fixture("busy-desktop.tsv");
+    let mut group = c.benchmark_group("fixture");
+
+    group.bench_function("cluely_only", |b| {
+        b.iter(|| detect_cluely_in_rust(&windows))
+    });
+    group.bench_function("full_scan", |b| {
+        b.iter_batched(
+            || windows.clone(),
+            |windows| scan_windows_rust(windows, false),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn live_scans(c: &mut Criterion) {
+    let mut group = c.benchmark_group("live");
+
+    group.bench_function("cluely_only", |b| b.iter(detect_cluely_rust));
+    group.bench_function("full_scan", |b| b.iter(|| scan_all_rust(false)));
+    #[cfg(feature = "parallel")]
+    group.bench_function("full_scan_parallel", |b| {
+        b.iter(|| no_cluely_driver::scan_all_parallel_rust(false))
+    });
+
+    let mut detector = IncrementalDetector::new();
+    detector.detect();
+    group.bench_function("incremental", |b| b.iter(|| detector.detect()));
+    group.finish();
+}
+
+criterion_group!(benches, fixture_scans, live_scans);
+criterion_main!(benches);
@@ -0,0 +1,233 @@
+//! Agent mode (`--agent`) and fleet summaries (`aggregate`).
+//!
+//! Run on every Mac by MDM or a launch daemon, `--agent` prints one JSON
+//! record per line (NDJSON) and nothing else, each tagged with the host's
+//! name, serial number, console user and this tool's version, so a log
+//! shipper can collect them centrally. `aggregate` reads such files back and
+//! summarizes the fleet from each host's latest record.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::output::{DetectionPayload, SCHEMA_VERSION};
+
+static AGENT: AtomicBool = AtomicBool::new(false);
+
+pub fn enable() {
+    AGENT.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    AGENT.load(Ordering::Relaxed)
+}
+
+/// Which machine and user a record comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    /// Hardware serial number; absent if it could not be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    /// User logged in at the console, or running the agent if nobody is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub agent_version: String,
+}
+
+impl HostInfo {
+    /// This machine, read once per run
+    pub fn current() -> &'static HostInfo {
+        static HOST: OnceLock<HostInfo> = OnceLock::new();
+        HOST.get_or_init(|| HostInfo {
+            hostname: hostname::get()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            serial_number: serial_number(),
+            username: console_user().or_else(|| std::env::var("USER").ok()),
+            agent_version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    }
+
+    /// What identifies the machine across records: its serial number, or
+    /// its name if the serial could not be read
+    fn key(&self) -> &str {
+        self.serial_number.as_deref().unwrap_or(&self.hostname)
+    }
+}
+
+/// The hardware serial number, from the I/O Registry
+fn serial_number() -> Option<String> {
+    let output = Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .map_err(|error| tracing::warn!(%error, "ioreg could not be run"))
+        .ok()?;
+    // "IOPlatformSerialNumber" = "C02XXXXXXXXX"
+    String::from_utf8_lossy(&output.stdout).lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "\"IOPlatformSerialNumber\"")
+            .then(|| value.trim().trim_matches('"').to_string())
+            .filter(|serial| !serial.is_empty())
+    })
+}
+
+/// The user who owns the console, i.e. is logged in at the screen
+fn console_user() -> Option<String> {
+    let output = Command::new("stat").args(["-f", "%Su", "/dev/console"]).output().ok()?;
+    let user = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    // The login window owns the console while nobody is logged in
+    (!user.is_empty() && user != "root").then_some(user)
+}
+
+/// One line of agent output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRecord {
+    pub schema_version: u32,
+    /// `scan` from `check`; `detected`, `cleared`, `status`, `scan-failed`
+    /// or `tampered` from `monitor`
+    pub record: String,
+    #[serde(flatten)]
+    pub host: HostInfo,
+    pub detection: DetectionPayload,
+    /// What happened, for `tampered` records
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Print a record for `detection` as one line
+pub fn emit(record: &str, detection: DetectionPayload) {
+    emit_detail(record, detection, None);
+}
+
+/// Print a record for `detection` with a description of what happened
+pub fn emit_detail(record: &str, detection: DetectionPayload, detail: Option<String>) {
+    let record = AgentRecord {
+        schema_version: SCHEMA_VERSION,
+        record: record.to_string(),
+        host: HostInfo::current().clone(),
+        detection,
+        detail,
+    };
+    std::println!("{}", serde_json::to_string(&record).unwrap());
+    // Shippers tail stdout; don't leave a record sitting in the buffer
+    let _ = std::io::stdout().flush();
+}
+
+/// A host's latest record, as listed by `aggregate`
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetHost {
+    pub hostname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub agent_version: String,
+    pub detected: bool,
+    pub severity: String,
+    pub window_count: u32,
+    /// When the latest record was taken (RFC 3339)
+    pub last_seen: String,
+    /// Records from this host across every file
+    pub records: usize,
+}
+
+/// Summary of every host seen in agent output
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetSummary {
+    pub hosts: usize,
+    pub detected_hosts: usize,
+    /// Hosts by the severity of their latest record
+    pub by_severity: BTreeMap<String, usize>,
+    /// Hosts by agent version, to spot machines left on an old release
+    pub by_agent_version: BTreeMap<String, usize>,
+    /// Detected hosts first, then by host name
+    pub host_list: Vec<FleetHost>,
+    /// Lines that were not agent records
+    pub skipped_lines: usize,
+}
+
+/// Merge agent output from `files` ("-" reads standard input) into a
+/// fleet summary, using each host's latest record
+pub fn aggregate(files: &[impl AsRef<Path>]) -> Result<FleetSummary, String> {
+    let mut latest: HashMap<String, (DateTime<Utc>, AgentRecord, usize)> = HashMap::new();
+    let mut skipped_lines = 0;
+
+    for file in files {
+        let file = file.as_ref();
+        let contents = if file == Path::new("-") {
+            std::io::read_to_string(std::io::stdin())
+                .map_err(|e| format!("Failed to read standard input: {}", e))?
+        } else {
+            fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?
+        };
+
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((taken, record)) = serde_json::from_str::<AgentRecord>(line)
+                .ok()
+                .and_then(|record| {
+                    let taken = DateTime::parse_from_rfc3339(&record.detection.timestamp).ok()?;
+                    Some((taken.with_timezone(&Utc), record))
+                })
+            else {
+                tracing::warn!(file = %file.display(), line = number + 1, "not an agent record; skipped");
+                skipped_lines += 1;
+                continue;
+            };
+            let entry = latest
+                .entry(record.host.key().to_string())
+                .or_insert_with(|| (taken, record.clone(), 0));
+            entry.2 += 1;
+            if taken >= entry.0 {
+                entry.0 = taken;
+                entry.1 = record;
+            }
+        }
+    }
+
+    let mut host_list: Vec<FleetHost> = latest
+        .into_values()
+        .map(|(_, record, records)| FleetHost {
+            hostname: record.host.hostname,
+            serial_number: record.host.serial_number,
+            username: record.host.username,
+            agent_version: record.host.agent_version,
+            detected: record.detection.detected,
+            severity: record.detection.severity,
+            window_count: record.detection.window_count,
+            last_seen: record.detection.timestamp,
+            records,
+        })
+        .collect();
+    host_list.sort_by(|a, b| {
+        b.detected
+            .cmp(&a.detected)
+            .then_with(|| a.hostname.to_lowercase().cmp(&b.hostname.to_lowercase()))
+    });
+
+    let mut by_severity = BTreeMap::new();
+    let mut by_agent_version = BTreeMap::new();
+    for host in &host_list {
+        *by_severity.entry(host.severity.clone()).or_default() += 1;
+        *by_agent_version.entry(host.agent_version.clone()).or_default() += 1;
+    }
+    Ok(FleetSummary {
+        hosts: host_list.len(),
+        detected_hosts: host_list.iter().filter(|host| host.detected).count(),
+        by_severity,
+        by_agent_version,
+        host_list,
+        skipped_lines,
+    })
+}
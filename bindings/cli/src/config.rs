@@ -0,0 +1,474 @@
+//! Configuration file support.
+//!
+//! Settings are read from `~/.config/cluely-detector/config.toml` unless a
+//! different file is passed with `--config`. A missing default file is not an
+//! error; every setting has a built-in default. Apps whose findings are
+//! suppressed are listed separately, in `ignore.toml` in the same directory.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use no_cluely_driver::{AllowList, DetectorConfig, Signature, SignatureError, TargetSpec, TransparencyRule};
+use serde::{Deserialize, Serialize};
+
+use crate::exit_code::{ExitCodes, FailOn};
+use crate::signatures;
+
+/// Top-level configuration file contents
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Notification backends used by `monitor` (`[[notifier]]` tables)
+    #[serde(rename = "notifier")]
+    pub notifiers: Vec<NotifierEntry>,
+    /// Default for `monitor --schedule`; a cron expression
+    pub schedule: Option<String>,
+    /// Default for `monitor --coalesce`, in seconds
+    pub coalesce: Option<u64>,
+    /// Default for `monitor --confirm-after`, in scans
+    pub confirm_after: Option<u32>,
+    /// Default for `monitor --clear-after`, in scans
+    pub clear_after: Option<u32>,
+    /// Turns on `monitor --watch-permissions`
+    pub watch_permissions: bool,
+    /// Default for `--fail-on`
+    pub fail_on: Option<FailOn>,
+    /// Exit codes of the detection commands (`[exit_codes]` table)
+    pub exit_codes: ExitCodes,
+    /// Cluely signatures added to the built-in ones (`[[signature]]` tables)
+    #[serde(rename = "signature")]
+    pub signatures: Vec<SignatureConfig>,
+    /// Other apps watched for like Cluely (`[[target]]` tables)
+    #[serde(rename = "target")]
+    pub targets: Vec<TargetConfig>,
+    /// When `scan-all` flags nearly invisible windows (`[transparency]` table)
+    pub transparency: TransparencyConfig,
+    /// Where `signatures update` gets signature bundles (`[signature_updates]` table)
+    pub signature_updates: SignatureUpdatesConfig,
+}
+
+impl Config {
+    /// Detector settings with the installed signature bundle and the
+    /// configured signatures compiled
+    pub fn detector(&self) -> Result<DetectorConfig, String> {
+        let mut detector = DetectorConfig::default();
+        if let Some(bundle) = signatures::installed()? {
+            for signature in bundle.compile()? {
+                detector.signatures.push(signature);
+            }
+            detector.signatures.set_version(bundle.version);
+        }
+        for signature in self.compile_signatures()? {
+            detector.signatures.push(signature);
+        }
+        for target in &self.targets {
+            target.add_to(&mut detector)?;
+        }
+        detector.transparency = self.transparency.rule()?;
+        detector.allow = load_ignore()?;
+        Ok(detector)
+    }
+
+    /// The `[[signature]]` entries, compiled
+    pub fn compile_signatures(&self) -> Result<Vec<Signature>, String> {
+        compile_signatures(&self.signatures)
+    }
+}
+
+/// Compile `[[signature]]` entries, naming the first one that fails
+pub fn compile_signatures(signatures: &[SignatureConfig]) -> Result<Vec<Signature>, String> {
+    signatures
+        .iter()
+        .map(|signature| {
+            signature
+                .compile()
+                .map_err(|e| format!("Signature \"{}\": {}", signature.name, e))
+        })
+        .collect()
+}
+
+/// Extra patterns identifying Cluely. A pattern starting with `^` is an
+/// anchored regex; any other pattern matches as a substring. Every pattern
+/// given must match.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureConfig {
+    pub name: String,
+    /// Name of the process that owns the window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Window title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Bundle ID of the owning app
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+}
+
+impl SignatureConfig {
+    fn compile(&self) -> Result<Signature, String> {
+        if self.owner.is_none() && self.title.is_none() && self.bundle_id.is_none() {
+            return Err("needs an owner, title or bundle_id pattern".to_string());
+        }
+
+        let mut signature = Signature::new(&self.name);
+        let compile = |e: SignatureError| e.to_string();
+        if let Some(pattern) = &self.owner {
+            signature = signature.owner(pattern).map_err(compile)?;
+        }
+        if let Some(pattern) = &self.title {
+            signature = signature.title(pattern).map_err(compile)?;
+        }
+        if let Some(pattern) = &self.bundle_id {
+            signature = signature.bundle_id(pattern).map_err(compile)?;
+        }
+        Ok(signature)
+    }
+}
+
+/// Another app to watch for. Windows of an app matching any name pattern,
+/// bundle ID or Team ID are analyzed and counted like Cluely's.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    pub name: String,
+    /// Owner name patterns, like a signature's `owner`
+    #[serde(default)]
+    pub name_patterns: Vec<String>,
+    #[serde(default)]
+    pub bundle_ids: Vec<String>,
+    #[serde(default)]
+    pub team_ids: Vec<String>,
+}
+
+impl TargetConfig {
+    pub fn add_to(&self, detector: &mut DetectorConfig) -> Result<(), String> {
+        if self.name_patterns.is_empty() && self.bundle_ids.is_empty() && self.team_ids.is_empty() {
+            return Err(format!(
+                "Target \"{}\" needs a name pattern, bundle ID or Team ID",
+                self.name
+            ));
+        }
+        detector
+            .watch_target(TargetSpec {
+                name: self.name.clone(),
+                name_patterns: self.name_patterns.clone(),
+                bundle_ids: self.bundle_ids.clone(),
+                team_ids: self.team_ids.clone(),
+            })
+            .map_err(|e| format!("Target \"{}\": {}", self.name, e))
+    }
+}
+
+/// Thresholds and allow-list of the transparency rule; anything left out
+/// keeps the library default
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransparencyConfig {
+    /// Windows at or below this opacity (0.0 to 1.0) count as invisible
+    pub max_alpha: Option<f64>,
+    pub min_width: Option<f64>,
+    pub min_height: Option<f64>,
+    /// Also flag windows without a title
+    pub untitled: bool,
+    /// Owner names never flagged
+    pub allowed_owners: Vec<String>,
+}
+
+impl TransparencyConfig {
+    pub fn rule(&self) -> Result<TransparencyRule, String> {
+        let mut rule = TransparencyRule::default();
+        if let Some(max_alpha) = self.max_alpha {
+            if !(0.0..=1.0).contains(&max_alpha) {
+                return Err(format!(
+                    "Transparency max_alpha {} is not between 0 and 1",
+                    max_alpha
+                ));
+            }
+            rule.max_alpha = max_alpha;
+        }
+        rule.min_width = self.min_width.unwrap_or(rule.min_width);
+        rule.min_height = self.min_height.unwrap_or(rule.min_height);
+        rule.require_title = !self.untitled;
+        rule.allowed_owners = self.allowed_owners.clone();
+        Ok(rule)
+    }
+}
+
+/// A `[[notifier]]` table: the backend, and how long a detection must last
+/// before it is told
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifierEntry {
+    /// Seconds a detection must have lasted before this notifier hears of
+    /// it; told at once if absent or 0
+    #[serde(default)]
+    pub escalate_after: Option<u64>,
+    #[serde(flatten)]
+    pub notifier: NotifierConfig,
+}
+
+impl From<NotifierConfig> for NotifierEntry {
+    fn from(notifier: NotifierConfig) -> Self {
+        Self {
+            escalate_after: None,
+            notifier,
+        }
+    }
+}
+
+/// A notification backend, selected by its `type` key
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum NotifierConfig {
+    Webhook(WebhookConfig),
+    Teams(TeamsConfig),
+    Discord(DiscordConfig),
+    Slack(SlackConfig),
+    Email(EmailConfig),
+    Mqtt(MqttConfig),
+    Gelf(GelfConfig),
+    /// macOS notification banner
+    Macos(MacosConfig),
+    /// Audible alarm when Cluely is first detected
+    Sound(SoundConfig),
+}
+
+/// Generic JSON webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// Key for the `X-Cluely-Signature` HMAC-SHA256 header
+    pub secret: Option<String>,
+    /// Extra attempts after a failed delivery
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Microsoft Teams incoming webhook or Workflows URL
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TeamsConfig {
+    pub url: String,
+    /// Card format the endpoint expects
+    #[serde(default)]
+    pub card: TeamsCard,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Teams payload flavour: Workflows take Adaptive Cards, legacy
+/// Office 365 connectors take MessageCards
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamsCard {
+    #[default]
+    Adaptive,
+    Message,
+}
+
+/// Discord channel webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub url: String,
+    /// Overrides the webhook's default display name
+    pub username: Option<String>,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Slack incoming webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlackConfig {
+    pub url: String,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Email through an SMTP server
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    /// `smtps://host[:port]` for TLS from the start (port 465 by default),
+    /// or `smtp://host[:port]` for STARTTLS (port 587 by default)
+    pub server: String,
+    /// Sender address, e.g. `"Cluely Detector <alerts@example.com>"`
+    pub from: String,
+    /// Recipient addresses
+    pub to: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// MQTT broker, e.g. for Home Assistant or Node-RED
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    /// `mqtt://host[:port]`, or `mqtts://host[:port]` for TLS
+    pub broker: String,
+    /// Defaults to `cluely-detector/<hostname>/state`
+    pub topic: Option<String>,
+    /// 0 (at most once), 1 (at least once) or 2 (exactly once)
+    #[serde(default = "default_qos")]
+    pub qos: u8,
+    /// Keep the last state on the broker for subscribers that connect later
+    #[serde(default = "default_retain")]
+    pub retain: bool,
+    /// Defaults to `cluely-detector-<hostname>`
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// PEM file of the CA that signed the broker's certificate, when it is
+    /// not trusted by the system
+    pub ca_file: Option<PathBuf>,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_qos() -> u8 {
+    1
+}
+
+fn default_retain() -> bool {
+    true
+}
+
+/// Graylog GELF input
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GelfConfig {
+    /// `udp://host[:port]` or `tcp://host[:port]`; the port defaults to 12201
+    pub address: String,
+    /// Additional fields sent with every message, e.g. `environment = "lab"`
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+/// Source of signature bundles; both default to the official feed
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SignatureUpdatesConfig {
+    /// Bundle URL; its signature is fetched from the same URL plus ".sig"
+    pub url: Option<String>,
+    /// Ed25519 public key the bundles are signed with, as 64 hex digits,
+    /// for an organization that publishes its own
+    pub public_key: Option<String>,
+}
+
+/// macOS notification banner
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MacosConfig {
+    /// What clicking a detection notification opens
+    pub open: ClickAction,
+}
+
+/// What a detection notification opens when clicked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickAction {
+    /// The HTML report of the detection, in the default browser
+    #[default]
+    Report,
+    /// `cluely-detector report` in a Terminal window
+    Terminal,
+    /// Nothing; the notification is only a banner
+    None,
+}
+
+/// Alarm sound played on detection
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SoundConfig {
+    /// System sound name (e.g. "Sosumi") or path to an audio file
+    pub sound: String,
+}
+
+/// Webhook delivery retries when not configured
+pub const DEFAULT_RETRIES: u32 = 3;
+
+fn default_retries() -> u32 {
+    DEFAULT_RETRIES
+}
+
+/// Directory holding the configuration file and other per-user settings
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("cluely-detector"))
+}
+
+/// Apps whose findings are never reported, e.g. a monitoring tool the
+/// organization deploys and discloses. Each list is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IgnoreFile {
+    /// Bundle IDs; apps nested in an allowed app are allowed too
+    pub bundle_ids: Vec<String>,
+    /// Team IDs of the developer certificate the app is signed with
+    pub team_ids: Vec<String>,
+    /// Window owner (process) names, matched whole
+    pub owners: Vec<String>,
+}
+
+impl IgnoreFile {
+    fn allow_list(self) -> Result<AllowList, String> {
+        if let Some(team_id) = self
+            .team_ids
+            .iter()
+            .find(|id| id.len() != 10 || !id.bytes().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit()))
+        {
+            return Err(format!(
+                "Team ID \"{}\" is not 10 capital letters and digits (see `codesign -dv` of the app)",
+                team_id
+            ));
+        }
+        Ok(AllowList {
+            bundle_ids: self.bundle_ids,
+            team_ids: self.team_ids,
+            owners: self.owners,
+        })
+    }
+}
+
+/// Path of the ignore file, if there is a home directory
+pub fn ignore_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("ignore.toml"))
+}
+
+/// Load the allow-list from the ignore file; empty if there is none
+pub fn load_ignore() -> Result<AllowList, String> {
+    let Some(path) = ignore_path().filter(|path| path.exists()) else {
+        return Ok(AllowList::default());
+    };
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: IgnoreFile =
+        toml::from_str(&contents).map_err(|e| format!("Invalid ignore file {}: {}", path.display(), e))?;
+    file.allow_list()
+        .map_err(|e| format!("Invalid ignore file {}: {}", path.display(), e))
+}
+
+/// Load the configuration from `path`, or from the default location if none is given
+pub fn load(path: Option<&Path>) -> Result<Config, String> {
+    let (path, required) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match config_dir() {
+            Some(dir) => (dir.join("config.toml"), false),
+            None => return Ok(Config::default()),
+        },
+    };
+
+    if !required && !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    toml::from_str(&contents).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
@@ -0,0 +1,411 @@
+//! Environment diagnostics (`doctor` subcommand).
+//!
+//! Each check reports what it found and, when something is wrong, the step
+//! that fixes it. Checks never change anything on the machine.
+
+use std::path::Path;
+use std::process::Command;
+
+use serde::Serialize;
+
+use no_cluely_driver::{
+    has_accessibility_access_rust, has_screen_capture_access_rust, try_detect_cluely_rust,
+    verify_self_integrity_rust, SignatureStatus,
+};
+
+use crate::{config, history, notify, service, suppress};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// Outcome of one diagnostic
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl Check {
+    pub(crate) fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            remediation: None,
+        }
+    }
+
+    pub(crate) fn warn(name: &'static str, detail: impl Into<String>, remediation: &str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            remediation: Some(remediation.to_string()),
+        }
+    }
+
+    pub(crate) fn fail(name: &'static str, detail: impl Into<String>, remediation: &str) -> Self {
+        Self {
+            name,
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            remediation: Some(remediation.to_string()),
+        }
+    }
+}
+
+fn macos_version() -> Option<(u32, u32, String)> {
+    let output = Command::new("sw_vers")
+        .arg("-productVersion")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut parts = version.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, version))
+}
+
+fn check_macos_version() -> Check {
+    const NAME: &str = "macOS version";
+
+    match macos_version() {
+        None => Check::warn(
+            NAME,
+            "Could not determine the macOS version",
+            "Make sure /usr/bin/sw_vers is available",
+        ),
+        Some((major, minor, version)) if major == 10 && minor < 15 => Check::fail(
+            NAME,
+            format!("macOS {} is not supported", version),
+            "Upgrade to macOS 10.15 (Catalina) or later",
+        ),
+        Some((major, _, version)) if major >= 15 => Check::ok(
+            NAME,
+            format!(
+                "macOS {}; Screen Recording permission has to be re-confirmed periodically",
+                version
+            ),
+        ),
+        Some((_, _, version)) => Check::ok(NAME, format!("macOS {}", version)),
+    }
+}
+
+fn check_window_list() -> Check {
+    const NAME: &str = "Window list";
+
+    match try_detect_cluely_rust() {
+        Ok(_) => Check::ok(NAME, "The window server answered the scan"),
+        Err(e) => Check::fail(
+            NAME,
+            format!("Scan failed: {}", e),
+            "Run from a logged-in user's desktop session rather than over SSH or as a \
+             LaunchDaemon; use 'cluely-detector service install' for background monitoring",
+        ),
+    }
+}
+
+fn check_user() -> Check {
+    const NAME: &str = "User";
+
+    if unsafe { libc::getuid() } == 0 {
+        Check::warn(
+            NAME,
+            "Running as root",
+            "Run as the logged-in user; root does not see that user's windows, \
+             and history and configuration would be written to root's home",
+        )
+    } else {
+        Check::ok(NAME, "Running as the current user")
+    }
+}
+
+fn check_screen_recording() -> Check {
+    const NAME: &str = "Screen Recording permission";
+
+    if has_screen_capture_access_rust() {
+        Check::ok(NAME, "Granted; window titles are visible")
+    } else {
+        Check::warn(
+            NAME,
+            "Not granted; other apps' window titles are hidden. Detection still works from \
+             window owners, sharing state and layers",
+            "Allow your terminal in System Settings > Privacy & Security > \
+             Screen & System Audio Recording",
+        )
+    }
+}
+
+fn check_accessibility() -> Check {
+    const NAME: &str = "Accessibility permission";
+
+    if has_accessibility_access_rust() {
+        Check::ok(NAME, "Granted; `scan-all --inspect` can read flagged windows' content")
+    } else {
+        Check::warn(
+            NAME,
+            "Not granted; only needed for `scan-all --inspect`",
+            "Allow your terminal in System Settings > Privacy & Security > Accessibility",
+        )
+    }
+}
+
+fn check_config(path: Option<&Path>) -> Vec<Check> {
+    let loaded = match config::load(path) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            return vec![Check::fail(
+                "Configuration",
+                e,
+                "Fix the file or pass a different one with --config",
+            )]
+        }
+    };
+
+    let location = path
+        .map(Path::to_path_buf)
+        .or_else(|| config::config_dir().map(|dir| dir.join("config.toml")))
+        .filter(|path| path.exists())
+        .map_or(
+            "No configuration file; using defaults".to_string(),
+            |path| format!("Loaded {}", path.display()),
+        );
+
+    let notifiers = match notify::from_config(&loaded.notifiers) {
+        Ok(notifiers) if notifiers.is_empty() => Check::ok(
+            "Notifiers",
+            "None configured; monitor only logs state changes",
+        ),
+        Ok(notifiers) => Check::ok(
+            "Notifiers",
+            notifiers
+                .iter()
+                .map(|channel| match channel.escalate_after {
+                    Some(after) => format!(
+                        "{} (after {})",
+                        channel.notifier.name(),
+                        notify::describe_threshold(after)
+                    ),
+                    None => channel.notifier.name().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Err(e) => Check::fail(
+            "Notifiers",
+            e,
+            "Fix the [[notifier]] entry in the configuration file",
+        ),
+    };
+
+    let signatures = match loaded.compile_signatures() {
+        Ok(_) if loaded.signatures.is_empty() => {
+            Check::ok("Signatures", "Built-in signatures only")
+        }
+        Ok(_) => Check::ok(
+            "Signatures",
+            format!(
+                "{} configured in addition to the built-in ones",
+                loaded.signatures.len()
+            ),
+        ),
+        Err(e) => Check::fail(
+            "Signatures",
+            e,
+            "Fix the [[signature]] entry in the configuration file",
+        ),
+    };
+
+    let transparency = match loaded.transparency.rule() {
+        Ok(rule) => Check::ok(
+            "Transparency rule",
+            format!(
+                "Flags windows at or below alpha {}, {} owner(s) allowed",
+                rule.max_alpha,
+                rule.allowed_owners.len()
+            ),
+        ),
+        Err(e) => Check::fail(
+            "Transparency rule",
+            e,
+            "Fix the [transparency] table in the configuration file",
+        ),
+    };
+
+    let mut detector = no_cluely_driver::DetectorConfig::default();
+    let targets = match loaded
+        .targets
+        .iter()
+        .try_for_each(|target| target.add_to(&mut detector))
+    {
+        Ok(()) if loaded.targets.is_empty() => Check::ok("Watched apps", "Cluely only"),
+        Ok(()) => Check::ok(
+            "Watched apps",
+            loaded
+                .targets
+                .iter()
+                .map(|target| target.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        Err(e) => Check::fail(
+            "Watched apps",
+            e,
+            "Fix the [[target]] entry in the configuration file",
+        ),
+    };
+
+    let allowed = match config::load_ignore() {
+        Ok(allow) if allow.is_empty() => {
+            Check::ok("Allow-list", "Nothing allowed; every finding is reported")
+        }
+        Ok(allow) => Check::ok(
+            "Allow-list",
+            format!(
+                "{} bundle ID(s), {} Team ID(s) and {} owner(s) never reported",
+                allow.bundle_ids.len(),
+                allow.team_ids.len(),
+                allow.owners.len()
+            ),
+        ),
+        Err(e) => Check::fail(
+            "Allow-list",
+            e,
+            "Fix ignore.toml in the configuration directory",
+        ),
+    };
+
+    let suppressions = match suppress::load() {
+        Ok(suppressions) if suppressions.is_empty() => {
+            Check::ok("Suppressions", "No findings suppressed")
+        }
+        Ok(suppressions) => {
+            let today = chrono::Local::now().date_naive();
+            let expired: Vec<&str> = suppressions
+                .iter()
+                .filter(|suppression| suppression.is_expired(today))
+                .map(|suppression| suppression.rule.as_str())
+                .collect();
+            if expired.is_empty() {
+                Check::ok(
+                    "Suppressions",
+                    format!("{} suppression(s) in effect", suppressions.len()),
+                )
+            } else {
+                Check::warn(
+                    "Suppressions",
+                    format!(
+                        "{} of {} suppression(s) expired: {}",
+                        expired.len(),
+                        suppressions.len(),
+                        expired.join(", ")
+                    ),
+                    "Renew or remove the expired entries in suppressions.toml",
+                )
+            }
+        }
+        Err(e) => Check::fail(
+            "Suppressions",
+            e,
+            "Fix suppressions.toml in the configuration directory",
+        ),
+    };
+
+    vec![
+        Check::ok("Configuration", location),
+        notifiers,
+        signatures,
+        targets,
+        transparency,
+        allowed,
+        suppressions,
+    ]
+}
+
+fn check_history() -> Check {
+    const NAME: &str = "History database";
+
+    match history::History::open_default() {
+        Ok(_) => Check::ok(
+            NAME,
+            history::default_path()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+        ),
+        Err(e) => Check::warn(
+            NAME,
+            e,
+            "Check the permissions of ~/Library/Application Support/no-cluely",
+        ),
+    }
+}
+
+fn check_service() -> Check {
+    const NAME: &str = "Background service";
+
+    match service::status() {
+        Ok((_, status)) if !status.installed && !status.loaded => Check::ok(NAME, "Not installed"),
+        Ok((_, status)) if status.pid.is_some() => Check::ok(NAME, "Installed and running"),
+        Ok(_) => Check::warn(
+            NAME,
+            "Installed but not running",
+            "Check ~/Library/Logs/cluely-detector/monitor.err.log, then run \
+             'cluely-detector service install' again",
+        ),
+        Err(e) => Check::warn(NAME, e, "Make sure launchctl is available"),
+    }
+}
+
+fn check_integrity() -> Check {
+    const NAME: &str = "Detector integrity";
+
+    match verify_self_integrity_rust() {
+        Ok(report) if !report.is_intact() => Check::fail(
+            NAME,
+            report.problems().join("; "),
+            "Reinstall cluely-detector from a trusted source and find out what changed it",
+        ),
+        Ok(report) if report.signature == SignatureStatus::Unsigned => Check::warn(
+            NAME,
+            format!("{} is not code signed", report.path),
+            "Use a signed release so a patched binary can be told apart",
+        ),
+        Ok(report) if report.signature == SignatureStatus::Unchecked => Check::warn(
+            NAME,
+            format!("The code signature of {} could not be checked", report.path),
+            "Run 'codesign --verify' on it to see why",
+        ),
+        Ok(report) => Check::ok(
+            NAME,
+            match &report.team_id {
+                Some(team_id) => format!("Signature valid (Team ID {})", team_id),
+                None => "Signature valid".to_string(),
+            },
+        ),
+        Err(e) => Check::warn(NAME, e.to_string(), "Run cluely-detector on macOS"),
+    }
+}
+
+/// Run every diagnostic
+pub fn run_checks(config: Option<&Path>) -> Vec<Check> {
+    let mut checks = vec![
+        check_macos_version(),
+        check_user(),
+        check_window_list(),
+        check_screen_recording(),
+        check_accessibility(),
+        check_integrity(),
+    ];
+    checks.extend(check_config(config));
+    checks.push(check_history());
+    checks.push(check_service());
+    checks
+}
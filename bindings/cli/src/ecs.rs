@@ -0,0 +1,108 @@
+//! Elastic Common Schema output (`--format ecs`).
+//!
+//! A detection becomes one ECS event per Cluely process, or a single event
+//! when none is known, printed one per line for Filebeat or Elastic Agent to
+//! ingest. Standard fields carry what Elastic Security rules and dashboards
+//! look for; the rest of the detection is kept under `cluely_detector`.
+
+use serde_json::{json, Value};
+
+use crate::agent::HostInfo;
+use crate::output::DetectionPayload;
+
+/// ECS version the events follow
+pub const ECS_VERSION: &str = "8.11.0";
+
+/// `event.severity` for a severity level, on Elastic's 0-100 risk scale
+fn severity_score(severity: &str) -> u32 {
+    match severity {
+        "Low" => 21,
+        "Medium" => 47,
+        "High" => 73,
+        _ => 0,
+    }
+}
+
+/// ECS events for `payload`
+pub fn events(payload: &DetectionPayload) -> Vec<Value> {
+    let host = HostInfo::current();
+    let mut base = json!({
+        "@timestamp": payload.timestamp,
+        "ecs": { "version": ECS_VERSION },
+        "message": if payload.detected {
+            format!("Cluely detected ({} severity)", payload.severity)
+        } else {
+            "Cluely not detected".to_string()
+        },
+        "event": {
+            "kind": if payload.detected { "alert" } else { "event" },
+            "category": ["malware"],
+            "type": ["info"],
+            "module": "cluely_detector",
+            "dataset": "cluely_detector.detection",
+            "severity": severity_score(&payload.severity),
+        },
+        "host": {
+            "hostname": host.hostname,
+            "name": host.hostname,
+            "os": { "type": "macos", "platform": "darwin" },
+        },
+        "agent": { "type": "cluely-detector", "version": host.agent_version },
+        "cluely_detector": {
+            "detected": payload.detected,
+            "severity": payload.severity,
+            "window_count": payload.window_count,
+            "screen_capture_evasion_count": payload.screen_capture_evasion_count,
+            "elevated_layer_count": payload.elevated_layer_count,
+            "max_layer_detected": payload.max_layer_detected,
+            "evasion_techniques": payload.evasion_techniques,
+        },
+    });
+    if let Some(duration) = payload.last_scan_duration_ms {
+        base["event"]["duration"] = json!((duration * 1_000_000.0) as u64);
+    }
+    if let Some(serial) = &host.serial_number {
+        base["host"]["id"] = json!(serial);
+    }
+    if let Some(user) = &host.username {
+        base["user"] = json!({ "name": user });
+    }
+    if !payload.evasion_techniques.is_empty() {
+        let rule_ids: Vec<&str> = payload
+            .evasion_techniques
+            .iter()
+            .map(|technique| technique.rule_id.as_str())
+            .collect();
+        base["rule"] = json!({ "id": rule_ids });
+        if let Some(ruleset) = &payload.ruleset {
+            base["rule"]["version"] = json!(ruleset.version.to_string());
+        }
+    }
+    if payload.detected {
+        base["threat"] = json!({
+            "software": { "name": "Cluely", "type": "Tool", "platforms": ["macOS"] },
+        });
+    }
+
+    let processes: Vec<Value> = payload
+        .installations
+        .iter()
+        .flatten()
+        .flat_map(|installation| {
+            installation.processes.iter().map(|process| {
+                let mut event = base.clone();
+                event["process"] = json!({ "pid": process.pid, "name": process.name });
+                event["cluely_detector"]["role"] = json!(process.role);
+                if let Some(bundle) = &installation.bundle {
+                    event["cluely_detector"]["bundle"] = json!(bundle);
+                }
+                event
+            })
+        })
+        .collect();
+    if processes.is_empty() {
+        vec![base]
+    } else {
+        processes
+    }
+}
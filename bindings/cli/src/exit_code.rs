@@ -0,0 +1,75 @@
+//! Exit-code contract for the detection commands.
+//!
+//! `check`, `report`, `stats` and `scan-all` exit with a code that tells
+//! scripts exactly what was found. Which findings count as a failure is set
+//! with `--fail-on` (or `fail_on` in the configuration file), and the codes
+//! themselves can be changed in the `[exit_codes]` table.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Nothing that `--fail-on` cares about was found
+pub const SUCCESS: i32 = 0;
+/// Any other error: bad configuration, unwritable files, network failures
+pub const ERROR: i32 = 1;
+/// Invalid command-line arguments (EX_USAGE)
+pub const USAGE: i32 = 64;
+
+/// Which findings make a detection command fail
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOn {
+    /// Only Cluely itself
+    #[default]
+    Cluely,
+    /// Cluely, or any other app using screen sharing evasion
+    Any,
+    /// Never; only errors change the exit code
+    None,
+}
+
+/// Exit codes for scan outcomes (`[exit_codes]` table)
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExitCodes {
+    /// Cluely was detected
+    pub cluely: i32,
+    /// Another app uses screen sharing evasion (with `--fail-on any`)
+    pub evasion: i32,
+    /// The window list could not be read: no GUI session, or access denied
+    pub permission: i32,
+}
+
+impl Default for ExitCodes {
+    fn default() -> Self {
+        Self {
+            cluely: 10,
+            evasion: 11,
+            permission: 2,
+        }
+    }
+}
+
+/// `--fail-on` together with the configured codes
+#[derive(Debug, Clone, Copy)]
+pub struct ExitPolicy {
+    pub fail_on: FailOn,
+    pub codes: ExitCodes,
+}
+
+impl ExitPolicy {
+    /// Whether the outcome depends on the generic evasion scan as well
+    pub fn needs_evasion_scan(&self) -> bool {
+        self.fail_on == FailOn::Any
+    }
+
+    /// Exit code for a completed scan
+    pub fn code(&self, cluely_detected: bool, evasion_detected: bool) -> i32 {
+        match self.fail_on {
+            FailOn::None => SUCCESS,
+            _ if cluely_detected => self.codes.cluely,
+            FailOn::Any if evasion_detected => self.codes.evasion,
+            _ => SUCCESS,
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Rules for other security tools, built from the Cluely binaries running
+//! on this machine (`export`).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+
+use no_cluely_driver::{SignedBinary, CLUELY_WEB_DOMAIN};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Shown by Santa when it blocks a binary
+const SANTA_MESSAGE: &str = "Cluely is blocked on this Mac by your organization";
+
+/// A rule in the format `santactl rule --import` and Santa sync servers take
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct SantaRule {
+    pub identifier: String,
+    /// `TEAMID`, `CDHASH` or `BINARY` (SHA-256 of the file)
+    pub rule_type: &'static str,
+    pub policy: &'static str,
+    pub custom_msg: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SantaRules {
+    pub rules: Vec<SantaRule>,
+}
+
+impl SantaRule {
+    fn block(rule_type: &'static str, identifier: String) -> Self {
+        Self {
+            identifier,
+            rule_type,
+            policy: "BLOCKLIST",
+            custom_msg: SANTA_MESSAGE,
+        }
+    }
+}
+
+/// Blocklist rules covering `binaries`: by Team ID, which also blocks
+/// future builds, or with `by_cdhash` only the exact builds seen. A binary
+/// without a Team ID is blocked by CDHash, and an unsigned one by the
+/// SHA-256 of its file.
+pub fn santa_rules(binaries: &[SignedBinary], by_cdhash: bool) -> SantaRules {
+    let rules: BTreeSet<SantaRule> = binaries
+        .iter()
+        .filter_map(|binary| match (&binary.team_id, &binary.cdhash) {
+            (Some(team_id), _) if !by_cdhash => Some(SantaRule::block("TEAMID", team_id.clone())),
+            (_, Some(cdhash)) => Some(SantaRule::block("CDHASH", cdhash.clone())),
+            (_, None) => read_binary(&binary.path)
+                .map(|contents| SantaRule::block("BINARY", hex::encode(Sha256::digest(contents)))),
+        })
+        .collect();
+    SantaRules {
+        rules: rules.into_iter().collect(),
+    }
+}
+
+/// Shortest and longest string worth matching on
+const YARA_STRING_LENGTH: std::ops::RangeInclusive<usize> = 8..=120;
+/// Most strings taken from one binary
+const YARA_MAX_STRINGS: usize = 8;
+
+/// The contents of the executable at `path`, or None with a warning
+fn read_binary(path: &str) -> Option<Vec<u8>> {
+    fs::read(path)
+        .map_err(|error| tracing::warn!(path = %path, %error, "could not read binary"))
+        .ok()
+}
+
+/// Printable ASCII runs in `contents` that mention Cluely, longest first;
+/// what `strings` would print, narrowed to what tells this app apart
+fn notable_strings(contents: &[u8]) -> Vec<String> {
+    let found: BTreeSet<&str> = contents
+        .split(|&byte| !(0x20..0x7f).contains(&byte))
+        .filter(|run| YARA_STRING_LENGTH.contains(&run.len()))
+        .filter_map(|run| std::str::from_utf8(run).ok())
+        .filter(|text| text.to_ascii_lowercase().contains("cluely"))
+        .collect();
+    let mut found: Vec<&str> = found.into_iter().collect();
+    found.sort_by_key(|text| std::cmp::Reverse(text.len()));
+    found
+        .into_iter()
+        .take(YARA_MAX_STRINGS)
+        .map(str::to_string)
+        .collect()
+}
+
+/// `text` as a YARA string literal
+fn yara_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A YARA identifier from a file name: letters, digits and underscores
+fn yara_identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// YARA rules matching `binaries` elsewhere, one per distinct file: by
+/// SHA-256, or by signing identity and notable strings, so a rebuilt or
+/// updated copy is still found. Matches Mach-O files only.
+pub fn yara_rules(binaries: &[SignedBinary]) -> (String, usize) {
+    // Copies of one file at several paths get one rule
+    let mut by_hash: BTreeMap<String, (&SignedBinary, Vec<u8>)> = BTreeMap::new();
+    for binary in binaries {
+        if let Some(contents) = read_binary(&binary.path) {
+            by_hash
+                .entry(hex::encode(Sha256::digest(&contents)))
+                .or_insert((binary, contents));
+        }
+    }
+
+    let mut document = format!(
+        "// Generated by cluely-detector {} on {}\n\nimport \"hash\"\n",
+        env!("CARGO_PKG_VERSION"),
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    for (sha256, (binary, contents)) in &by_hash {
+        let file_name = binary.path.rsplit('/').next().unwrap_or_default();
+        let _ = writeln!(
+            document,
+            "\nrule Cluely_{}_{} {{",
+            yara_identifier(file_name),
+            &sha256[..8]
+        );
+        let _ = writeln!(document, "    meta:");
+        let _ = writeln!(
+            document,
+            "        description = {}",
+            yara_string(&format!("Cluely executable, seen at {}", binary.path))
+        );
+        let _ = writeln!(document, "        sha256 = {}", yara_string(sha256));
+        let identity = [
+            ("team_id", &binary.team_id),
+            ("signing_id", &binary.signing_id),
+            ("cdhash", &binary.cdhash),
+        ];
+        for (key, value) in identity {
+            if let Some(value) = value {
+                let _ = writeln!(document, "        {} = {}", key, yara_string(value));
+            }
+        }
+
+        // A Team ID and signing ID are stored as text in the signature
+        let mut strings: Vec<(String, &str)> = Vec::new();
+        if let Some(team_id) = &binary.team_id {
+            strings.push(("$team_id".to_string(), team_id));
+        }
+        if let Some(signing_id) = &binary.signing_id {
+            strings.push(("$signing_id".to_string(), signing_id));
+        }
+        let mut identity: Vec<String> = strings.iter().map(|(name, _)| name.clone()).collect();
+        let notable = notable_strings(contents);
+        for (index, text) in notable.iter().enumerate() {
+            strings.push((format!("$str{}", index + 1), text));
+        }
+        if !notable.is_empty() {
+            identity.push(format!("{} of ($str*)", notable.len().div_ceil(2)));
+        }
+        if !strings.is_empty() {
+            let _ = writeln!(document, "    strings:");
+            for (name, text) in &strings {
+                let _ = writeln!(document, "        {} = {} ascii", name, yara_string(text));
+            }
+        }
+
+        let _ = writeln!(document, "    condition:");
+        let _ = writeln!(
+            document,
+            "        (uint32(0) == 0xfeedfacf or uint32be(0) == 0xcafebabe) and"
+        );
+        if identity.is_empty() {
+            let _ = writeln!(
+                document,
+                "        hash.sha256(0, filesize) == {}",
+                yara_string(sha256)
+            );
+        } else {
+            let _ = writeln!(
+                document,
+                "        (hash.sha256(0, filesize) == {} or ({}))",
+                yara_string(sha256),
+                identity.join(" and ")
+            );
+        }
+        let _ = writeln!(document, "}}");
+    }
+    (document, by_hash.len())
+}
+
+/// Namespace STIX 2.1 gives for deterministic identifiers; an indicator
+/// exported from any host gets the same ID, so threat-intel platforms merge
+/// them
+const STIX_NAMESPACE: Uuid = Uuid::from_u128(0x00abedb4_aa42_466c_9c01_fed23315a9b7);
+
+/// A STIX identifier for the `kind` object described by `key`
+fn stix_id(kind: &str, key: &str) -> String {
+    format!(
+        "{}--{}",
+        kind,
+        Uuid::new_v5(&STIX_NAMESPACE, format!("{}:{}", kind, key).as_bytes())
+    )
+}
+
+/// `text` as a STIX pattern string literal
+fn stix_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// A STIX 2.1 bundle describing Cluely as a tool, with an indicator for
+/// each running executable's SHA-256, each process name and Cluely's web
+/// domain, all linked to the tool. Returns the bundle and its indicator
+/// count.
+pub fn stix_bundle(binaries: &[SignedBinary], process_names: &[String]) -> (Value, usize) {
+    let now = chrono::Utc::now()
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string();
+    let tool_id = stix_id("tool", "Cluely");
+    let mut objects = vec![json!({
+        "type": "tool",
+        "spec_version": "2.1",
+        "id": tool_id,
+        "created": now,
+        "modified": now,
+        "name": "Cluely",
+        "description": "Desktop overlay that hides its windows from screen capture and sharing",
+        "tool_types": ["information-gathering"],
+    })];
+
+    let mut indicators: Vec<(String, String)> = Vec::new();
+    let hashes: BTreeMap<String, &str> = binaries
+        .iter()
+        .filter_map(|binary| {
+            let contents = read_binary(&binary.path)?;
+            Some((hex::encode(Sha256::digest(contents)), binary.path.as_str()))
+        })
+        .collect();
+    for (sha256, path) in hashes {
+        indicators.push((
+            format!(
+                "Cluely executable {}",
+                path.rsplit('/').next().unwrap_or(path)
+            ),
+            format!("[file:hashes.'SHA-256' = {}]", stix_literal(&sha256)),
+        ));
+    }
+    let names: BTreeSet<&String> = process_names
+        .iter()
+        .filter(|name| !name.is_empty())
+        .collect();
+    for name in names {
+        indicators.push((
+            format!("Cluely process {}", name),
+            format!("[process:name = {}]", stix_literal(name)),
+        ));
+    }
+    indicators.push((
+        "Cluely web app domain".to_string(),
+        format!(
+            "[domain-name:value = {} OR domain-name:value LIKE {}]",
+            stix_literal(CLUELY_WEB_DOMAIN),
+            stix_literal(&format!("%.{}", CLUELY_WEB_DOMAIN))
+        ),
+    ));
+
+    let count = indicators.len();
+    for (name, pattern) in indicators {
+        let indicator_id = stix_id("indicator", &pattern);
+        objects.push(json!({
+            "type": "indicator",
+            "spec_version": "2.1",
+            "id": indicator_id,
+            "created": now,
+            "modified": now,
+            "name": name,
+            "indicator_types": ["anomalous-activity"],
+            "pattern": pattern,
+            "pattern_type": "stix",
+            "valid_from": now,
+        }));
+        objects.push(json!({
+            "type": "relationship",
+            "spec_version": "2.1",
+            "id": stix_id("relationship", &indicator_id),
+            "created": now,
+            "modified": now,
+            "relationship_type": "indicates",
+            "source_ref": indicator_id,
+            "target_ref": tool_id,
+        }));
+    }
+
+    let host = hostname::get()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let bundle = json!({
+        "type": "bundle",
+        "id": stix_id("bundle", &format!("{}@{}", host, now)),
+        "objects": objects,
+    });
+    (bundle, count)
+}
@@ -0,0 +1,154 @@
+//! Liveness of a running `monitor` or `serve` (`/v1/health`, `status`).
+//!
+//! The daemon keeps a `Health` record up to date after every scan and
+//! answers `GET /v1/health` with it: `serve` on its own API, `monitor` on a
+//! small listener enabled with `--health`. `status` reads that endpoint, so
+//! checking on the daemon never costs a window scan and shows whether the
+//! daemon itself is still scanning.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tiny_http::{Method, Request, Server};
+
+use crate::server::{json_response, ErrorBody};
+
+/// Where `monitor --health` listens and `status` looks by default; also the
+/// default address of `serve`
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// No scan has finished yet
+    Starting,
+    /// The last scan succeeded
+    Ok,
+    /// Scans are failing, so the reported detection state is stale
+    Degraded,
+}
+
+/// What a running daemon reports about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub status: HealthStatus,
+    /// `monitor` or `serve`
+    pub mode: String,
+    pub version: String,
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: u64,
+    /// When the last scan was attempted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_scan: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_successful_scan: Option<DateTime<Utc>>,
+    pub total_scans: u64,
+    pub failed_scans: u64,
+    /// Failed scans since the last successful one
+    pub consecutive_failures: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Whether the daemon currently reports Cluely as detected
+    pub detected: bool,
+}
+
+pub type SharedHealth = Arc<Mutex<Health>>;
+
+impl Health {
+    pub fn new(mode: &str) -> Self {
+        Health {
+            status: HealthStatus::Starting,
+            mode: mode.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            pid: std::process::id(),
+            started_at: Utc::now(),
+            uptime_seconds: 0,
+            last_scan: None,
+            last_successful_scan: None,
+            total_scans: 0,
+            failed_scans: 0,
+            consecutive_failures: 0,
+            last_error: None,
+            detected: false,
+        }
+    }
+
+    /// Record a successful scan; `detected` is the state now reported
+    pub fn scanned(&mut self, detected: bool) {
+        let now = Utc::now();
+        self.status = HealthStatus::Ok;
+        self.last_scan = Some(now);
+        self.last_successful_scan = Some(now);
+        self.total_scans += 1;
+        self.consecutive_failures = 0;
+        self.last_error = None;
+        self.detected = detected;
+    }
+
+    /// Record a scan that could not see the windows
+    pub fn failed(&mut self, error: impl ToString) {
+        self.status = HealthStatus::Degraded;
+        self.last_scan = Some(Utc::now());
+        self.total_scans += 1;
+        self.failed_scans += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(error.to_string());
+    }
+
+    /// This record as of now, for a response
+    pub fn current(&self) -> Health {
+        Health {
+            uptime_seconds: (Utc::now() - self.started_at).num_seconds().max(0) as u64,
+            ..self.clone()
+        }
+    }
+}
+
+/// Answer `GET /v1/health` on `bind` from a background thread
+pub fn listen(bind: &str, health: SharedHealth) -> Result<(), String> {
+    let server = Server::http(bind).map_err(|e| format!("Failed to bind {}: {}", bind, e))?;
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            respond(request, &health);
+        }
+    });
+    Ok(())
+}
+
+fn respond(request: Request, health: &Mutex<Health>) {
+    let response = match (request.method(), request.url()) {
+        (Method::Get, "/v1/health") => json_response(200, &health.lock().unwrap().current()),
+        (Method::Get, _) => json_response(404, &ErrorBody { error: "not found" }),
+        _ => json_response(
+            405,
+            &ErrorBody {
+                error: "method not allowed",
+            },
+        ),
+    };
+    // The client may have disconnected; nothing useful to do about it
+    let _ = request.respond(response);
+}
+
+/// The health of the daemon listening on `address`
+pub fn query(address: &str) -> Result<Health, String> {
+    let url = if address.contains("://") {
+        format!("{}/v1/health", address.trim_end_matches('/'))
+    } else {
+        format!("http://{}/v1/health", address)
+    };
+    let body = ureq::get(&url)
+        .timeout(QUERY_TIMEOUT)
+        .call()
+        .map_err(|e| format!("No daemon answered at {}: {}", address, e))?
+        .into_string()
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+    serde_json::from_str(&body)
+        .map_err(|e| format!("{} did not return a health record: {}", url, e))
+}
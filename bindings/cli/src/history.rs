@@ -0,0 +1,517 @@
+//! Scan history database.
+//!
+//! Every scan taken by `monitor` and `serve` is appended to
+//! `~/Library/Application Support/no-cluely/history.db`, so the record shows
+//! not only when Cluely was seen but also that monitoring was running when it
+//! was not.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::output::{DetectionPayload, FindingPayload, WindowPayload, SCHEMA_VERSION};
+
+/// Most days a command can look back over. Subtracting much more from now
+/// overflows chrono's date range, and no scan is a century old.
+pub const MAX_DAYS: i64 = 36_500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY,
+    scanned_at INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    detected INTEGER NOT NULL,
+    window_count INTEGER NOT NULL,
+    screen_capture_evasion_count INTEGER NOT NULL,
+    elevated_layer_count INTEGER NOT NULL,
+    max_layer_detected INTEGER NOT NULL,
+    severity TEXT NOT NULL,
+    evasion_techniques TEXT NOT NULL,
+    windows TEXT NOT NULL,
+    ruleset TEXT
+);
+CREATE INDEX IF NOT EXISTS scans_scanned_at ON scans (scanned_at);
+";
+
+const COLUMNS: &str = "id, scanned_at, source, detected, window_count, \
+    screen_capture_evasion_count, elevated_layer_count, max_layer_detected, \
+    severity, evasion_techniques, windows, ruleset";
+
+/// Columns added since the table was first created, and their types;
+/// databases written by older versions get them when opened
+const ADDED_COLUMNS: &[(&str, &str)] = &[("ruleset", "TEXT")];
+
+/// Rule IDs of the techniques older versions stored as plain text, by the
+/// start of their description
+const LEGACY_TECHNIQUES: &[(&str, &str)] = &[
+    ("Screen capture evasion", "screen-capture-evasion"),
+    ("Elevated layer positioning", "elevated-layer"),
+    ("Active capture", "active-capture"),
+];
+
+/// Stored techniques; scans recorded before findings were structured kept
+/// only descriptions, which get back the rule ID they were written for
+fn parse_techniques(json: &str) -> Vec<FindingPayload> {
+    if let Ok(findings) = serde_json::from_str(json) {
+        return findings;
+    }
+    let messages: Vec<String> = serde_json::from_str(json).unwrap_or_default();
+    messages
+        .into_iter()
+        .map(|message| FindingPayload {
+            rule_id: LEGACY_TECHNIQUES
+                .iter()
+                .find(|(prefix, _)| message.starts_with(prefix))
+                .map_or("unknown", |(_, rule_id)| rule_id)
+                .to_string(),
+            message,
+            evidence: Default::default(),
+        })
+        .collect()
+}
+
+/// One stored scan
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanRecord {
+    pub id: i64,
+    /// Command that took the scan (`monitor` or `serve`)
+    pub source: String,
+    #[serde(flatten)]
+    pub detection: DetectionPayload,
+    pub windows: Vec<WindowPayload>,
+}
+
+impl ScanRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let scanned_at: i64 = row.get(1)?;
+        let techniques: String = row.get(9)?;
+        let windows: String = row.get(10)?;
+        let ruleset: Option<String> = row.get(11)?;
+
+        Ok(Self {
+            id: row.get(0)?,
+            source: row.get(2)?,
+            detection: DetectionPayload {
+                schema_version: SCHEMA_VERSION,
+                detected: row.get(3)?,
+                window_count: row.get(4)?,
+                screen_capture_evasion_count: row.get(5)?,
+                elevated_layer_count: row.get(6)?,
+                max_layer_detected: row.get(7)?,
+                severity: row.get(8)?,
+                evasion_techniques: parse_techniques(&techniques),
+                timestamp: crate::timestamp::rfc3339(
+                    DateTime::from_timestamp_millis(scanned_at).unwrap_or_default(),
+                ),
+                last_scan_duration_ms: None,
+                browser_tabs: None,
+                installations: None,
+                ruleset: ruleset.and_then(|ruleset| serde_json::from_str(&ruleset).ok()),
+            },
+            windows: serde_json::from_str(&windows).unwrap_or_default(),
+        })
+    }
+}
+
+/// A continuous period of Cluely activity reconstructed from scans
+#[derive(Debug, Clone, Serialize)]
+pub struct Session {
+    pub first_seen: String,
+    pub last_seen: String,
+    pub duration_seconds: i64,
+    /// Scans that saw Cluely during the session
+    pub scans: usize,
+    pub max_severity: String,
+    pub max_window_count: u32,
+    /// Still detected at the last scan in the requested range
+    pub ongoing: bool,
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "Low" => 1,
+        "Medium" => 2,
+        "High" => 3,
+        _ => 0,
+    }
+}
+
+fn scan_time(scan: &ScanRecord) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&scan.detection.timestamp)
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_default()
+}
+
+/// Split chronologically ordered scans into runs of consecutive detections,
+/// each with whether it was still going at the last scan. A gap longer than
+/// `max_gap` between scans means monitoring was not running, so a run never
+/// spans one.
+fn episodes(scans: &[ScanRecord], max_gap: Duration) -> Vec<(&[ScanRecord], bool)> {
+    let mut episodes = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (index, scan) in scans.iter().enumerate() {
+        let gap = index > 0 && scan_time(scan) - scan_time(&scans[index - 1]) > max_gap;
+        if gap || !scan.detection.detected {
+            if let Some(first) = start.take() {
+                episodes.push((&scans[first..index], false));
+            }
+        }
+        if scan.detection.detected && start.is_none() {
+            start = Some(index);
+        }
+    }
+
+    if let Some(first) = start {
+        episodes.push((&scans[first..], true));
+    }
+    episodes
+}
+
+/// The highest severity among `scans`, the earliest if several tie
+fn max_severity(scans: &[ScanRecord]) -> String {
+    let mut max = &scans[0].detection.severity;
+    for scan in scans {
+        if severity_rank(&scan.detection.severity) > severity_rank(max) {
+            max = &scan.detection.severity;
+        }
+    }
+    max.clone()
+}
+
+/// Group chronologically ordered scans into sessions of consecutive
+/// detections. A gap longer than `max_gap` between scans means monitoring
+/// was not running, so a session never spans one.
+pub fn timeline(scans: &[ScanRecord], max_gap: Duration) -> Vec<Session> {
+    episodes(scans, max_gap)
+        .into_iter()
+        .map(|(scans, ongoing)| {
+            let (first, last) = (&scans[0], &scans[scans.len() - 1]);
+            Session {
+                first_seen: first.detection.timestamp.clone(),
+                last_seen: last.detection.timestamp.clone(),
+                duration_seconds: (scan_time(last) - scan_time(first)).num_seconds(),
+                scans: scans.len(),
+                max_severity: max_severity(scans),
+                max_window_count: scans
+                    .iter()
+                    .map(|scan| scan.detection.window_count)
+                    .max()
+                    .unwrap_or(0),
+                ongoing,
+            }
+        })
+        .collect()
+}
+
+/// One detection episode as a row of `history export`, with a summary of
+/// the evidence recorded during it
+#[derive(Debug, Clone, Serialize)]
+pub struct EpisodeRow {
+    /// 1-based position in the export
+    pub episode: usize,
+    pub start: String,
+    pub end: String,
+    pub duration_seconds: i64,
+    /// Still detected at the last scan in the requested range
+    pub ongoing: bool,
+    pub scans: usize,
+    /// Scans to look at with `history show`
+    pub first_scan_id: i64,
+    pub last_scan_id: i64,
+    pub max_severity: String,
+    pub max_window_count: u32,
+    pub max_screen_capture_evasion_count: u32,
+    pub max_elevated_layer_count: u32,
+    pub max_layer_detected: i32,
+    #[serde(flatten)]
+    pub evidence: EvidenceSummary,
+}
+
+/// One scan as a row of `history export --rows scans`
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanRow {
+    pub id: i64,
+    pub timestamp: String,
+    pub source: String,
+    pub detected: bool,
+    pub severity: String,
+    pub window_count: u32,
+    pub screen_capture_evasion_count: u32,
+    pub elevated_layer_count: u32,
+    pub max_layer_detected: i32,
+    #[serde(flatten)]
+    pub evidence: EvidenceSummary,
+}
+
+impl ScanRow {
+    pub fn from_record(scan: &ScanRecord) -> Self {
+        let detection = &scan.detection;
+        let mut row = Self {
+            id: scan.id,
+            timestamp: detection.timestamp.clone(),
+            source: scan.source.clone(),
+            detected: detection.detected,
+            severity: detection.severity.clone(),
+            window_count: detection.window_count,
+            screen_capture_evasion_count: detection.screen_capture_evasion_count,
+            elevated_layer_count: detection.elevated_layer_count,
+            max_layer_detected: detection.max_layer_detected,
+            evidence: EvidenceSummary::default(),
+        };
+        row.evidence.add(scan);
+        row
+    }
+}
+
+/// The evidence behind an export row, each value listed once in the order
+/// it was first recorded
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvidenceSummary {
+    pub rule_ids: Vec<String>,
+    pub techniques: Vec<String>,
+    /// Owners of the Cluely windows seen
+    pub window_owners: Vec<String>,
+}
+
+impl EvidenceSummary {
+    fn add(&mut self, scan: &ScanRecord) {
+        fn add(list: &mut Vec<String>, value: &str) {
+            if !list.iter().any(|listed| listed == value) {
+                list.push(value.to_string());
+            }
+        }
+        for technique in &scan.detection.evasion_techniques {
+            add(&mut self.rule_ids, &technique.rule_id);
+            add(&mut self.techniques, &technique.message);
+        }
+        for window in &scan.windows {
+            add(&mut self.window_owners, &window.owner);
+        }
+    }
+}
+
+/// The detection episodes among chronologically ordered scans, as by
+/// [`timeline`], with their evidence
+pub fn episode_rows(scans: &[ScanRecord], max_gap: Duration) -> Vec<EpisodeRow> {
+    episodes(scans, max_gap)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (scans, ongoing))| {
+            let (first, last) = (&scans[0], &scans[scans.len() - 1]);
+            let max = |field: fn(&DetectionPayload) -> u32| {
+                scans
+                    .iter()
+                    .map(|scan| field(&scan.detection))
+                    .max()
+                    .unwrap_or(0)
+            };
+            let mut row = EpisodeRow {
+                episode: index + 1,
+                start: first.detection.timestamp.clone(),
+                end: last.detection.timestamp.clone(),
+                duration_seconds: (scan_time(last) - scan_time(first)).num_seconds(),
+                ongoing,
+                scans: scans.len(),
+                first_scan_id: first.id,
+                last_scan_id: last.id,
+                max_severity: max_severity(scans),
+                max_window_count: max(|detection| detection.window_count),
+                max_screen_capture_evasion_count: max(|detection| {
+                    detection.screen_capture_evasion_count
+                }),
+                max_elevated_layer_count: max(|detection| detection.elevated_layer_count),
+                max_layer_detected: scans
+                    .iter()
+                    .map(|scan| scan.detection.max_layer_detected)
+                    .max()
+                    .unwrap_or(0),
+                evidence: EvidenceSummary::default(),
+            };
+            for scan in scans {
+                row.evidence.add(scan);
+            }
+            row
+        })
+        .collect()
+}
+
+/// Parse a `--from`/`--to` value: RFC 3339, `YYYY-MM-DD HH:MM[:SS]` or
+/// `YYYY-MM-DD`, all in UTC. With `end_of_day`, a bare date means the end of
+/// that day rather than its start.
+pub fn parse_time(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time.and_utc());
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        format!(
+            "Invalid time '{}': expected YYYY-MM-DD, 'YYYY-MM-DD HH:MM' or RFC 3339",
+            value
+        )
+    })?;
+    let date = if end_of_day {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    };
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// Default database location
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("no-cluely").join("history.db"))
+}
+
+/// Handle to the history database
+pub struct History {
+    conn: Connection,
+}
+
+/// Bring a table created by an older version up to date
+fn add_columns(conn: &Connection) -> rusqlite::Result<()> {
+    let mut statement = conn.prepare("SELECT name FROM pragma_table_info('scans')")?;
+    let existing = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (name, kind) in ADDED_COLUMNS {
+        if !existing.iter().any(|column| column == name) {
+            conn.execute_batch(&format!("ALTER TABLE scans ADD COLUMN {} {}", name, kind))?;
+        }
+    }
+    Ok(())
+}
+
+impl History {
+    /// Open (creating if needed) the database at `path`
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+
+        let conn = Connection::open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        conn.execute_batch(SCHEMA)
+            .and_then(|_| add_columns(&conn))
+            .map_err(|e| format!("Failed to initialise {}: {}", path.display(), e))?;
+        Ok(Self { conn })
+    }
+
+    /// Open the database at the default location
+    pub fn open_default() -> Result<Self, String> {
+        let path = default_path().ok_or("Could not determine the application data directory")?;
+        Self::open(&path)
+    }
+
+    /// Append a scan, returning its id
+    pub fn record(
+        &self,
+        source: &str,
+        detection: &DetectionPayload,
+        windows: &[WindowPayload],
+    ) -> Result<i64, String> {
+        let scanned_at = DateTime::parse_from_rfc3339(&detection.timestamp)
+            .map(|time| time.timestamp_millis())
+            .unwrap_or_else(|_| Utc::now().timestamp_millis());
+
+        self.conn
+            .execute(
+                "INSERT INTO scans (scanned_at, source, detected, window_count, \
+                 screen_capture_evasion_count, elevated_layer_count, max_layer_detected, \
+                 severity, evasion_techniques, windows, ruleset) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    scanned_at,
+                    source,
+                    detection.detected,
+                    detection.window_count,
+                    detection.screen_capture_evasion_count,
+                    detection.elevated_layer_count,
+                    detection.max_layer_detected,
+                    detection.severity,
+                    serde_json::to_string(&detection.evasion_techniques).unwrap(),
+                    serde_json::to_string(windows).unwrap(),
+                    detection
+                        .ruleset
+                        .as_ref()
+                        .map(|ruleset| serde_json::to_string(ruleset).unwrap()),
+                ],
+            )
+            .map_err(|e| format!("Failed to record scan: {}", e))?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Most recent scans first
+    pub fn list(&self, limit: usize, detected_only: bool) -> Result<Vec<ScanRecord>, String> {
+        let sql = format!(
+            "SELECT {} FROM scans WHERE detected >= ?1 ORDER BY scanned_at DESC, id DESC LIMIT ?2",
+            COLUMNS
+        );
+        let mut statement = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+        let rows = statement
+            .query_map(params![detected_only, limit as i64], ScanRecord::from_row)
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read history: {}", e))
+    }
+
+    /// Scans between `from` (inclusive) and `to` (exclusive), oldest first
+    pub fn range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ScanRecord>, String> {
+        let sql = format!(
+            "SELECT {} FROM scans WHERE scanned_at >= ?1 AND scanned_at < ?2 \
+             ORDER BY scanned_at, id",
+            COLUMNS
+        );
+        let mut statement = self
+            .conn
+            .prepare(&sql)
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+        let rows = statement
+            .query_map(
+                params![
+                    from.map_or(i64::MIN, |time| time.timestamp_millis()),
+                    to.map_or(i64::MAX, |time| time.timestamp_millis()),
+                ],
+                ScanRecord::from_row,
+            )
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read history: {}", e))
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<ScanRecord>, String> {
+        let sql = format!("SELECT {} FROM scans WHERE id = ?1", COLUMNS);
+        self.conn
+            .query_row(&sql, params![id], ScanRecord::from_row)
+            .optional()
+            .map_err(|e| format!("Failed to query history: {}", e))
+    }
+
+    /// Delete scans older than `before`, or every scan if `before` is None.
+    /// Returns the number of scans removed.
+    pub fn purge(&self, before: Option<DateTime<Utc>>) -> Result<usize, String> {
+        let cutoff = before.map_or(i64::MAX, |time| time.timestamp_millis());
+        let removed = self
+            .conn
+            .execute("DELETE FROM scans WHERE scanned_at < ?1", params![cutoff])
+            .map_err(|e| format!("Failed to purge history: {}", e))?;
+        // Give the space back to the filesystem
+        let _ = self.conn.execute_batch("VACUUM");
+        Ok(removed)
+    }
+}
@@ -0,0 +1,341 @@
+//! Signature file checks (`signatures lint`).
+//!
+//! A pattern that compiles can still be wrong: a short substring like
+//! "clue" matches the Clue period tracker, and `^.*` matches every window.
+//! Linting reads a bundle or any file of `[[signature]]` tables, keeping
+//! the position of every value so each problem is reported with its line,
+//! and tries each pattern against the names of common apps.
+
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use no_cluely_driver::{normalize, Pattern};
+
+/// Substrings this short or shorter match too many names to be safe
+const MIN_SUBSTRING_LENGTH: usize = 4;
+
+/// Owner names of apps that are not Cluely, any of which a pattern should
+/// leave alone
+const COMMON_OWNERS: &[&str] = &[
+    "Clue",
+    "Clueless",
+    "Finder",
+    "Safari",
+    "Google Chrome",
+    "Firefox",
+    "Microsoft Teams",
+    "zoom.us",
+    "Slack",
+    "Notes",
+    "Mail",
+    "Terminal",
+    "Code",
+    "Cursor",
+    "Notion",
+    "Obsidian",
+    "1Password",
+    "Spotify",
+    "Messages",
+    "Window Server",
+    "Dock",
+    "Control Center",
+];
+
+/// Bundle IDs of apps that are not Cluely
+const COMMON_BUNDLE_IDS: &[&str] = &[
+    "com.biowink.clue",
+    "com.apple.finder",
+    "com.apple.Safari",
+    "com.google.Chrome",
+    "org.mozilla.firefox",
+    "com.microsoft.teams2",
+    "us.zoom.xos",
+    "com.tinyspeck.slackmacgap",
+    "com.apple.Notes",
+    "com.microsoft.VSCode",
+    "notion.id",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file would be rejected
+    Error,
+    /// The file works, but likely not as intended
+    Warning,
+}
+
+/// One problem in the file
+#[derive(Debug, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// 1-based line the problem is on; None if the file could not be
+    /// parsed far enough to tell
+    pub line: Option<usize>,
+    /// Name of the signature the problem is in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub message: String,
+}
+
+/// What `signatures lint` found
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    pub signatures: usize,
+    pub issues: Vec<Issue>,
+}
+
+impl LintReport {
+    pub fn errors(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.issues.len() - self.errors()
+    }
+}
+
+/// The layout of a bundle, with every value's position kept
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignatureFile {
+    #[serde(default)]
+    #[allow(dead_code)] // Only checked for its type
+    version: Option<u64>,
+    #[serde(default)]
+    #[allow(dead_code)] // Only checked for its type
+    released: Option<NaiveDate>,
+    #[serde(default)]
+    signature: Vec<Spanned<SignatureEntry>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignatureEntry {
+    name: Spanned<String>,
+    owner: Option<Spanned<String>>,
+    title: Option<Spanned<String>>,
+    bundle_id: Option<Spanned<String>>,
+}
+
+/// Line of the byte at `offset` in `contents`
+fn line_of(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].matches('\n').count() + 1
+}
+
+struct Linter<'a> {
+    contents: &'a str,
+    issues: Vec<Issue>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, severity: Severity, span: Range<usize>, signature: &str, message: String) {
+        self.issues.push(Issue {
+            severity,
+            line: Some(line_of(self.contents, span.start)),
+            signature: Some(signature.to_string()),
+            message,
+        });
+    }
+
+    fn check_pattern(&mut self, signature: &str, field: &str, pattern: &Spanned<String>) {
+        let text = pattern.get_ref();
+        let span = pattern.span();
+        let compiled = match Pattern::parse(text) {
+            Ok(compiled) => compiled,
+            Err(e) => {
+                // The regex crate draws a caret diagram; the reason is last
+                let reason = e.message.lines().last().unwrap_or_default();
+                let reason = reason.trim_start_matches("error: ");
+                return self.report(
+                    Severity::Error,
+                    span,
+                    signature,
+                    format!("{} `{}` does not compile: {}", field, text, reason),
+                );
+            }
+        };
+
+        if let Pattern::Contains(needle) = &compiled {
+            if needle.chars().count() <= MIN_SUBSTRING_LENGTH {
+                self.report(
+                    Severity::Warning,
+                    span.clone(),
+                    signature,
+                    format!(
+                        "{} \"{}\" matches anything containing it; use a longer substring or an anchored regex like \"^{}$\"",
+                        field, text, text
+                    ),
+                );
+            }
+        }
+        if let Pattern::Regex(_) = &compiled {
+            self.check_regex_text(signature, field, text, span.clone());
+        }
+        if compiled.matches("") {
+            return self.report(
+                Severity::Warning,
+                span,
+                signature,
+                format!("{} `{}` matches every window", field, text),
+            );
+        }
+
+        let samples = match field {
+            "owner" => COMMON_OWNERS,
+            "bundle_id" => COMMON_BUNDLE_IDS,
+            _ => return,
+        };
+        let caught: Vec<&str> = samples
+            .iter()
+            .copied()
+            .filter(|sample| compiled.matches(&normalize::lower(sample)))
+            .collect();
+        if !caught.is_empty() {
+            self.report(
+                Severity::Warning,
+                span,
+                signature,
+                format!("{} \"{}\" also matches {}", field, text, caught.join(", ")),
+            );
+        }
+    }
+
+    /// Regexes are matched without lookalike folding (see `Pattern`): a
+    /// character normalizing changes never matches, and a lookalike from
+    /// another script only matches itself. Digits are left alone; `[0-9]`
+    /// means digits in a regex.
+    fn check_regex_text(&mut self, signature: &str, field: &str, text: &str, span: Range<usize>) {
+        let unnormalized: String = text
+            .chars()
+            .filter(|c| normalize::lower(&c.to_string()) != c.to_lowercase().to_string())
+            .collect();
+        if !unnormalized.is_empty() {
+            self.report(
+                Severity::Warning,
+                span.clone(),
+                signature,
+                format!(
+                    "{} `{}` contains \"{}\", which names are normalized away from, so that part never matches",
+                    field, text, unnormalized
+                ),
+            );
+        }
+
+        let mut lookalikes: Vec<char> = text
+            .chars()
+            .flat_map(char::to_lowercase)
+            .filter(|c| !c.is_ascii() && normalize::is_confusable(*c))
+            .collect();
+        lookalikes.sort_unstable();
+        lookalikes.dedup();
+        if !lookalikes.is_empty() {
+            let lookalikes: String = lookalikes.into_iter().collect();
+            self.report(
+                Severity::Warning,
+                span,
+                signature,
+                format!(
+                    "{} `{}` contains \"{}\", which looks like Latin letters; a regex only matches it literally, unlike a substring",
+                    field, text, lookalikes
+                ),
+            );
+        }
+    }
+
+    fn check_entry(&mut self, entry: &Spanned<SignatureEntry>, names: &mut HashSet<String>) {
+        let signature = entry.get_ref();
+        let name = signature.name.get_ref().as_str();
+        if !names.insert(name.to_string()) {
+            self.report(
+                Severity::Warning,
+                signature.name.span(),
+                name,
+                format!("Another signature is also named \"{}\"", name),
+            );
+        }
+
+        let fields = [
+            ("owner", &signature.owner),
+            ("title", &signature.title),
+            ("bundle_id", &signature.bundle_id),
+        ];
+        if fields.iter().all(|(_, pattern)| pattern.is_none()) {
+            self.report(
+                Severity::Error,
+                entry.span(),
+                name,
+                "needs an owner, title or bundle_id pattern".to_string(),
+            );
+            return;
+        }
+        if let (Some(title), None, None) =
+            (&signature.title, &signature.owner, &signature.bundle_id)
+        {
+            self.report(
+                Severity::Warning,
+                title.span(),
+                name,
+                "matches on the window title alone, so any document or web page with that title matches too; add an owner or bundle_id pattern".to_string(),
+            );
+        }
+        for (field, pattern) in fields {
+            if let Some(pattern) = pattern {
+                self.check_pattern(name, field, pattern);
+            }
+        }
+    }
+}
+
+/// Check the signature file at `path`
+pub fn lint(path: &Path) -> Result<LintReport, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: SignatureFile = match toml::from_str(&contents) {
+        Ok(file) => file,
+        Err(e) => {
+            return Ok(LintReport {
+                signatures: 0,
+                issues: vec![Issue {
+                    severity: Severity::Error,
+                    line: e.span().map(|span| line_of(&contents, span.start)),
+                    signature: None,
+                    message: e.message().to_string(),
+                }],
+            })
+        }
+    };
+
+    let mut linter = Linter {
+        contents: &contents,
+        issues: Vec::new(),
+    };
+    let mut names = HashSet::new();
+    for entry in &file.signature {
+        linter.check_entry(entry, &mut names);
+    }
+    if file.signature.is_empty() {
+        linter.issues.push(Issue {
+            severity: Severity::Warning,
+            line: None,
+            signature: None,
+            message: "The file has no [[signature]] tables".to_string(),
+        });
+    }
+
+    let mut issues = linter.issues;
+    issues.sort_by_key(|issue| issue.line);
+    Ok(LintReport {
+        signatures: file.signature.len(),
+        issues,
+    })
+}
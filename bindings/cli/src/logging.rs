@@ -0,0 +1,280 @@
+//! Diagnostic logging (`--log-level`, `--log-format`).
+//!
+//! Command output goes to stdout as before; warnings, scan timings and
+//! detection events from the CLI and the driver library go to stderr through
+//! `tracing`. JSON lines suit daemon deployments where logs are collected.
+//! Without `--log-level`, `RUST_LOG` is honoured, falling back to warnings only.
+//!
+//! `monitor --log-file` additionally appends every scan result as JSON lines
+//! to a file that is rotated daily or by size, keeping a bounded number of
+//! old files so a long-running monitor can't fill the disk.
+//!
+//! `--os-log` sends scan results and detection events to Apple's unified
+//! logging under the [`OS_LOG_SUBSYSTEM`] subsystem, where they can be
+//! queried with `log show` next to other endpoint telemetry.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local, NaiveDate};
+use clap::ValueEnum;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+/// Unified logging subsystem for `--os-log`
+pub const OS_LOG_SUBSYSTEM: &str = "io.nocluely";
+/// Unified logging category for `--os-log`
+const OS_LOG_CATEGORY: &str = "detection";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn directive(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// When `--log-file` starts a new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogRotation {
+    /// At the first write of each day; old files get the date appended
+    #[default]
+    Daily,
+    /// Once the file reaches `--log-max-size`; old files get .1, .2, … appended
+    Size,
+}
+
+/// A log file that rotates itself as it is written to
+pub struct RotatingFile {
+    path: PathBuf,
+    rotation: LogRotation,
+    max_bytes: u64,
+    /// Rotated files to keep
+    keep: usize,
+    file: File,
+    size: u64,
+    /// Day the current file was started, for daily rotation
+    day: NaiveDate,
+    /// The last write ended mid-line; a record is never split across files
+    mid_line: bool,
+}
+
+impl RotatingFile {
+    pub fn open(
+        path: PathBuf,
+        rotation: LogRotation,
+        max_bytes: u64,
+        keep: usize,
+    ) -> Result<Self, String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = append(&path)?;
+        let metadata = file
+            .metadata()
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // An existing file belongs to the day it was last written
+        let day = metadata
+            .modified()
+            .map(|modified| DateTime::<Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+
+        Ok(Self {
+            path,
+            rotation,
+            max_bytes,
+            keep,
+            file,
+            size: metadata.len(),
+            day,
+            mid_line: false,
+        })
+    }
+
+    fn needs_rotation(&self, incoming: usize) -> bool {
+        if self.size == 0 || self.mid_line {
+            return false;
+        }
+        match self.rotation {
+            LogRotation::Daily => Local::now().date_naive() != self.day,
+            LogRotation::Size => self.size + incoming as u64 > self.max_bytes,
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        match self.rotation {
+            LogRotation::Daily => {
+                fs::rename(&self.path, suffixed(&self.path, &self.day.to_string()))?;
+                self.prune_daily()?;
+            }
+            LogRotation::Size => {
+                // monitor.log.1 is the newest; shift the others up and drop the oldest
+                let _ = fs::remove_file(suffixed(&self.path, &self.keep.to_string()));
+                for index in (1..self.keep).rev() {
+                    let _ = fs::rename(
+                        suffixed(&self.path, &index.to_string()),
+                        suffixed(&self.path, &(index + 1).to_string()),
+                    );
+                }
+                if self.keep > 0 {
+                    fs::rename(&self.path, suffixed(&self.path, "1"))?;
+                } else {
+                    fs::remove_file(&self.path)?;
+                }
+            }
+        }
+
+        self.file = append(&self.path).map_err(io::Error::other)?;
+        self.size = 0;
+        self.day = Local::now().date_naive();
+        Ok(())
+    }
+
+    /// Delete dated files beyond the newest `keep`
+    fn prune_daily(&self) -> io::Result<()> {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return Ok(());
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+
+        let mut rotated: Vec<PathBuf> = fs::read_dir(dir)?
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .strip_prefix(&prefix)
+                    .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok())
+            })
+            .map(|entry| entry.path())
+            .collect();
+        // ISO dates sort chronologically
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.keep);
+        for path in &rotated[..excess] {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.needs_rotation(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        if written > 0 {
+            self.mid_line = buf[written - 1] != b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn append(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// `monitor.log` with `suffix` appended: `monitor.log.1`, `monitor.log.2024-06-28`
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Install the global subscriber. `default` applies when neither
+/// `--log-level` nor `RUST_LOG` is set. With `file`, scan results are also
+/// written there as JSON lines, and with `os_log` sent to unified logging,
+/// whatever the stderr level.
+pub fn init(
+    level: Option<LogLevel>,
+    format: LogFormat,
+    default: LogLevel,
+    file: Option<RotatingFile>,
+    os_log: bool,
+) {
+    let filter = match level {
+        Some(level) => EnvFilter::new(level.directive()),
+        None => EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| EnvFilter::new(default.directive())),
+    };
+
+    let stderr = match format {
+        LogFormat::Text => fmt::layer()
+            .with_writer(io::stderr)
+            .with_target(false)
+            .with_ansi(
+                io::stderr().is_terminal() && colored::control::SHOULD_COLORIZE.should_colorize(),
+            )
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_writer(io::stderr)
+            .boxed(),
+    }
+    .with_filter(filter);
+
+    let file = file.map(|file| {
+        fmt::layer()
+            .json()
+            .with_writer(Mutex::new(file))
+            .with_filter(EnvFilter::new(format!("{}=info", env!("CARGO_CRATE_NAME"))))
+    });
+
+    let os_log = os_log.then(|| {
+        tracing_oslog::OsLogger::new(OS_LOG_SUBSYSTEM, OS_LOG_CATEGORY).with_filter(EnvFilter::new(
+            format!("{}=info,warn", env!("CARGO_CRATE_NAME")),
+        ))
+    });
+
+    let _ = tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .with(os_log)
+        .try_init();
+}
@@ -1,12 +1,84 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
-use serde_json;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::process;
-use std::thread;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 // Import the detection functions from our Rust library
-use no_cluely_driver::{detect_cluely_rust as detect_cluely, ClueLyDetectionResult};
+use no_cluely_driver::{
+    analyze_displays_rust, audit_persistence_rust, detect_capture_evasion_rust,
+    detect_cluely_with_rust, get_cluely_binaries_rust, get_cluely_browser_tabs_rust, get_cluely_processes_rust, get_artifact_handles_rust, get_cluely_installations_rust, get_screen_recorders_rust, get_suspicious_processes_rust, has_screen_capture_access_rust,
+    scan_all_with_rust, scan_sessions_rust, verify_self_integrity_rust, ActivityLevel, ActivityMonitor, CaptureSignal, ClueLyDetectionResult,
+    DetectionError, DetectorConfig, DetectionEvent, EvasionScan, Finding, Monitor, Rule, SignatureSet, UserSession, WindowInfo, RULESET_VERSION,
+    WindowTracker,
+};
+
+// Human-readable output in every module goes through these, so `--plain`
+// can replace emoji in one place and `--agent` can keep stdout to records.
+// They shadow the std macros from here on; machine-readable output calls
+// `std::println!` directly.
+macro_rules! println {
+    () => { if !$crate::agent::is_enabled() { ::std::println!() } };
+    ($($arg:tt)*) => {
+        if !$crate::agent::is_enabled() {
+            ::std::println!("{}", $crate::plain::text(&::std::format!($($arg)*)))
+        }
+    };
+}
+
+macro_rules! eprintln {
+    () => { ::std::eprintln!() };
+    ($($arg:tt)*) => { ::std::eprintln!("{}", $crate::plain::text(&::std::format!($($arg)*))) };
+}
+
+mod agent;
+mod config;
+mod doctor;
+mod ecs;
+mod exit_code;
+mod export;
+mod health;
+mod history;
+mod lint;
+mod logging;
+#[cfg(target_os = "macos")]
+mod menubar;
+mod notify;
+#[cfg(target_os = "macos")]
+mod objc;
+mod osquery;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod output;
+mod plain;
+mod remove;
+mod report;
+mod rules;
+mod schedule;
+mod schema;
+mod screenshot;
+mod server;
+mod service;
+mod session;
+mod signatures;
+#[cfg(target_os = "macos")]
+mod simulate;
+mod snapshot;
+mod state;
+mod suppress;
+mod timestamp;
+mod trends;
+mod update;
+mod watchdog;
+#[cfg_attr(not(target_os = "macos"), path = "workspace_stub.rs")]
+mod workspace;
+
+use config::{MacosConfig, NotifierConfig, SoundConfig, WebhookConfig};
+use exit_code::{ExitPolicy, FailOn};
+use logging::{LogFormat, LogLevel, LogRotation};
+use output::{OutputFormat, StateChangeEvent};
 
 #[derive(Parser)]
 #[command(name = "cluely-detector")]
@@ -15,66 +87,936 @@ use no_cluely_driver::{detect_cluely_rust as detect_cluely, ClueLyDetectionResul
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Output format for check, report, stats, scan-all, ps and history
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Configuration file (default: ~/.config/cluely-detector/config.toml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Findings that make check, report, stats and scan-all exit non-zero [default: cluely]
+    #[arg(long, value_enum, global = true)]
+    fail_on: Option<FailOn>,
+
+    /// No colors or emoji; check, report, stats and scan-all print a single
+    /// key=value line. Repeat (-qq) to print nothing and rely on the exit code
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "format")]
+    quiet: u8,
+
+    /// Diagnostic log level on stderr [default: warn, or $RUST_LOG]
+    #[arg(long, value_enum, global = true)]
+    log_level: Option<LogLevel>,
+
+    /// Diagnostic log format
+    #[arg(long, value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Also send scan results and detection events to unified logging
+    /// (subsystem io.nocluely)
+    #[arg(long, global = true)]
+    os_log: bool,
+
+    /// ASCII markers instead of emoji and symbols; the default when the
+    /// locale isn't UTF-8
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Time zone for timestamps: local, UTC or an IANA name such as
+    /// Europe/Berlin; JSON timestamps stay RFC 3339 with its offset
+    #[arg(long, global = true, default_value = "UTC")]
+    timezone: timestamp::Zone,
+
+    /// strftime-style format for timestamps in text output, reports and
+    /// CSV exports [default: "%Y-%m-%d %H:%M:%S %Z"]
+    #[arg(long, global = true, value_parser = timestamp::parse_format)]
+    timestamp_format: Option<String>,
+
+    /// Fleet agent mode for check and monitor: print only NDJSON records
+    /// tagged with this host's name, serial number and user, for a log
+    /// shipper to collect and `aggregate` to summarize
+    #[arg(long, global = true, conflicts_with_all = ["format", "quiet"])]
+    agent: bool,
 }
 
+/// Set by `--quiet`; errors are printed without decoration
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// How often `monitor` prints the current status when nothing changes
+const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Subcommand)]
 enum Commands {
     /// Quick check if Cluely is running
-    Check,
+    Check {
+        /// Check every logged-in user's session, not just this one, and
+        /// report which account Cluely runs under (needs root)
+        #[arg(long)]
+        all_sessions: bool,
+        /// Also look for Cluely's web app in open Safari and Chrome tabs,
+        /// reported separately at low severity (asks for Automation access)
+        #[arg(long, conflicts_with = "all_sessions")]
+        browser_tabs: bool,
+    },
     /// Show detailed detection report
-    Report,
+    Report {
+        /// Write the report to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
     /// Monitor continuously for Cluely (Ctrl+C to stop)
     Monitor {
+        /// Check interval in seconds [default: 10]
+        #[arg(short, long)]
+        interval: Option<u64>,
+        /// Scan only at times matching this cron expression, e.g. "*/5 9-18 * * 1-5"
+        #[arg(long, value_name = "CRON", conflicts_with = "interval")]
+        schedule: Option<String>,
+        /// Longest gap between scans while nothing changes [default: 6 × interval]
+        #[arg(long, value_name = "SECS", conflicts_with = "schedule")]
+        max_interval: Option<u64>,
+        /// Seconds Cluely must stay gone before it is reported as stopped
+        #[arg(long, value_name = "SECS", default_value_t = 30)]
+        debounce: u64,
+        /// Scans in a row that must find Cluely before it is reported as
+        /// detected [default: 1]
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        confirm_after: Option<u32>,
+        /// Scans in a row that must find Cluely gone before it is reported
+        /// as stopped, on top of --debounce [default: 1]
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        clear_after: Option<u32>,
+        /// Only parse windows that appeared since the last scan, for lower
+        /// CPU use on systems with many windows
+        #[arg(long)]
+        incremental: bool,
+        /// Append every scan result to this file as JSON lines
+        #[arg(long, value_name = "PATH")]
+        log_file: Option<PathBuf>,
+        /// When to start a new log file
+        #[arg(long, value_enum, default_value_t = LogRotation::Daily, requires = "log_file")]
+        log_rotate: LogRotation,
+        /// Size in megabytes at which `--log-rotate size` starts a new file
+        #[arg(long, value_name = "MB", default_value_t = 10, requires = "log_file")]
+        log_max_size: u64,
+        /// Number of rotated log files to keep
+        #[arg(long, value_name = "N", default_value_t = 7, requires = "log_file")]
+        log_keep: usize,
+        /// POST the JSON detection payload to this URL on every state change
+        #[arg(long)]
+        webhook: Option<String>,
+        /// Sign webhook bodies with HMAC-SHA256 using this secret
+        #[arg(long, requires = "webhook")]
+        webhook_secret: Option<String>,
+        /// Send at most one notification per this many seconds, and none
+        /// repeating the last one's findings [default: 300]
+        #[arg(long, value_name = "SECS")]
+        coalesce: Option<u64>,
+        /// Show a macOS notification banner on every state change
+        #[arg(long)]
+        notify: bool,
+        /// Play a system sound (e.g. "Sosumi") or audio file when Cluely is detected
+        #[arg(long, value_name = "NAME|PATH")]
+        sound: Option<String>,
+        /// Don't record scans in the history database
+        #[arg(long)]
+        no_history: bool,
+        /// Only alert on windows, processes or files not in the saved baseline
+        #[arg(long)]
+        baseline: bool,
+        /// Report apps newly allowed Screen Recording or Input Monitoring
+        /// (needs Full Disk Access)
+        #[arg(long)]
+        watch_permissions: bool,
+        /// Answer GET /v1/health on this address, for `status` [default: 127.0.0.1:7878]
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = health::DEFAULT_ADDRESS)]
+        health: Option<String>,
+        /// Report it as tampering when another process terminates the
+        /// monitor, or kills it and launchd restarts it
+        #[arg(long)]
+        watchdog: bool,
+    },
+    /// Output detection results as JSON
+    Json,
+    /// Show detection statistics, with trends from the scan history
+    Stats {
+        /// Days of history to show trends for
+        #[arg(long, default_value_t = trends::DEFAULT_DAYS, value_parser = clap::value_parser!(u32).range(1..=history::MAX_DAYS))]
+        days: u32,
+    },
+    /// Print a Jamf Pro extension attribute result (DETECTED, CLEAN or ERROR)
+    JamfEa,
+    /// Print the JSON Schema for machine-readable output
+    Schema {
+        #[arg(value_enum, default_value = "detection")]
+        name: schema::SchemaName,
+    },
+    /// Run as an osquery extension providing the cluely_detections and
+    /// capture_evasion_windows tables
+    OsqueryExtension {
+        /// Path to the osquery extension manager socket
+        #[arg(long)]
+        socket: PathBuf,
+        /// Seconds to wait for the extension manager socket
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+        /// Seconds between liveness pings to osquery
+        #[arg(long, default_value_t = 3)]
+        interval: u64,
+        /// Log registration and shutdown to stderr (same as --log-level info)
+        #[arg(long)]
+        verbose: bool,
+    },
+    /// Show detection status in the macOS menu bar
+    Menubar {
         /// Check interval in seconds
         #[arg(short, long, default_value_t = 10)]
         interval: u64,
     },
-    /// Output detection results as JSON
-    Json,
-    /// Show detection statistics
-    Stats,
+    /// Serve detection results over a local HTTP API
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:7878")]
+        bind: String,
+        /// Scan interval in seconds
+        #[arg(short, long, default_value_t = 10)]
+        interval: u64,
+        /// Number of scans kept for /v1/history
+        #[arg(long, default_value_t = 1000)]
+        history_size: usize,
+        /// Value for the Access-Control-Allow-Origin header (for browser dashboards)
+        #[arg(long)]
+        allow_origin: Option<String>,
+        /// Don't record scans in the history database
+        #[arg(long)]
+        no_history: bool,
+    },
+    /// Show the health of a running `monitor --health` or `serve`, without scanning
+    Status {
+        /// Address the daemon answers health checks on
+        #[arg(long, value_name = "ADDR", default_value = health::DEFAULT_ADDRESS)]
+        address: String,
+    },
+    /// Scan every window, of any app, for screen sharing evasion techniques
+    ScanAll {
+        /// Also report Dock, menu bar, Finder and other system windows
+        #[arg(long)]
+        include_system: bool,
+        /// Flag apps named within this many edits of Cluely ("Cluey", "Culely")
+        /// as possible renamed instances, with low confidence
+        #[arg(long, value_name = "DISTANCE")]
+        fuzzy: Option<usize>,
+        /// Read each flagged window's buttons and text through the
+        /// Accessibility API, to identify untitled overlays (needs
+        /// Accessibility permission)
+        #[arg(long)]
+        inspect: bool,
+    },
+    /// List processes with windows hidden from capture, below the desktop or nearly transparent
+    Ps {
+        /// Only list processes showing this signal (repeatable)
+        #[arg(long, value_enum)]
+        signal: Vec<SignalFilter>,
+        /// Only list processes whose name contains this text (case-insensitive)
+        #[arg(long)]
+        name: Option<String>,
+        /// Also list Dock, menu bar, Finder and other system processes
+        #[arg(long)]
+        include_system: bool,
+    },
+    /// List apps allowed to record the screen, and which of them are running now
+    Recorders,
+    /// List processes holding Cluely files open, which gives them away under any name
+    Handles {
+        /// Look at every process, not only Cluely's and those with suspicious windows
+        #[arg(long)]
+        all: bool,
+    },
+    /// Show which display each Cluely window is on, and flag any kept off the shared screen
+    Displays {
+        /// ID of the display being shared, if the meeting app's sharing
+        /// toolbar can't be seen (see the IDs this command lists)
+        #[arg(long, value_name = "ID")]
+        shared: Option<u32>,
+    },
+    /// Save an annotated PNG of each detected Cluely window
+    Screenshot {
+        /// Directory to save the images in
+        #[arg(short, long, default_value = ".")]
+        out: PathBuf,
+    },
+    /// Record Cluely windows, processes and files on disk as JSON for `diff`
+    Snapshot {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Compare two snapshots, or a snapshot with the current state
+    Diff {
+        /// BEFORE and AFTER snapshot files; AFTER defaults to a fresh scan
+        #[arg(value_name = "SNAPSHOT", num_args = 0..=2)]
+        files: Vec<PathBuf>,
+        /// Use the saved baseline as BEFORE
+        #[arg(long)]
+        baseline: bool,
+    },
+    /// Summarize `--agent` output collected from many Macs, one line per
+    /// host from its latest record
+    Aggregate {
+        /// NDJSON files written by `--agent`; "-" reads standard input
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Export rules that block the Cluely binaries running now, for other
+    /// security tools (--format santa or yara), or STIX indicators for
+    /// threat-intel platforms (--format stix)
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Santa: block only the exact builds seen (CDHash), not everything
+        /// signed with the same Team ID
+        #[arg(long)]
+        cdhash: bool,
+    },
+    /// Manage the known-good snapshot used by `monitor --baseline` and `diff --baseline`
+    Baseline {
+        #[command(subcommand)]
+        action: BaselineAction,
+    },
+    /// List Cluely files machine-wide and in every user's home directory (needs root)
+    Audit,
+    /// Check that this machine can run scans reliably and suggest fixes
+    Doctor,
+    /// Start a fake Cluely (a process plus a window hidden from screen capture
+    /// above normal windows) and check that it is detected
+    Simulate {
+        /// Leave the fake Cluely running this many seconds after the check,
+        /// to try other tools against it
+        #[arg(long, default_value_t = 0, value_name = "SECS")]
+        hold: u64,
+        /// Only start the process, without a window
+        #[arg(long)]
+        no_window: bool,
+        /// Run as the fake Cluely itself, quitting after SECS
+        #[arg(long, hide = true, value_name = "SECS")]
+        host: Option<u64>,
+    },
+    /// Update cluely-detector to the latest signed GitHub release
+    SelfUpdate {
+        /// Only report whether an update is available
+        #[arg(long)]
+        check: bool,
+        /// Reinstall the latest release even if it is the running version; an
+        /// older one is never installed
+        #[arg(long, conflicts_with = "check")]
+        force: bool,
+    },
+    /// List the detection rules, explain one, or test signatures against a
+    /// recorded window dump
+    Rules {
+        #[command(subcommand)]
+        action: RulesAction,
+    },
+    /// Update the Cluely signatures from a signed bundle, without a new release
+    Signatures {
+        #[command(subcommand)]
+        action: SignaturesAction,
+    },
+    /// Quit Cluely and delete its launch agents, app bundle and support files
+    Remove {
+        /// List what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation before each step
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Browse or purge recorded scan history
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Manage the background monitoring LaunchAgent
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+/// Window signals `ps` can filter on
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SignalFilter {
+    /// sharing_state 0: the window opts out of screen sharing
+    SharingDisabled,
+    /// Window layer below zero
+    NegativeLayer,
+    /// Alpha below 0.5
+    LowAlpha,
+}
+
+impl From<SignalFilter> for CaptureSignal {
+    fn from(filter: SignalFilter) -> Self {
+        match filter {
+            SignalFilter::SharingDisabled => CaptureSignal::SharingDisabled,
+            SignalFilter::NegativeLayer => CaptureSignal::NegativeLayer,
+            SignalFilter::LowAlpha => CaptureSignal::LowAlpha,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum BaselineAction {
+    /// Snapshot this machine's current state as the baseline
+    Save,
+    /// Print the saved baseline
+    Show,
+    /// Delete the saved baseline
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum RulesAction {
+    /// List every rule that can fire for a Cluely window
+    List,
+    /// Explain what a rule checks
+    Explain {
+        /// Rule ID from `rules list`, e.g. screen-capture-evasion
+        id: String,
+    },
+    /// Run the rules over a JSON window dump instead of the live window
+    /// list, exiting 1 if a window's `expect_cluely` is not met
+    Test {
+        /// Array of windows, or a document with a `windows` array such as a
+        /// snapshot
+        #[arg(value_name = "FIXTURE")]
+        fixture: PathBuf,
+        /// Match with the built-in signatures plus the `[[signature]]`
+        /// entries of this file, instead of the signatures in use
+        #[arg(long, value_name = "FILE")]
+        signatures: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SignaturesAction {
+    /// Fetch the latest signature bundle and install it if its signature
+    /// verifies and it is newer than the installed one
+    Update {
+        /// Fetch from this URL instead of the configured one
+        #[arg(long)]
+        url: Option<String>,
+        /// Install the bundle even if it is not newer
+        #[arg(long)]
+        force: bool,
+        /// Check the bundle without installing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Go back to the bundle installed before the last update
+    Rollback,
+    /// Show the signatures in use: the installed bundle's version, release
+    /// date and checksum, and configured signatures
+    Show,
+    /// Copy the installed bundle and its signature (FILE.sig) to FILE, to
+    /// import on a machine without internet access
+    Export {
+        file: PathBuf,
+    },
+    /// Install a bundle exported by another machine or downloaded by hand,
+    /// checking it like `update`; its signature must be in FILE.sig
+    Import {
+        file: PathBuf,
+        /// Install the bundle even if it is not newer
+        #[arg(long)]
+        force: bool,
+        /// Check the bundle without installing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check a signature file for schema and pattern errors and for
+    /// patterns likely to match apps other than Cluely, exiting 1 on errors
+    Lint {
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// List recent scans, newest first
+    List {
+        /// Maximum number of scans to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// Only show scans where Cluely was detected
+        #[arg(long)]
+        detected: bool,
+    },
+    /// Reconstruct periods of Cluely activity from recorded scans
+    Timeline {
+        /// Start of the period (YYYY-MM-DD, 'YYYY-MM-DD HH:MM' or RFC 3339, UTC)
+        #[arg(long, value_parser = |value: &str| history::parse_time(value, false))]
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        /// End of the period; a bare date includes that whole day
+        #[arg(long, value_parser = |value: &str| history::parse_time(value, true))]
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        /// Longest gap between scans, in seconds, before a session is split
+        /// because monitoring was not running
+        #[arg(long, default_value_t = 300)]
+        max_gap: i64,
+    },
+    /// Export recorded scans or detection episodes as CSV (the default) or
+    /// JSON, for analysts and spreadsheets
+    Export {
+        /// Start of the period (YYYY-MM-DD, 'YYYY-MM-DD HH:MM' or RFC 3339, UTC)
+        #[arg(long, value_parser = |value: &str| history::parse_time(value, false))]
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        /// End of the period; a bare date includes that whole day
+        #[arg(long, value_parser = |value: &str| history::parse_time(value, true))]
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        /// What each row is
+        #[arg(long, value_enum, default_value = "episodes")]
+        rows: ExportRows,
+        /// Longest gap between scans, in seconds, before an episode is split
+        /// because monitoring was not running
+        #[arg(long, default_value_t = 300)]
+        max_gap: i64,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+    },
+    /// Show one scan in full
+    Show {
+        /// Scan id from `history list`
+        id: i64,
+    },
+    /// Delete recorded scans
+    Purge {
+        /// Delete scans older than this many days
+        #[arg(long, value_name = "DAYS", required_unless_present = "all", value_parser = clap::value_parser!(u32).range(..=history::MAX_DAYS))]
+        older_than: Option<u32>,
+        /// Delete every recorded scan
+        #[arg(long, conflicts_with = "older_than")]
+        all: bool,
+    },
+}
+
+/// Rows of `history export`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportRows {
+    /// One row per detection episode, with its evidence
+    Episodes,
+    /// One row per recorded scan
+    Scans,
+}
+
+#[derive(Subcommand)]
+enum ServiceAction {
+    /// Install and start a LaunchAgent that runs `monitor` at login
+    Install {
+        /// Check interval in seconds [default: 10]
+        #[arg(short, long)]
+        interval: Option<u64>,
+        /// Scan only at times matching this cron expression, e.g. "*/5 9-18 * * 1-5"
+        #[arg(long, value_name = "CRON", conflicts_with = "interval")]
+        schedule: Option<String>,
+        /// Run the monitor with --watchdog, reporting it whenever another
+        /// process stops it (launchd restarts it either way)
+        #[arg(long)]
+        watchdog: bool,
+    },
+    /// Stop the LaunchAgent and remove it
+    Uninstall,
+    /// Show whether the LaunchAgent is installed and running
+    Status,
 }
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = Cli::try_parse().unwrap_or_else(|e| {
+        let _ = e.print();
+        process::exit(if e.use_stderr() {
+            exit_code::USAGE
+        } else {
+            exit_code::SUCCESS
+        })
+    });
+
+    if cli.quiet > 0 {
+        QUIET.store(true, Ordering::Relaxed);
+        colored::control::set_override(false);
+    }
+    if cli.agent {
+        if !matches!(cli.command, None | Some(Commands::Check { all_sessions: false, .. } | Commands::Monitor { .. })) {
+            fail("--agent works with check and monitor".to_string());
+        }
+        agent::enable();
+        colored::control::set_override(false);
+    }
+    if cli.plain || cli.quiet > 0 || plain::detect() {
+        plain::enable();
+    }
+    timestamp::configure(cli.timezone, cli.timestamp_format.clone());
+    let default_log_level = match &cli.command {
+        _ if cli.quiet > 0 => LogLevel::Error,
+        Some(Commands::OsqueryExtension { verbose: true, .. }) => LogLevel::Info,
+        _ => LogLevel::Warn,
+    };
+    let log_file = match &cli.command {
+        Some(Commands::Monitor {
+            log_file: Some(path),
+            log_rotate,
+            log_max_size,
+            log_keep,
+            ..
+        }) => Some(
+            logging::RotatingFile::open(
+                path.clone(),
+                *log_rotate,
+                log_max_size.saturating_mul(1024 * 1024),
+                *log_keep,
+            )
+            .unwrap_or_else(|e| fail(e)),
+        ),
+        _ => None,
+    };
+    logging::init(
+        cli.log_level,
+        cli.log_format,
+        default_log_level,
+        log_file,
+        cli.os_log,
+    );
+    for problem in self_integrity_problems() {
+        tracing::error!(problem = %problem, "detector integrity check failed");
+    }
+    if cli.quiet > 0 {
+        run_quiet(&cli);
+    }
 
     match &cli.command {
-        Some(Commands::Check) => cmd_check(),
-        Some(Commands::Report) => cmd_report(),
-        Some(Commands::Monitor { interval }) => cmd_monitor(*interval),
-        Some(Commands::Json) => cmd_json(),
-        Some(Commands::Stats) => cmd_stats(),
+        Some(Commands::Check { all_sessions: true, .. }) => cmd_check_sessions(cli.format, &exit_policy(&cli), &detector_config(&cli)),
+        Some(Commands::Check { all_sessions: false, browser_tabs }) => {
+            cmd_check(cli.format, &exit_policy(&cli), &detector_config(&cli), *browser_tabs)
+        }
+        Some(Commands::Report { out }) => {
+            cmd_report(cli.format, out.as_deref(), &exit_policy(&cli), &detector_config(&cli))
+        }
+        Some(Commands::Monitor {
+            interval,
+            schedule,
+            max_interval,
+            debounce,
+            confirm_after,
+            clear_after,
+            incremental,
+            webhook,
+            webhook_secret,
+            coalesce,
+            notify,
+            sound,
+            no_history,
+            baseline,
+            watch_permissions,
+            health,
+            watchdog,
+            ..
+        }) => {
+            let mut config = load_config(cli.config.as_deref());
+            if let Some(url) = webhook {
+                config.notifiers.push(NotifierConfig::Webhook(WebhookConfig {
+                    url: url.clone(),
+                    secret: webhook_secret.clone(),
+                    retries: config::DEFAULT_RETRIES,
+                }).into());
+            }
+            if *notify {
+                config.notifiers.push(NotifierConfig::Macos(MacosConfig::default()).into());
+            }
+            if let Some(sound) = sound {
+                config.notifiers.push(NotifierConfig::Sound(SoundConfig {
+                    sound: sound.clone(),
+                }).into());
+            }
+            let notifiers = notify::from_config(&config.notifiers).unwrap_or_else(|e| fail(e));
+            let detector = config.detector().unwrap_or_else(|e| fail(e));
+            let mut monitor = if *incremental {
+                Monitor::incremental(detector)
+            } else {
+                Monitor::with_config(detector)
+            };
+            if *watch_permissions || config.watch_permissions {
+                monitor.watch_permissions();
+            }
+            let coalesce = coalesce
+                .or(config.coalesce)
+                .unwrap_or(notify::DEFAULT_COALESCE);
+            let history = if *no_history { None } else { open_history() };
+            let baseline = baseline
+                .then(|| snapshot::load_baseline().unwrap_or_else(|e| fail(e)));
+            // --interval and --max-interval override a schedule from the configuration file
+            let interval_given = interval.is_some() || max_interval.is_some();
+            let interval = Duration::from_secs(interval.unwrap_or(schedule::DEFAULT_INTERVAL).max(1));
+            let schedule = match schedule.as_ref().or(config.schedule.as_ref()) {
+                Some(expression) if !interval_given => {
+                    schedule::Schedule::cron(expression).unwrap_or_else(|e| fail(e))
+                }
+                _ => schedule::Schedule::Every(interval),
+            };
+            let max_interval = max_interval
+                .map(Duration::from_secs)
+                .unwrap_or(interval * schedule::DEFAULT_BACKOFF_FACTOR);
+            let state = open_monitor_state(baseline.is_some());
+            let health = health.as_deref().and_then(|address| {
+                let shared = std::sync::Arc::new(std::sync::Mutex::new(health::Health::new("monitor")));
+                // A taken port shouldn't stop the monitor itself, least of all as a service
+                match health::listen(address, std::sync::Arc::clone(&shared)) {
+                    Ok(()) => Some(shared),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "health endpoint unavailable");
+                        None
+                    }
+                }
+            });
+            cmd_monitor(
+                schedule::Pacer::new(schedule, max_interval),
+                Duration::from_secs(*debounce),
+                confirm_after.or(config.confirm_after).unwrap_or(1).max(1),
+                clear_after.or(config.clear_after).unwrap_or(1).max(1),
+                notify::Dispatcher::new(notifiers, Duration::from_secs(coalesce)),
+                monitor,
+                history,
+                baseline,
+                state,
+                health,
+                *watchdog,
+            );
+        }
+        Some(Commands::Json) => cmd_json(&exit_policy(&cli), &detector_config(&cli)),
+        Some(Commands::Stats { days }) => cmd_stats(*days, cli.format, &exit_policy(&cli), &detector_config(&cli)),
+        Some(Commands::JamfEa) => cmd_jamf_ea(&detector_config(&cli)),
+        Some(Commands::Schema { name }) => cmd_schema(*name),
+        Some(Commands::OsqueryExtension {
+            socket,
+            timeout,
+            interval,
+            ..
+        }) => cmd_osquery_extension(socket, *timeout, *interval, detector_config(&cli)),
+        #[cfg(target_os = "macos")]
+        Some(Commands::Menubar { interval }) => menubar::run(*interval),
+        #[cfg(not(target_os = "macos"))]
+        Some(Commands::Menubar { .. }) => unsupported_platform("menubar"),
+        Some(Commands::Serve {
+            bind,
+            interval,
+            history_size,
+            allow_origin,
+            no_history,
+        }) => cmd_serve(server::ServeOptions {
+            bind: bind.clone(),
+            interval: *interval,
+            history_size: *history_size,
+            allow_origin: allow_origin.clone(),
+            history: if *no_history { None } else { open_history() },
+            detector: detector_config(&cli),
+        }),
+        Some(Commands::Status { address }) => cmd_status(address, cli.format),
+        Some(Commands::ScanAll {
+            include_system,
+            fuzzy,
+            inspect,
+        }) => {
+            let detector = DetectorConfig {
+                fuzzy_max_distance: *fuzzy,
+                inspect_content: *inspect,
+                ..detector_config(&cli)
+            };
+            cmd_scan_all(&detector, *include_system, cli.format, &exit_policy(&cli))
+        }
+        Some(Commands::Ps {
+            signal,
+            name,
+            include_system,
+        }) => cmd_ps(signal, name.as_deref(), *include_system, cli.format),
+        Some(Commands::Recorders) => cmd_recorders(cli.format),
+        Some(Commands::Handles { all }) => cmd_handles(*all, cli.format),
+        Some(Commands::Displays { shared }) => cmd_displays(*shared, cli.format),
+        Some(Commands::Screenshot { out }) => cmd_screenshot(out, cli.format),
+        Some(Commands::Snapshot { out }) => cmd_snapshot(out.as_deref()),
+        Some(Commands::Diff { files, baseline }) => cmd_diff(files, *baseline, cli.format),
+        Some(Commands::Aggregate { files }) => cmd_aggregate(files, cli.format),
+        Some(Commands::Export { out, cdhash }) => cmd_export(out.as_deref(), *cdhash, cli.format),
+        Some(Commands::Baseline { action }) => cmd_baseline(action, cli.format),
+        Some(Commands::Audit) => cmd_audit(cli.format),
+        Some(Commands::Doctor) => cmd_doctor(cli.config.as_deref(), cli.format),
+        #[cfg(target_os = "macos")]
+        Some(Commands::Simulate { host: Some(lifetime), no_window, .. }) => {
+            simulate::host(!no_window, Duration::from_secs(*lifetime))
+        }
+        #[cfg(target_os = "macos")]
+        Some(Commands::Simulate { hold, no_window, host: None }) => cmd_simulate(*hold, *no_window, cli.format),
+        #[cfg(not(target_os = "macos"))]
+        Some(Commands::Simulate { .. }) => unsupported_platform("simulate"),
+        Some(Commands::SelfUpdate { check, force }) => cmd_self_update(*check, *force),
+        Some(Commands::Rules { action }) => cmd_rules(action, &cli),
+        Some(Commands::Signatures { action }) => cmd_signatures(action, cli.config.as_deref(), cli.format),
+        Some(Commands::Remove { dry_run, yes }) => {
+            let remaining = remove::run(&remove::RemoveOptions {
+                dry_run: *dry_run,
+                assume_yes: *yes,
+            });
+            process::exit(if remaining { 1 } else { 0 });
+        }
+        Some(Commands::History { action }) => cmd_history(action, cli.format),
+        Some(Commands::Service { action }) => cmd_service(action, cli.config.as_deref(), cli.os_log),
         None => {
             // Default behavior - quick check
-            cmd_check();
+            cmd_check(cli.format, &exit_policy(&cli), &detector_config(&cli), false);
         }
     }
 }
 
-fn cmd_check() {
+fn cmd_check(format: OutputFormat, policy: &ExitPolicy, detector: &DetectorConfig, browser_tabs: bool) {
+    let result = detect_or_exit(policy, detector);
+    let tabs = browser_tabs.then(get_cluely_browser_tabs_rust);
+    // The window list was just read, so this only fails if the session ended
+    let installations = get_cluely_installations_rust().unwrap_or_default();
+
+    let mut payload = output::DetectionPayload::from_result(&result);
+    payload.browser_tabs = tabs
+        .as_ref()
+        .map(|tabs| tabs.iter().map(output::BrowserTabPayload::from_tab).collect());
+    payload.installations = Some(installations.iter().map(output::InstallationPayload::from_installation).collect());
+    if agent::is_enabled() {
+        agent::emit("scan", payload);
+        exit_with_outcome(policy, detector, result.is_detected);
+    }
+    if output::print_detection(&payload, format) {
+        exit_with_outcome(policy, detector, result.is_detected);
+    }
+
     println!("{}", "🎯 Cluely Detection".bold().blue());
     println!("{}", "=================".blue());
     println!();
 
-    let result = detect_cluely();
-    
     if result.is_detected {
         println!("{}", "🚨 CLUELY DETECTED".bold().red());
         println!("{}", "Employee monitoring software is running on this system.".red());
         println!();
         println!("{}", "💡 Use 'cluely-detector report' for detailed analysis".yellow());
-        process::exit(1);
     } else {
         println!("{}", "✅ NO CLUELY DETECTED".bold().green());
         println!("{}", "No employee monitoring software found.".green());
-        process::exit(0);
     }
+
+    if !installations.is_empty() {
+        println!();
+        for installation in &installations {
+            let bundle = installation
+                .bundle
+                .as_ref()
+                .map_or_else(|| "Outside any app bundle".to_string(), |bundle| bundle.display().to_string());
+            println!("📦 {}: {}", bundle.bold(), installation.describe());
+            for process in &installation.processes {
+                println!("     {:>7}  {:<15} {}", process.pid, process.role.label(), process.name.dimmed());
+            }
+            if installation.windowless() {
+                println!(
+                    "{}",
+                    "   ⚠️  No windows, but Cluely is still running; closing its windows did not quit it".yellow()
+                );
+            }
+        }
+    }
+
+    if let Some(tabs) = tabs.filter(|tabs| !tabs.is_empty()) {
+        println!();
+        println!(
+            "{}",
+            format!("🌐 Cluely web app open in {} browser tab(s) (low severity)", tabs.len()).yellow()
+        );
+        for tab in &tabs {
+            println!("   {:<14} {}  {}", tab.browser.label(), tab.url, tab.title.dimmed());
+        }
+    }
+    exit_with_outcome(policy, detector, result.is_detected);
+}
+
+fn cmd_check_sessions(format: OutputFormat, policy: &ExitPolicy, detector: &DetectorConfig) {
+    let sessions = scan_sessions_or_exit(policy);
+    let detected = sessions.iter().any(|session| session.cluely_detected());
+
+    let payload: Vec<_> = sessions.iter().map(output::SessionPayload::from_session).collect();
+    if output::print_document(&payload, format) {
+        exit_with_outcome(policy, detector, detected);
+    }
+
+    println!("{}", "🎯 Cluely Detection (All Sessions)".bold().blue());
+    println!("{}", "=================================".blue());
+    println!();
+
+    if sessions.is_empty() {
+        println!("{}", "No users are logged in.".yellow());
+    }
+    for session in &sessions {
+        let user = if session.user.is_empty() {
+            format!("uid {}", session.uid)
+        } else {
+            format!("{} (uid {})", session.user, session.uid)
+        };
+        let state = match (session.on_console, session.logged_in) {
+            (true, _) => "on screen",
+            (false, true) => "switched out",
+            (false, false) => "not logged in",
+        };
+        if session.cluely_detected() {
+            println!("{} {} {}", "🚨".red(), user.bold().red(), format!("[{}]", state).dimmed());
+            for process in &session.cluely_processes {
+                println!("     {:>7}  {}", process.pid, process.path.yellow());
+                let chain = output::ProcessPayload::from_process(process).chain();
+                println!("              {}", format!("{} ({})", chain, process.origin.describe()).dimmed());
+            }
+        } else {
+            println!("{} {} {}", "✅".green(), user.bold(), format!("[{}]", state).dimmed());
+        }
+    }
+
+    println!();
+    if detected {
+        println!("{}", "🚨 CLUELY DETECTED".bold().red());
+    } else {
+        println!("{}", "✅ NO CLUELY DETECTED IN ANY SESSION".bold().green());
+    }
+    exit_with_outcome(policy, detector, detected);
 }
 
-fn cmd_report() {
+fn cmd_report(format: OutputFormat, out: Option<&Path>, policy: &ExitPolicy, detector: &DetectorConfig) {
+    if report::is_document(format) {
+        let data = report::ReportData::collect(format == OutputFormat::Html);
+        let document = report::render(&data, format).unwrap_or_else(|e| fail(e));
+        match out {
+            Some(path) => {
+                std::fs::write(path, document)
+                    .unwrap_or_else(|e| fail(format!("Failed to write {}: {}", path.display(), e)));
+                println!("{}", format!("📄 Report saved to {}", path.display()).green());
+            }
+            None => {
+                let _ = std::io::Write::write_all(&mut std::io::stdout(), &document);
+            }
+        }
+        exit_with_outcome(policy, detector, data.scan.detection.detected);
+    }
+    if out.is_some() {
+        fail("--out is only supported with --format html, markdown or pdf".to_string());
+    }
+
     // Generate detailed report using the same logic as the C function
-    let result = detect_cluely();
-    
+    let result = detect_or_exit(policy, detector);
+
+    if output::print_payload(&result, format) {
+        exit_with_outcome(policy, detector, result.is_detected);
+    }
+
     let report = if result.is_detected {
         format!(
             "🚨 CLUELY EMPLOYEE MONITORING DETECTED\n\
@@ -118,11 +1060,210 @@ fn cmd_report() {
     };
     
     println!("{}", report);
+    exit_with_outcome(policy, detector, result.is_detected);
+}
+
+/// Print an error to stderr, undecorated in quiet mode
+fn print_error(message: &str) {
+    if QUIET.load(Ordering::Relaxed) {
+        eprintln!("error: {}", message);
+    } else {
+        eprintln!("{}", format!("❌ {}", message).red());
+    }
+}
+
+/// Print an error and exit with a failure status
+fn fail(message: String) -> ! {
+    print_error(&message);
+    process::exit(exit_code::ERROR);
+}
+
+/// Fail a command that needs AppKit
+#[cfg(not(target_os = "macos"))]
+fn unsupported_platform(command: &str) -> ! {
+    fail(format!("{} is unsupported on this platform", command))
+}
+
+/// Run the `--quiet` form of a detection command and exit. Returns for
+/// commands without one, which then run normally, just without colors.
+fn run_quiet(cli: &Cli) {
+    let print = cli.quiet == 1;
+    match &cli.command {
+        None
+        | Some(Commands::Check { all_sessions: false, .. } | Commands::Stats { .. } | Commands::Report { out: None }) => {
+            let policy = exit_policy(cli);
+            let detector = detector_config(cli);
+            let result = detect_or_exit(&policy, &detector);
+            if print {
+                let mut payload = output::DetectionPayload::from_result(&result);
+                if let Some(Commands::Check { browser_tabs: true, .. }) = &cli.command {
+                    payload.browser_tabs = Some(
+                        get_cluely_browser_tabs_rust()
+                            .iter()
+                            .map(output::BrowserTabPayload::from_tab)
+                            .collect(),
+                    );
+                }
+                if let None | Some(Commands::Check { .. }) = &cli.command {
+                    payload.installations = Some(
+                        get_cluely_installations_rust()
+                            .unwrap_or_default()
+                            .iter()
+                            .map(output::InstallationPayload::from_installation)
+                            .collect(),
+                    );
+                }
+                println!("{}", output::summary_line(&payload));
+            }
+            exit_with_outcome(&policy, &detector, result.is_detected);
+        }
+        Some(Commands::Check { all_sessions: true, .. }) => {
+            let policy = exit_policy(cli);
+            let sessions = scan_sessions_or_exit(&policy);
+            if print {
+                let users: Vec<&str> = sessions
+                    .iter()
+                    .filter(|session| session.cluely_detected())
+                    .map(|session| session.user.as_str())
+                    .collect();
+                println!(
+                    "detected={} sessions={} users={}",
+                    !users.is_empty(),
+                    sessions.iter().filter(|session| session.logged_in).count(),
+                    users.join(",")
+                );
+            }
+            exit_with_outcome(&policy, &detector_config(cli), sessions.iter().any(|session| session.cluely_detected()));
+        }
+        Some(Commands::ScanAll {
+            include_system,
+            fuzzy,
+            ..
+        }) => {
+            let policy = exit_policy(cli);
+            let detector = DetectorConfig {
+                fuzzy_max_distance: *fuzzy,
+                ..detector_config(cli)
+            };
+            let (scan, suppressed) = scan_all_or_exit(&detector, *include_system, &policy);
+            if print {
+                println!(
+                    "cluely={} evasion={} flagged={} windows={} suppressed={}",
+                    scan.cluely_detected(),
+                    scan.evasion_detected(),
+                    scan.findings.len(),
+                    scan.windows.len(),
+                    suppressed.len()
+                );
+            }
+            process::exit(policy.code(scan.cluely_detected(), scan.evasion_detected()));
+        }
+        _ => {}
+    }
+}
+
+fn exit_policy(cli: &Cli) -> ExitPolicy {
+    let config = load_config(cli.config.as_deref());
+    // Shortcuts stops at a "Run Shell Script" action that exits non-zero, so
+    // the result is in the line unless --fail-on asks for the codes as well
+    let fail_on = match cli.format {
+        OutputFormat::Shortcuts => cli.fail_on.unwrap_or(FailOn::None),
+        _ => cli.fail_on.or(config.fail_on).unwrap_or_default(),
+    };
+    ExitPolicy {
+        fail_on,
+        codes: config.exit_codes,
+    }
+}
+
+/// The built-in signatures plus any `[[signature]]` entries, exiting if a
+/// pattern does not compile
+fn detector_config(cli: &Cli) -> DetectorConfig {
+    load_config(cli.config.as_deref())
+        .detector()
+        .unwrap_or_else(|e| fail(e))
+}
+
+/// Scan every user's session, exiting with the permission code if not root
+fn scan_sessions_or_exit(policy: &ExitPolicy) -> Vec<UserSession> {
+    scan_sessions_rust().unwrap_or_else(|e| {
+        tracing::error!(error = %e, "session scan failed");
+        if e == DetectionError::PermissionDenied {
+            print_error("--all-sessions needs root; run it with sudo");
+        } else {
+            print_error(&e.to_string());
+        }
+        process::exit(policy.codes.permission);
+    })
+}
+
+/// Scan for Cluely, exiting with the permission code if the window list cannot be read
+fn detect_or_exit(policy: &ExitPolicy, detector: &DetectorConfig) -> ClueLyDetectionResult {
+    let (result, _) = detect_cluely_with_rust(detector).unwrap_or_else(|e| {
+        tracing::error!(error = %e, "scan failed");
+        print_error(&e.to_string());
+        process::exit(policy.codes.permission);
+    });
+    tracing::info!(
+        detected = result.is_detected,
+        windows = result.window_count,
+        screen_capture_evasion = result.screen_capture_evasion_count,
+        elevated_layer = result.elevated_layer_count,
+        "scan"
+    );
+    result
+}
+
+/// Exit with the code the contract assigns to this outcome. Runs the generic
+/// evasion scan first if `--fail-on any` needs it.
+fn exit_with_outcome(policy: &ExitPolicy, detector: &DetectorConfig, cluely_detected: bool) -> ! {
+    let evasion_detected = !cluely_detected
+        && policy.needs_evasion_scan()
+        && scan_all_with_rust(detector, false).is_ok_and(|scan| scan.evasion_detected());
+    process::exit(policy.code(cluely_detected, evasion_detected));
+}
+
+fn load_config(path: Option<&Path>) -> config::Config {
+    config::load(path).unwrap_or_else(|e| fail(e))
 }
 
-fn cmd_monitor(interval: u64) {
+/// How `monitor` scans: in full each time, or only the windows that changed
+#[allow(clippy::too_many_arguments)]
+fn cmd_monitor(
+    mut pacer: schedule::Pacer,
+    debounce: Duration,
+    confirm_after: u32,
+    clear_after: u32,
+    dispatcher: notify::Dispatcher,
+    mut monitor: Monitor,
+    history: Option<history::History>,
+    baseline: Option<snapshot::Snapshot>,
+    mut state: Option<state::StateTracker>,
+    health: Option<health::SharedHealth>,
+    watchdog: bool,
+) {
     println!("{}", "🔍 Monitoring for Cluely (Press Ctrl+C to stop)".bold().blue());
     println!("{}", "=============================================".blue());
+    println!("{}", format!("Scanning {}", pacer.describe()).blue());
+    if baseline.is_some() {
+        println!("{}", "Alerting only on deviations from the saved baseline".blue());
+    }
+    if watchdog {
+        println!("{}", "Watchdog on: reporting it if another process stops this monitor".blue());
+    }
+    if confirm_after > 1 || clear_after > 1 {
+        println!("{}", format!(
+            "Reporting Cluely after {} scan(s) in a row find it, and gone after {} find it gone",
+            confirm_after, clear_after
+        ).blue());
+    }
+    let resumed = state.as_ref().is_some_and(|state| state.state.detected);
+    if let Some(since) = state.as_ref().and_then(|state| state.state.state_since).filter(|_| resumed) {
+        println!("{}", format!(
+            "Resuming: Cluely was detected when monitoring last ran (since {})",
+            timestamp::display(since)
+        ).yellow());
+    }
     println!();
 
     // Set up Ctrl+C handler
@@ -133,22 +1274,264 @@ fn cmd_monitor(interval: u64) {
         r.store(false, std::sync::atomic::Ordering::SeqCst);
     }).expect("Error setting Ctrl+C handler");
 
-    let mut last_detection_state = false;
-    let mut check_count = 0;
+    // Reported with the next scan, which says whether Cluely is running now
+    let mut tampering: Vec<String> = Vec::new();
+    // The binary is checked every status interval until it is found changed
+    let mut next_integrity_check = watchdog.then(std::time::Instant::now);
+    if watchdog {
+        if let Err(e) = watchdog::catch_termination(running.clone()) {
+            tracing::warn!(error = %e, "watchdog cannot catch termination signals");
+        }
+        match &mut state {
+            Some(state) => {
+                // A previous run that never stopped was killed
+                tampering.extend(state.unfinished.as_ref().and_then(watchdog::describe_unfinished));
+                state.watch();
+            }
+            None => tracing::warn!("without the state file the watchdog cannot tell when the monitor was killed"),
+        }
+    }
+
+    workspace::observe_app_events();
 
-    while running.load(std::sync::atomic::Ordering::SeqCst) {
-        check_count += 1;
-        let result = detect_cluely();
-        let is_detected = result.is_detected;
-        
-        let timestamp = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
+    // After a restart, an unchanged detection is not announced again
+    let mut last_detection_state = resumed;
+    let mut last_scan_detected = resumed;
+    // When Cluely was last seen to disappear, while that is not yet announced
+    let mut gone_since: Option<std::time::Instant> = None;
+    // Scans in a row that disagree with the announced state, so a single
+    // glitched window list can't flip it
+    let mut disagreeing_scans: u32 = 0;
+    let mut last_status = std::time::Instant::now();
+    let mut announced_pause = None;
+    let mut next_scan = pacer.first(chrono::Local::now());
+    // Follows Cluely's windows while it is running
+    let mut tracker = WindowTracker::new();
+    // Samples the CPU use of Cluely's processes with hidden windows, and
+    // the ones whose use is at capture level
+    let mut cpu_monitor = ActivityMonitor::new();
+    let mut capturing: HashSet<i32> = HashSet::new();
+    let mut session = session::Session::start(resumed);
+
+    while let Some(due) = next_scan {
+        // Say when the next scan is if it is a while off, so a quiet log
+        // outside working hours isn't mistaken for a hang
+        if due - chrono::Local::now() >= chrono::Duration::hours(1) && announced_pause != Some(due) {
+            println!("{} {}",
+                format!("[{}]", timestamp::now()).cyan(),
+                format!("⏸  Outside the schedule; next scan at {}", timestamp::display(due.with_timezone(&chrono::Utc))).yellow()
+            );
+            announced_pause = Some(due);
+        }
+        match schedule::sleep_until(due, &running) {
+            schedule::Wake::Stopped => break,
+            schedule::Wake::AppEvent => {
+                next_scan = pacer
+                    .app_event(chrono::Local::now())
+                    .map_or(Some(due), |soon| Some(soon.min(due)));
+                continue;
+            }
+            schedule::Wake::Due => {}
+        }
+        let scan_started = std::time::Instant::now();
+        let blind_scans = monitor.consecutive_failures();
+        // A scan that cannot see the windows says nothing either way, so the
+        // last state stands rather than Cluely being reported gone
+        let events = match monitor.poll() {
+            Ok(events) => events,
+            Err(error) => {
+                let failures = monitor.consecutive_failures();
+                tracing::warn!(error = %error, consecutive_failures = failures, "monitor scan failed");
+                session.failed();
+                if let Some(health) = &health {
+                    health.lock().unwrap().failed(error);
+                }
+                if failures == 1 || last_status.elapsed() >= STATUS_INTERVAL {
+                    last_status = std::time::Instant::now();
+                    if agent::is_enabled() {
+                        agent::emit("scan-failed", output::DetectionPayload::from_result(&monitor.result()));
+                    }
+                    println!("{} {}",
+                        format!("[{}]", timestamp::now()).cyan(),
+                        format!(
+                            "⚠️  Status: UNKNOWN - scan failed ({}), {} in a row; keeping the last state",
+                            error, failures
+                        ).yellow()
+                    );
+                }
+                pacer.scanned(false);
+                next_scan = pacer.next_after(chrono::Local::now());
+                if next_scan.is_none() {
+                    tracing::warn!("the schedule never matches again; stopping");
+                }
+                continue;
+            }
+        };
+        let result = monitor.result();
+        let windows: Vec<WindowInfo> = monitor.windows().cloned().collect();
+        if blind_scans > 0 {
+            tracing::info!(failed_scans = blind_scans, "monitor scans recovered");
+            println!("{} {}",
+                format!("[{}]", timestamp::now()).cyan(),
+                format!("✅ Scanning again after {} failed scans", blind_scans).green()
+            );
+        }
+        let mut payload = output::DetectionPayload::from_result(&result);
+
+        // A hidden window whose owner keeps using capture-level CPU is
+        // recording, not waiting
+        let now_capturing: Vec<_> = if result.is_detected {
+            cpu_monitor.poll().unwrap_or_default()
+        } else {
+            cpu_monitor.reset();
+            Vec::new()
+        }
+        .into_iter()
+        .filter(|process| process.is_cluely && process.level == ActivityLevel::Capturing)
+        .collect();
+        if !now_capturing.is_empty() {
+            payload.severity = escalate_severity(&payload.severity);
+            for process in &now_capturing {
+                let finding = Finding::new(
+                    Rule::ActiveCapture,
+                    format!("Active capture ({} using {:.0}% CPU)", process.name, process.cpu_percent),
+                )
+                .with("process", process.name.as_str())
+                .with("pid", process.pid)
+                .with("cpu_percent", process.cpu_percent);
+                payload.evasion_techniques.push(output::FindingPayload::from_finding(&finding));
+            }
+        }
+
+        // With a baseline, only additions relative to it count as a detection
+        let deviations = baseline.as_ref().map(|baseline| {
+            snapshot::diff(baseline, &snapshot::Snapshot::from_scan(&result, &windows)).additions()
+        });
+        if let Some(deviations) = &deviations {
+            payload.detected = !deviations.is_empty();
+        }
+        let is_detected = payload.detected;
+        if next_integrity_check.is_some_and(|due| due <= std::time::Instant::now()) {
+            let problems = self_integrity_problems();
+            next_integrity_check = if problems.is_empty() {
+                Some(std::time::Instant::now() + STATUS_INTERVAL)
+            } else {
+                tampering.push(problems.join("; "));
+                None
+            };
+        }
+        for detail in tampering.drain(..) {
+            report_tampering(&dispatcher, payload.clone(), detail);
+        }
+        tracing::info!(
+            detected = is_detected,
+            windows = result.window_count,
+            screen_capture_evasion = result.screen_capture_evasion_count,
+            elevated_layer = result.elevated_layer_count,
+            deviations = deviations.as_ref().map(Vec::len),
+            duration_ms = scan_started.elapsed().as_secs_f64() * 1000.0,
+            "monitor scan"
+        );
+
+        if let Some(history) = &history {
+            let windows: Vec<_> = windows.iter().map(output::WindowPayload::from_window).collect();
+            if let Err(e) = history.record("monitor", &payload, &windows) {
+                tracing::warn!(error = %e, "failed to record scan history");
+            }
+        }
         
-        if is_detected != last_detection_state {
+        let timestamp = timestamp::now();
+
+        // Movement between scans says more than any one position
+        let behaviors = if result.is_detected {
+            tracker.poll().unwrap_or_default()
+        } else {
+            tracker.reset();
+            Vec::new()
+        };
+        for event in &events {
+            let line = match event {
+                // Announced below, once debounced
+                DetectionEvent::Detected(_) | DetectionEvent::Cleared => continue,
+                DetectionEvent::WindowAppeared(_) => event.describe().red(),
+                DetectionEvent::WindowDisappeared(_) => event.describe().green(),
+                DetectionEvent::StateChanged { .. } => event.describe().yellow(),
+                // Reported by poll as an error, handled above
+                DetectionEvent::ScanFailed { .. } => continue,
+                DetectionEvent::PermissionGranted(_) => {
+                    tracing::warn!(event = %event.describe(), "permission granted");
+                    println!("{} {}",
+                        format!("[{}]", timestamp).cyan(),
+                        format!("🔓 {}", event.describe()).bold().yellow()
+                    );
+                    continue;
+                }
+            };
+            tracing::info!(event = %event.describe(), "window event");
+            println!("{} {}", format!("[{}]", timestamp).cyan(), line);
+        }
+        let started_capturing: Vec<_> = now_capturing
+            .iter()
+            .filter(|process| !capturing.contains(&process.pid))
+            .collect();
+        for process in &started_capturing {
+            tracing::warn!(pid = process.pid, cpu_percent = process.cpu_percent, "{}", process.describe());
+            println!("{} {}",
+                format!("[{}]", timestamp).cyan(),
+                format!("🎥 {}", process.describe()).bold().red()
+            );
+        }
+        let capture_changed = !started_capturing.is_empty() || capturing.len() != now_capturing.len();
+        capturing = now_capturing.iter().map(|process| process.pid).collect();
+        for behavior in &behaviors {
+            tracing::info!(window_id = behavior.window_id(), behavior = %behavior.describe(), "window behavior");
+            println!("{} {}",
+                format!("[{}]", timestamp).cyan(),
+                format!("⚠️  {}", behavior.describe()).yellow()
+            );
+        }
+
+        // A detection is announced once --confirm-after scans in a row have
+        // seen it. Cluely has to stay gone for --clear-after scans and the
+        // debounce period before it counts as stopped, so a restart doesn't
+        // produce a stopped/detected pair of alerts
+        disagreeing_scans = if is_detected != last_detection_state { disagreeing_scans + 1 } else { 0 };
+        let state_changed = if is_detected {
+            gone_since = None;
+            !last_detection_state && disagreeing_scans >= confirm_after
+        } else if last_detection_state {
+            let since = *gone_since.get_or_insert_with(std::time::Instant::now);
+            since.elapsed() >= debounce && disagreeing_scans >= clear_after
+        } else {
+            false
+        };
+        if is_detected && !state_changed && !last_detection_state {
+            tracing::info!(scans = disagreeing_scans, confirm_after, "detection not yet confirmed");
+        }
+        let activity = is_detected != last_scan_detected
+            || gone_since.is_some()
+            || disagreeing_scans > 0
+            || !events.is_empty()
+            || !behaviors.is_empty()
+            || capture_changed;
+        last_scan_detected = is_detected;
+
+        if state_changed {
+            gone_since = None;
+            disagreeing_scans = 0;
+            tracing::info!(
+                event = if is_detected { "detected" } else { "stopped" },
+                severity = %payload.severity,
+                "detection state changed"
+            );
             if is_detected {
                 println!("{} {}", 
                     format!("[{}]", timestamp).cyan(),
                     "🚨 CLUELY DETECTED - Monitoring software started!".bold().red()
                 );
+                for deviation in deviations.iter().flatten() {
+                    println!("   {}", format!("+ {}", deviation).red());
+                }
             } else {
                 println!("{} {}", 
                     format!("[{}]", timestamp).cyan(),
@@ -156,43 +1539,175 @@ fn cmd_monitor(interval: u64) {
                 );
             }
             last_detection_state = is_detected;
-        } else if check_count % 6 == 0 { // Status update every minute (if interval is 10s)
-            let status = if is_detected { "DETECTED".red() } else { "NOT DETECTED".green() };
+            last_status = std::time::Instant::now();
+            let window_ids: Vec<i32> = windows.iter().map(|window| window.window_id).collect();
+            if agent::is_enabled() {
+                agent::emit(if is_detected { "detected" } else { "cleared" }, payload.clone());
+            }
+            dispatcher.dispatch(StateChangeEvent::transition(payload), &window_ids);
+        } else if last_status.elapsed() >= STATUS_INTERVAL {
+            last_status = std::time::Instant::now();
+            if agent::is_enabled() {
+                agent::emit("status", payload);
+            }
+            let status = match (last_detection_state, is_detected) {
+                (true, true) => "DETECTED".red(),
+                (true, false) => format!("DETECTED (gone for {}/{} scans)", disagreeing_scans, clear_after).red(),
+                (false, true) => format!("NOT DETECTED (unconfirmed detection, {}/{} scans)", disagreeing_scans, confirm_after).yellow(),
+                (false, false) => "NOT DETECTED".green(),
+            };
             println!("{} Status: {}", 
                 format!("[{}]", timestamp).cyan(),
                 status
             );
         }
 
-        thread::sleep(Duration::from_secs(interval));
+        session.scanned(is_detected, last_detection_state);
+        if let Some(health) = &health {
+            health.lock().unwrap().scanned(last_detection_state);
+        }
+        if let Some(state) = &mut state {
+            if let Err(e) = state.scanned(last_detection_state, state_changed) {
+                tracing::warn!(error = %e, "failed to save monitor state");
+            }
+        }
+
+        pacer.scanned(activity);
+        next_scan = pacer.next_after(chrono::Local::now());
+        if next_scan.is_none() {
+            tracing::warn!("the schedule never matches again; stopping");
+        }
+    }
+
+    if let Some(sender) = watchdog.then(watchdog::unexpected_sender).flatten() {
+        let payload = output::DetectionPayload::from_result(&monitor.result());
+        report_tampering(&dispatcher, payload, sender.describe());
     }
+    dispatcher.finish();
+    if let Some(Err(e)) = state.as_mut().map(state::StateTracker::finish) {
+        tracing::warn!(error = %e, "failed to save monitor state");
+    }
+
+    let summary = session.finish();
+    tracing::info!(
+        runtime_seconds = summary.runtime_seconds,
+        scans = summary.scans,
+        failed_scans = summary.failed_scans,
+        episodes = summary.episodes,
+        longest_episode_seconds = summary.longest_episode_seconds,
+        "monitor session ended"
+    );
+    let saved = session::default_path().and_then(|path| match summary.save(&path) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to save the session summary");
+            None
+        }
+    });
 
     println!();
     println!("{}", "👋 Monitoring stopped".yellow());
+    print_session_summary(&summary, saved.as_deref());
 }
 
-fn cmd_json() {
-    let result = detect_cluely();
-    
-    let json_result = serde_json::json!({
-        "detected": result.is_detected,
-        "window_count": result.window_count,
-        "screen_capture_evasion_count": result.screen_capture_evasion_count,
-        "elevated_layer_count": result.elevated_layer_count,
-        "max_layer_detected": result.max_layer_detected,
-        "severity": get_severity_level(&result),
-        "evasion_techniques": get_evasion_techniques(&result),
-        "timestamp": chrono::Utc::now().to_rfc3339()
-    });
+/// What a monitor run saw, printed when it stops
+fn print_session_summary(summary: &session::SessionSummary, saved: Option<&Path>) {
+    let time = |at: Option<chrono::DateTime<chrono::Utc>>| {
+        at.map_or("never".to_string(), timestamp::display)
+    };
+    println!();
+    println!("{}", "📋 Session summary".bold());
+    println!("   Runtime:            {}", format_duration(summary.runtime_seconds));
+    println!("   Scans:              {} ({} failed)", summary.scans, summary.failed_scans);
+    let episodes = match summary.episodes {
+        0 => "0".green(),
+        episodes => format!(
+            "{} (longest {}{})",
+            episodes,
+            format_duration(summary.longest_episode_seconds),
+            if summary.detected_at_exit { ", still detected at exit" } else { "" }
+        ).red(),
+    };
+    println!("   Detection episodes: {}", episodes);
+    println!("   First seen:         {}", time(summary.first_seen));
+    println!("   Last seen:          {}", time(summary.last_seen));
+    if let Some(path) = saved {
+        println!("   Saved to {}", path.display());
+    }
+}
 
-    println!("{}", serde_json::to_string_pretty(&json_result).unwrap());
+/// What suggests this binary was patched or replaced; a detector that was
+/// can't vouch for its own results
+fn self_integrity_problems() -> Vec<String> {
+    match verify_self_integrity_rust() {
+        Ok(report) => report.problems(),
+        Err(e) => {
+            tracing::debug!(error = %e, "self-integrity check unavailable");
+            Vec::new()
+        }
+    }
 }
 
-fn cmd_stats() {
-    let result = detect_cluely();
-    
-    println!("{}", "📊 Detection Statistics".bold().blue());
-    println!("{}", "======================".blue());
+/// Report that something other than the user or launchd stopped the monitor,
+/// or that its binary was changed, with `detection` as the latest state seen
+fn report_tampering(dispatcher: &notify::Dispatcher, detection: output::DetectionPayload, detail: String) {
+    tracing::error!(detail = %detail, "monitor tampering");
+    if agent::is_enabled() {
+        agent::emit_detail("tampered", detection.clone(), Some(detail.clone()));
+    }
+    println!("{} {}",
+        format!("[{}]", timestamp::now()).cyan(),
+        format!("🛑 {}", detail).bold().red()
+    );
+    dispatcher.dispatch(StateChangeEvent::tampered(detection, detail), &[]);
+}
+
+/// The same as `check --format json`, without installations or tabs
+fn cmd_json(policy: &ExitPolicy, detector: &DetectorConfig) {
+    let result = detect_or_exit(policy, detector);
+    output::print_payload(&result, OutputFormat::Json);
+    exit_with_outcome(policy, detector, result.is_detected);
+}
+
+fn cmd_stats(days: u32, format: OutputFormat, policy: &ExitPolicy, detector: &DetectorConfig) {
+    let result = detect_or_exit(policy, detector);
+    let monitor = state::default_path().and_then(|path| {
+        state::MonitorState::load(&path)
+            .map_err(|e| tracing::warn!(error = %e, "failed to load monitor state"))
+            .ok()
+            .flatten()
+    });
+    // Trends need a history; stats shouldn't create an empty one
+    let today = chrono::Local::now().date_naive();
+    let trends = history::default_path()
+        .filter(|path| path.exists())
+        .and_then(|_| open_history())
+        .and_then(|history| {
+            let from = (today - chrono::Duration::days(i64::from(days) - 1))
+                .and_hms_opt(0, 0, 0)
+                .and_then(|start| start.and_local_timezone(chrono::Local).earliest())
+                .map(|start| start.with_timezone(&chrono::Utc));
+            history
+                .range(from, None)
+                .map_err(|e| tracing::warn!(error = %e, "failed to read scan history"))
+                .ok()
+        })
+        .map(|scans| trends::compute(&scans, today, days));
+
+    let capture_evasion = detect_capture_evasion_rust();
+
+    let payload = output::StatsPayload {
+        detection: output::DetectionPayload::from_result(&result),
+        monitor: monitor.clone(),
+        capture_evasion: output::CaptureEvasionPayload::from_report(&capture_evasion),
+        trends: trends.clone(),
+    };
+    if output::print_document(&payload, format) {
+        exit_with_outcome(policy, detector, result.is_detected);
+    }
+
+    println!("{}", "📊 Detection Statistics".bold().blue());
+    println!("{}", "======================".blue());
     println!();
     
     println!("{:<30} {}", "Detection Status:", 
@@ -218,6 +1733,1394 @@ fn cmd_stats() {
             }
         }
     }
+
+    println!();
+    println!("{:<30} {}", "Apps Hiding From Capture:", capture_evasion.apps.len().to_string().cyan());
+    for app in &capture_evasion.apps {
+        let label = format!("{} (pid {})", app.name, app.pid);
+        println!(
+            "  • {} — {} window(s){}",
+            if app.is_cluely { label.red() } else { label.yellow() },
+            app.window_ids.len(),
+            if app.is_cluely { " [Cluely]" } else { "" }
+        );
+    }
+
+    if let Some(monitor) = monitor {
+        println!();
+        println!("{}", "Monitoring (all runs):".bold().blue());
+        println!("{:<30} {}", "Total Uptime:", format_duration(monitor.uptime_seconds as i64).cyan());
+        println!("{:<30} {}", "Monitor Runs:", monitor.runs.to_string().cyan());
+        println!("{:<30} {}", "Scans:", monitor.total_scans.to_string().cyan());
+        println!("{:<30} {}", "Detections:", monitor.total_detections.to_string().cyan());
+        println!("{:<30} {}", "Time Cluely Was Detected:", format_duration(monitor.detected_seconds as i64).cyan());
+        if let Some(first) = monitor.first_started {
+            println!("{:<30} {}", "Monitoring Since:", timestamp::display(first));
+        }
+        if let Some(last) = monitor.last_scan {
+            println!("{:<30} {}", "Last Monitor Scan:", timestamp::display(last));
+        }
+    }
+
+    if let Some(trends) = trends.filter(|trends| trends.scans > 0) {
+        println!();
+        println!("{}", format!("Trends (last {} days, from history):", days).bold().blue());
+        println!("{:<30} {}  {} to {}", "Detections per Day:", trends.sparkline.red(), trends.from, trends.to);
+        println!("{:<30} {}", "Detection Episodes:", trends.episodes.to_string().cyan());
+        println!("{:<30} {} of {}", "Days With Detections:", trends.days_with_detections().to_string().cyan(), trends.per_day.len());
+        if let (Some(average), Some(longest)) = (trends.average_episode_seconds, trends.longest_episode_seconds) {
+            println!("{:<30} {}", "Average Episode:", format_duration(average).cyan());
+            println!("{:<30} {}", "Longest Episode:", format_duration(longest).cyan());
+        }
+        if !trends.active_hours.is_empty() {
+            println!("{:<30} {} (local time)", "Typical Active Hours:", trends.active_hours.join(", ").yellow());
+        }
+        println!("{:<30} {}", "Scans Recorded:", trends.scans.to_string().cyan());
+    }
+    exit_with_outcome(policy, detector, result.is_detected);
+}
+
+/// Run the evasion scan, exiting with the permission code if the window list cannot be read.
+/// Findings covered by `suppressions.toml` are taken out and returned separately.
+fn scan_all_or_exit(
+    detector: &DetectorConfig,
+    include_system: bool,
+    policy: &ExitPolicy,
+) -> (EvasionScan, Vec<suppress::SuppressedFinding>) {
+    let suppressions = suppress::load().unwrap_or_else(|e| fail(e));
+    let mut scan = scan_all_with_rust(detector, include_system).unwrap_or_else(|e| {
+        print_error(&e.to_string());
+        process::exit(policy.codes.permission);
+    });
+    let suppressed = suppress::apply(&mut scan, &suppressions, chrono::Local::now().date_naive());
+    (scan, suppressed)
+}
+
+fn cmd_scan_all(detector: &DetectorConfig, include_system: bool, format: OutputFormat, policy: &ExitPolicy) {
+    let (scan, suppressed) = scan_all_or_exit(detector, include_system, policy);
+    let payload = output::EvasionScanPayload::from_scan(&scan, suppressed);
+    let code = policy.code(payload.cluely_detected, payload.evasion_detected);
+
+    // CSV gets one row per flagged window
+    let printed = match format {
+        OutputFormat::Csv => output::print_document(&payload.findings, format),
+        _ => output::print_document(&payload, format),
+    };
+    if printed {
+        process::exit(code);
+    }
+
+    println!("{}", "🔍 Screen Sharing Evasion Scan".bold().blue());
+    println!("{}", "==============================".blue());
+    println!();
+
+    for finding in &payload.findings {
+        if finding.is_cluely {
+            println!("{}", "🚨 CLUELY EVASION DETECTED".bold().red());
+        } else if finding.cluely_like.is_some() {
+            println!("{}", "🚨 CLUELY-LIKE OVERLAY DETECTED".bold().red());
+        } else if finding.confidence == "low" {
+            println!("{}", "⚠️  Possible renamed monitoring tool (low confidence)".bold().yellow());
+        } else {
+            println!("{}", "⚠️  Screen sharing evasion detected".bold().yellow());
+        }
+        println!("   Window: {} [{}] (ID {})", finding.name, finding.owner.cyan(), finding.window_id);
+        for technique in &finding.techniques {
+            println!("   • {}", technique);
+        }
+        println!(
+            "   Sharing state {}, backing type {}, layer {} ({}), alpha {:.3}",
+            finding.sharing_state, finding.backing_type, finding.layer, finding.layer_class, finding.alpha
+        );
+        if let Some(bounds) = &finding.bounds {
+            println!(
+                "   Bounds: ({:.1}, {:.1}) {}x{}",
+                bounds.x, bounds.y, bounds.width, bounds.height
+            );
+        }
+        if let Some(content) = &finding.content {
+            println!("   Content: {}", content.dimmed());
+        }
+        println!();
+    }
+
+    if !payload.system_analysis.is_empty() {
+        println!("{}", "System-wide analysis:".bold());
+        for result in &payload.system_analysis {
+            println!("   📊 {}", result);
+        }
+        println!();
+    }
+
+    println!("{:<30} {}", "Windows analyzed:", payload.total_windows.to_string().cyan());
+    println!("{:<30} {}", "Flagged windows:", payload.findings.len().to_string().cyan());
+    println!("{:<30} {}", "Suppressed findings:", payload.suppressed.len().to_string().cyan());
+    for finding in &payload.suppressed {
+        let until = finding.expires.map(|expires| format!(", until {}", expires)).unwrap_or_default();
+        println!(
+            "   {} [{}] {} ({}{})",
+            finding.rule_id.dimmed(),
+            finding.owner,
+            finding.message,
+            finding.justification,
+            until
+        );
+    }
+    if payload.cluely_detected {
+        println!("{}", "🚨 Cluely detected".bold().red());
+    } else if payload.evasion_detected {
+        println!("{}", "⚠️  Evasion techniques found; no Cluely windows".yellow());
+    } else {
+        println!("{}", "✅ No screen sharing evasion techniques detected".green());
+    }
+    process::exit(code);
+}
+
+fn cmd_displays(shared: Option<u32>, format: OutputFormat) {
+    let report = analyze_displays_rust(shared).unwrap_or_else(|e| fail(e.to_string()));
+    if output::print_document(&output::DisplayReportPayload::from_report(&report), format) {
+        return;
+    }
+
+    println!("{}", "🔍 Cluely Across Displays".bold().blue());
+    println!("{}", "=========================".blue());
+    println!();
+
+    for entry in &report.displays {
+        let display = &entry.display;
+        let mut tags = Vec::new();
+        if display.is_main {
+            tags.push("main");
+        }
+        if report.shared_display == Some(display.id) {
+            tags.push("shared");
+        }
+        println!(
+            "Display {} {}x{} at ({}, {}){}",
+            display.id.to_string().cyan(),
+            display.bounds.width,
+            display.bounds.height,
+            display.bounds.x,
+            display.bounds.y,
+            if tags.is_empty() { String::new() } else { format!(" [{}]", tags.join(", ")) }
+        );
+        if entry.cluely_window_ids.is_empty() {
+            println!("   No Cluely windows");
+        } else {
+            println!(
+                "   {}",
+                format!("Cluely windows: {}", join_ids(&entry.cluely_window_ids)).red()
+            );
+        }
+    }
+    println!();
+
+    match report.shared_display {
+        None => println!("{}", "No screen share seen; pass --shared ID to name the shared display".yellow()),
+        Some(_) if report.overlay_hidden_from_share() => println!(
+            "{}",
+            format!(
+                "🚨 Cluely windows {} are on a display that is not being shared",
+                join_ids(&report.unshared_overlays)
+            )
+            .bold()
+            .red()
+        ),
+        Some(_) => println!("{}", "✅ No Cluely windows outside the shared display".green()),
+    }
+}
+
+fn join_ids(ids: &[i32]) -> String {
+    ids.iter().map(i32::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn cmd_ps(signals: &[SignalFilter], name: Option<&str>, include_system: bool, format: OutputFormat) {
+    let name = name.map(str::to_lowercase);
+    let processes: Vec<_> = get_suspicious_processes_rust(include_system)
+        .unwrap_or_else(|e| fail(e.to_string()))
+        .into_iter()
+        .filter(|process| {
+            signals
+                .iter()
+                .all(|&signal| process.signals.contains(&signal.into()))
+        })
+        .filter(|process| {
+            name.as_ref()
+                .is_none_or(|name| process.name.to_lowercase().contains(name))
+        })
+        .collect();
+
+    let payload: Vec<_> = processes
+        .iter()
+        .map(output::SuspiciousProcessPayload::from_process)
+        .collect();
+    if output::print_document(&payload, format) {
+        return;
+    }
+
+    if processes.is_empty() {
+        println!("{}", "✅ No processes with suspicious windows".green());
+        return;
+    }
+
+    println!("{:>7}  {:<30} {:>7}  {}", "PID".bold(), "NAME".bold(), "WINDOWS".bold(), "SIGNALS".bold());
+    for process in &processes {
+        let name = if process.is_cluely {
+            process.name.red().bold()
+        } else {
+            process.name.normal()
+        };
+        println!(
+            "{:>7}  {:<30} {:>7}  {}",
+            process.pid,
+            name,
+            process.windows.len(),
+            process
+                .signals
+                .iter()
+                .map(|signal| signal.label())
+                .collect::<Vec<_>>()
+                .join(", ")
+                .yellow()
+        );
+    }
+}
+
+fn cmd_recorders(format: OutputFormat) {
+    let recorders = get_screen_recorders_rust().unwrap_or_else(|e| fail(e.to_string()));
+    if output::print_document(&output::RecordersPayload::from_recorders(&recorders), format) {
+        return;
+    }
+
+    if !recorders.grants_readable {
+        print_error("Screen Recording grants could not be read");
+        println!(
+            "{}",
+            "💡 Give your terminal Full Disk Access (System Settings > Privacy & Security > Full Disk Access)".yellow()
+        );
+        process::exit(exit_code::ERROR);
+    }
+    if recorders.recorders.is_empty() {
+        println!("{}", "✅ No app has ever been allowed to record the screen".green());
+        return;
+    }
+
+    println!("{:<13} {:<30} {:>7}  {}", "STATUS".bold(), "NAME".bold(), "PID".bold(), "CLIENT".bold());
+    for recorder in &recorders.recorders {
+        let status = if recorder.meeting_app && recorder.recording() {
+            "meeting app".green()
+        } else if recorder.recording() {
+            "recording".red().bold()
+        } else if recorder.allowed {
+            "not running".normal()
+        } else {
+            "denied".dimmed()
+        };
+        let pid = recorder.pids.first().map(i32::to_string).unwrap_or_default();
+        println!("{:<13} {:<30} {:>7}  {}", status, recorder.name, pid, recorder.client.dimmed());
+    }
+
+    println!();
+    let unexpected = recorders.unexpected().count();
+    if unexpected > 0 {
+        println!(
+            "{}",
+            format!("⚠️  {} app(s) besides the meeting app can be recording your screen now", unexpected)
+                .bold()
+                .yellow()
+        );
+    } else {
+        println!("{}", "✅ Nothing besides the meeting app can be recording your screen".bold().green());
+    }
+}
+
+fn cmd_handles(all: bool, format: OutputFormat) {
+    let processes = get_artifact_handles_rust(all).unwrap_or_else(|e| fail(e.to_string()));
+    let payload: Vec<_> = processes.iter().map(output::HandlesPayload::from_process).collect();
+    if output::print_document(&payload, format) {
+        return;
+    }
+
+    if processes.is_empty() {
+        println!("{}", "✅ No process has Cluely files open".green());
+        return;
+    }
+
+    for process in &processes {
+        let name = format!("{} (pid {})", process.name, process.pid);
+        if process.is_cluely {
+            println!("{} {}", "🚨".red(), name.bold().red());
+        } else {
+            println!("{} {} {}", "🚨".red(), name.bold().red(), "[not named like Cluely]".yellow());
+            if !process.path.is_empty() {
+                println!("     {}", process.path.dimmed());
+            }
+        }
+        for handle in &process.handles {
+            println!("     {:<15} {}", handle.kind.label(), handle.path.yellow());
+        }
+    }
+
+    let disguised = processes.iter().filter(|process| !process.is_cluely).count();
+    if disguised > 0 {
+        println!();
+        println!(
+            "{}",
+            format!("⚠️  {} process(es) work on Cluely's files under another name", disguised)
+                .bold()
+                .yellow()
+        );
+    }
+}
+
+fn cmd_audit(format: OutputFormat) {
+    let audit = audit_persistence_rust().unwrap_or_else(|e| match e {
+        DetectionError::PermissionDenied => fail("audit needs root; run it with sudo".to_string()),
+        e => fail(e.to_string()),
+    });
+    if output::print_document(&output::AuditPayload::from_audit(&audit), format) {
+        return;
+    }
+
+    println!("{}", "🔎 Cluely Installation Audit".bold().blue());
+    println!("{}", "===========================".blue());
+    println!();
+
+    let print_items = |items: &[no_cluely_driver::PersistenceItem]| {
+        for item in items {
+            println!("     {:<24} {}", item.kind.label(), item.path.display().to_string().yellow());
+        }
+    };
+    if audit.system.is_empty() {
+        println!("{} {}", "✅".green(), "Machine-wide".bold());
+    } else {
+        println!("{} {}", "🚨".red(), "Machine-wide".bold().red());
+        print_items(&audit.system);
+    }
+    for user in &audit.users {
+        if user.installed() {
+            println!("{} {} {}", "🚨".red(), user.user.bold().red(), user.home.display().to_string().dimmed());
+            print_items(&user.items);
+        } else {
+            println!("{} {} {}", "✅".green(), user.user.bold(), user.home.display().to_string().dimmed());
+        }
+    }
+
+    println!();
+    let installed_for = audit.users.iter().filter(|user| user.installed()).count();
+    if audit.installed() {
+        println!(
+            "{}",
+            format!(
+                "🚨 Cluely files found{} for {} of {} users",
+                if audit.system.is_empty() { "" } else { " machine-wide and" },
+                installed_for,
+                audit.users.len()
+            )
+            .bold()
+            .red()
+        );
+        println!("{}", "💡 Run 'cluely-detector remove' as each affected user to clean up".yellow());
+    } else {
+        println!("{}", "✅ No Cluely files on this machine".bold().green());
+    }
+}
+
+fn cmd_jamf_ea(detector: &DetectorConfig) {
+    // Jamf reads the <result> element from stdout; the exit code is ignored
+    let result = detect_cluely_with_rust(detector).map(|(result, _)| result);
+    if let Err(e) = &result {
+        tracing::error!(error = %e, "scan failed");
+    }
+    std::println!("{}", output::jamf_extension_attribute(result.as_ref()));
+}
+
+fn cmd_schema(name: schema::SchemaName) {
+    let schema = schema::generate(name);
+    std::println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+}
+
+fn cmd_osquery_extension(socket: &Path, timeout: u64, interval: u64, detector: DetectorConfig) {
+    if let Err(e) = osquery::run(socket, timeout, interval, detector) {
+        fail(e);
+    }
+}
+
+fn cmd_serve(options: server::ServeOptions) {
+    println!(
+        "{}",
+        format!("🌐 Serving detection API on http://{}", options.bind)
+            .bold()
+            .blue()
+    );
+    println!("   GET /v1/detect   latest detection result");
+    println!("   GET /v1/windows  Cluely windows from the latest scan");
+    println!("   GET /v1/history  recent scan results (?limit=N)");
+    println!("   GET /v1/events   WebSocket stream of detection state changes");
+    println!("   GET /v1/health   scanner liveness, for 'cluely-detector status'");
+    println!();
+
+    if let Err(e) = server::run(options) {
+        fail(e);
+    }
+}
+
+fn cmd_status(address: &str, format: OutputFormat) {
+    let health = health::query(address).unwrap_or_else(|e| {
+        fail(format!(
+            "{}\n💡 Start one with 'cluely-detector service install', 'monitor --health' or 'serve'",
+            e
+        ))
+    });
+    let degraded = health.status == health::HealthStatus::Degraded;
+    if output::print_document(&health, format) {
+        process::exit(if degraded { exit_code::ERROR } else { exit_code::SUCCESS });
+    }
+
+    println!("{}", "🩺 Detector Daemon Status".bold().blue());
+    println!("{}", "========================".blue());
+    println!();
+
+    let status = match health.status {
+        health::HealthStatus::Ok => "OK".green(),
+        health::HealthStatus::Starting => "STARTING".yellow(),
+        health::HealthStatus::Degraded => "DEGRADED - scans are failing".red(),
+    };
+    let time = |time: Option<chrono::DateTime<chrono::Utc>>| {
+        time.map_or("never".to_string(), timestamp::display)
+    };
+    println!("{:<22} {}", "Health:", status.bold());
+    println!("{:<22} {} (pid {}, version {})", "Daemon:", health.mode, health.pid, health.version);
+    println!("{:<22} {} ({})", "Running since:", time(Some(health.started_at)),
+        format_duration(health.uptime_seconds as i64));
+    println!("{:<22} {}", "Last scan:", time(health.last_scan));
+    println!("{:<22} {}", "Last successful scan:", time(health.last_successful_scan));
+    println!("{:<22} {} of {} ({} in a row)", "Failed scans:",
+        health.failed_scans, health.total_scans, health.consecutive_failures);
+    if let Some(error) = &health.last_error {
+        println!("{:<22} {}", "Last error:", error.yellow());
+    }
+    println!("{:<22} {}", "Detection:",
+        if health.detected { "DETECTED".red() } else { "NOT DETECTED".green() });
+    if degraded {
+        println!();
+        println!("{}", "⚠️  The detection state is from before scans started failing".yellow());
+        process::exit(exit_code::ERROR);
+    }
+}
+
+fn cmd_doctor(config: Option<&Path>, format: OutputFormat) {
+    let checks = doctor::run_checks(config);
+    let failed = checks
+        .iter()
+        .any(|check| check.status == doctor::CheckStatus::Fail);
+
+    if !output::print_document(&checks, format) {
+        println!("{}", "🩺 Cluely Detector Doctor".bold().blue());
+        println!("{}", "========================".blue());
+        println!();
+
+        print_checks(&checks);
+
+        println!();
+        if failed {
+            println!("{}", "Some checks failed; scans may miss Cluely until they are fixed".red());
+        } else {
+            println!("{}", "Ready to scan".green());
+        }
+    }
+
+    process::exit(if failed { 1 } else { 0 });
+}
+
+fn print_checks(checks: &[doctor::Check]) {
+    for check in checks {
+        let icon = match check.status {
+            doctor::CheckStatus::Ok => "✅",
+            doctor::CheckStatus::Warn => "⚠️ ",
+            doctor::CheckStatus::Fail => "❌",
+        };
+        println!("{} {}: {}", icon, check.name.bold(), check.detail);
+        if let Some(remediation) = &check.remediation {
+            println!("   {}", format!("→ {}", remediation).yellow());
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn cmd_simulate(hold: u64, no_window: bool, format: OutputFormat) {
+    // The fake Cluely quits by itself if this process dies without cleaning up
+    let lifetime = simulate::STARTUP_TIMEOUT + Duration::from_secs(hold + 30);
+    let mut simulation = simulate::Simulation::start(!no_window, lifetime).unwrap_or_else(|e| fail(e));
+    let checks = simulation.verify();
+    let failed = checks
+        .iter()
+        .any(|check| check.status == doctor::CheckStatus::Fail);
+
+    if !output::print_document(&checks, format) {
+        println!("{}", "🧪 Cluely Simulation".bold().blue());
+        println!("{}", "====================".blue());
+        println!();
+        print_checks(&checks);
+        println!();
+        if failed {
+            println!("{}", "The detector missed the fake Cluely; it may miss the real one too".red());
+        } else {
+            println!("{}", "The detector found the fake Cluely".green());
+        }
+    }
+
+    if hold > 0 && !failed {
+        let running = std::sync::Arc::new(AtomicBool::new(true));
+        let r = running.clone();
+        ctrlc::set_handler(move || r.store(false, Ordering::SeqCst)).expect("Error setting Ctrl+C handler");
+
+        eprintln!(
+            "{}",
+            format!("Leaving the fake Cluely (pid {}) running for {}s; Ctrl+C to stop it early", simulation.pid(), hold).yellow()
+        );
+        let until = std::time::Instant::now() + Duration::from_secs(hold);
+        while running.load(Ordering::SeqCst) && std::time::Instant::now() < until {
+            std::thread::sleep(Duration::from_millis(250));
+        }
+    }
+
+    drop(simulation);
+    process::exit(if failed { exit_code::ERROR } else { exit_code::SUCCESS });
+}
+
+fn cmd_self_update(check: bool, force: bool) {
+    println!(
+        "{}",
+        format!(
+            "🔄 Checking for updates (current version {})",
+            env!("CARGO_PKG_VERSION")
+        )
+        .blue()
+    );
+
+    match update::run(check, force).unwrap_or_else(|e| fail(e)) {
+        update::UpdateOutcome::UpToDate { version } => {
+            println!("{}", format!("✅ Up to date (latest release is {})", version).green());
+        }
+        update::UpdateOutcome::Available { version } => {
+            println!("{}", format!("⬆️  Version {} is available", version).yellow());
+            println!("{}", "💡 Run 'cluely-detector self-update' to install it".yellow());
+        }
+        update::UpdateOutcome::Updated { version, path } => {
+            println!(
+                "{}",
+                format!("✅ Updated to {} ({})", version, path.display()).bold().green()
+            );
+            if matches!(service::status(), Ok((_, status)) if status.loaded) {
+                println!(
+                    "{}",
+                    "💡 Run 'cluely-detector service install' to restart background monitoring on the new version".yellow()
+                );
+            }
+        }
+    }
+}
+
+fn cmd_rules(action: &RulesAction, cli: &Cli) {
+    match action {
+        RulesAction::List => {
+            let rules = rules::list();
+            if output::print_document(&rules, cli.format) {
+                return;
+            }
+            println!("{}", format!("📏 Detection Rules (ruleset version {})", RULESET_VERSION).bold().blue());
+            println!();
+            println!("{}", "Cluely detection:".bold());
+            for rule in rules.iter().filter(|rule| rule.bit.is_some()) {
+                println!("  {:<24} {}", rule.id.cyan(), rule.summary);
+            }
+            println!();
+            println!("{}", "Evasion scan (scan-all):".bold());
+            for rule in rules.iter().filter(|rule| rule.bit.is_none()) {
+                println!("  {:<24} {}", rule.id.cyan(), rule.summary);
+            }
+            println!();
+            println!("{}", "💡 Run 'cluely-detector rules explain <id>' for details, and 'cluely-detector signatures show' for the signatures in use".yellow());
+        }
+        RulesAction::Explain { id } => {
+            let rule = rules::explain(id).unwrap_or_else(|e| fail(e));
+            if output::print_document(&rule, cli.format) {
+                return;
+            }
+            println!("{}", rule.id.bold().cyan());
+            println!("{}", rule.summary.bold());
+            println!();
+            println!("{}", rule.explanation);
+        }
+        RulesAction::Test { fixture, signatures } => {
+            let set = match signatures {
+                Some(path) => {
+                    let mut set = SignatureSet::builtin();
+                    for signature in signatures::load_file(path).unwrap_or_else(|e| fail(e)) {
+                        set.push(signature);
+                    }
+                    set
+                }
+                None => detector_config(cli).signatures,
+            };
+            let report = rules::test(fixture, &set).unwrap_or_else(|e| fail(e));
+            let code = if report.failures > 0 { 1 } else { 0 };
+            if output::print_document(&report, cli.format) {
+                process::exit(code);
+            }
+            print_rule_test(&report);
+            process::exit(code);
+        }
+    }
+}
+
+fn print_rule_test(report: &rules::TestReport) {
+    println!("{}", "🧪 Rule Test".bold().blue());
+    println!("{}", "============".blue());
+    println!();
+    for window in &report.windows {
+        let marker = if !window.passed {
+            "❌".to_string()
+        } else if window.expect_cluely.is_some() {
+            "✅".to_string()
+        } else {
+            "  ".to_string()
+        };
+        let title = window.title.as_deref().map(|title| format!(" \"{}\"", title)).unwrap_or_default();
+        match &window.signature {
+            Some(signature) => println!(
+                "{} {} [{}]{} → {} ({})",
+                marker,
+                window.window_id,
+                window.owner.red(),
+                title,
+                signature.bold(),
+                window.rules_fired.join(", ")
+            ),
+            None => println!("{} {} [{}]{} → not Cluely", marker, window.window_id, window.owner.cyan(), title),
+        }
+        if !window.passed {
+            let expected = if window.expect_cluely == Some(true) { "detected" } else { "not detected" };
+            println!("   {}", format!("expected to be {}", expected).red());
+        }
+    }
+    println!();
+    let detection = &report.detection;
+    println!(
+        "Result: {} ({} Cluely windows, severity {})",
+        if detection.detected { "DETECTED".red().bold() } else { "NOT DETECTED".green().bold() },
+        detection.window_count,
+        detection.severity
+    );
+    if report.failures > 0 {
+        println!("{}", format!("❌ {} windows did not match their expectation", report.failures).bold().red());
+    } else if report.windows.iter().any(|window| window.expect_cluely.is_some()) {
+        println!("{}", "✅ Every expectation was met".bold().green());
+    }
+}
+
+fn cmd_signatures(action: &SignaturesAction, config_path: Option<&Path>, format: OutputFormat) {
+    match action {
+        SignaturesAction::Update { url, force, dry_run } => {
+            let config = load_config(config_path);
+            println!("{}", "🔄 Checking for new signatures".blue());
+            let outcome = signatures::update(&config.signature_updates, url.as_deref(), *force, *dry_run);
+            if !print_signatures_outcome(outcome.unwrap_or_else(|e| fail(e))) {
+                return;
+            }
+        }
+        SignaturesAction::Import { file, force, dry_run } => {
+            let config = load_config(config_path);
+            let outcome = signatures::import(&config.signature_updates, file, *force, *dry_run);
+            if !print_signatures_outcome(outcome.unwrap_or_else(|e| fail(e))) {
+                return;
+            }
+        }
+        SignaturesAction::Rollback => {
+            let (version, replaced) = signatures::rollback().unwrap_or_else(|e| fail(e));
+            println!("{}", format!("⏪ Rolled back to signatures version {}", version).green());
+            if let Some(replaced) = replaced {
+                println!("   Version {} is kept as the previous bundle", replaced);
+            }
+        }
+        SignaturesAction::Export { file } => {
+            let version = signatures::export(file).unwrap_or_else(|e| fail(e));
+            println!(
+                "{}",
+                format!("📦 Exported signatures version {} to {}", version, file.display()).green()
+            );
+            println!("   Copy it and its .sig file to the other machine and run 'cluely-detector signatures import'");
+            return;
+        }
+        SignaturesAction::Lint { file } => {
+            let report = lint::lint(file).unwrap_or_else(|e| fail(e));
+            let code = if report.errors() > 0 { 1 } else { 0 };
+            if !output::print_document(&report, format) {
+                print_lint(file, &report);
+            }
+            process::exit(code);
+        }
+        SignaturesAction::Show => {
+            let config = load_config(config_path);
+            let ruleset = signatures::active(&config.signatures).unwrap_or_else(|e| fail(e));
+            if !output::print_document(&ruleset, format) {
+                print_ruleset(&ruleset);
+            }
+            return;
+        }
+    }
+    if matches!(service::status(), Ok((_, status)) if status.loaded) {
+        println!(
+            "{}",
+            "💡 Run 'cluely-detector service install' to restart background monitoring with these signatures".yellow()
+        );
+    }
+}
+
+/// Print what an update or import did; false if nothing changed
+fn print_signatures_outcome(outcome: signatures::UpdateOutcome) -> bool {
+    match outcome {
+        signatures::UpdateOutcome::UpToDate { version } => {
+            println!("{}", format!("✅ Signatures are up to date (version {})", version).green());
+            false
+        }
+        signatures::UpdateOutcome::Verified { bundle, replaced } => {
+            println!(
+                "{}",
+                format!(
+                    "✅ Signatures version {} ({} signatures) passed every check; nothing was installed",
+                    bundle.version,
+                    bundle.signatures.len()
+                )
+                .green()
+            );
+            if let Some(replaced) = replaced {
+                println!("   It would replace version {}", replaced);
+            }
+            false
+        }
+        signatures::UpdateOutcome::Installed { bundle, replaced } => {
+            let released = bundle
+                .released
+                .map(|date| format!(", released {}", date))
+                .unwrap_or_default();
+            println!(
+                "{}",
+                format!(
+                    "✅ Installed signatures version {} ({} signatures{})",
+                    bundle.version,
+                    bundle.signatures.len(),
+                    released
+                )
+                .bold()
+                .green()
+            );
+            if let Some(replaced) = replaced {
+                println!("   Replaced version {}; 'cluely-detector signatures rollback' restores it", replaced);
+            }
+            true
+        }
+    }
+}
+
+fn print_lint(path: &Path, report: &lint::LintReport) {
+    for issue in &report.issues {
+        let location = match issue.line {
+            Some(line) => format!("{}:{}", path.display(), line),
+            None => path.display().to_string(),
+        };
+        let label = match issue.severity {
+            lint::Severity::Error => "error".red().bold(),
+            lint::Severity::Warning => "warning".yellow().bold(),
+        };
+        let signature = issue
+            .signature
+            .as_deref()
+            .map(|name| format!("signature \"{}\": ", name))
+            .unwrap_or_default();
+        println!("{}: {}: {}{}", location, label, signature, issue.message);
+    }
+    if !report.issues.is_empty() {
+        println!();
+    }
+    let summary = format!(
+        "{} signatures, {} errors, {} warnings",
+        report.signatures,
+        report.errors(),
+        report.warnings()
+    );
+    if report.errors() > 0 {
+        println!("{}", format!("❌ {}", summary).bold().red());
+    } else if report.warnings() > 0 {
+        println!("{}", format!("⚠️  {}", summary).bold().yellow());
+    } else {
+        println!("{}", format!("✅ {}", summary).bold().green());
+    }
+}
+
+fn print_ruleset(ruleset: &signatures::Ruleset) {
+    println!("{}", "🔎 Active Signatures".bold().blue());
+    println!("{}", "===================".blue());
+    println!();
+    println!("Built-in:   {} signatures", ruleset.builtin);
+    match &ruleset.bundle {
+        Some(bundle) => {
+            println!("Bundle:     version {}", bundle.version.to_string().bold());
+            if let Some(released) = bundle.released {
+                println!("Released:   {}", released);
+            }
+            println!("SHA-256:    {}", bundle.sha256);
+            println!("Installed:  {}", bundle.path.display());
+            print_signature_list(&bundle.signatures);
+        }
+        None => {
+            println!("Bundle:     {}", "none installed".yellow());
+            println!();
+            println!("{}", "💡 Run 'cluely-detector signatures update' to install the latest signatures".yellow());
+        }
+    }
+    if !ruleset.configured.is_empty() {
+        println!();
+        println!("Configured: {} signatures", ruleset.configured.len());
+        print_signature_list(&ruleset.configured);
+    }
+}
+
+fn print_signature_list(signatures: &[config::SignatureConfig]) {
+    for signature in signatures {
+        let patterns: Vec<String> = [
+            ("owner", &signature.owner),
+            ("title", &signature.title),
+            ("bundle_id", &signature.bundle_id),
+        ]
+        .into_iter()
+        .filter_map(|(field, pattern)| pattern.as_ref().map(|pattern| format!("{} {}", field, pattern)))
+        .collect();
+        println!("  • {} ({})", signature.name.cyan(), patterns.join(", "));
+    }
+}
+
+fn cmd_screenshot(out: &Path, format: OutputFormat) {
+    let screenshots = screenshot::capture_all(out).unwrap_or_else(|e| fail(e));
+    let failed = screenshots.iter().any(|shot| shot.error.is_some());
+
+    if !output::print_document(&screenshots, format) {
+        if screenshots.is_empty() {
+            println!("{}", "✅ No Cluely windows to capture".green());
+        }
+        for shot in &screenshots {
+            match (&shot.path, &shot.error) {
+                (Some(path), _) => {
+                    println!("📸 Window {} [{}] → {}", shot.window_id, shot.owner.cyan(), path.display());
+                    if shot.blank {
+                        println!(
+                            "   {}",
+                            "⚠️  Blank: the window excludes itself from screen capture".yellow()
+                        );
+                    }
+                }
+                (None, error) => println!(
+                    "{} Window {} [{}]: {}",
+                    "❌".red(),
+                    shot.window_id,
+                    shot.owner,
+                    error.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+        if !has_screen_capture_access_rust() {
+            println!();
+            println!(
+                "{}",
+                "⚠️  Screen Recording permission is not granted; window contents may be missing".yellow()
+            );
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+fn cmd_snapshot(out: Option<&Path>) {
+    let snapshot = snapshot::Snapshot::take();
+    match out {
+        Some(path) => {
+            snapshot.save(path).unwrap_or_else(|e| fail(e));
+            println!("{}", format!("📸 Snapshot saved to {}", path.display()).green());
+        }
+        None => std::println!("{}", serde_json::to_string_pretty(&snapshot).unwrap()),
+    }
+}
+
+fn cmd_diff(files: &[PathBuf], baseline: bool, format: OutputFormat) {
+    let (before, after) = match (baseline, files) {
+        (true, [_, _, ..]) => fail("--baseline takes at most one snapshot to compare".to_string()),
+        (true, _) => (
+            snapshot::load_baseline().unwrap_or_else(|e| fail(e)),
+            files.first(),
+        ),
+        (false, [before, rest @ ..]) => (
+            snapshot::Snapshot::load(before).unwrap_or_else(|e| fail(e)),
+            rest.first(),
+        ),
+        (false, []) => fail("Give a snapshot to compare, or use --baseline".to_string()),
+    };
+
+    let after = match after {
+        Some(path) => snapshot::Snapshot::load(path).unwrap_or_else(|e| fail(e)),
+        None => snapshot::Snapshot::take(),
+    };
+    let diff = snapshot::diff(&before, &after);
+
+    if !output::print_document(&diff, format) {
+        println!("{}", "🔀 Scan Comparison".bold().blue());
+        println!("{}", "=================".blue());
+        println!();
+        println!("Before: {}", diff.before);
+        println!("After:  {}", diff.after);
+        println!();
+
+        if diff.is_empty() {
+            println!("{}", "✅ No differences".green());
+        }
+
+        let section = |title: &str, added: Vec<String>, removed: Vec<String>| {
+            if added.is_empty() && removed.is_empty() {
+                return;
+            }
+            println!("{}", title.bold());
+            for item in added {
+                println!("   {}", format!("+ {}", item).red());
+            }
+            for item in removed {
+                println!("   {}", format!("- {}", item).green());
+            }
+            println!();
+        };
+
+        section(
+            "Windows",
+            diff.windows.added.iter().map(snapshot::describe_window).collect(),
+            diff.windows.removed.iter().map(snapshot::describe_window).collect(),
+        );
+        section(
+            "Processes",
+            diff.processes.added.iter().map(snapshot::describe_process).collect(),
+            diff.processes.removed.iter().map(snapshot::describe_process).collect(),
+        );
+        section(
+            "Files on disk",
+            diff.persistence.added.iter().map(snapshot::describe_persistence).collect(),
+            diff.persistence.removed.iter().map(snapshot::describe_persistence).collect(),
+        );
+    }
+
+    // Like diff(1): 1 when the scans differ
+    process::exit(if diff.is_empty() { 0 } else { 1 });
+}
+
+fn cmd_export(out: Option<&Path>, by_cdhash: bool, format: OutputFormat) {
+    if !matches!(format, OutputFormat::Santa | OutputFormat::Yara | OutputFormat::Stix) {
+        fail("Choose a rule format: --format santa, yara or stix".to_string());
+    }
+    let binaries = get_cluely_binaries_rust().unwrap_or_else(|e| fail(e.to_string()));
+    if binaries.is_empty() {
+        fail("No Cluely binaries are running; nothing to export".to_string());
+    }
+    let (document, count, kind, hint) = match format {
+        OutputFormat::Santa => {
+            let rules = export::santa_rules(&binaries, by_cdhash);
+            let document = serde_json::to_string_pretty(&rules).unwrap() + "\n";
+            (document, rules.rules.len(), "Santa", "Import with: sudo santactl rule --import")
+        }
+        OutputFormat::Yara => {
+            let (document, count) = export::yara_rules(&binaries);
+            (document, count, "YARA", "Scan with: yara -r")
+        }
+        _ => {
+            let names: Vec<String> = get_cluely_processes_rust().into_iter().map(|process| process.name).collect();
+            let (bundle, count) = export::stix_bundle(&binaries, &names);
+            let document = serde_json::to_string_pretty(&bundle).unwrap() + "\n";
+            (document, count, "STIX indicator", "Share the bundle with your threat-intel platform:")
+        }
+    };
+
+    match out {
+        Some(path) => {
+            std::fs::write(path, document)
+                .unwrap_or_else(|e| fail(format!("Failed to write {}: {}", path.display(), e)));
+            println!("{}", format!("📄 {} {} rule(s) saved to {}", count, kind, path.display()).green());
+            println!("   {} {}", hint, path.display());
+        }
+        None => print!("{}", document),
+    }
+}
+
+fn cmd_aggregate(files: &[PathBuf], format: OutputFormat) {
+    let summary = agent::aggregate(files).unwrap_or_else(|e| fail(e));
+    if output::print_document(&summary, format) {
+        return;
+    }
+
+    println!("{}", "📊 Fleet Summary".bold().blue());
+    println!("{}", "===============".blue());
+    println!();
+
+    if summary.hosts == 0 {
+        println!("{}", "No agent records found".yellow());
+        return;
+    }
+    let detected = format!("{} with Cluely detected", summary.detected_hosts);
+    println!(
+        "Hosts: {} ({})",
+        summary.hosts,
+        if summary.detected_hosts > 0 { detected.bold().red() } else { detected.green() }
+    );
+    let counts = |counts: &std::collections::BTreeMap<String, usize>| {
+        counts.iter().map(|(key, count)| format!("{} {}", key, count)).collect::<Vec<_>>().join(", ")
+    };
+    println!("Severity: {}", counts(&summary.by_severity));
+    println!("Agent versions: {}", counts(&summary.by_agent_version));
+    println!();
+
+    for host in &summary.host_list {
+        let marker = if host.detected { "🚨".red() } else { "✅".green() };
+        let name = if host.detected { host.hostname.bold().red() } else { host.hostname.normal() };
+        println!(
+            "{} {:<24} {:<14} {:<12} {:<8} {}",
+            marker,
+            name,
+            host.serial_number.as_deref().unwrap_or("-"),
+            host.username.as_deref().unwrap_or("-"),
+            host.severity,
+            timestamp::reformat(&host.last_seen).dimmed()
+        );
+    }
+    if summary.skipped_lines > 0 {
+        println!();
+        println!("{}", format!("⚠️  Skipped {} line(s) that were not agent records", summary.skipped_lines).yellow());
+    }
+}
+
+fn cmd_baseline(action: &BaselineAction, format: OutputFormat) {
+    let path = snapshot::baseline_path()
+        .unwrap_or_else(|| fail("Could not determine the configuration directory".to_string()));
+
+    match action {
+        BaselineAction::Save => {
+            let baseline = snapshot::Snapshot::take();
+            baseline.save(&path).unwrap_or_else(|e| fail(e));
+            println!(
+                "{}",
+                format!("📸 Baseline saved to {}", path.display()).green()
+            );
+            println!(
+                "   {} windows, {} processes, {} files on disk",
+                baseline.windows.len(),
+                baseline.processes.len(),
+                baseline.persistence.len()
+            );
+        }
+        BaselineAction::Show => {
+            let baseline = snapshot::load_baseline().unwrap_or_else(|e| fail(e));
+            if format == OutputFormat::Text {
+                std::println!("{}", serde_json::to_string_pretty(&baseline).unwrap());
+            } else {
+                output::print_document(&baseline, format);
+            }
+        }
+        BaselineAction::Clear => {
+            if path.exists() {
+                std::fs::remove_file(&path).unwrap_or_else(|e| {
+                    fail(format!("Failed to remove {}: {}", path.display(), e))
+                });
+            }
+            println!("{}", "🗑️  Baseline cleared".green());
+        }
+    }
+}
+
+fn cmd_history(action: &HistoryAction, format: OutputFormat) {
+    let history = history::History::open_default().unwrap_or_else(|e| fail(e));
+
+    match action {
+        HistoryAction::List { limit, detected } => {
+            let scans = history.list(*limit, *detected).unwrap_or_else(|e| fail(e));
+            if output::print_document(&scans, format) {
+                return;
+            }
+
+            println!("{}", "📜 Scan History".bold().blue());
+            println!("{}", "==============".blue());
+            println!();
+
+            if scans.is_empty() {
+                println!("{}", "No scans recorded yet".yellow());
+                println!();
+                println!("{}", "💡 Scans are recorded while 'cluely-detector monitor' runs".yellow());
+                return;
+            }
+
+            for scan in &scans {
+                let status = if scan.detection.detected {
+                    "DETECTED    ".red()
+                } else {
+                    "NOT DETECTED".green()
+                };
+                println!(
+                    "{:>6}  {}  {}  windows: {}  severity: {}  ({})",
+                    scan.id,
+                    timestamp::reformat(&scan.detection.timestamp).cyan(),
+                    status,
+                    scan.detection.window_count,
+                    scan.detection.severity,
+                    scan.source
+                );
+            }
+        }
+        HistoryAction::Timeline { from, to, max_gap } => {
+            let scans = history.range(*from, *to).unwrap_or_else(|e| fail(e));
+            let sessions = history::timeline(&scans, chrono::Duration::seconds(*max_gap));
+            if output::print_document(&sessions, format) {
+                return;
+            }
+
+            println!("{}", "🕒 Cluely Activity Timeline".bold().blue());
+            println!("{}", "==========================".blue());
+            println!();
+
+            let (Some(first), Some(last)) = (scans.first(), scans.last()) else {
+                println!("{}", "No scans recorded in this period".yellow());
+                return;
+            };
+            println!(
+                "Scans: {} from {} to {}",
+                scans.len(),
+                timestamp::reformat(&first.detection.timestamp),
+                timestamp::reformat(&last.detection.timestamp)
+            );
+            println!();
+
+            if sessions.is_empty() {
+                println!("{}", "✅ No Cluely activity in this period".green());
+                return;
+            }
+
+            for session in &sessions {
+                let end = if session.ongoing {
+                    format!("{} (ongoing)", timestamp::reformat(&session.last_seen))
+                } else {
+                    timestamp::reformat(&session.last_seen)
+                };
+                println!(
+                    "{} {} → {}",
+                    "🚨".red(),
+                    timestamp::reformat(&session.first_seen).cyan(),
+                    end.cyan()
+                );
+                println!(
+                    "   Duration: {}  ·  Scans: {}  ·  Max severity: {}  ·  Max windows: {}",
+                    format_duration(session.duration_seconds),
+                    session.scans,
+                    session.max_severity,
+                    session.max_window_count
+                );
+            }
+
+            let total: i64 = sessions.iter().map(|session| session.duration_seconds).sum();
+            println!();
+            println!(
+                "Total: {} sessions, {} of activity",
+                sessions.len(),
+                format_duration(total)
+            );
+        }
+        HistoryAction::Show { id } => {
+            let scan = history
+                .get(*id)
+                .unwrap_or_else(|e| fail(e))
+                .unwrap_or_else(|| fail(format!("No scan with id {}", id)));
+            if output::print_document(&scan, format) {
+                return;
+            }
+
+            let detection = &scan.detection;
+            println!("{}", format!("📜 Scan #{}", scan.id).bold().blue());
+            println!("{}", "==============".blue());
+            println!();
+            println!("Time: {}", timestamp::reformat(&detection.timestamp));
+            println!("Source: {}", scan.source);
+            println!(
+                "Status: {}",
+                if detection.detected {
+                    "DETECTED".red()
+                } else {
+                    "NOT DETECTED".green()
+                }
+            );
+            println!("Severity: {}", detection.severity);
+            println!("Windows: {}", detection.window_count);
+            println!(
+                "Screen capture evasion: {}",
+                detection.screen_capture_evasion_count
+            );
+            println!("Elevated layers: {}", detection.elevated_layer_count);
+            println!("Max layer: {}", detection.max_layer_detected);
+
+            if !detection.evasion_techniques.is_empty() {
+                println!();
+                println!("Evasion techniques:");
+                for technique in &detection.evasion_techniques {
+                    println!("  • {}", technique);
+                }
+            }
+
+            if !scan.windows.is_empty() {
+                println!();
+                println!("Windows:");
+                for window in &scan.windows {
+                    println!(
+                        "  • #{} {} (sharing state: {}, layer: {}, alpha: {:.3})",
+                        window.window_id, window.owner, window.sharing_state, window.layer, window.alpha
+                    );
+                }
+            }
+        }
+        HistoryAction::Export { from, to, rows, max_gap, out } => {
+            let scans = history.range(*from, *to).unwrap_or_else(|e| fail(e));
+            let document = match rows {
+                ExportRows::Episodes => serde_json::to_value(history::episode_rows(&scans, chrono::Duration::seconds(*max_gap))),
+                ExportRows::Scans => serde_json::to_value(scans.iter().map(history::ScanRow::from_record).collect::<Vec<_>>()),
+            }
+            .unwrap();
+            let count = document.as_array().map_or(0, Vec::len);
+            let document = match format {
+                OutputFormat::Text | OutputFormat::Csv => output::to_csv(&document),
+                OutputFormat::Json => serde_json::to_string_pretty(&document).unwrap() + "\n",
+                _ => fail("Choose an export format: --format csv or json".to_string()),
+            };
+
+            match out {
+                Some(path) => {
+                    std::fs::write(path, document)
+                        .unwrap_or_else(|e| fail(format!("Failed to write {}: {}", path.display(), e)));
+                    let kind = match rows {
+                        ExportRows::Episodes => "episode(s)",
+                        ExportRows::Scans => "scan(s)",
+                    };
+                    println!("{}", format!("📄 {} {} exported to {}", count, kind, path.display()).green());
+                }
+                None => print!("{}", document),
+            }
+        }
+        HistoryAction::Purge { older_than, all } => {
+            let before = if *all {
+                None
+            } else {
+                older_than.map(|days| chrono::Utc::now() - chrono::Duration::days(days.into()))
+            };
+            let removed = history.purge(before).unwrap_or_else(|e| fail(e));
+            println!("{}", format!("🗑️  Removed {} scans", removed).green());
+        }
+    }
+}
+
+/// Human-readable duration such as "1h 05m" or "42s"
+fn format_duration(seconds: i64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h {:02}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {:02}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Open the history database, warning and carrying on without it on failure
+fn open_history() -> Option<history::History> {
+    history::History::open_default()
+        .map_err(|e| tracing::warn!(error = %e, "scan history disabled"))
+        .ok()
+}
+
+/// Load the saved monitor state, warning and carrying on without it on failure
+fn open_monitor_state(baseline: bool) -> Option<state::StateTracker> {
+    state::default_path()
+        .ok_or_else(|| "Could not determine the data directory".to_string())
+        .and_then(|path| state::StateTracker::start(path, baseline))
+        .map_err(|e| tracing::warn!(error = %e, "monitor state disabled"))
+        .ok()
+}
+
+fn cmd_service(action: &ServiceAction, config: Option<&Path>, os_log: bool) {
+    match action {
+        ServiceAction::Install { interval, schedule, watchdog } => {
+            // Catch config mistakes now rather than in a crash-looping agent
+            let loaded = load_config(config);
+            if let Err(e) = notify::from_config(&loaded.notifiers) {
+                fail(e);
+            }
+            // --interval overrides a schedule from the configuration file
+            let effective_schedule = match interval {
+                Some(_) => None,
+                None => schedule.as_ref().or(loaded.schedule.as_ref()),
+            };
+            if let Some(expression) = effective_schedule {
+                if let Err(e) = schedule::Schedule::cron(expression) {
+                    fail(e);
+                }
+            }
+
+            let paths = service::install(config, *interval, schedule.as_deref(), *watchdog, os_log)
+                .unwrap_or_else(|e| fail(e));
+            println!("{}", "✅ Background monitoring installed".bold().green());
+            println!("   Agent:  {}", paths.plist.display());
+            println!("   Output: {}", paths.stdout_log.display());
+            println!("   Errors: {}", paths.stderr_log.display());
+            if loaded.notifiers.is_empty() {
+                println!();
+                println!(
+                    "{}",
+                    "💡 No notifiers are configured; state changes will only be logged".yellow()
+                );
+            }
+        }
+        ServiceAction::Uninstall => {
+            let paths = service::uninstall().unwrap_or_else(|e| fail(e));
+            println!("{}", "✅ Background monitoring removed".bold().green());
+            println!("   Logs kept in {}", paths.stdout_log.parent().unwrap().display());
+        }
+        ServiceAction::Status => {
+            let (paths, status) = service::status().unwrap_or_else(|e| fail(e));
+            println!("{}", "🛠️  Background Monitoring Service".bold().blue());
+            println!("{}", "===============================".blue());
+            println!();
+
+            if !status.installed && !status.loaded {
+                println!("{}", "Not installed".yellow());
+                println!();
+                println!("{}", "💡 Use 'cluely-detector service install' to set it up".yellow());
+                return;
+            }
+
+            let state = match (status.loaded, status.pid) {
+                (true, Some(pid)) => format!("running (pid {})", pid).green(),
+                (true, None) => "loaded, not running".yellow(),
+                (false, _) => "installed, not loaded".yellow(),
+            };
+            println!("Label:  {}", service::LABEL);
+            println!("State:  {}", state);
+            if let Some(code) = status.last_exit_code {
+                println!("Last exit: {}", code);
+            }
+            println!("Agent:  {}", paths.plist.display());
+            println!("Output: {}", paths.stdout_log.display());
+            println!("Errors: {}", paths.stderr_log.display());
+        }
+    }
 }
 
 fn get_severity_level(result: &ClueLyDetectionResult) -> String {
@@ -236,15 +3139,31 @@ fn get_severity_level(result: &ClueLyDetectionResult) -> String {
     }
 }
 
-fn get_evasion_techniques(result: &ClueLyDetectionResult) -> Vec<String> {
+/// One severity level up, for a detection with evidence of active capture
+fn escalate_severity(severity: &str) -> String {
+    match severity {
+        "Low" => "Medium".to_string(),
+        "Medium" | "High" => "High".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn get_evasion_techniques(result: &ClueLyDetectionResult) -> Vec<Finding> {
     let mut techniques = Vec::new();
     
     if result.screen_capture_evasion_count > 0 {
-        techniques.push(format!("Screen capture evasion ({} windows)", result.screen_capture_evasion_count));
+        techniques.push(
+            Finding::new(Rule::ScreenCaptureEvasion, format!("Screen capture evasion ({} windows)", result.screen_capture_evasion_count))
+                .with("windows", result.screen_capture_evasion_count),
+        );
     }
     
     if result.elevated_layer_count > 0 {
-        techniques.push(format!("Elevated layer positioning ({} windows)", result.elevated_layer_count));
+        techniques.push(
+            Finding::new(Rule::ElevatedLayer, format!("Elevated layer positioning ({} windows)", result.elevated_layer_count))
+                .with("windows", result.elevated_layer_count)
+                .with("max_layer", result.max_layer_detected),
+        );
     }
     
     techniques
@@ -0,0 +1,210 @@
+//! Menu bar (NSStatusItem) mode.
+//!
+//! AppKit is driven through the Objective-C runtime directly, in the same
+//! hand-written FFI style the driver uses for Core Graphics. All AppKit calls
+//! happen on the main thread: setup runs before `[NSApp run]`, and refreshes
+//! come from an NSTimer on the main run loop.
+
+use std::ffi::{c_void, CString};
+use std::fs;
+use std::mem;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use no_cluely_driver::{cluely_report_rust, detect_cluely_rust};
+
+use crate::get_severity_level;
+use crate::objc::{
+    class, class_addMethod, nsstring, objc_allocateClassPair, objc_msgSend, objc_registerClassPair,
+    sel, send, send_bool, send_f64, send_id, send_isize, Id, Sel,
+};
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+const NS_APPLICATION_ACTIVATION_POLICY_ACCESSORY: isize = 1;
+const NS_VARIABLE_STATUS_ITEM_LENGTH: f64 = -1.0;
+
+/// Menu entries updated on every refresh
+struct MenuItems {
+    status_item: Id,
+    status: Id,
+    windows: Id,
+    evasion: Id,
+    layers: Id,
+}
+
+// Only touched from the main thread
+unsafe impl Send for MenuItems {}
+unsafe impl Sync for MenuItems {}
+
+static ITEMS: OnceLock<MenuItems> = OnceLock::new();
+
+unsafe fn menu_item(title: &str, action: Option<&str>, target: Id) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel, Id, Sel, Id) -> Id =
+        mem::transmute(objc_msgSend as *const c_void);
+    let item = f(
+        send(class("NSMenuItem"), "alloc"),
+        sel("initWithTitle:action:keyEquivalent:"),
+        nsstring(title),
+        action.map(sel).unwrap_or(std::ptr::null()),
+        nsstring(""),
+    );
+    if !target.is_null() {
+        send_id(item, "setTarget:", target);
+    }
+    // Items without an action are informational rows
+    send_bool(item, "setEnabled:", action.is_some());
+    item
+}
+
+/// Register the controller class that receives timer and menu callbacks
+unsafe fn controller_class() -> Id {
+    let name = CString::new("CluelyMenuBarController").unwrap();
+    let controller = objc_allocateClassPair(class("NSObject"), name.as_ptr(), 0);
+    let types = CString::new("v@:@").unwrap();
+
+    class_addMethod(
+        controller,
+        sel("refresh:"),
+        refresh as *const c_void,
+        types.as_ptr(),
+    );
+    class_addMethod(
+        controller,
+        sel("showReport:"),
+        show_report as *const c_void,
+        types.as_ptr(),
+    );
+
+    objc_registerClassPair(controller);
+    controller
+}
+
+extern "C" fn refresh(_this: Id, _cmd: Sel, _sender: Id) {
+    let Some(items) = ITEMS.get() else {
+        return;
+    };
+
+    let result = detect_cluely_rust();
+    let (icon, status) = if result.is_detected {
+        ("🔴", "🚨 Cluely detected")
+    } else {
+        ("🟢", "✅ No Cluely detected")
+    };
+
+    unsafe {
+        let button = send(items.status_item, "button");
+        send_id(button, "setTitle:", nsstring(icon));
+        send_id(items.status, "setTitle:", nsstring(status));
+        send_id(
+            items.windows,
+            "setTitle:",
+            nsstring(&format!(
+                "Windows: {}  ·  Severity: {}",
+                result.window_count,
+                get_severity_level(&result)
+            )),
+        );
+        send_id(
+            items.evasion,
+            "setTitle:",
+            nsstring(&format!(
+                "Screen capture evasion: {}",
+                result.screen_capture_evasion_count
+            )),
+        );
+        send_id(
+            items.layers,
+            "setTitle:",
+            nsstring(&format!("Elevated layers: {}", result.elevated_layer_count)),
+        );
+    }
+}
+
+extern "C" fn show_report(_this: Id, _cmd: Sel, _sender: Id) {
+    let report = cluely_report_rust();
+
+    let path = std::env::temp_dir().join("cluely-detector-report.txt");
+    if fs::write(&path, report).is_ok() {
+        // Opens in TextEdit
+        let _ = Command::new("open").arg("-e").arg(&path).spawn();
+    }
+}
+
+/// Run the status item until the user chooses Quit
+pub fn run(interval: u64) {
+    unsafe {
+        let app = send(class("NSApplication"), "sharedApplication");
+        // Accessory apps have no Dock icon or app menu
+        send_isize(
+            app,
+            "setActivationPolicy:",
+            NS_APPLICATION_ACTIVATION_POLICY_ACCESSORY,
+        );
+
+        let controller = send(send(controller_class(), "alloc"), "init");
+
+        let status_bar = send(class("NSStatusBar"), "systemStatusBar");
+        let status_item = send_f64(
+            status_bar,
+            "statusItemWithLength:",
+            NS_VARIABLE_STATUS_ITEM_LENGTH,
+        );
+        send(status_item, "retain");
+
+        let menu = send(send(class("NSMenu"), "alloc"), "init");
+        send_bool(menu, "setAutoenablesItems:", false);
+
+        let items = MenuItems {
+            status_item,
+            status: menu_item("Scanning…", None, std::ptr::null_mut()),
+            windows: menu_item("", None, std::ptr::null_mut()),
+            evasion: menu_item("", None, std::ptr::null_mut()),
+            layers: menu_item("", None, std::ptr::null_mut()),
+        };
+        for item in [items.status, items.windows, items.evasion, items.layers] {
+            send_id(menu, "addItem:", item);
+        }
+        send_id(menu, "addItem:", send(class("NSMenuItem"), "separatorItem"));
+        send_id(
+            menu,
+            "addItem:",
+            menu_item("Show full report", Some("showReport:"), controller),
+        );
+        send_id(
+            menu,
+            "addItem:",
+            menu_item("Scan now", Some("refresh:"), controller),
+        );
+        send_id(menu, "addItem:", send(class("NSMenuItem"), "separatorItem"));
+        // No target: terminate: travels up the responder chain to NSApp
+        send_id(
+            menu,
+            "addItem:",
+            menu_item(
+                "Quit Cluely Detector",
+                Some("terminate:"),
+                std::ptr::null_mut(),
+            ),
+        );
+        send_id(status_item, "setMenu:", menu);
+
+        let _ = ITEMS.set(items);
+        refresh(controller, sel("refresh:"), std::ptr::null_mut());
+
+        let schedule: unsafe extern "C" fn(Id, Sel, f64, Id, Sel, Id, bool) -> Id =
+            mem::transmute(objc_msgSend as *const c_void);
+        schedule(
+            class("NSTimer"),
+            sel("scheduledTimerWithTimeInterval:target:selector:userInfo:repeats:"),
+            interval.max(1) as f64,
+            controller,
+            sel("refresh:"),
+            std::ptr::null_mut(),
+            true,
+        );
+
+        send(app, "run");
+    }
+}
@@ -0,0 +1,115 @@
+//! Alert deduplication and rate limiting.
+//!
+//! At most one notification is delivered per coalescing window. Events that
+//! arrive sooner are held until the window ends, and only the latest is
+//! delivered, and only if its findings differ from the last notification.
+//! A detection that flaps off and back on with the same windows therefore
+//! produces one alert rather than three.
+
+use std::time::{Duration, Instant};
+
+use crate::output::StateChangeEvent;
+
+/// Seconds between notifications when not configured
+pub const DEFAULT_COALESCE: u64 = 300;
+
+/// What makes two events the same finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Fingerprint {
+    /// A `tampered` event is never the same finding as a detection
+    event: &'static str,
+    detected: bool,
+    /// Sorted window IDs
+    windows: Vec<i32>,
+    techniques: Vec<String>,
+}
+
+/// An event queued for delivery with the windows it is about
+pub struct Alert {
+    event: StateChangeEvent,
+    fingerprint: Fingerprint,
+}
+
+impl Alert {
+    pub fn new(event: StateChangeEvent, window_ids: &[i32]) -> Self {
+        let mut windows = window_ids.to_vec();
+        windows.sort_unstable();
+        let fingerprint = Fingerprint {
+            event: event.event,
+            detected: event.detection.detected,
+            windows,
+            techniques: event
+                .detection
+                .evasion_techniques
+                .iter()
+                .map(|technique| technique.message.clone())
+                .collect(),
+        };
+        Self { event, fingerprint }
+    }
+
+    pub fn event(&self) -> &StateChangeEvent {
+        &self.event
+    }
+}
+
+pub struct Coalescer {
+    window: Duration,
+    /// The last delivered finding and when it was delivered
+    last: Option<(Fingerprint, Instant)>,
+    /// The latest event held back during the current window
+    pending: Option<Alert>,
+}
+
+impl Coalescer {
+    /// A zero `window` delivers every event that differs from the last one
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last: None,
+            pending: None,
+        }
+    }
+
+    /// Queue an event received at `now`; returns it if it is to be
+    /// delivered right away
+    pub fn push(&mut self, alert: Alert, now: Instant) -> Option<StateChangeEvent> {
+        match &self.last {
+            Some((_, sent)) if now.duration_since(*sent) < self.window => {
+                if self.pending.replace(alert).is_some() {
+                    tracing::debug!("superseded a held notification");
+                }
+                None
+            }
+            _ => self.deliver(alert, now),
+        }
+    }
+
+    /// When the held event is due; None if nothing is held
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref()?;
+        self.last.as_ref().map(|(_, sent)| *sent + self.window)
+    }
+
+    /// Release the held event once its window has ended, or at shutdown
+    pub fn flush(&mut self, now: Instant) -> Option<StateChangeEvent> {
+        let alert = self.pending.take()?;
+        self.deliver(alert, now)
+    }
+
+    fn deliver(&mut self, alert: Alert, now: Instant) -> Option<StateChangeEvent> {
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(fingerprint, _)| *fingerprint == alert.fingerprint)
+        {
+            tracing::debug!(
+                event = alert.event.event,
+                "dropped a notification identical to the last one"
+            );
+            return None;
+        }
+        self.last = Some((alert.fingerprint, now));
+        Some(alert.event)
+    }
+}
@@ -0,0 +1,52 @@
+use serde_json::json;
+
+use crate::config::DiscordConfig;
+use crate::output::StateChangeEvent;
+
+use super::{facts, headline, post_json, Notifier};
+
+const COLOR_DETECTED: u32 = 0xD70000;
+const COLOR_CLEAR: u32 = 0x2EB886;
+
+/// Posts detection state changes to a Discord channel webhook as an embed
+pub struct DiscordNotifier {
+    config: DiscordConfig,
+}
+
+impl DiscordNotifier {
+    pub fn new(config: DiscordConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let fields: Vec<_> = facts(event)
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value, "inline": true }))
+            .collect();
+
+        let mut payload = json!({
+            "embeds": [{
+                "title": headline(event),
+                "color": if event.is_alert() { COLOR_DETECTED } else { COLOR_CLEAR },
+                "fields": fields,
+                "timestamp": event.detection.timestamp,
+            }],
+        });
+        if let Some(username) = &self.config.username {
+            payload["username"] = json!(username);
+        }
+
+        post_json(
+            &self.config.url,
+            &payload.to_string(),
+            &[],
+            self.config.retries,
+        )
+    }
+}
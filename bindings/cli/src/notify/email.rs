@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::config::EmailConfig;
+use crate::output::StateChangeEvent;
+
+use super::{facts, headline, with_retries, Notifier};
+
+/// How long one attempt may take to connect and hand the message over
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Emails detection state changes through an SMTP server. The connection is
+/// always encrypted: with TLS from the start for `smtps://`, or upgraded
+/// with STARTTLS for `smtp://`.
+pub struct EmailNotifier {
+    transport: SmtpTransport,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    retries: u32,
+}
+
+impl EmailNotifier {
+    pub fn new(config: EmailConfig) -> Result<Self, String> {
+        let (implicit_tls, address) = if let Some(address) = config.server.strip_prefix("smtps://")
+        {
+            (true, address)
+        } else if let Some(address) = config.server.strip_prefix("smtp://") {
+            (false, address)
+        } else {
+            return Err(format!(
+                "Email server \"{}\" must start with smtp:// or smtps://",
+                config.server
+            ));
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|_| format!("Email server port \"{}\" is not a number", port))?,
+            ),
+            None => (address, if implicit_tls { 465 } else { 587 }),
+        };
+        if host.is_empty() {
+            return Err(format!("Email server \"{}\" has no host", config.server));
+        }
+
+        let builder = if implicit_tls {
+            SmtpTransport::relay(host)
+        } else {
+            SmtpTransport::starttls_relay(host)
+        }
+        .map_err(|e| format!("Email server \"{}\": {}", config.server, e))?;
+        let mut builder = builder.port(port).timeout(Some(TIMEOUT));
+        if let Some(username) = config.username {
+            builder = builder.credentials(Credentials::new(
+                username,
+                config.password.unwrap_or_default(),
+            ));
+        }
+
+        let parse = |address: &str| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Email address \"{}\" is invalid: {}", address, e))
+        };
+        if config.to.is_empty() {
+            return Err("Email notifier has no `to` addresses".to_string());
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from: parse(&config.from)?,
+            to: config
+                .to
+                .iter()
+                .map(|address| parse(address))
+                .collect::<Result<_, _>>()?,
+            retries: config.retries,
+        })
+    }
+
+    fn message(&self, event: &StateChangeEvent) -> Result<Message, String> {
+        let mut body = String::new();
+        for (name, value) in facts(event) {
+            body.push_str(&format!("{}: {}\n", name, value));
+        }
+
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(headline(event));
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        builder
+            .body(body)
+            .map_err(|e| format!("Failed to build the email: {}", e))
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let message = self.message(event)?;
+        with_retries(self.retries, || {
+            self.transport
+                .send(&message)
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
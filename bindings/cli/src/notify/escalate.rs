@@ -0,0 +1,120 @@
+//! Escalation of long-lasting detections.
+//!
+//! A notifier with `escalate_after` hears nothing about a detection until it
+//! has lasted that long, then gets a "still detected" alert and every
+//! notification after it until the detection clears. A banner can go out on
+//! the first scan, Slack after ten minutes and email after an hour. The
+//! clock runs from the first `detected` event received, not the first one
+//! delivered, so coalescing doesn't push escalation back.
+
+use std::time::{Duration, Instant};
+
+use crate::output::StateChangeEvent;
+
+use super::Notifier;
+
+/// A notifier and how long a detection must last before it is told
+pub struct Channel {
+    pub notifier: Box<dyn Notifier>,
+    /// None to tell it of every change at once
+    pub escalate_after: Option<Duration>,
+}
+
+pub struct Escalator {
+    channels: Vec<Channel>,
+    /// Whether each channel has been told of the current detection
+    told: Vec<bool>,
+    /// The latest `detected` event and when the detection began
+    detection: Option<(StateChangeEvent, Instant)>,
+}
+
+impl Escalator {
+    pub fn new(channels: Vec<Channel>) -> Self {
+        Self {
+            told: vec![false; channels.len()],
+            channels,
+            detection: None,
+        }
+    }
+
+    /// Track an event as it is received, before coalescing
+    pub fn observe(&mut self, event: &StateChangeEvent, now: Instant) {
+        match (event.event, &mut self.detection) {
+            ("detected", Some((latest, _))) => *latest = event.clone(),
+            ("detected", None) => self.detection = Some((event.clone(), now)),
+            ("cleared", _) => self.detection = None,
+            _ => {}
+        }
+    }
+
+    /// When the next channel is due to be told of the current detection;
+    /// None if there is no detection or no channel left to tell
+    pub fn deadline(&self) -> Option<Instant> {
+        let (_, since) = self.detection.as_ref()?;
+        self.pending().map(|(_, after)| *since + after).min()
+    }
+
+    /// Tell every channel whose threshold has passed by `now` that the
+    /// detection is still there
+    pub fn escalate(&mut self, now: Instant) {
+        let Some((latest, since)) = &self.detection else {
+            return;
+        };
+        let due: Vec<(usize, Duration)> = self
+            .pending()
+            .filter(|(_, after)| *since + *after <= now)
+            .collect();
+        for (index, after) in due {
+            let mut event = latest.clone();
+            event.detail = Some(format!("Still detected after {}", describe(after)));
+            send(&*self.channels[index].notifier, &event);
+            self.told[index] = true;
+        }
+    }
+
+    /// Deliver an event that made it through coalescing. Detections and
+    /// clears go to the channels that are told at once and to those already
+    /// escalated to; tampering goes to every channel.
+    pub fn deliver(&mut self, event: &StateChangeEvent) {
+        for (channel, told) in self.channels.iter().zip(&self.told) {
+            if event.event == "tampered" || channel.escalate_after.is_none() || *told {
+                send(&*channel.notifier, event);
+            }
+        }
+        if event.event == "cleared" {
+            self.told.fill(false);
+        }
+    }
+
+    /// Channels not yet told of the current detection, with their thresholds
+    fn pending(&self) -> impl Iterator<Item = (usize, Duration)> + '_ {
+        self.channels
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.told[*index])
+            .filter_map(|(index, channel)| channel.escalate_after.map(|after| (index, after)))
+    }
+}
+
+fn send(notifier: &dyn Notifier, event: &StateChangeEvent) {
+    if let Err(e) = notifier.notify(event) {
+        tracing::warn!(
+            notifier = notifier.name(),
+            error = %e,
+            "notification failed"
+        );
+    }
+}
+
+/// A threshold in words, e.g. "10 minutes" or "1 hour"
+pub fn describe(after: Duration) -> String {
+    let seconds = after.as_secs();
+    let (count, unit) = if seconds.is_multiple_of(3600) {
+        (seconds / 3600, "hour")
+    } else if seconds.is_multiple_of(60) {
+        (seconds / 60, "minute")
+    } else {
+        (seconds, "second")
+    };
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
@@ -0,0 +1,229 @@
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::config::GelfConfig;
+use crate::output::StateChangeEvent;
+
+use super::{headline, with_retries, Notifier};
+
+/// Port Graylog's GELF inputs listen on by default
+const DEFAULT_PORT: u16 = 12201;
+/// Largest UDP datagram Graylog accepts without chunking
+const MAX_DATAGRAM: usize = 8192;
+/// Chunk header: magic bytes, message ID, sequence number and count
+const CHUNK_HEADER: usize = 12;
+/// Most chunks one message may be split into
+const MAX_CHUNKS: usize = 128;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Protocol {
+    Udp,
+    Tcp,
+}
+
+/// Sends detection state changes to a Graylog GELF input over UDP or TCP.
+///
+/// Each message carries the detection as additional fields (`_severity`,
+/// `_window_count`...) plus any configured ones, so Graylog streams and
+/// alerts can match on them without parsing the message text.
+pub struct GelfNotifier {
+    protocol: Protocol,
+    address: String,
+    fields: Map<String, Value>,
+    retries: u32,
+}
+
+impl GelfNotifier {
+    pub fn new(config: GelfConfig) -> Result<Self, String> {
+        let (protocol, address) = if let Some(address) = config.address.strip_prefix("udp://") {
+            (Protocol::Udp, address)
+        } else if let Some(address) = config.address.strip_prefix("tcp://") {
+            (Protocol::Tcp, address)
+        } else {
+            return Err(format!(
+                "GELF address \"{}\" must start with udp:// or tcp://",
+                config.address
+            ));
+        };
+        let address = address.trim_end_matches('/');
+        if address.is_empty() {
+            return Err(format!("GELF address \"{}\" has no host", config.address));
+        }
+        let address = if address
+            .rsplit_once(':')
+            .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+        {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+
+        let mut fields = Map::new();
+        for (name, value) in config.fields {
+            let name = name.strip_prefix('_').unwrap_or(&name).to_string();
+            // GELF reserves `_id`, and field names are limited to these characters
+            if name.is_empty()
+                || name == "id"
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+            {
+                return Err(format!(
+                    "GELF field \"{}\" must be letters, digits, '_', '.' or '-', and not \"id\"",
+                    name
+                ));
+            }
+            fields.insert(format!("_{}", name), Value::String(value));
+        }
+
+        Ok(Self {
+            protocol,
+            address,
+            fields,
+            retries: config.retries,
+        })
+    }
+
+    /// The GELF 1.1 message for `event`
+    fn message(&self, event: &StateChangeEvent) -> Value {
+        let detection = &event.detection;
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&detection.timestamp)
+            .map(|time| time.timestamp_millis() as f64 / 1000.0)
+            .unwrap_or_else(|_| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64()
+            });
+        // Syslog levels: alert, critical, error, warning, informational
+        let level = match (detection.detected, detection.severity.as_str()) {
+            _ if event.event == "tampered" => 1,
+            (false, _) => 6,
+            (true, "High") => 2,
+            (true, "Medium") => 3,
+            (true, _) => 4,
+        };
+        let host = hostname::get()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "unknown".to_string());
+
+        let mut message = json!({
+            "version": "1.1",
+            "host": host,
+            "short_message": headline(event),
+            "timestamp": timestamp,
+            "level": level,
+            "_event": event.event,
+            "_detected": detection.detected,
+            "_severity": detection.severity,
+            "_window_count": detection.window_count,
+            "_screen_capture_evasion_count": detection.screen_capture_evasion_count,
+            "_elevated_layer_count": detection.elevated_layer_count,
+            "_schema_version": event.schema_version,
+        });
+        if let Some(detail) = &event.detail {
+            message["_detail"] = json!(detail);
+        }
+        if !detection.evasion_techniques.is_empty() {
+            let messages: Vec<&str> = detection
+                .evasion_techniques
+                .iter()
+                .map(|technique| technique.message.as_str())
+                .collect();
+            let rule_ids: Vec<&str> = detection
+                .evasion_techniques
+                .iter()
+                .map(|technique| technique.rule_id.as_str())
+                .collect();
+            message["full_message"] = json!(messages.join("\n"));
+            message["_evasion_techniques"] = json!(messages.join(", "));
+            message["_rule_ids"] = json!(rule_ids.join(","));
+        }
+        if let Value::Object(message) = &mut message {
+            message.extend(self.fields.clone());
+        }
+        message
+    }
+
+    /// The first address the configured host resolves to
+    fn resolve(&self) -> Result<SocketAddr, String> {
+        self.address
+            .to_socket_addrs()
+            .map_err(|e| format!("{}: {}", self.address, e))?
+            .next()
+            .ok_or_else(|| format!("{} did not resolve", self.address))
+    }
+
+    fn send_udp(&self, body: &[u8]) -> Result<(), String> {
+        let address = self.resolve()?;
+        let local = if address.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+        socket
+            .connect(address)
+            .map_err(|e| format!("{}: {}", self.address, e))?;
+        if body.len() <= MAX_DATAGRAM {
+            return socket.send(body).map(drop).map_err(|e| e.to_string());
+        }
+
+        let chunks: Vec<&[u8]> = body.chunks(MAX_DATAGRAM - CHUNK_HEADER).collect();
+        if chunks.len() > MAX_CHUNKS {
+            return Err(format!(
+                "message of {} bytes is too large for GELF UDP",
+                body.len()
+            ));
+        }
+        // Only has to be unique among this sender's messages in flight
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+            ^ u64::from(std::process::id());
+        for (sequence, chunk) in chunks.iter().enumerate() {
+            let mut datagram = Vec::with_capacity(CHUNK_HEADER + chunk.len());
+            datagram.extend_from_slice(&[0x1e, 0x0f]);
+            datagram.extend_from_slice(&id.to_be_bytes());
+            datagram.push(sequence as u8);
+            datagram.push(chunks.len() as u8);
+            datagram.extend_from_slice(chunk);
+            socket.send(&datagram).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn send_tcp(&self, body: &[u8]) -> Result<(), String> {
+        let address = self.resolve()?;
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)
+            .map_err(|e| format!("{}: {}", self.address, e))?;
+        stream
+            .set_write_timeout(Some(TIMEOUT))
+            .map_err(|e| e.to_string())?;
+        // Messages over TCP are delimited by a null byte
+        stream
+            .write_all(body)
+            .and_then(|()| stream.write_all(&[0]))
+            .and_then(|()| stream.flush())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Notifier for GelfNotifier {
+    fn name(&self) -> &str {
+        "gelf"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let body = serde_json::to_vec(&self.message(event)).unwrap();
+        with_retries(self.retries, || match self.protocol {
+            Protocol::Udp => self.send_udp(&body),
+            Protocol::Tcp => self.send_tcp(&body),
+        })
+    }
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use crate::config::{ClickAction, MacosConfig};
+use crate::output::{OutputFormat, StateChangeEvent};
+use crate::report;
+
+use super::Notifier;
+
+/// Where `terminal-notifier` is looked for besides `PATH`, which is minimal
+/// under launchd
+const TERMINAL_NOTIFIER_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin"];
+
+/// Seconds the detection alert stays up when nobody answers it
+const ALERT_TIMEOUT: u32 = 600;
+
+/// Shows a macOS notification banner.
+///
+/// Clicking a detection notification opens the HTML report of the detection
+/// or runs `cluely-detector report` in Terminal (`open` in the
+/// configuration). A banner posted by `osascript` can't take a click action,
+/// so that needs `terminal-notifier` (`brew install terminal-notifier`);
+/// without it, detections are shown as an alert with a button instead.
+/// `osascript` banners are attributed to Script Editor, so it must be
+/// allowed to post notifications in System Settings > Notifications.
+pub struct MacosNotifier {
+    config: MacosConfig,
+}
+
+impl MacosNotifier {
+    pub fn new(config: MacosConfig) -> Self {
+        Self { config }
+    }
+
+    fn message(event: &StateChangeEvent) -> (&'static str, String) {
+        let detection = &event.detection;
+        if let Some(detail) = event.detail.as_ref().filter(|_| event.event == "tampered") {
+            ("⚠️ Cluely detector tampered with", detail.clone())
+        } else if detection.detected {
+            (
+                "🚨 Cluely detected",
+                format!(
+                    "{} window(s), {} hidden from screen capture. Severity: {}",
+                    detection.window_count,
+                    detection.screen_capture_evasion_count,
+                    detection.severity
+                ),
+            )
+        } else {
+            (
+                "✅ Cluely stopped",
+                "Cluely monitoring is no longer running.".to_string(),
+            )
+        }
+    }
+
+    /// Prepare what clicking the notification opens: a fresh HTML report,
+    /// or a `.command` file that Terminal runs
+    fn click_target(&self) -> Result<Option<PathBuf>, String> {
+        let dir = dirs::data_dir()
+            .map(|dir| dir.join("no-cluely"))
+            .ok_or("no home directory for the report")?;
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let (path, contents) = match self.config.open {
+            ClickAction::None => return Ok(None),
+            ClickAction::Report => {
+                let data = report::ReportData::collect(true);
+                (
+                    dir.join("latest-report.html"),
+                    report::render(&data, OutputFormat::Html)?,
+                )
+            }
+            ClickAction::Terminal => {
+                let program = std::env::current_exe()
+                    .map_err(|e| format!("Could not locate the cluely-detector binary: {}", e))?;
+                let script = format!(
+                    "#!/bin/sh\nexec {} report\n",
+                    shell_quote(&program.to_string_lossy())
+                );
+                (dir.join("open-report.command"), script.into_bytes())
+            }
+        };
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        if self.config.open == ClickAction::Terminal {
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+        }
+        Ok(Some(path))
+    }
+}
+
+/// Quote a string as an AppleScript string literal
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote a string for `sh`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// `file://` URL of an absolute path, with everything but unreserved
+/// characters and slashes percent-encoded
+fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+fn terminal_notifier() -> Option<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(TERMINAL_NOTIFIER_DIRS.iter().map(PathBuf::from))
+        .map(|dir| dir.join("terminal-notifier"))
+        .find(|path| path.is_file())
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to run {:?}: {}", command.get_program(), e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+impl Notifier for MacosNotifier {
+    fn name(&self) -> &str {
+        "macos"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let (subtitle, message) = Self::message(event);
+        // Only a new detection has something to open; a failure to prepare
+        // it still leaves the banner
+        let target = if event.event == "detected" {
+            self.click_target().unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "notification will not open the report");
+                None
+            })
+        } else {
+            None
+        };
+
+        if let Some(notifier) = terminal_notifier() {
+            let mut command = Command::new(notifier);
+            command
+                .args(["-title", "Cluely Detector"])
+                .args(["-subtitle", subtitle])
+                .args(["-message", &message])
+                .args(["-group", "cluely-detector"]);
+            if let Some(target) = &target {
+                command.arg("-open").arg(file_url(target));
+            }
+            return run(&mut command);
+        }
+
+        let Some(target) = target else {
+            let script = format!(
+                "display notification {} with title {} subtitle {}",
+                applescript_string(&message),
+                applescript_string("Cluely Detector"),
+                applescript_string(subtitle)
+            );
+            return run(Command::new("osascript").arg("-e").arg(&script));
+        };
+
+        // The alert waits for an answer, which mustn't hold up the next
+        // notification
+        let button = match self.config.open {
+            ClickAction::Terminal => "Open in Terminal",
+            _ => "Open Report",
+        };
+        let script = format!(
+            "set answer to display alert {} message {} as critical buttons {{\"Dismiss\", {}}} default button {} giving up after {}\n\
+             if button returned of answer is {} then do shell script \"open \" & quoted form of {}",
+            applescript_string(subtitle),
+            applescript_string(&message),
+            applescript_string(button),
+            applescript_string(button),
+            ALERT_TIMEOUT,
+            applescript_string(button),
+            applescript_string(&target.to_string_lossy())
+        );
+        let mut child = Command::new("osascript")
+            .arg("-e")
+            .arg(&script)
+            .spawn()
+            .map_err(|e| format!("failed to run osascript: {}", e))?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
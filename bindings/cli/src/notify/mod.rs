@@ -0,0 +1,278 @@
+//! Notification backends for monitor mode.
+//!
+//! Notifiers receive detection state changes. Delivery happens on a
+//! single worker thread so slow endpoints and retries never delay scanning,
+//! while events still arrive in the order they occurred. Repeated alerts
+//! about the same findings are coalesced first (see [`coalesce`]), and
+//! notifiers with a threshold only hear of detections that outlast it (see
+//! [`escalate`]).
+
+mod coalesce;
+mod discord;
+mod email;
+mod escalate;
+mod gelf;
+mod macos;
+mod mqtt;
+mod slack;
+mod sound;
+mod teams;
+mod webhook;
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::{NotifierConfig, NotifierEntry};
+use crate::output::StateChangeEvent;
+use crate::timestamp;
+use coalesce::{Alert, Coalescer};
+use escalate::Escalator;
+
+pub use coalesce::DEFAULT_COALESCE;
+pub use discord::DiscordNotifier;
+pub use email::EmailNotifier;
+pub use escalate::{describe as describe_threshold, Channel};
+pub use gelf::GelfNotifier;
+pub use macos::MacosNotifier;
+pub use mqtt::MqttNotifier;
+pub use slack::SlackNotifier;
+pub use sound::SoundNotifier;
+pub use teams::TeamsNotifier;
+pub use webhook::WebhookNotifier;
+
+/// Longest pause between delivery attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A destination for detection state changes
+pub trait Notifier: Send {
+    /// Short name used in error messages
+    fn name(&self) -> &str;
+
+    /// Deliver one event, returning a description of the failure if it could not be sent
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String>;
+}
+
+/// Build notifiers for every configured backend, with their escalation
+/// thresholds
+pub fn from_config(entries: &[NotifierEntry]) -> Result<Vec<Channel>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let notifier: Box<dyn Notifier> = match &entry.notifier {
+                NotifierConfig::Webhook(webhook) => Box::new(WebhookNotifier::new(webhook.clone())),
+                NotifierConfig::Teams(teams) => Box::new(TeamsNotifier::new(teams.clone())),
+                NotifierConfig::Discord(discord) => Box::new(DiscordNotifier::new(discord.clone())),
+                NotifierConfig::Slack(slack) => Box::new(SlackNotifier::new(slack.clone())),
+                NotifierConfig::Email(email) => Box::new(EmailNotifier::new(email.clone())?),
+                NotifierConfig::Mqtt(mqtt) => Box::new(MqttNotifier::new(mqtt.clone())?),
+                NotifierConfig::Gelf(gelf) => Box::new(GelfNotifier::new(gelf.clone())?),
+                NotifierConfig::Macos(macos) => Box::new(MacosNotifier::new(macos.clone())),
+                NotifierConfig::Sound(sound) => Box::new(SoundNotifier::new(&sound.sound)?),
+            };
+            Ok(Channel {
+                notifier,
+                escalate_after: entry
+                    .escalate_after
+                    .filter(|seconds| *seconds > 0)
+                    .map(Duration::from_secs),
+            })
+        })
+        .collect()
+}
+
+/// One-line summary for chat-style notifiers
+fn headline(event: &StateChangeEvent) -> String {
+    let host = hostname::get()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "unknown host".to_string());
+
+    if event.event == "tampered" {
+        format!("⚠️ Cluely detector on {} was tampered with", host)
+    } else if event.detection.detected {
+        format!("🚨 Cluely detected on {}", host)
+    } else {
+        format!("✅ Cluely no longer detected on {}", host)
+    }
+}
+
+/// Label/value pairs describing the detection, for card-style notifiers
+fn facts(event: &StateChangeEvent) -> Vec<(&'static str, String)> {
+    let detection = &event.detection;
+    let mut facts = Vec::new();
+    if let Some(detail) = &event.detail {
+        facts.push(("What happened", detail.clone()));
+    }
+    facts.extend([
+        ("Severity", detection.severity.clone()),
+        ("Cluely windows", detection.window_count.to_string()),
+        (
+            "Screen capture evasion",
+            detection.screen_capture_evasion_count.to_string(),
+        ),
+        (
+            "Elevated layers",
+            detection.elevated_layer_count.to_string(),
+        ),
+    ]);
+    if !detection.evasion_techniques.is_empty() {
+        let techniques: Vec<&str> = detection
+            .evasion_techniques
+            .iter()
+            .map(|technique| technique.message.as_str())
+            .collect();
+        facts.push(("Techniques", techniques.join(", ")));
+    }
+    facts.push(("Time", timestamp::reformat(&detection.timestamp)));
+    facts
+}
+
+/// Delivers events to a set of notifiers on a background thread, at most
+/// one per `coalesce` window, escalating long-lasting detections
+pub struct Dispatcher {
+    sender: Option<Sender<Alert>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Dispatcher {
+    pub fn new(channels: Vec<Channel>, coalesce: Duration) -> Self {
+        if channels.is_empty() {
+            return Self {
+                sender: None,
+                worker: None,
+            };
+        }
+
+        let (sender, receiver) = mpsc::channel::<Alert>();
+        let worker = thread::spawn(move || {
+            let mut coalescer = Coalescer::new(coalesce);
+            let mut escalator = Escalator::new(channels);
+            loop {
+                let deadline = match (coalescer.deadline(), escalator.deadline()) {
+                    (Some(held), Some(escalation)) => Some(held.min(escalation)),
+                    (held, escalation) => held.or(escalation),
+                };
+                let received = match deadline {
+                    Some(deadline) => {
+                        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    }
+                    None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                let now = Instant::now();
+                let event = match received {
+                    Ok(alert) => {
+                        escalator.observe(alert.event(), now);
+                        coalescer.push(alert, now)
+                    }
+                    Err(RecvTimeoutError::Timeout) => {
+                        escalator.escalate(now);
+                        // The timeout may have been for an escalation
+                        // rather than the held event
+                        match coalescer.deadline() {
+                            Some(held) if held <= now => coalescer.flush(now),
+                            _ => None,
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        // Don't lose a held event when monitoring stops
+                        if let Some(event) = coalescer.flush(now) {
+                            escalator.deliver(&event);
+                        }
+                        break;
+                    }
+                };
+                if let Some(event) = event {
+                    escalator.deliver(&event);
+                }
+            }
+        });
+
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+        }
+    }
+
+    /// Queue an event about the given Cluely windows
+    pub fn dispatch(&self, event: StateChangeEvent, window_ids: &[i32]) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Alert::new(event, window_ids));
+        }
+    }
+
+    /// Wait for queued notifications to be delivered
+    pub fn finish(mut self) {
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Why an attempt to send failed, and so whether another may succeed
+enum SendError {
+    /// A transport error or an overloaded server; worth trying again
+    Retry(String),
+    /// Rejected in a way a retry would repeat, such as a 4xx response
+    Fatal(String),
+}
+
+impl From<String> for SendError {
+    fn from(error: String) -> Self {
+        SendError::Retry(error)
+    }
+}
+
+/// Run `send` until it succeeds, at most `retries` more times, with
+/// exponential backoff in between. A `Fatal` error stops at once.
+fn with_retries<E: Into<SendError>>(
+    retries: u32,
+    mut send: impl FnMut() -> Result<(), E>,
+) -> Result<(), String> {
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 0;
+
+    loop {
+        let error = match send().map_err(Into::into) {
+            Ok(()) => return Ok(()),
+            Err(SendError::Fatal(e)) => return Err(e),
+            Err(SendError::Retry(e)) => e,
+        };
+        if attempt >= retries {
+            return Err(format!(
+                "giving up after {} attempts: {}",
+                attempt + 1,
+                error
+            ));
+        }
+        attempt += 1;
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// POST a JSON body, retrying transport errors, 429 and 5xx responses with
+/// exponential backoff
+pub fn post_json(
+    url: &str,
+    body: &str,
+    headers: &[(&str, String)],
+    retries: u32,
+) -> Result<(), String> {
+    with_retries(retries, || {
+        let mut request = ureq::post(url)
+            .timeout(Duration::from_secs(10))
+            .set("Content-Type", "application/json");
+        for (name, value) in headers {
+            request = request.set(name, value);
+        }
+
+        match request.send_string(body) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(code, _)) if code != 429 && code < 500 => Err(
+                SendError::Fatal(format!("{} responded with HTTP {}", url, code)),
+            ),
+            Err(e) => Err(SendError::Retry(e.to_string())),
+        }
+    })
+}
@@ -0,0 +1,131 @@
+use std::fs;
+use std::time::{Duration, Instant};
+
+use rumqttc::{Client, Event, MqttOptions, Outgoing, Packet, QoS, Transport};
+
+use crate::config::MqttConfig;
+use crate::output::StateChangeEvent;
+
+use super::{with_retries, Notifier};
+
+/// How long one attempt may take to connect, publish and be acknowledged
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Publishes the JSON state-change event to an MQTT broker.
+///
+/// Each event gets its own short connection: state changes are rare, and a
+/// broker restart between them then never leaves a stale session behind.
+/// Messages are retained by default, so a subscriber that connects later
+/// (Home Assistant after a restart, say) still sees the current state.
+pub struct MqttNotifier {
+    options: MqttOptions,
+    topic: String,
+    qos: QoS,
+    retain: bool,
+    retries: u32,
+}
+
+impl MqttNotifier {
+    pub fn new(config: MqttConfig) -> Result<Self, String> {
+        let (tls, address) = if let Some(address) = config.broker.strip_prefix("mqtts://") {
+            (true, address)
+        } else if let Some(address) = config.broker.strip_prefix("mqtt://") {
+            (false, address)
+        } else {
+            return Err(format!(
+                "MQTT broker \"{}\" must start with mqtt:// or mqtts://",
+                config.broker
+            ));
+        };
+        let address = address.trim_end_matches('/');
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .map_err(|_| format!("MQTT broker port \"{}\" is not a number", port))?,
+            ),
+            None => (address, if tls { 8883 } else { 1883 }),
+        };
+        if host.is_empty() {
+            return Err(format!("MQTT broker \"{}\" has no host", config.broker));
+        }
+        let qos = rumqttc::qos(config.qos)
+            .map_err(|_| format!("MQTT qos {} is not 0, 1 or 2", config.qos))?;
+
+        let hostname = hostname::get()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|_| "unknown".to_string());
+        let client_id = config
+            .client_id
+            .unwrap_or_else(|| format!("cluely-detector-{}", hostname));
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = config.username {
+            options.set_credentials(username, config.password.unwrap_or_default());
+        }
+        if let Some(ca_file) = &config.ca_file {
+            let ca = fs::read(ca_file)
+                .map_err(|e| format!("Failed to read {}: {}", ca_file.display(), e))?;
+            options.set_transport(Transport::tls(ca, None, None));
+        } else if tls {
+            options.set_transport(Transport::tls_with_default_config());
+        }
+
+        Ok(Self {
+            options,
+            topic: config
+                .topic
+                .unwrap_or_else(|| format!("cluely-detector/{}/state", hostname)),
+            qos,
+            retain: config.retain,
+            retries: config.retries,
+        })
+    }
+
+    /// Connect, publish `body` and wait until the broker has it
+    fn publish(&self, body: &str) -> Result<(), String> {
+        let (client, mut connection) = Client::new(self.options.clone(), 10);
+        client
+            .publish(self.topic.as_str(), self.qos, self.retain, body)
+            .map_err(|e| e.to_string())?;
+
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let event = connection
+                .recv_timeout(remaining)
+                .map_err(|_| format!("no acknowledgement within {} seconds", TIMEOUT.as_secs()))?
+                .map_err(|e| e.to_string())?;
+            let delivered = matches!(
+                (self.qos, event),
+                (QoS::AtMostOnce, Event::Outgoing(Outgoing::Publish(_)))
+                    | (QoS::AtLeastOnce, Event::Incoming(Packet::PubAck(_)))
+                    | (QoS::ExactlyOnce, Event::Incoming(Packet::PubComp(_)))
+            );
+            if delivered {
+                break;
+            }
+        }
+
+        // Say goodbye, so the broker doesn't report an unexpected disconnect
+        if client.disconnect().is_ok() {
+            while let Ok(Ok(event)) = connection.recv_timeout(Duration::from_secs(1)) {
+                if event == Event::Outgoing(Outgoing::Disconnect) {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Notifier for MqttNotifier {
+    fn name(&self) -> &str {
+        "mqtt"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let body = serde_json::to_string(event).unwrap();
+        with_retries(self.retries, || self.publish(&body))
+    }
+}
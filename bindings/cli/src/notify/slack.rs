@@ -0,0 +1,49 @@
+use serde_json::json;
+
+use crate::config::SlackConfig;
+use crate::output::StateChangeEvent;
+
+use super::{facts, headline, post_json, Notifier};
+
+/// Posts detection state changes to a Slack channel through an incoming
+/// webhook
+pub struct SlackNotifier {
+    config: SlackConfig,
+}
+
+impl SlackNotifier {
+    pub fn new(config: SlackConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let headline = headline(event);
+        let fields: Vec<_> = facts(event)
+            .into_iter()
+            .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+            .collect();
+
+        // `text` is what notifications and clients without blocks show
+        let payload = json!({
+            "text": headline,
+            "blocks": [
+                { "type": "header", "text": { "type": "plain_text", "text": headline } },
+                // A section takes at most 10 fields
+                { "type": "section", "fields": fields.into_iter().take(10).collect::<Vec<_>>() },
+            ],
+        });
+
+        post_json(
+            &self.config.url,
+            &payload.to_string(),
+            &[],
+            self.config.retries,
+        )
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+
+use crate::output::StateChangeEvent;
+
+use super::Notifier;
+
+/// Directories searched for named sounds, in the same order as macOS
+const SOUND_DIRS: &[&str] = &[
+    "~/Library/Sounds",
+    "/Library/Sounds",
+    "/System/Library/Sounds",
+];
+const SOUND_EXTENSIONS: &[&str] = &["aiff", "wav", "mp3", "m4a", "caf"];
+
+/// Plays an alarm through `afplay` when Cluely is first detected
+pub struct SoundNotifier {
+    path: PathBuf,
+}
+
+impl SoundNotifier {
+    /// `sound` is either a path to an audio file or a system sound name such as "Sosumi"
+    pub fn new(sound: &str) -> Result<Self, String> {
+        resolve_sound(sound)
+            .map(|path| Self { path })
+            .ok_or_else(|| format!("Sound '{}' not found", sound))
+    }
+}
+
+fn resolve_sound(sound: &str) -> Option<PathBuf> {
+    let path = Path::new(sound);
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+
+    let home = dirs::home_dir().unwrap_or_default();
+    SOUND_DIRS
+        .iter()
+        .map(|dir| match dir.strip_prefix("~/") {
+            Some(relative) => home.join(relative),
+            None => PathBuf::from(dir),
+        })
+        .flat_map(|dir| {
+            SOUND_EXTENSIONS
+                .iter()
+                .map(move |extension| dir.join(format!("{}.{}", sound, extension)))
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+impl Notifier for SoundNotifier {
+    fn name(&self) -> &str {
+        "sound"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        if !event.is_alert() {
+            return Ok(());
+        }
+
+        // Don't wait for playback so other notifiers aren't held up, but
+        // reap afplay so the monitor doesn't collect zombies
+        let mut child = Command::new("afplay")
+            .arg(&self.path)
+            .spawn()
+            .map_err(|e| format!("failed to run afplay: {}", e))?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
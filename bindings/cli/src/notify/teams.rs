@@ -0,0 +1,84 @@
+use serde_json::{json, Value};
+
+use crate::config::{TeamsCard, TeamsConfig};
+use crate::output::StateChangeEvent;
+
+use super::{facts, headline, post_json, Notifier};
+
+/// Posts detection state changes to a Microsoft Teams channel
+pub struct TeamsNotifier {
+    config: TeamsConfig,
+}
+
+impl TeamsNotifier {
+    pub fn new(config: TeamsConfig) -> Self {
+        Self { config }
+    }
+
+    fn adaptive_card(event: &StateChangeEvent) -> Value {
+        let facts: Vec<_> = facts(event)
+            .into_iter()
+            .map(|(title, value)| json!({ "title": title, "value": value }))
+            .collect();
+
+        json!({
+            "type": "message",
+            "attachments": [{
+                "contentType": "application/vnd.microsoft.card.adaptive",
+                "content": {
+                    "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                    "type": "AdaptiveCard",
+                    "version": "1.4",
+                    "body": [
+                        {
+                            "type": "TextBlock",
+                            "text": headline(event),
+                            "weight": "Bolder",
+                            "size": "Medium",
+                            "wrap": true,
+                            "color": if event.is_alert() { "Attention" } else { "Good" },
+                        },
+                        { "type": "FactSet", "facts": facts },
+                    ],
+                },
+            }],
+        })
+    }
+
+    fn message_card(event: &StateChangeEvent) -> Value {
+        let title = headline(event);
+        let facts: Vec<_> = facts(event)
+            .into_iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+
+        json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": title,
+            "themeColor": if event.is_alert() { "D70000" } else { "2EB886" },
+            "title": title,
+            "sections": [{ "facts": facts }],
+        })
+    }
+}
+
+impl Notifier for TeamsNotifier {
+    fn name(&self) -> &str {
+        "teams"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let payload = match self.config.card {
+            TeamsCard::Adaptive => Self::adaptive_card(event),
+            TeamsCard::Message => Self::message_card(event),
+        };
+
+        post_json(
+            &self.config.url,
+            &payload.to_string(),
+            &[],
+            self.config.retries,
+        )
+    }
+}
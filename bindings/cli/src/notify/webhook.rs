@@ -0,0 +1,48 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::WebhookConfig;
+use crate::output::StateChangeEvent;
+
+use super::{post_json, Notifier};
+
+/// POSTs the JSON state-change event to an arbitrary URL.
+///
+/// When a secret is configured the body is signed with HMAC-SHA256 and the
+/// hex digest is sent as `X-Cluely-Signature: sha256=<digest>`.
+pub struct WebhookNotifier {
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self { config }
+    }
+
+    fn signature(&self, body: &str) -> Option<String> {
+        let secret = self.config.secret.as_ref()?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).ok()?;
+        mac.update(body.as_bytes());
+        Some(format!(
+            "sha256={}",
+            hex::encode(mac.finalize().into_bytes())
+        ))
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn notify(&self, event: &StateChangeEvent) -> Result<(), String> {
+        let body = serde_json::to_string(event).unwrap();
+        let headers: Vec<_> = self
+            .signature(&body)
+            .map(|signature| ("X-Cluely-Signature", signature))
+            .into_iter()
+            .collect();
+
+        post_json(&self.config.url, &body, &headers, self.config.retries)
+    }
+}
@@ -0,0 +1,71 @@
+//! Objective-C runtime bindings shared by the AppKit integrations: menu bar
+//! mode and the monitor's workspace observer.
+
+use std::ffi::{c_void, CString};
+use std::mem;
+use std::os::raw::c_char;
+
+pub type Id = *mut c_void;
+pub type Sel = *const c_void;
+
+#[link(name = "objc")]
+extern "C" {
+    pub fn objc_getClass(name: *const c_char) -> Id;
+    pub fn sel_registerName(name: *const c_char) -> Sel;
+    pub fn objc_msgSend();
+    pub fn objc_allocateClassPair(superclass: Id, name: *const c_char, extra_bytes: usize) -> Id;
+    pub fn objc_registerClassPair(class: Id);
+    pub fn class_addMethod(class: Id, name: Sel, imp: *const c_void, types: *const c_char) -> bool;
+}
+
+pub fn class(name: &str) -> Id {
+    let name = CString::new(name).unwrap();
+    unsafe { objc_getClass(name.as_ptr()) }
+}
+
+pub fn sel(name: &str) -> Sel {
+    let name = CString::new(name).unwrap();
+    unsafe { sel_registerName(name.as_ptr()) }
+}
+
+// objc_msgSend must be called through a function pointer of the exact
+// signature of the method being invoked.
+
+pub unsafe fn send(receiver: Id, selector: &str) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel) -> Id = mem::transmute(objc_msgSend as *const c_void);
+    f(receiver, sel(selector))
+}
+
+pub unsafe fn send_id(receiver: Id, selector: &str, arg: Id) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel, Id) -> Id = mem::transmute(objc_msgSend as *const c_void);
+    f(receiver, sel(selector), arg)
+}
+
+pub unsafe fn send_isize(receiver: Id, selector: &str, arg: isize) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel, isize) -> Id =
+        mem::transmute(objc_msgSend as *const c_void);
+    f(receiver, sel(selector), arg)
+}
+
+pub unsafe fn send_f64(receiver: Id, selector: &str, arg: f64) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel, f64) -> Id = mem::transmute(objc_msgSend as *const c_void);
+    f(receiver, sel(selector), arg)
+}
+
+pub unsafe fn send_bool(receiver: Id, selector: &str, arg: bool) -> Id {
+    let f: unsafe extern "C" fn(Id, Sel, bool) -> Id =
+        mem::transmute(objc_msgSend as *const c_void);
+    f(receiver, sel(selector), arg)
+}
+
+/// Autoreleased NSString
+pub unsafe fn nsstring(value: &str) -> Id {
+    let value = CString::new(value.replace('\0', "")).unwrap();
+    let f: unsafe extern "C" fn(Id, Sel, *const c_char) -> Id =
+        mem::transmute(objc_msgSend as *const c_void);
+    f(
+        class("NSString"),
+        sel("stringWithUTF8String:"),
+        value.as_ptr(),
+    )
+}
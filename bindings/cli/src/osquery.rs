@@ -0,0 +1,561 @@
+//! osquery extension mode.
+//!
+//! Speaks the osquery extension Thrift protocol (binary protocol, buffered
+//! transport over a Unix socket): the extension registers its tables with the
+//! extension manager, then serves `ping`/`call`/`shutdown` on its own socket at
+//! `<manager socket>.<uuid>`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thrift::protocol::{
+    TBinaryInputProtocol, TBinaryOutputProtocol, TFieldIdentifier, TInputProtocol, TListIdentifier,
+    TMapIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol, TStructIdentifier, TType,
+};
+use thrift::transport::{
+    ReadHalf, TBufferedReadTransport, TBufferedWriteTransport, TIoChannel, WriteHalf,
+};
+use thrift::{ApplicationError, ApplicationErrorKind, TransportErrorKind};
+
+use no_cluely_driver::{detect_cluely_with_rust, DetectionError, DetectorConfig};
+
+use crate::get_severity_level;
+
+const EXTENSION_NAME: &str = "cluely_detector";
+const EXT_SUCCESS: i32 = 0;
+const EXT_FAILED: i32 = 1;
+
+type Row = BTreeMap<String, String>;
+type InputProtocol = TBinaryInputProtocol<TBufferedReadTransport<ReadHalf<UnixStream>>>;
+type OutputProtocol = TBinaryOutputProtocol<TBufferedWriteTransport<WriteHalf<UnixStream>>>;
+
+/// A virtual table exposed to osquery
+struct Table {
+    name: &'static str,
+    columns: &'static [(&'static str, &'static str)],
+    generate: fn(&DetectorConfig) -> Result<Vec<Row>, DetectionError>,
+}
+
+const TABLES: &[Table] = &[
+    Table {
+        name: "cluely_detections",
+        columns: &[
+            ("detected", "INTEGER"),
+            ("window_count", "INTEGER"),
+            ("screen_capture_evasion_count", "INTEGER"),
+            ("elevated_layer_count", "INTEGER"),
+            ("max_layer_detected", "INTEGER"),
+            ("severity", "TEXT"),
+        ],
+        generate: generate_detections,
+    },
+    Table {
+        name: "capture_evasion_windows",
+        columns: &[
+            ("window_id", "INTEGER"),
+            ("owner", "TEXT"),
+            ("sharing_state", "INTEGER"),
+            ("layer", "INTEGER"),
+            ("layer_class", "TEXT"),
+        ],
+        generate: generate_evasion_windows,
+    },
+];
+
+impl Table {
+    /// Column definitions in the route format osquery expects
+    fn column_rows(&self) -> Vec<Row> {
+        self.columns
+            .iter()
+            .map(|(name, kind)| {
+                Row::from([
+                    ("id".to_string(), "column".to_string()),
+                    ("name".to_string(), name.to_string()),
+                    ("type".to_string(), kind.to_string()),
+                    ("op".to_string(), "0".to_string()),
+                ])
+            })
+            .collect()
+    }
+}
+
+fn generate_detections(detector: &DetectorConfig) -> Result<Vec<Row>, DetectionError> {
+    let (result, _) = detect_cluely_with_rust(detector)?;
+
+    Ok(vec![Row::from([
+        (
+            "detected".to_string(),
+            (result.is_detected as i32).to_string(),
+        ),
+        ("window_count".to_string(), result.window_count.to_string()),
+        (
+            "screen_capture_evasion_count".to_string(),
+            result.screen_capture_evasion_count.to_string(),
+        ),
+        (
+            "elevated_layer_count".to_string(),
+            result.elevated_layer_count.to_string(),
+        ),
+        (
+            "max_layer_detected".to_string(),
+            result.max_layer_detected.to_string(),
+        ),
+        ("severity".to_string(), get_severity_level(&result)),
+    ])])
+}
+
+fn generate_evasion_windows(detector: &DetectorConfig) -> Result<Vec<Row>, DetectionError> {
+    let (_, windows) = detect_cluely_with_rust(detector)?;
+
+    Ok(windows
+        .into_iter()
+        .filter(|window| window.sharing_state == 0)
+        .map(|window| {
+            let layer_class = window.layer_class().label().to_string();
+            Row::from([
+                ("window_id".to_string(), window.window_id.to_string()),
+                ("owner".to_string(), window.owner),
+                (
+                    "sharing_state".to_string(),
+                    window.sharing_state.to_string(),
+                ),
+                ("layer".to_string(), window.layer.to_string()),
+                ("layer_class".to_string(), layer_class),
+            ])
+        })
+        .collect())
+}
+
+/// `ExtensionStatus` as returned by the extension manager
+struct ExtensionStatus {
+    code: i32,
+    message: String,
+    uuid: i64,
+}
+
+/// Register with the osquery extension manager and serve table requests,
+/// scanning with `detector`, until osquery goes away.
+pub fn run(
+    socket: &Path,
+    timeout: u64,
+    interval: u64,
+    detector: DetectorConfig,
+) -> Result<(), String> {
+    let mut manager = ManagerClient::connect(socket, Duration::from_secs(timeout))?;
+
+    let status = manager
+        .register_extension()
+        .map_err(|e| format!("Failed to register extension: {}", e))?;
+    if status.code != EXT_SUCCESS {
+        return Err(format!(
+            "Extension registration rejected: {}",
+            status.message
+        ));
+    }
+
+    let extension_socket = format!("{}.{}", socket.display(), status.uuid);
+    let _ = fs::remove_file(&extension_socket);
+    let listener = UnixListener::bind(&extension_socket)
+        .map_err(|e| format!("Failed to bind {}: {}", extension_socket, e))?;
+
+    tracing::info!(
+        extension = EXTENSION_NAME,
+        uuid = status.uuid,
+        socket = %extension_socket,
+        "registered osquery extension"
+    );
+
+    let uuid = status.uuid;
+    let detector = Arc::new(detector);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let detector = Arc::clone(&detector);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, uuid, &detector) {
+                    tracing::warn!(error = %e, "osquery connection error");
+                }
+            });
+        }
+    });
+
+    // osquery does not tell extensions when it exits; ping until it stops answering
+    loop {
+        thread::sleep(Duration::from_secs(interval.max(1)));
+
+        match manager.ping() {
+            Ok(status) if status.code == EXT_SUCCESS => continue,
+            _ => {
+                tracing::info!("extension manager went away, shutting down");
+                let _ = fs::remove_file(&extension_socket);
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn open_protocols(stream: UnixStream) -> thrift::Result<(InputProtocol, OutputProtocol)> {
+    let (read_half, write_half) = stream.split()?;
+    Ok((
+        TBinaryInputProtocol::new(TBufferedReadTransport::new(read_half), true),
+        TBinaryOutputProtocol::new(TBufferedWriteTransport::new(write_half), true),
+    ))
+}
+
+/// Client side of the `ExtensionManager` service
+struct ManagerClient {
+    input: InputProtocol,
+    output: OutputProtocol,
+    sequence: i32,
+}
+
+impl ManagerClient {
+    fn connect(socket: &Path, timeout: Duration) -> Result<Self, String> {
+        let deadline = Instant::now() + timeout;
+
+        // osqueryd may still be creating its socket when it launches extensions
+        let stream = loop {
+            match UnixStream::connect(socket) {
+                Ok(stream) => break stream,
+                Err(e) if Instant::now() >= deadline => {
+                    return Err(format!("Cannot connect to {}: {}", socket.display(), e));
+                }
+                Err(_) => thread::sleep(Duration::from_millis(200)),
+            }
+        };
+
+        let (input, output) = open_protocols(stream).map_err(|e| e.to_string())?;
+        Ok(Self {
+            input,
+            output,
+            sequence: 0,
+        })
+    }
+
+    fn register_extension(&mut self) -> thrift::Result<ExtensionStatus> {
+        self.call("registerExtension", |o| {
+            write_field(o, "info", TType::Struct, 1, |o| {
+                o.write_struct_begin(&TStructIdentifier::new("InternalExtensionInfo"))?;
+                write_field(o, "name", TType::String, 1, |o| {
+                    o.write_string(EXTENSION_NAME)
+                })?;
+                write_field(o, "version", TType::String, 2, |o| {
+                    o.write_string(env!("CARGO_PKG_VERSION"))
+                })?;
+                write_field(o, "sdk_version", TType::String, 3, |o| o.write_string(""))?;
+                write_field(o, "min_sdk_version", TType::String, 4, |o| {
+                    o.write_string("")
+                })?;
+                o.write_field_stop()?;
+                o.write_struct_end()
+            })?;
+            write_field(o, "registry", TType::Map, 2, |o| {
+                o.write_map_begin(&TMapIdentifier::new(TType::String, TType::Map, 1))?;
+                o.write_string("table")?;
+                o.write_map_begin(&TMapIdentifier::new(
+                    TType::String,
+                    TType::List,
+                    TABLES.len() as i32,
+                ))?;
+                for table in TABLES {
+                    o.write_string(table.name)?;
+                    write_rows(o, &table.column_rows())?;
+                }
+                o.write_map_end()?;
+                o.write_map_end()
+            })
+        })
+    }
+
+    fn ping(&mut self) -> thrift::Result<ExtensionStatus> {
+        self.call("ping", |_| Ok(()))
+    }
+
+    /// Invoke a manager method whose result is an `ExtensionStatus`
+    fn call<F>(&mut self, method: &str, write_args: F) -> thrift::Result<ExtensionStatus>
+    where
+        F: FnOnce(&mut dyn TOutputProtocol) -> thrift::Result<()>,
+    {
+        self.sequence += 1;
+        self.output.write_message_begin(&TMessageIdentifier::new(
+            method,
+            TMessageType::Call,
+            self.sequence,
+        ))?;
+        self.output
+            .write_struct_begin(&TStructIdentifier::new(format!("{}_args", method)))?;
+        write_args(&mut self.output)?;
+        self.output.write_field_stop()?;
+        self.output.write_struct_end()?;
+        self.output.write_message_end()?;
+        self.output.flush()?;
+
+        let reply = self.input.read_message_begin()?;
+        if reply.message_type == TMessageType::Exception {
+            let error = thrift::Error::read_application_error_from_in_protocol(&mut self.input)?;
+            self.input.read_message_end()?;
+            return Err(thrift::Error::Application(error));
+        }
+
+        let mut status = None;
+        read_struct(&mut self.input, |i, id| {
+            if id == 0 {
+                status = Some(read_status(i)?);
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        })?;
+        self.input.read_message_end()?;
+
+        status.ok_or_else(|| {
+            thrift::Error::Application(ApplicationError::new(
+                ApplicationErrorKind::MissingResult,
+                format!("{} returned no result", method),
+            ))
+        })
+    }
+}
+
+/// Serve the `Extension` service for one osquery connection
+fn handle_connection(
+    stream: UnixStream,
+    uuid: i64,
+    detector: &DetectorConfig,
+) -> thrift::Result<()> {
+    let (mut input, mut output) = open_protocols(stream)?;
+
+    loop {
+        let message = match input.read_message_begin() {
+            Ok(message) => message,
+            Err(thrift::Error::Transport(e)) if e.kind == TransportErrorKind::EndOfFile => {
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+
+        match message.name.as_str() {
+            "ping" => {
+                read_struct(&mut input, |_, _| Ok(false))?;
+                input.read_message_end()?;
+                write_result(&mut output, &message, |o| {
+                    write_field(o, "success", TType::Struct, 0, |o| {
+                        write_status(o, EXT_SUCCESS, "OK", uuid)
+                    })
+                })?;
+            }
+            "call" => {
+                let mut registry = String::new();
+                let mut item = String::new();
+                let mut request = Row::new();
+                read_struct(&mut input, |i, id| {
+                    match id {
+                        1 => registry = i.read_string()?,
+                        2 => item = i.read_string()?,
+                        3 => request = read_string_map(i)?,
+                        _ => return Ok(false),
+                    }
+                    Ok(true)
+                })?;
+                input.read_message_end()?;
+
+                let (code, status_message, rows) =
+                    handle_call(&registry, &item, &request, detector);
+                write_result(&mut output, &message, |o| {
+                    write_field(o, "success", TType::Struct, 0, |o| {
+                        o.write_struct_begin(&TStructIdentifier::new("ExtensionResponse"))?;
+                        write_field(o, "status", TType::Struct, 1, |o| {
+                            write_status(o, code, &status_message, uuid)
+                        })?;
+                        write_field(o, "response", TType::List, 2, |o| write_rows(o, &rows))?;
+                        o.write_field_stop()?;
+                        o.write_struct_end()
+                    })
+                })?;
+            }
+            "shutdown" => {
+                read_struct(&mut input, |_, _| Ok(false))?;
+                input.read_message_end()?;
+                write_result(&mut output, &message, |_| Ok(()))?;
+                process::exit(0);
+            }
+            _ => {
+                input.skip(TType::Struct)?;
+                input.read_message_end()?;
+                output.write_message_begin(&TMessageIdentifier::new(
+                    message.name.clone(),
+                    TMessageType::Exception,
+                    message.sequence_number,
+                ))?;
+                thrift::Error::write_application_error_to_out_protocol(
+                    &ApplicationError::new(
+                        ApplicationErrorKind::UnknownMethod,
+                        format!("Unknown method {}", message.name),
+                    ),
+                    &mut output,
+                )?;
+                output.write_message_end()?;
+                output.flush()?;
+            }
+        }
+    }
+}
+
+/// Dispatch a registry call, returning (status code, status message, rows).
+/// A scan that fails is reported as a failed call rather than an empty
+/// table, so osquery does not read it as Cluely being absent.
+fn handle_call(
+    registry: &str,
+    item: &str,
+    request: &Row,
+    detector: &DetectorConfig,
+) -> (i32, String, Vec<Row>) {
+    let table = match TABLES.iter().find(|table| table.name == item) {
+        Some(table) if registry == "table" => table,
+        _ => {
+            return (
+                EXT_FAILED,
+                format!("Unknown registry item {}/{}", registry, item),
+                Vec::new(),
+            )
+        }
+    };
+
+    match request.get("action").map(String::as_str) {
+        Some("generate") => match (table.generate)(detector) {
+            Ok(rows) => (EXT_SUCCESS, "OK".to_string(), rows),
+            Err(error) => {
+                tracing::warn!(table = table.name, %error, "scan failed");
+                (EXT_FAILED, format!("Scan failed: {}", error), Vec::new())
+            }
+        },
+        Some("columns") => (EXT_SUCCESS, "OK".to_string(), table.column_rows()),
+        other => (
+            EXT_FAILED,
+            format!("Unsupported table action {:?}", other),
+            Vec::new(),
+        ),
+    }
+}
+
+/// Read a struct, handing each field to `on_field`; fields it declines are skipped
+fn read_struct<F>(i: &mut dyn TInputProtocol, mut on_field: F) -> thrift::Result<()>
+where
+    F: FnMut(&mut dyn TInputProtocol, i16) -> thrift::Result<bool>,
+{
+    i.read_struct_begin()?;
+    loop {
+        let field = i.read_field_begin()?;
+        if field.field_type == TType::Stop {
+            break;
+        }
+        if !on_field(i, field.id.unwrap_or(-1))? {
+            i.skip(field.field_type)?;
+        }
+        i.read_field_end()?;
+    }
+    i.read_struct_end()
+}
+
+fn read_status(i: &mut dyn TInputProtocol) -> thrift::Result<ExtensionStatus> {
+    let mut status = ExtensionStatus {
+        code: EXT_FAILED,
+        message: String::new(),
+        uuid: 0,
+    };
+    read_struct(i, |i, id| {
+        match id {
+            1 => status.code = i.read_i32()?,
+            2 => status.message = i.read_string()?,
+            3 => status.uuid = i.read_i64()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(status)
+}
+
+fn read_string_map(i: &mut dyn TInputProtocol) -> thrift::Result<Row> {
+    let header = i.read_map_begin()?;
+    let mut map = Row::new();
+    for _ in 0..header.size {
+        let key = i.read_string()?;
+        let value = i.read_string()?;
+        map.insert(key, value);
+    }
+    i.read_map_end()?;
+    Ok(map)
+}
+
+fn write_field<F>(
+    o: &mut dyn TOutputProtocol,
+    name: &str,
+    field_type: TType,
+    id: i16,
+    write_value: F,
+) -> thrift::Result<()>
+where
+    F: FnOnce(&mut dyn TOutputProtocol) -> thrift::Result<()>,
+{
+    o.write_field_begin(&TFieldIdentifier::new(name, field_type, id))?;
+    write_value(o)?;
+    o.write_field_end()
+}
+
+fn write_status(
+    o: &mut dyn TOutputProtocol,
+    code: i32,
+    message: &str,
+    uuid: i64,
+) -> thrift::Result<()> {
+    o.write_struct_begin(&TStructIdentifier::new("ExtensionStatus"))?;
+    write_field(o, "code", TType::I32, 1, |o| o.write_i32(code))?;
+    write_field(o, "message", TType::String, 2, |o| o.write_string(message))?;
+    write_field(o, "uuid", TType::I64, 3, |o| o.write_i64(uuid))?;
+    o.write_field_stop()?;
+    o.write_struct_end()
+}
+
+fn write_rows(o: &mut dyn TOutputProtocol, rows: &[Row]) -> thrift::Result<()> {
+    o.write_list_begin(&TListIdentifier::new(TType::Map, rows.len() as i32))?;
+    for row in rows {
+        o.write_map_begin(&TMapIdentifier::new(
+            TType::String,
+            TType::String,
+            row.len() as i32,
+        ))?;
+        for (key, value) in row {
+            o.write_string(key)?;
+            o.write_string(value)?;
+        }
+        o.write_map_end()?;
+    }
+    o.write_list_end()
+}
+
+/// Write a `<method>_result` reply; `write_success` emits field 0 (or nothing for void)
+fn write_result<F>(
+    o: &mut dyn TOutputProtocol,
+    request: &TMessageIdentifier,
+    write_success: F,
+) -> thrift::Result<()>
+where
+    F: FnOnce(&mut dyn TOutputProtocol) -> thrift::Result<()>,
+{
+    o.write_message_begin(&TMessageIdentifier::new(
+        request.name.clone(),
+        TMessageType::Reply,
+        request.sequence_number,
+    ))?;
+    o.write_struct_begin(&TStructIdentifier::new(format!("{}_result", request.name)))?;
+    write_success(o)?;
+    o.write_field_stop()?;
+    o.write_struct_end()?;
+    o.write_message_end()?;
+    o.flush()
+}
@@ -0,0 +1,925 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use no_cluely_driver::{
+    BrowserTab, CaptureEvasionReport, ClueLyDetectionResult, DetectionError, DisplayReport, EvasionScan, Evidence, Finding, Installation,
+    PersistenceAudit, PersistenceItem,
+    ProcessHandles, ProcessInfo, ScreenRecorders, SuspiciousProcess, UserSession, WindowBounds, WindowFinding, WindowInfo,
+};
+
+use crate::suppress::SuppressedFinding;
+use crate::{get_evasion_techniques, get_severity_level};
+
+/// Output format for detection results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable terminal output
+    Text,
+    /// JSON document (same as the `json` subcommand)
+    Json,
+    /// XML property list, for Jamf/Munki and other Mac management tools
+    Plist,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
+    /// Self-contained HTML document, for sharing (report only)
+    Html,
+    /// Markdown document, for pasting into tickets (report only)
+    Markdown,
+    /// PDF document; needs the `pdf` build feature (report only)
+    Pdf,
+    /// Santa blocklist rules, for `santactl rule --import` (export only)
+    Santa,
+    /// YARA rules, for hunting with existing scanners (export only)
+    Yara,
+    /// STIX 2.1 bundle of indicators, for threat-intel platforms such as
+    /// MISP (export only)
+    Stix,
+    /// Elastic Common Schema events, one per line (check and report only)
+    Ecs,
+    /// One line starting with DETECTED or CLEAR, for Apple Shortcuts and
+    /// AppleScript (check and report only)
+    Shortcuts,
+}
+
+/// Version of the payload and event schemas printed by `schema`. Bumped on
+/// incompatible changes; new fields may be added without a bump.
+pub const SCHEMA_VERSION: u32 = 2;
+
+fn schema_version() -> u32 {
+    SCHEMA_VERSION
+}
+
+/// Machine-readable detection payload shared by the JSON and plist outputs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DetectionPayload {
+    /// Version of this schema
+    #[serde(default = "schema_version")]
+    pub schema_version: u32,
+    /// Whether any Cluely window was found
+    pub detected: bool,
+    /// Number of Cluely windows
+    pub window_count: u32,
+    /// Cluely windows hidden from screen capture (sharing state 0)
+    pub screen_capture_evasion_count: u32,
+    /// Cluely windows above the normal window layer
+    pub elevated_layer_count: u32,
+    /// Highest window layer used by Cluely; 0 if none is elevated
+    pub max_layer_detected: i32,
+    #[schemars(extend("enum" = ["None", "Low", "Medium", "High"]))]
+    pub severity: String,
+    /// Each technique in use, with the rule that caught it
+    pub evasion_techniques: Vec<FindingPayload>,
+    /// When the scan ran (RFC 3339)
+    #[schemars(extend("format" = "date-time"))]
+    pub timestamp: String,
+    /// How long the scan took, in milliseconds; absent for scans read back
+    /// from history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_scan_duration_ms: Option<f64>,
+    /// Browser tabs open on Cluely's web app, a low-severity finding that
+    /// does not count towards `detected`; only present with `--browser-tabs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub browser_tabs: Option<Vec<BrowserTabPayload>>,
+    /// Running Cluely processes grouped by the app bundle they run from,
+    /// including updaters and helpers without windows; only present from
+    /// `check`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installations: Option<Vec<InstallationPayload>>,
+    /// The detection logic that produced this result; absent for scans
+    /// recorded before it was kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ruleset: Option<RulesetPayload>,
+}
+
+/// A technique in use, as the rule that caught it saw it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FindingPayload {
+    /// Stable ID of the rule, e.g. "elevated-layer"; branch on this rather
+    /// than on the message
+    pub rule_id: String,
+    /// Human-readable description, which may be reworded between releases
+    pub message: String,
+    /// The values the rule looked at, e.g. `{"layer": 3}`
+    #[serde(default)]
+    pub evidence: BTreeMap<String, serde_json::Value>,
+}
+
+impl FindingPayload {
+    pub fn from_finding(finding: &Finding) -> Self {
+        Self {
+            rule_id: finding.rule_id.id().to_string(),
+            message: finding.message.clone(),
+            evidence: finding
+                .evidence
+                .iter()
+                .map(|(key, value)| {
+                    let value = match value {
+                        Evidence::Integer(value) => serde_json::json!(value),
+                        Evidence::Number(value) => serde_json::json!(value),
+                        Evidence::Text(value) => serde_json::json!(value),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for FindingPayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Which rules and signatures a result came from, so it can be read
+/// against the detection logic of the time
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RulesetPayload {
+    /// Version of the built-in detection rules
+    pub version: u32,
+    /// Version of the installed signature bundle; 0 if none was installed
+    pub signatures_version: u64,
+    /// Stable IDs of the rules that fired, e.g. "screen-capture-evasion"
+    pub rules_fired: Vec<String>,
+}
+
+impl RulesetPayload {
+    pub fn from_result(result: &ClueLyDetectionResult) -> Self {
+        Self {
+            version: result.ruleset_version,
+            signatures_version: result.signatures_version,
+            rules_fired: result
+                .fired_rules()
+                .iter()
+                .map(|rule| rule.id().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// A browser tab open on Cluely's web app
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BrowserTabPayload {
+    #[schemars(extend("enum" = ["Safari", "Google Chrome"]))]
+    pub browser: String,
+    pub url: String,
+    pub title: String,
+}
+
+impl BrowserTabPayload {
+    pub fn from_tab(tab: &BrowserTab) -> Self {
+        Self {
+            browser: tab.browser.label().to_string(),
+            url: tab.url.clone(),
+            title: tab.title.clone(),
+        }
+    }
+}
+
+/// A Cluely installation and the processes running from it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstallationPayload {
+    /// The app bundle; absent for processes run from outside any bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+    /// Windows owned by any of its processes
+    pub window_count: usize,
+    /// The app first, then helpers, updaters and crash reporters
+    pub processes: Vec<CompanionPayload>,
+}
+
+/// One process of a Cluely installation
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CompanionPayload {
+    pub pid: i32,
+    pub name: String,
+    #[schemars(extend("enum" = ["app", "helper", "updater", "crash reporter"]))]
+    pub role: String,
+}
+
+impl InstallationPayload {
+    pub fn from_installation(installation: &Installation) -> Self {
+        Self {
+            bundle: installation.bundle.as_ref().map(|bundle| bundle.display().to_string()),
+            window_count: installation.window_count,
+            processes: installation
+                .processes
+                .iter()
+                .map(|process| CompanionPayload {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    role: process.role.label().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DetectionPayload {
+    pub fn from_result(result: &ClueLyDetectionResult) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            detected: result.is_detected,
+            window_count: result.window_count,
+            screen_capture_evasion_count: result.screen_capture_evasion_count,
+            elevated_layer_count: result.elevated_layer_count,
+            max_layer_detected: result.max_layer_detected,
+            severity: get_severity_level(result),
+            evasion_techniques: get_evasion_techniques(result).iter().map(FindingPayload::from_finding).collect(),
+            timestamp: crate::timestamp::rfc3339(chrono::Utc::now()),
+            last_scan_duration_ms: Some(result.last_scan_duration().as_secs_f64() * 1000.0),
+            browser_tabs: None,
+            installations: None,
+            ruleset: Some(RulesetPayload::from_result(result)),
+        }
+    }
+}
+
+/// Machine-readable details of a single Cluely window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowPayload {
+    pub window_id: i32,
+    pub owner: String,
+    pub sharing_state: i32,
+    pub layer: i32,
+    /// What the layer is used for, e.g. "floating" or "above-screen-capture".
+    /// Empty in records written before it was added.
+    #[serde(default)]
+    pub layer_class: String,
+    /// Opacity, 0.0 (invisible) to 1.0. Records written before it was
+    /// added read as opaque.
+    #[serde(default = "opaque")]
+    pub alpha: f64,
+}
+
+fn opaque() -> f64 {
+    1.0
+}
+
+impl WindowPayload {
+    pub fn from_window(window: &WindowInfo) -> Self {
+        Self {
+            window_id: window.window_id,
+            owner: window.owner.clone(),
+            sharing_state: window.sharing_state,
+            layer: window.layer,
+            layer_class: window.layer_class().label().to_string(),
+            alpha: window.alpha,
+        }
+    }
+}
+
+/// Machine-readable details of a running Cluely process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessPayload {
+    pub pid: i32,
+    pub name: String,
+    pub path: String,
+    /// Parent processes, nearest first and ending at launchd
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<ParentPayload>,
+    /// How the process was started, e.g. "launchd job com.example.agent";
+    /// absent in snapshots taken before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launched_by: Option<String>,
+}
+
+impl ProcessPayload {
+    pub fn from_process(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid,
+            name: process.name.clone(),
+            path: process.path.clone(),
+            parents: process
+                .parents
+                .iter()
+                .map(|parent| ParentPayload {
+                    pid: parent.pid,
+                    name: parent.name.clone(),
+                })
+                .collect(),
+            launched_by: Some(process.origin.describe()),
+        }
+    }
+
+    /// The parent chain from launchd down to this process, e.g.
+    /// "launchd → Cluely Helper → Cluely Agent"
+    pub fn chain(&self) -> String {
+        let mut chain: Vec<&str> = self
+            .parents
+            .iter()
+            .rev()
+            .map(|parent| parent.name.as_str())
+            .collect();
+        chain.push(&self.name);
+        chain.join(" → ")
+    }
+}
+
+/// A process above a Cluely process in its parent chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParentPayload {
+    pub pid: i32,
+    pub name: String,
+}
+
+/// Machine-readable details of a Cluely file left on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistencePayload {
+    pub kind: String,
+    pub path: String,
+}
+
+impl PersistencePayload {
+    pub fn from_item(item: &PersistenceItem) -> Self {
+        Self {
+            kind: item.kind.label().to_string(),
+            path: item.path.display().to_string(),
+        }
+    }
+}
+
+/// Machine-readable result of `audit`
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditPayload {
+    pub installed: bool,
+    pub system: Vec<PersistencePayload>,
+    pub users: Vec<UserAuditPayload>,
+}
+
+/// One user's entry in `AuditPayload`
+#[derive(Debug, Clone, Serialize)]
+pub struct UserAuditPayload {
+    pub user: String,
+    pub home: String,
+    pub installed: bool,
+    pub items: Vec<PersistencePayload>,
+}
+
+impl AuditPayload {
+    pub fn from_audit(audit: &PersistenceAudit) -> Self {
+        Self {
+            installed: audit.installed(),
+            system: audit.system.iter().map(PersistencePayload::from_item).collect(),
+            users: audit
+                .users
+                .iter()
+                .map(|user| UserAuditPayload {
+                    user: user.user.clone(),
+                    home: user.home.display().to_string(),
+                    installed: user.installed(),
+                    items: user.items.iter().map(PersistencePayload::from_item).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Machine-readable result of the generic evasion scan (`scan-all`)
+#[derive(Debug, Clone, Serialize)]
+pub struct EvasionScanPayload {
+    pub schema_version: u32,
+    pub total_windows: usize,
+    pub evasion_detected: bool,
+    pub cluely_detected: bool,
+    pub findings: Vec<EvasionFindingPayload>,
+    /// Findings left out because `suppressions.toml` covers them
+    pub suppressed: Vec<SuppressedFinding>,
+    pub system_analysis: Vec<String>,
+    pub timestamp: String,
+}
+
+impl EvasionScanPayload {
+    pub fn from_scan(scan: &EvasionScan, suppressed: Vec<SuppressedFinding>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            total_windows: scan.windows.len(),
+            evasion_detected: scan.evasion_detected(),
+            cluely_detected: scan.cluely_detected(),
+            findings: scan
+                .findings
+                .iter()
+                .map(EvasionFindingPayload::from_finding)
+                .collect(),
+            suppressed,
+            system_analysis: scan.system_analysis.clone(),
+            timestamp: crate::timestamp::rfc3339(chrono::Utc::now()),
+        }
+    }
+}
+
+/// A window flagged by the generic evasion scan
+#[derive(Debug, Clone, Serialize)]
+pub struct EvasionFindingPayload {
+    pub window_id: i32,
+    pub name: String,
+    pub owner: String,
+    pub is_cluely: bool,
+    pub techniques: Vec<FindingPayload>,
+    /// "high", or "low" when only the owner's name is suspicious
+    pub confidence: &'static str,
+    pub sharing_state: i32,
+    pub backing_type: i32,
+    pub layer: i32,
+    pub layer_class: &'static str,
+    pub alpha: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<BoundsPayload>,
+    /// Set when the window behaves like Cluely's overlay, whatever its name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cluely_like: Option<OverlayPayload>,
+    /// Summary of the window's buttons and text, with `scan-all --inspect`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Shown on every Space, whichever desktop the user is on
+    pub follows_user_across_spaces: bool,
+    /// Left in place by Mission Control
+    pub stationary: bool,
+}
+
+/// Evidence for a Cluely-like overlay
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayPayload {
+    pub layer: i32,
+    pub bounds: BoundsPayload,
+    pub meeting_app: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BoundsPayload {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl BoundsPayload {
+    fn from_bounds(bounds: WindowBounds) -> Self {
+        Self {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+        }
+    }
+}
+
+impl EvasionFindingPayload {
+    pub fn from_finding(finding: &WindowFinding) -> Self {
+        let window = &finding.window;
+        Self {
+            window_id: window.window_id,
+            name: window.name.clone(),
+            owner: window.owner.clone(),
+            is_cluely: finding.is_cluely,
+            techniques: finding.techniques.iter().map(FindingPayload::from_finding).collect(),
+            confidence: finding.confidence.label(),
+            sharing_state: window.sharing_state,
+            backing_type: window.backing_type,
+            layer: window.layer,
+            layer_class: window.layer_class().label(),
+            alpha: window.alpha,
+            bounds: window.bounds.map(BoundsPayload::from_bounds),
+            cluely_like: finding.cluely_like.as_ref().map(|evidence| OverlayPayload {
+                layer: evidence.layer,
+                bounds: BoundsPayload::from_bounds(evidence.bounds),
+                meeting_app: evidence.meeting_app.clone(),
+            }),
+            content: finding.content.as_ref().map(|content| content.summary()),
+            follows_user_across_spaces: finding.follows_user_across_spaces,
+            stationary: finding.stationary,
+        }
+    }
+}
+
+/// `displays` output
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayReportPayload {
+    pub displays: Vec<DisplayPayload>,
+    pub shared_display: Option<u32>,
+    /// Cluely windows on a display other than the shared one
+    pub unshared_overlays: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisplayPayload {
+    pub id: u32,
+    pub is_main: bool,
+    pub bounds: BoundsPayload,
+    pub cluely_window_ids: Vec<i32>,
+}
+
+impl DisplayReportPayload {
+    pub fn from_report(report: &DisplayReport) -> Self {
+        Self {
+            displays: report
+                .displays
+                .iter()
+                .map(|entry| DisplayPayload {
+                    id: entry.display.id,
+                    is_main: entry.display.is_main,
+                    bounds: BoundsPayload::from_bounds(entry.display.bounds),
+                    cluely_window_ids: entry.cluely_window_ids.clone(),
+                })
+                .collect(),
+            shared_display: report.shared_display,
+            unshared_overlays: report.unshared_overlays.clone(),
+        }
+    }
+}
+
+/// A process listed by `ps`
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspiciousProcessPayload {
+    pub pid: i32,
+    pub name: String,
+    pub is_cluely: bool,
+    pub signals: Vec<&'static str>,
+    pub window_ids: Vec<i32>,
+}
+
+impl SuspiciousProcessPayload {
+    pub fn from_process(process: &SuspiciousProcess) -> Self {
+        Self {
+            pid: process.pid,
+            name: process.name.clone(),
+            is_cluely: process.is_cluely,
+            signals: process.signals.iter().map(|signal| signal.label()).collect(),
+            window_ids: process.windows.iter().map(|window| window.window_id).collect(),
+        }
+    }
+}
+
+/// Machine-readable result of `recorders`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordersPayload {
+    /// False without Full Disk Access, when no grants can be listed
+    pub grants_readable: bool,
+    pub recorders: Vec<RecorderPayload>,
+}
+
+/// An app listed by `recorders`
+#[derive(Debug, Clone, Serialize)]
+pub struct RecorderPayload {
+    pub client: String,
+    pub name: String,
+    pub allowed: bool,
+    /// Allowed and running, so it can be capturing the screen now
+    pub recording: bool,
+    pub meeting_app: bool,
+    pub pids: Vec<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl RecordersPayload {
+    pub fn from_recorders(recorders: &ScreenRecorders) -> Self {
+        Self {
+            grants_readable: recorders.grants_readable,
+            recorders: recorders
+                .recorders
+                .iter()
+                .map(|recorder| RecorderPayload {
+                    client: recorder.client.clone(),
+                    name: recorder.name.clone(),
+                    allowed: recorder.allowed,
+                    recording: recorder.recording(),
+                    meeting_app: recorder.meeting_app,
+                    pids: recorder.pids.clone(),
+                    last_modified: recorder
+                        .last_modified
+                        .and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0))
+                        .map(crate::timestamp::rfc3339),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A process listed by `handles`
+#[derive(Debug, Clone, Serialize)]
+pub struct HandlesPayload {
+    pub pid: i32,
+    pub name: String,
+    pub path: String,
+    /// Whether its name or path matches the signatures
+    pub is_cluely: bool,
+    pub handles: Vec<HandlePayload>,
+}
+
+/// A Cluely file a process has open
+#[derive(Debug, Clone, Serialize)]
+pub struct HandlePayload {
+    pub kind: &'static str,
+    pub path: String,
+}
+
+impl HandlesPayload {
+    pub fn from_process(process: &ProcessHandles) -> Self {
+        Self {
+            pid: process.pid,
+            name: process.name.clone(),
+            path: process.path.clone(),
+            is_cluely: process.is_cluely,
+            handles: process
+                .handles
+                .iter()
+                .map(|handle| HandlePayload {
+                    kind: handle.kind.label(),
+                    path: handle.path.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A user account listed by `check --all-sessions`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionPayload {
+    pub uid: u32,
+    pub user: String,
+    pub logged_in: bool,
+    pub on_console: bool,
+    pub detected: bool,
+    pub processes: Vec<ProcessPayload>,
+}
+
+impl SessionPayload {
+    pub fn from_session(session: &UserSession) -> Self {
+        Self {
+            uid: session.uid,
+            user: session.user.clone(),
+            logged_in: session.logged_in,
+            on_console: session.on_console,
+            detected: session.cluely_detected(),
+            processes: session
+                .cluely_processes
+                .iter()
+                .map(ProcessPayload::from_process)
+                .collect(),
+        }
+    }
+}
+
+/// Detection state transition pushed to event subscribers
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StateChangeEvent {
+    /// Version of this schema
+    pub schema_version: u32,
+    /// `detected`, `cleared`, `snapshot` for the state sent on subscribe, or
+    /// `tampered` when something stopped the monitor or changed its binary
+    /// (`monitor --watchdog`)
+    #[schemars(extend("enum" = ["detected", "cleared", "snapshot", "tampered"]))]
+    pub event: &'static str,
+    pub detection: DetectionPayload,
+    /// What happened, for `tampered` events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl StateChangeEvent {
+    pub fn transition(detection: DetectionPayload) -> Self {
+        let event = if detection.detected {
+            "detected"
+        } else {
+            "cleared"
+        };
+        Self {
+            schema_version: SCHEMA_VERSION,
+            event,
+            detection,
+            detail: None,
+        }
+    }
+
+    pub fn snapshot(detection: DetectionPayload) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            event: "snapshot",
+            detection,
+            detail: None,
+        }
+    }
+
+    /// Something other than the user or launchd stopped the monitor, or its
+    /// binary was changed; `detection` is the latest state it saw
+    pub fn tampered(detection: DetectionPayload, detail: String) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            event: "tampered",
+            detection,
+            detail: Some(detail),
+        }
+    }
+
+    /// Whether this calls for attention rather than reporting all clear
+    pub fn is_alert(&self) -> bool {
+        self.detection.detected || self.event == "tampered"
+    }
+}
+
+/// Print the detection payload in a machine-readable format.
+/// Returns false for `OutputFormat::Text` so callers can fall back to their own rendering.
+pub fn print_payload(result: &ClueLyDetectionResult, format: OutputFormat) -> bool {
+    print_detection(&DetectionPayload::from_result(result), format)
+}
+
+/// Print a detection in a machine-readable format, including the formats
+/// only a detection can be shown in. Returns false for `OutputFormat::Text`.
+pub fn print_detection(payload: &DetectionPayload, format: OutputFormat) -> bool {
+    match format {
+        OutputFormat::Ecs => {
+            for event in crate::ecs::events(payload) {
+                std::println!("{}", serde_json::to_string(&event).unwrap());
+            }
+        }
+        OutputFormat::Shortcuts => std::println!("{}", shortcuts_line(payload)),
+        _ => return print_document(payload, format),
+    }
+    true
+}
+
+/// The detection as one line that a shortcut can test with "begins with",
+/// since Shortcuts and `do shell script` only see the output
+fn shortcuts_line(payload: &DetectionPayload) -> String {
+    if payload.detected {
+        format!(
+            "DETECTED: Cluely is running ({} window(s), {} hidden from screen capture, severity {})",
+            payload.window_count, payload.screen_capture_evasion_count, payload.severity
+        )
+    } else {
+        "CLEAR: Cluely is not running".to_string()
+    }
+}
+
+/// `stats` output: the current scan plus totals from `monitor`
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsPayload {
+    #[serde(flatten)]
+    pub detection: DetectionPayload,
+    /// None if `monitor` has never run on this machine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<crate::state::MonitorState>,
+    /// Apps hiding windows from screen capture, Cluely or not
+    pub capture_evasion: CaptureEvasionPayload,
+    /// Trends over the scan history; None without a history database
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trends: Option<crate::trends::Trends>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureEvasionPayload {
+    pub window_count: usize,
+    pub apps: Vec<CaptureEvasionAppPayload>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureEvasionAppPayload {
+    pub pid: i32,
+    pub name: String,
+    pub is_cluely: bool,
+    pub window_ids: Vec<i32>,
+}
+
+impl CaptureEvasionPayload {
+    pub fn from_report(report: &CaptureEvasionReport) -> Self {
+        Self {
+            window_count: report.window_count(),
+            apps: report
+                .apps
+                .iter()
+                .map(|app| CaptureEvasionAppPayload {
+                    pid: app.pid,
+                    name: app.name.clone(),
+                    is_cluely: app.is_cluely,
+                    window_ids: app.window_ids.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Print any serializable value in a machine-readable format.
+/// Returns false for `OutputFormat::Text`, like `print_payload`.
+pub fn print_document<T: Serialize>(value: &T, format: OutputFormat) -> bool {
+    match format {
+        OutputFormat::Text => return false,
+        OutputFormat::Json => {
+            std::println!("{}", serde_json::to_string_pretty(value).unwrap());
+        }
+        OutputFormat::Plist => {
+            let mut buffer = Vec::new();
+            plist::to_writer_xml(&mut buffer, value).unwrap();
+            std::println!("{}", String::from_utf8_lossy(&buffer));
+        }
+        OutputFormat::Csv => {
+            print!("{}", to_csv(&serde_json::to_value(value).unwrap()));
+        }
+        OutputFormat::Html | OutputFormat::Markdown | OutputFormat::Pdf => crate::fail(format!(
+            "{:?} output is only available for 'report'",
+            format
+        )),
+        OutputFormat::Santa | OutputFormat::Yara | OutputFormat::Stix => crate::fail(format!(
+            "{:?} output is only available for 'export'",
+            format
+        )),
+        OutputFormat::Ecs | OutputFormat::Shortcuts => crate::fail(format!(
+            "{:?} output is only available for 'check' and 'report'",
+            format
+        )),
+    }
+
+    true
+}
+
+/// Columns holding RFC 3339 times, written in the `--timestamp-format` in CSV
+const TIME_COLUMNS: &[&str] = &["timestamp", "first_seen", "last_seen", "start", "end", "last_modified"];
+
+/// Render an object, or an array of objects, as CSV. Columns come from the
+/// first row's keys; array fields are joined with "; " and nested objects
+/// are embedded as JSON.
+pub fn to_csv(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    let rows = match value {
+        Value::Array(rows) => rows.as_slice(),
+        row => std::slice::from_ref(row),
+    };
+    let columns: Vec<&String> = match rows.first() {
+        Some(Value::Object(first)) => first.keys().collect(),
+        _ => return String::new(),
+    };
+
+    let cell = |column: &str, value: Option<&Value>| -> String {
+        let text = match value {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(text)) if TIME_COLUMNS.contains(&column) => crate::timestamp::for_export(text),
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+            Some(other) => other.to_string(),
+        };
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text
+        }
+    };
+
+    let mut csv = columns
+        .iter()
+        .map(|column| column.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let line: Vec<String> = columns
+            .iter()
+            .map(|column| cell(column, row.get(column.as_str())))
+            .collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// One-line `key=value` summary printed by `--quiet`
+pub fn summary_line(payload: &DetectionPayload) -> String {
+    let mut line = format!(
+        "detected={} severity={} windows={} evasion={} elevated={}",
+        payload.detected,
+        payload.severity.to_lowercase(),
+        payload.window_count,
+        payload.screen_capture_evasion_count,
+        payload.elevated_layer_count
+    );
+    if let Some(tabs) = &payload.browser_tabs {
+        line.push_str(&format!(" browser_tabs={}", tabs.len()));
+    }
+    if let Some(installations) = &payload.installations {
+        let processes: usize = installations.iter().map(|installation| installation.processes.len()).sum();
+        line.push_str(&format!(" processes={}", processes));
+    }
+    line
+}
+
+/// Jamf Pro extension attribute payload: a single `<result>` element. A
+/// failed scan is an ERROR, never CLEAN, so inventory can tell machines
+/// that were not scanned apart.
+pub fn jamf_extension_attribute(result: Result<&ClueLyDetectionResult, &DetectionError>) -> String {
+    let status = match result {
+        Ok(result) if result.is_detected => "DETECTED".to_string(),
+        Ok(_) => "CLEAN".to_string(),
+        Err(e) => format!("ERROR: {}", e),
+    };
+    format!("<result>{}</result>", status)
+}
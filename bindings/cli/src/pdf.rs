@@ -0,0 +1,171 @@
+//! Minimal PDF layout for Markdown reports (`pdf` feature).
+//!
+//! Only the Markdown the report template produces is understood: headings,
+//! paragraphs, bullet lists and tables. Text is set in the standard PDF
+//! Helvetica fonts, so nothing has to be embedded, and characters outside
+//! ASCII are replaced.
+
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference,
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const BODY_SIZE: f32 = 10.0;
+/// Characters of body text that fit on one line between the margins
+const LINE_CHARS: usize = 95;
+const POINT_MM: f32 = 0.3528;
+
+struct Writer {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+impl Writer {
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn line(&mut self, text: &str, size: f32, bold: bool, indent: f32) {
+        let height = size * POINT_MM * 1.5;
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+        self.y -= height;
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer
+            .use_text(text, size, Mm(MARGIN + indent), Mm(self.y), font);
+    }
+
+    fn gap(&mut self, size: f32) {
+        self.y -= size * POINT_MM * 0.6;
+    }
+
+    fn wrapped(&mut self, text: &str, bold: bool, indent: f32) {
+        for line in wrap(text, LINE_CHARS - (indent / 2.0) as usize) {
+            self.line(&line, BODY_SIZE, bold, indent);
+        }
+    }
+}
+
+/// Keep printable ASCII, which the standard fonts can show, and drop Markdown emphasis
+fn plain(text: &str) -> String {
+    text.replace("**", "")
+        .replace('`', "")
+        .replace("\\|", "|")
+        .replace('·', "-")
+        .chars()
+        .filter_map(|c| match c {
+            ' '..='~' => Some(c),
+            '\u{2014}' | '\u{2013}' => Some('-'),
+            c if c.is_alphanumeric() => Some('?'),
+            _ => None,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Split a Markdown table row into cells, honouring escaped pipes
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim().trim_start_matches('|').trim_end_matches('|');
+    let mut cells = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|cell| plain(cell)).collect()
+}
+
+/// Lay out `markdown` as an A4 PDF
+pub fn from_markdown(title: &str, markdown: &str) -> Result<Vec<u8>, String> {
+    let (doc, page, layer) =
+        PdfDocument::new(plain(title), Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Report");
+    let regular = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+    let layer = doc.get_page(page).get_layer(layer);
+    let mut writer = Writer {
+        doc,
+        layer,
+        regular,
+        bold,
+        y: PAGE_HEIGHT - MARGIN,
+    };
+
+    let mut in_table = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix("# ") {
+            writer.line(&plain(heading), 18.0, true, 0.0);
+            writer.gap(18.0);
+        } else if let Some(heading) = trimmed.strip_prefix("## ") {
+            writer.gap(14.0);
+            writer.line(&plain(heading), 13.0, true, 0.0);
+            writer.gap(13.0);
+        } else if let Some(item) = trimmed.strip_prefix("- ") {
+            writer.wrapped(&format!("- {}", plain(item)), false, 4.0);
+        } else if trimmed.starts_with('|') {
+            if trimmed.starts_with("|---") {
+                continue;
+            }
+            let cells: Vec<String> = table_cells(trimmed)
+                .into_iter()
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            if cells.is_empty() {
+                in_table = true;
+                continue;
+            }
+            // The first row of a table is its header, unless the table has none
+            writer.wrapped(&cells.join(" | "), !in_table, 0.0);
+            in_table = true;
+        } else if trimmed == "---" {
+            writer.gap(BODY_SIZE * 2.0);
+        } else if trimmed.is_empty() {
+            in_table = false;
+            writer.gap(BODY_SIZE);
+        } else {
+            writer.wrapped(&plain(trimmed), false, 0.0);
+        }
+    }
+
+    writer
+        .doc
+        .save_to_bytes()
+        .map_err(|e| format!("Failed to write the PDF: {}", e))
+}
@@ -0,0 +1,95 @@
+//! Plain ASCII output (`--plain`).
+//!
+//! Human-readable output uses emoji and typographic symbols, which turn into
+//! mojibake on terminals without UTF-8 and in log pipelines. In plain mode,
+//! status emoji become bracketed markers such as `[ALERT]`, decorative ones
+//! are dropped, and symbols like bullets and arrows get ASCII equivalents.
+//! Plain mode is also chosen automatically when the locale isn't UTF-8.
+//!
+//! Only human-readable text goes through [`text`] (see the `println!`
+//! wrapper in `main.rs`); JSON, plist and CSV output is printed as-is.
+
+use std::borrow::Cow;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Status emoji and the markers that replace them
+const MARKERS: &[(&str, &str)] = &[
+    ("🚨", "[ALERT]"),
+    ("✅", "[OK]"),
+    // Warnings are padded for the emoji's width
+    ("⚠️  ", "[WARN] "),
+    ("⚠️", "[WARN]"),
+    ("⚠", "[WARN]"),
+    ("❌", "[ERROR]"),
+    ("💡", "[TIP]"),
+    ("⏸", "[PAUSED]"),
+    ("✓", "[OK]"),
+    ("✗", "[FAILED]"),
+];
+
+/// Emoji that only decorate a banner; dropped along with the space after them
+const DECORATIONS: &[&str] = &[
+    "📊", "🔍", "📸", "📜", "📄", "🌐", "🩺", "🔄", "⬆️", "🔀", "🕒", "🛠️", "🧹", "🗑️", "🎯", "👋", "📦",
+];
+
+/// Typographic symbols and their ASCII equivalents
+const SYMBOLS: &[(&str, &str)] = &[
+    ("•", "*"),
+    ("·", "-"),
+    ("→", "->"),
+    ("…", "..."),
+    ("—", "-"),
+    ("×", "x"),
+];
+
+pub fn enable() {
+    PLAIN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Whether the environment can't be trusted to show UTF-8: the locale
+/// (`LC_ALL`, `LC_CTYPE` or `LANG`, whichever is set first) doesn't name
+/// UTF-8, or the terminal is `dumb`. launchd jobs have no locale at all.
+pub fn detect() -> bool {
+    if env::var("TERM").is_ok_and(|term| term == "dumb") {
+        return true;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    !(locale.contains("utf-8") || locale.contains("utf8"))
+}
+
+/// `text` with emoji and symbols replaced if plain mode is on
+pub fn text(text: &str) -> Cow<'_, str> {
+    if !is_enabled() || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut text = text.to_string();
+    for (emoji, marker) in MARKERS {
+        text = text.replace(emoji, marker);
+    }
+    for emoji in DECORATIONS {
+        for pattern in [
+            format!("{}  ", emoji),
+            format!("{} ", emoji),
+            emoji.to_string(),
+        ] {
+            text = text.replace(&pattern, "");
+        }
+    }
+    for (symbol, ascii) in SYMBOLS {
+        text = text.replace(symbol, ascii);
+    }
+    Cow::Owned(text)
+}
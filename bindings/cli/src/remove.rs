@@ -0,0 +1,195 @@
+//! Guided Cluely uninstall (`remove` subcommand).
+//!
+//! Launch agents and daemons are unloaded first, because a `KeepAlive` job
+//! would relaunch the app as soon as it is quit. Then the running processes
+//! are stopped, and finally every file found on disk is deleted. Each step is
+//! confirmed interactively unless `--yes` is given.
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use colored::*;
+
+use no_cluely_driver::{
+    get_cluely_persistence_rust, get_cluely_processes_rust, PersistenceItem, PersistenceKind,
+    ProcessInfo,
+};
+
+use crate::service::{domain, launchctl};
+
+/// How long a process gets to exit after SIGTERM before it is killed
+const QUIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Options for `cluely-detector remove`
+pub struct RemoveOptions {
+    pub dry_run: bool,
+    pub assume_yes: bool,
+}
+
+fn confirm(question: &str, options: &RemoveOptions) -> bool {
+    if options.assume_yes {
+        return true;
+    }
+
+    print!("{} [y/N] ", question.bold());
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn is_launch_job(item: &PersistenceItem) -> bool {
+    matches!(
+        item.kind,
+        PersistenceKind::LaunchAgent | PersistenceKind::LaunchDaemon
+    )
+}
+
+fn unload(item: &PersistenceItem) -> Result<(), String> {
+    let domain = match item.kind {
+        PersistenceKind::LaunchDaemon => "system".to_string(),
+        _ => domain(),
+    };
+    launchctl(&["bootout", &domain, &item.path.display().to_string()]).map(|_| ())
+}
+
+fn quit(process: &ProcessInfo) -> Result<(), String> {
+    let pid = process.pid;
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+
+    let deadline = Instant::now() + QUIT_TIMEOUT;
+    // Signal 0 only checks whether the process still exists
+    while unsafe { libc::kill(pid, 0) } == 0 {
+        if Instant::now() >= deadline {
+            unsafe { libc::kill(pid, libc::SIGKILL) };
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+fn delete(item: &PersistenceItem) -> Result<(), String> {
+    let result = if item.path.is_dir() {
+        fs::remove_dir_all(&item.path)
+    } else {
+        fs::remove_file(&item.path)
+    };
+
+    result.map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => format!("{} (re-run with sudo)", e),
+        _ => e.to_string(),
+    })
+}
+
+fn report_step(description: &str, result: Result<(), String>) {
+    match result {
+        Ok(()) => println!("   {} {}", "✓".green(), description),
+        Err(e) => println!("   {} {}: {}", "✗".red(), description, e),
+    }
+}
+
+/// Walk through removing every Cluely process and file that can be found.
+/// Returns whether anything was left behind.
+pub fn run(options: &RemoveOptions) -> bool {
+    let processes = get_cluely_processes_rust();
+    let items = get_cluely_persistence_rust();
+    let launch_jobs: Vec<_> = items.iter().filter(|item| is_launch_job(item)).collect();
+
+    if processes.is_empty() && items.is_empty() {
+        println!(
+            "{}",
+            "✅ Nothing to remove: no Cluely processes or files found".green()
+        );
+        return false;
+    }
+
+    println!("{}", "🧹 Cluely Removal".bold().blue());
+    println!("{}", "================".blue());
+    println!();
+
+    if !launch_jobs.is_empty() {
+        println!("{}", "Unload launch agents and daemons".bold());
+        for item in &launch_jobs {
+            println!("   • {}: {}", item.kind.label(), item.path.display());
+        }
+        println!();
+    }
+    if !processes.is_empty() {
+        println!("{}", "Quit running processes".bold());
+        for process in &processes {
+            println!("   • {} (pid {}, {})", process.name, process.pid, process.origin.describe());
+        }
+        println!();
+    }
+    if !items.is_empty() {
+        println!("{}", "Delete files".bold());
+        for item in &items {
+            println!("   • {}: {}", item.kind.label(), item.path.display());
+        }
+        println!();
+    }
+
+    if options.dry_run {
+        println!("{}", "Dry run: nothing was changed".yellow());
+        return true;
+    }
+
+    if !launch_jobs.is_empty()
+        && confirm(
+            &format!("Unload {} launch jobs?", launch_jobs.len()),
+            options,
+        )
+    {
+        for item in &launch_jobs {
+            report_step(&format!("Unloaded {}", item.path.display()), unload(item));
+        }
+        println!();
+    }
+
+    if !processes.is_empty() && confirm(&format!("Quit {} processes?", processes.len()), options) {
+        for process in &processes {
+            report_step(
+                &format!("Quit {} (pid {})", process.name, process.pid),
+                quit(process),
+            );
+        }
+        println!();
+    }
+
+    if !items.is_empty()
+        && confirm(
+            &format!("Delete {} files and folders?", items.len()),
+            options,
+        )
+    {
+        for item in &items {
+            report_step(&format!("Deleted {}", item.path.display()), delete(item));
+        }
+        println!();
+    }
+
+    // Verify with a fresh scan rather than trusting the individual steps
+    let remaining_processes = get_cluely_processes_rust();
+    let remaining_items = get_cluely_persistence_rust();
+    if remaining_processes.is_empty() && remaining_items.is_empty() {
+        println!("{}", "✅ Cluely has been removed".bold().green());
+        return false;
+    }
+
+    println!("{}", "⚠️  Some Cluely components remain:".bold().yellow());
+    for process in &remaining_processes {
+        println!("   • Process {} (pid {})", process.name, process.pid);
+    }
+    for item in &remaining_items {
+        println!("   • {}: {}", item.kind.label(), item.path.display());
+    }
+    true
+}
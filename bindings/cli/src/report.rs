@@ -0,0 +1,177 @@
+//! Shareable detection reports (`report --format html|markdown|pdf`).
+//!
+//! A report is gathered once into [`ReportData`] and rendered through one of
+//! the templates in `templates/`, so every format shows the same facts. HTML
+//! embeds window thumbnails as data URIs and has no external references, so
+//! it can be attached to an email as-is. Markdown is meant for pasting into
+//! tickets, and PDF (with the `pdf` feature) is laid out from the Markdown.
+
+use base64::Engine;
+use minijinja::{context, Environment};
+use serde::Serialize;
+
+use no_cluely_driver::{capture_window_image_rust, detect_cluely_detailed_rust};
+
+use crate::history::{self, Session};
+use crate::output::{OutputFormat, WindowPayload};
+use crate::screenshot;
+use crate::snapshot::Snapshot;
+use crate::timestamp;
+
+const TEMPLATES: [(&str, &str); 2] = [
+    ("report.html", include_str!("../templates/report.html")),
+    ("report.md", include_str!("../templates/report.md")),
+];
+
+/// Widest thumbnail embedded in a report, in pixels
+const THUMBNAIL_WIDTH: u32 = 480;
+/// How far back the activity timeline looks
+const TIMELINE_DAYS: i64 = 30;
+/// Gap between scans that splits a timeline session, as for `history timeline`
+const TIMELINE_MAX_GAP_SECONDS: i64 = 300;
+
+/// A detected window with its preview
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportWindow {
+    #[serde(flatten)]
+    pub window: WindowPayload,
+    /// PNG data URI; None if the window could not be captured
+    pub thumbnail: Option<String>,
+    /// Captured, but macOS returned no pixels because the window opts out of capture
+    pub capture_blocked: bool,
+}
+
+/// Everything a rendered report shows
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportData {
+    pub generated_at: String,
+    pub version: &'static str,
+    pub scan: Snapshot,
+    pub windows: Vec<ReportWindow>,
+    /// None when no scan history has been recorded on this machine
+    pub timeline: Option<Vec<Session>>,
+    pub timeline_days: i64,
+}
+
+fn thumbnail(window_id: i32) -> (Option<String>, bool) {
+    let Some(image) = capture_window_image_rust(window_id as u32) else {
+        return (None, false);
+    };
+    if image.is_blank() {
+        return (None, true);
+    }
+
+    let thumbnail = screenshot::thumbnail_png(&image, THUMBNAIL_WIDTH)
+        .ok()
+        .map(|png| {
+            format!(
+                "data:image/png;base64,{}",
+                base64::engine::general_purpose::STANDARD.encode(png)
+            )
+        });
+    (thumbnail, false)
+}
+
+/// Detection sessions from recorded history, without creating a database
+/// on machines that never recorded one
+fn recent_timeline() -> Option<Vec<Session>> {
+    if !history::default_path()?.exists() {
+        return None;
+    }
+
+    let history = history::History::open_default().ok()?;
+    let from = chrono::Utc::now() - chrono::Duration::days(TIMELINE_DAYS);
+    let scans = history.range(Some(from), None).ok()?;
+    Some(history::timeline(
+        &scans,
+        chrono::Duration::seconds(TIMELINE_MAX_GAP_SECONDS),
+    ))
+}
+
+impl ReportData {
+    /// Scan now and collect everything the report needs. Capturing
+    /// thumbnails is skipped for formats that cannot show them.
+    pub fn collect(thumbnails: bool) -> Self {
+        let (result, windows) = detect_cluely_detailed_rust();
+        let scan = Snapshot::from_scan(&result, &windows);
+
+        let windows = scan
+            .windows
+            .iter()
+            .map(|window| {
+                let (thumbnail, capture_blocked) = if thumbnails {
+                    thumbnail(window.window_id)
+                } else {
+                    (None, false)
+                };
+                ReportWindow {
+                    window: window.clone(),
+                    thumbnail,
+                    capture_blocked,
+                }
+            })
+            .collect();
+
+        Self {
+            generated_at: timestamp::now(),
+            version: env!("CARGO_PKG_VERSION"),
+            scan,
+            windows,
+            timeline: recent_timeline(),
+            timeline_days: TIMELINE_DAYS,
+        }
+    }
+}
+
+/// Escape text for a Markdown table cell
+fn markdown_cell(value: String) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\n', '\r'], " ")
+}
+
+fn render_template(name: &str, data: &ReportData) -> Result<String, String> {
+    let mut env = Environment::new();
+    env.add_filter("md", markdown_cell);
+    env.add_filter("time", |value: String| timestamp::reformat(&value));
+    for (template_name, source) in TEMPLATES {
+        env.add_template(template_name, source)
+            .map_err(|e| format!("Invalid report template {}: {}", template_name, e))?;
+    }
+    env.get_template(name)
+        .and_then(|template| template.render(context! { report => data }))
+        .map_err(|e| format!("Failed to render the report: {}", e))
+}
+
+/// Whether `report` renders this format through a template rather than as data
+pub fn is_document(format: OutputFormat) -> bool {
+    matches!(
+        format,
+        OutputFormat::Html | OutputFormat::Markdown | OutputFormat::Pdf
+    )
+}
+
+/// Render the report as a document in `format`
+pub fn render(data: &ReportData, format: OutputFormat) -> Result<Vec<u8>, String> {
+    match format {
+        OutputFormat::Html => render_template("report.html", data).map(String::into_bytes),
+        OutputFormat::Markdown => render_template("report.md", data).map(String::into_bytes),
+        OutputFormat::Pdf => render_pdf(data),
+        _ => Err(format!("{:?} is not a report document format", format)),
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn render_pdf(data: &ReportData) -> Result<Vec<u8>, String> {
+    let markdown = render_template("report.md", data)?;
+    crate::pdf::from_markdown(
+        &format!("Cluely Detection Report - {}", data.scan.hostname),
+        &markdown,
+    )
+}
+
+#[cfg(not(feature = "pdf"))]
+fn render_pdf(_data: &ReportData) -> Result<Vec<u8>, String> {
+    Err("This build has no PDF support; rebuild with `cargo install --features pdf`".to_string())
+}
@@ -0,0 +1,185 @@
+//! Detection rules and signature tests (`rules` subcommand).
+//!
+//! `rules test` runs the Cluely rules over a recorded window dump instead
+//! of the live window list, so a new signature file can be tried against
+//! the windows it should and should not match before it is deployed.
+//!
+//! A dump is JSON: an array of windows, or any document with a `windows`
+//! array, such as a `snapshot`. Each window needs an `owner`; `title` (or
+//! `name`), `bundle_id`, `window_id`, `sharing_state`, `layer` and `alpha`
+//! are optional. A window may also say whether it should be detected with
+//! `expect_cluely`.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use no_cluely_driver::{normalize, ClueLyDetectionResult, Rule, SignatureSet, Subject, WindowInfo};
+
+use crate::output::DetectionPayload;
+
+/// A rule as `rules list` and `rules explain` show it
+#[derive(Debug, Serialize)]
+pub struct RuleInfo {
+    pub id: &'static str,
+    /// Bit in the C result's `rules_fired`; absent for rules that only fire
+    /// in the evasion scan
+    pub bit: Option<u32>,
+    pub summary: &'static str,
+    pub explanation: &'static str,
+}
+
+impl RuleInfo {
+    pub fn from_rule(rule: Rule) -> Self {
+        Self {
+            id: rule.id(),
+            bit: rule.in_detection_result().then(|| rule.bit()),
+            summary: rule.summary(),
+            explanation: rule.explanation(),
+        }
+    }
+}
+
+/// Every rule, in bit order
+pub fn list() -> Vec<RuleInfo> {
+    Rule::ALL.into_iter().map(RuleInfo::from_rule).collect()
+}
+
+/// The rule with this ID
+pub fn explain(id: &str) -> Result<RuleInfo, String> {
+    Rule::from_id(id).map(RuleInfo::from_rule).ok_or_else(|| {
+        format!(
+            "There is no rule \"{}\"; run 'cluely-detector rules list' to see them",
+            id
+        )
+    })
+}
+
+/// One window of a recorded dump
+#[derive(Debug, Clone, Deserialize)]
+struct DumpWindow {
+    #[serde(default)]
+    window_id: i32,
+    owner: String,
+    #[serde(default, alias = "name")]
+    title: Option<String>,
+    #[serde(default)]
+    bundle_id: Option<String>,
+    /// Defaults to 1, shared like an ordinary window
+    #[serde(default = "default_sharing_state")]
+    sharing_state: i32,
+    #[serde(default)]
+    layer: i32,
+    #[serde(default = "default_alpha")]
+    alpha: f64,
+    /// Whether the window should be detected; not checked if absent
+    #[serde(default)]
+    expect_cluely: Option<bool>,
+}
+
+fn default_sharing_state() -> i32 {
+    1
+}
+
+fn default_alpha() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Dump {
+    Windows(Vec<DumpWindow>),
+    Document { windows: Vec<DumpWindow> },
+}
+
+/// How one window of the dump came out
+#[derive(Debug, Serialize)]
+pub struct WindowOutcome {
+    pub window_id: i32,
+    pub owner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Name of the signature that matched; None if the window is not Cluely's
+    pub signature: Option<String>,
+    /// IDs of the rules that fired for this window
+    pub rules_fired: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect_cluely: Option<bool>,
+    /// False if the window was expected to be detected and was not, or the
+    /// other way around
+    pub passed: bool,
+}
+
+/// What `rules test` found
+#[derive(Debug, Serialize)]
+pub struct TestReport {
+    pub windows: Vec<WindowOutcome>,
+    /// The result the dump would have produced in a live scan
+    pub detection: DetectionPayload,
+    /// Windows whose expectation was not met
+    pub failures: usize,
+}
+
+fn lower(text: &Option<String>) -> Option<String> {
+    text.as_deref()
+        .filter(|text| !text.is_empty() && *text != "<No Title>")
+        .map(normalize::lower)
+}
+
+/// Run the Cluely rules with `signatures` over the dump at `path`
+pub fn test(path: &Path, signatures: &SignatureSet) -> Result<TestReport, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let windows = match serde_json::from_str(&contents)
+        .map_err(|e| format!("{} is not a window dump: {}", path.display(), e))?
+    {
+        Dump::Windows(windows) | Dump::Document { windows } => windows,
+    };
+
+    let mut result = ClueLyDetectionResult::new(signatures);
+    let mut outcomes = Vec::with_capacity(windows.len());
+    for window in windows {
+        let subject = Subject {
+            owner: normalize::lower(&window.owner),
+            title: lower(&window.title),
+            bundle_id: lower(&window.bundle_id),
+        };
+        let signature = signatures
+            .matching(&subject)
+            .map(|signature| signature.name.clone());
+
+        let mut rules_fired = Vec::new();
+        if signature.is_some() {
+            let info = WindowInfo {
+                owner: window.owner.clone(),
+                window_id: window.window_id,
+                sharing_state: window.sharing_state,
+                layer: window.layer,
+                alpha: window.alpha,
+            };
+            let mut alone = ClueLyDetectionResult::default();
+            alone.add_window(&info);
+            rules_fired = alone.fired_rules().into_iter().map(Rule::id).collect();
+            result.add_window(&info);
+        }
+
+        outcomes.push(WindowOutcome {
+            passed: window
+                .expect_cluely
+                .is_none_or(|expected| expected == signature.is_some()),
+            window_id: window.window_id,
+            owner: window.owner,
+            title: window.title,
+            signature,
+            rules_fired,
+            expect_cluely: window.expect_cluely,
+        });
+    }
+
+    Ok(TestReport {
+        failures: outcomes.iter().filter(|outcome| !outcome.passed).count(),
+        windows: outcomes,
+        detection: DetectionPayload::from_result(&result),
+    })
+}
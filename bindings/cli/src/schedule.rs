@@ -0,0 +1,191 @@
+//! When `monitor` scans (`--interval` or `--schedule`).
+//!
+//! A schedule is either an interval or a standard five-field cron expression
+//! (minute, hour, day of month, month, day of week) evaluated in local time,
+//! so `*/5 9-18 * * 1-5` scans every five minutes during working hours and not
+//! at all overnight or at weekends.
+//!
+//! Intervals adapt: scans speed up for a while after an app launches or quits,
+//! when Cluely is most likely to appear or disappear, and back off towards
+//! `--max-interval` while nothing changes. Cron schedules are kept exactly.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use croner::Cron;
+
+use crate::workspace;
+
+/// Seconds between scans when neither `--interval` nor a schedule is given
+pub const DEFAULT_INTERVAL: u64 = 10;
+
+/// Idle back-off limit, as a multiple of the interval, without `--max-interval`
+pub const DEFAULT_BACKOFF_FACTOR: u32 = 6;
+
+/// Longest single wait, so Ctrl+C is noticed promptly during long pauses
+const SLEEP_SLICE: Duration = Duration::from_millis(500);
+/// Delay between an app launching and the scan, so its windows exist by then
+const SETTLE_DELAY: Duration = Duration::from_secs(2);
+/// How long scans stay at the fast rate after an app launches or quits
+const BURST_DURATION: Duration = Duration::from_secs(60);
+/// Shortest gap between scans during a burst
+const MIN_BURST_INTERVAL: Duration = Duration::from_secs(2);
+
+pub enum Schedule {
+    /// Scan, then wait this long before the next scan
+    Every(Duration),
+    /// Scan at every time matching the cron expression
+    Cron(Box<Cron>),
+}
+
+impl Schedule {
+    /// Parse a five-field cron expression
+    pub fn cron(expression: &str) -> Result<Self, String> {
+        Cron::new(expression)
+            .parse()
+            .map(|cron| Schedule::Cron(Box::new(cron)))
+            .map_err(|e| format!("Invalid schedule '{}': {}", expression, e))
+    }
+
+    /// When the first scan is due if monitoring starts at `now`: right away
+    /// for an interval, at the next matching time for a cron expression
+    pub fn first(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Schedule::Every(_) => Some(now),
+            Schedule::Cron(cron) => cron.find_next_occurrence(&now, true).ok(),
+        }
+    }
+
+    /// When the scan after one at `now` is due; None if a cron expression
+    /// never matches again (e.g. February 30th)
+    pub fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match self {
+            Schedule::Every(interval) => after(now, *interval),
+            Schedule::Cron(cron) => cron.find_next_occurrence(&now, false).ok(),
+        }
+    }
+
+    /// Human-readable description for the monitor banner
+    pub fn describe(&self) -> String {
+        match self {
+            Schedule::Every(interval) => format!("every {}s", interval.as_secs()),
+            Schedule::Cron(cron) => format!("on schedule '{}'", cron.pattern),
+        }
+    }
+}
+
+fn after(now: DateTime<Local>, delay: Duration) -> Option<DateTime<Local>> {
+    chrono::Duration::from_std(delay)
+        .ok()
+        .map(|delay| now + delay)
+}
+
+/// Decides when the next scan is due, adapting intervals to activity
+pub struct Pacer {
+    schedule: Schedule,
+    max_interval: Duration,
+    /// Gap between interval scans; grows while nothing changes
+    current: Duration,
+    /// Scans run at the fast rate until then
+    burst_until: Option<Instant>,
+}
+
+impl Pacer {
+    /// `max_interval` bounds the idle back-off; it is ignored for cron schedules
+    pub fn new(schedule: Schedule, max_interval: Duration) -> Self {
+        let current = match &schedule {
+            Schedule::Every(interval) => *interval,
+            Schedule::Cron(_) => Duration::ZERO,
+        };
+        Self {
+            schedule,
+            max_interval: max_interval.max(current),
+            current,
+            burst_until: None,
+        }
+    }
+
+    /// Human-readable description for the monitor banner
+    pub fn describe(&self) -> String {
+        match &self.schedule {
+            Schedule::Every(interval) if self.max_interval > *interval => format!(
+                "{}, up to every {}s while nothing changes",
+                self.schedule.describe(),
+                self.max_interval.as_secs()
+            ),
+            _ => self.schedule.describe(),
+        }
+    }
+
+    pub fn first(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.schedule.first(now)
+    }
+
+    /// Record a scan; `activity` means something changed that is worth
+    /// watching closely, which resets the idle back-off
+    pub fn scanned(&mut self, activity: bool) {
+        let Schedule::Every(interval) = &self.schedule else {
+            return;
+        };
+        if activity {
+            self.current = *interval;
+        } else if !self.in_burst() {
+            self.current = (self.current * 3 / 2).min(self.max_interval);
+        }
+    }
+
+    /// An app launched or quit at `now`. Returns when to scan because of
+    /// it, or None if the schedule doesn't react to app events.
+    pub fn app_event(&mut self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let Schedule::Every(interval) = &self.schedule else {
+            return None;
+        };
+        self.current = *interval;
+        self.burst_until = Some(Instant::now() + BURST_DURATION);
+        after(now, SETTLE_DELAY)
+    }
+
+    /// When the scan after one finishing at `now` is due
+    pub fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match &self.schedule {
+            Schedule::Every(interval) if self.in_burst() => {
+                after(now, (*interval / 4).max(MIN_BURST_INTERVAL).min(*interval))
+            }
+            Schedule::Every(_) => after(now, self.current),
+            Schedule::Cron(_) => self.schedule.next_after(now),
+        }
+    }
+
+    fn in_burst(&self) -> bool {
+        self.burst_until.is_some_and(|until| Instant::now() < until)
+    }
+}
+
+/// Why [`sleep_until`] returned
+pub enum Wake {
+    /// The deadline passed
+    Due,
+    /// An app launched or quit first
+    AppEvent,
+    /// Ctrl+C cleared the running flag
+    Stopped,
+}
+
+/// Wait until `deadline`, returning early on an app launch or quit (see
+/// [`workspace::observe_app_events`]) or once `running` is cleared
+pub fn sleep_until(deadline: DateTime<Local>, running: &AtomicBool) -> Wake {
+    while running.load(Ordering::SeqCst) {
+        if workspace::take_app_event() {
+            return Wake::AppEvent;
+        }
+        let Ok(remaining) = (deadline - Local::now()).to_std() else {
+            return Wake::Due;
+        };
+        if remaining.is_zero() {
+            return Wake::Due;
+        }
+        workspace::wait(remaining.min(SLEEP_SLICE));
+    }
+    Wake::Stopped
+}
@@ -0,0 +1,45 @@
+//! JSON Schemas for machine-readable output (`schema`).
+//!
+//! Every payload carries a `schema_version` matching [`SCHEMA_VERSION`], so
+//! SIEM pipelines and other consumers can validate what they ingest and
+//! notice when the format changes.
+
+use clap::ValueEnum;
+use schemars::generate::SchemaSettings;
+use schemars::Schema;
+
+use crate::output::{DetectionPayload, StateChangeEvent, SCHEMA_VERSION};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaName {
+    /// Detection results from `json` and `--format json`
+    Detection,
+    /// Detection events sent to webhooks and `serve` event subscribers
+    Event,
+}
+
+impl SchemaName {
+    fn id(self) -> &'static str {
+        match self {
+            SchemaName::Detection => "detection",
+            SchemaName::Event => "event",
+        }
+    }
+}
+
+/// The JSON Schema (draft 2020-12) for `name` at the current version
+pub fn generate(name: SchemaName) -> Schema {
+    // Describe the serialized form, where defaulted fields are still present
+    let generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    let mut schema = match name {
+        SchemaName::Detection => generator.into_root_schema_for::<DetectionPayload>(),
+        SchemaName::Event => generator.into_root_schema_for::<StateChangeEvent>(),
+    };
+    schema.insert(
+        "$id".into(),
+        format!("urn:io.nocluely:schema:{}:{}", name.id(), SCHEMA_VERSION).into(),
+    );
+    schema
+}
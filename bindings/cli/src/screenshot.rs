@@ -0,0 +1,207 @@
+//! Window screenshots (`screenshot` subcommand).
+//!
+//! Each detected Cluely window is captured on its own and saved as a PNG
+//! with a caption bar naming the window ID, owner and capture time. The same
+//! details are stored as PNG text chunks so they survive cropping.
+
+use std::convert::Infallible;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use embedded_graphics::mono_font::ascii::FONT_10X20;
+use embedded_graphics::mono_font::MonoTextStyle;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::text::{Baseline, Text};
+use serde::Serialize;
+
+use no_cluely_driver::{capture_window_image_rust, get_cluely_windows_rust, WindowImage};
+
+use crate::timestamp;
+
+/// Height of the caption bar drawn above each window
+const CAPTION_HEIGHT: u32 = 28;
+const CAPTION_BACKGROUND: [u8; 4] = [32, 32, 32, 255];
+
+/// One saved screenshot, or why a window could not be captured
+#[derive(Debug, Clone, Serialize)]
+pub struct Screenshot {
+    pub window_id: i32,
+    pub owner: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// The window excluded itself from capture, so only the caption has content
+    pub blank: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// RGBA pixel buffer that embedded-graphics can draw text onto
+struct Canvas {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x < 0 || point.y < 0 {
+                continue;
+            }
+            let (x, y) = (point.x as u32, point.y as u32);
+            if x >= self.width || y >= self.height {
+                continue;
+            }
+            let offset = ((y * self.width + x) * 4) as usize;
+            self.rgba[offset..offset + 4].copy_from_slice(&[color.r(), color.g(), color.b(), 255]);
+        }
+        Ok(())
+    }
+}
+
+/// Place the window image below a caption bar wide enough for `caption`
+fn annotate(image: &WindowImage, caption: &str) -> Canvas {
+    let caption_width = 8 + caption.chars().count() as u32 * FONT_10X20.character_size.width;
+    let width = image.width.max(caption_width);
+    let height = image.height + CAPTION_HEIGHT;
+
+    let mut canvas = Canvas {
+        width,
+        height,
+        rgba: vec![0; (width * height * 4) as usize],
+    };
+    for pixel in canvas.rgba[..(width * CAPTION_HEIGHT * 4) as usize].chunks_exact_mut(4) {
+        pixel.copy_from_slice(&CAPTION_BACKGROUND);
+    }
+    let row_bytes = (image.width * 4) as usize;
+    for (y, row) in image.rgba.chunks_exact(row_bytes).enumerate() {
+        let offset = ((CAPTION_HEIGHT + y as u32) * width * 4) as usize;
+        canvas.rgba[offset..offset + row_bytes].copy_from_slice(row);
+    }
+
+    let style = MonoTextStyle::new(&FONT_10X20, Rgb888::WHITE);
+    let _ = Text::with_baseline(caption, Point::new(4, 4), style, Baseline::Top).draw(&mut canvas);
+    canvas
+}
+
+/// Encode RGBA pixels as PNG, with `text` stored as tEXt chunks
+fn encode_png<W: Write>(
+    writer: W,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    text: &[(&str, &str)],
+) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text) in text {
+        encoder.add_text_chunk(keyword.to_string(), text.to_string())?;
+    }
+    encoder.write_header()?.write_image_data(rgba)
+}
+
+fn write_png(path: &Path, canvas: &Canvas, caption: &str, owner: &str) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    let text = [
+        ("Title", caption),
+        ("Author", owner),
+        (
+            "Software",
+            concat!("cluely-detector ", env!("CARGO_PKG_VERSION")),
+        ),
+    ];
+    encode_png(
+        BufWriter::new(file),
+        canvas.width,
+        canvas.height,
+        &canvas.rgba,
+        &text,
+    )
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Shrink a window image to at most `max_width` pixels wide and encode it as PNG
+pub fn thumbnail_png(image: &WindowImage, max_width: u32) -> Result<Vec<u8>, String> {
+    let scale = (image.width as f64 / max_width as f64).max(1.0);
+    let width = ((image.width as f64 / scale) as u32).max(1);
+    let height = ((image.height as f64 / scale) as u32).max(1);
+
+    // Nearest-neighbour sampling is enough for a preview
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let source_y = ((y as f64 * scale) as u32).min(image.height - 1);
+        for x in 0..width {
+            let source_x = ((x as f64 * scale) as u32).min(image.width - 1);
+            let offset = ((source_y * image.width + source_x) * 4) as usize;
+            rgba.extend_from_slice(&image.rgba[offset..offset + 4]);
+        }
+    }
+
+    let mut png = Vec::new();
+    encode_png(&mut png, width, height, &rgba, &[])
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+    Ok(png)
+}
+
+/// Capture every detected Cluely window into `out_dir`
+pub fn capture_all(out_dir: &Path) -> Result<Vec<Screenshot>, String> {
+    fs::create_dir_all(out_dir)
+        .map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+
+    let now = chrono::Local::now();
+    let screenshots = get_cluely_windows_rust()
+        .into_iter()
+        .map(|window| {
+            let mut screenshot = Screenshot {
+                window_id: window.window_id,
+                owner: window.owner.clone(),
+                path: None,
+                blank: false,
+                error: None,
+            };
+
+            let Some(image) = capture_window_image_rust(window.window_id as u32) else {
+                screenshot.error = Some("The window could not be captured".to_string());
+                return screenshot;
+            };
+
+            let caption = format!(
+                "Window {} | {} | {}",
+                window.window_id,
+                window.owner,
+                timestamp::display(now.with_timezone(&chrono::Utc))
+            );
+            let path = out_dir.join(format!(
+                "cluely-window-{}-{}.png",
+                window.window_id,
+                now.format("%Y%m%d-%H%M%S")
+            ));
+
+            screenshot.blank = image.is_blank();
+            match write_png(&path, &annotate(&image, &caption), &caption, &window.owner) {
+                Ok(()) => screenshot.path = Some(path),
+                Err(e) => screenshot.error = Some(e),
+            }
+            screenshot
+        })
+        .collect();
+
+    Ok(screenshots)
+}
@@ -0,0 +1,455 @@
+//! Local HTTP API (`serve` mode).
+//!
+//! A single background scanner keeps the latest detection and a bounded
+//! in-memory history; HTTP handlers only read that shared state, so any
+//! number of dashboards can poll without triggering extra window scans.
+//! `/v1/events` upgrades to a WebSocket that receives state-change events
+//! from the same scanner, and `/v1/health` reports whether it is still
+//! scanning.
+//!
+//! The listening socket is our own: each connection's request line is
+//! peeked, `/v1/events` is upgraded here with tungstenite, and every other
+//! connection is relayed to tiny_http on a loopback port.
+
+use std::collections::VecDeque;
+use std::io::{self, Cursor, ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tungstenite::handshake::server::{
+    ErrorResponse, Request as HandshakeRequest, Response as HandshakeResponse,
+};
+use tungstenite::{HandshakeError, Message, WebSocket};
+
+use no_cluely_driver::{
+    detect_cluely_with_rust, ClueLyDetectionResult, DetectionError, DetectorConfig,
+};
+
+use crate::health::Health;
+use crate::history::History;
+use crate::output::{DetectionPayload, StateChangeEvent, WindowPayload};
+
+/// How often idle WebSocket connections are pinged to detect dead clients
+const WEBSOCKET_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a WebSocket read waits for a client frame before the stream
+/// checks for events to send
+const WEBSOCKET_READ_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How long a new connection may take to send its request line, and a
+/// WebSocket client its handshake
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Options for `cluely-detector serve`
+pub struct ServeOptions {
+    pub bind: String,
+    pub interval: u64,
+    pub history_size: usize,
+    pub allow_origin: Option<String>,
+    /// Database every scan is recorded in
+    pub history: Option<History>,
+    /// Signatures, targets and suppressions to scan with, as `monitor` does
+    pub detector: DetectorConfig,
+}
+
+/// Scanner state shared between the scan thread and request handlers
+struct ScanState {
+    detection: DetectionPayload,
+    windows: Vec<WindowPayload>,
+    history: VecDeque<DetectionPayload>,
+    subscribers: Vec<Sender<String>>,
+    health: Health,
+}
+
+impl ScanState {
+    /// Scan once; a scan that cannot see the windows is an error rather
+    /// than a clean result, so the last detection stands
+    fn scan(
+        detector: &DetectorConfig,
+    ) -> Result<(DetectionPayload, Vec<WindowPayload>), DetectionError> {
+        let (result, windows) = detect_cluely_with_rust(detector)?;
+        Ok((
+            DetectionPayload::from_result(&result),
+            windows.iter().map(WindowPayload::from_window).collect(),
+        ))
+    }
+
+    fn record(&mut self, detection: DetectionPayload, windows: Vec<WindowPayload>, limit: usize) {
+        let changed = detection.detected != self.detection.detected;
+        self.health.scanned(detection.detected);
+
+        self.history.push_back(detection.clone());
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+        self.detection = detection;
+        self.windows = windows;
+
+        if changed {
+            self.broadcast(&StateChangeEvent::transition(self.detection.clone()));
+        }
+    }
+
+    /// Send an event to every WebSocket subscriber, dropping closed ones
+    fn broadcast(&mut self, event: &StateChangeEvent) {
+        let message = serde_json::to_string(event).unwrap();
+        self.subscribers
+            .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+    }
+
+    fn subscribe(&mut self) -> Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+        let snapshot = StateChangeEvent::snapshot(self.detection.clone());
+        let _ = sender.send(serde_json::to_string(&snapshot).unwrap());
+        self.subscribers.push(sender);
+        receiver
+    }
+}
+
+#[derive(Serialize)]
+pub struct ErrorBody<'a> {
+    pub error: &'a str,
+}
+
+/// Run the HTTP API until the process is terminated
+pub fn run(options: ServeOptions) -> Result<(), String> {
+    let listener = TcpListener::bind(&options.bind)
+        .map_err(|e| format!("Failed to bind {}: {}", options.bind, e))?;
+    let server = Server::http("127.0.0.1:0")
+        .map_err(|e| format!("Failed to start the HTTP server: {}", e))?;
+    let internal = server
+        .server_addr()
+        .to_ip()
+        .ok_or("HTTP server has no TCP address")?;
+
+    // Scan once up front so every endpoint has data from the first request
+    let mut health = Health::new("serve");
+    let history = options.history;
+    let detector = options.detector;
+    let (detection, windows) = match ScanState::scan(&detector) {
+        Ok((detection, windows)) => {
+            health.scanned(detection.detected);
+            record_history(history.as_ref(), &detection, &windows);
+            (detection, windows)
+        }
+        Err(error) => {
+            tracing::warn!(%error, "scan failed");
+            health.failed(error);
+            let result = ClueLyDetectionResult::default();
+            (DetectionPayload::from_result(&result), Vec::new())
+        }
+    };
+    let state = Arc::new(Mutex::new(ScanState {
+        history: VecDeque::from([detection.clone()]),
+        detection,
+        windows,
+        subscribers: Vec::new(),
+        health,
+    }));
+
+    let scanner_state = Arc::clone(&state);
+    let interval = options.interval.max(1);
+    let history_size = options.history_size;
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(interval));
+        match ScanState::scan(&detector) {
+            Ok((detection, windows)) => {
+                record_history(history.as_ref(), &detection, &windows);
+                scanner_state
+                    .lock()
+                    .unwrap()
+                    .record(detection, windows, history_size);
+            }
+            Err(error) => {
+                tracing::warn!(%error, "scan failed");
+                scanner_state.lock().unwrap().health.failed(error);
+            }
+        }
+    });
+
+    let http_state = Arc::clone(&state);
+    let allow_origin = options.allow_origin;
+    let http_allow_origin = allow_origin.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle_request(request, &http_state, http_allow_origin.as_deref());
+        }
+    });
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                tracing::warn!(%error, "failed to accept a connection");
+                continue;
+            }
+        };
+        let state = Arc::clone(&state);
+        let allow_origin = allow_origin.clone();
+        thread::spawn(move || handle_connection(stream, internal, &state, allow_origin.as_deref()));
+    }
+
+    Ok(())
+}
+
+fn record_history(
+    history: Option<&History>,
+    detection: &DetectionPayload,
+    windows: &[WindowPayload],
+) {
+    if let Some(Err(e)) = history.map(|history| history.record("serve", detection, windows)) {
+        tracing::warn!(error = %e, "failed to record scan history");
+    }
+}
+
+/// Route a connection by its request line: `/v1/events` is upgraded here,
+/// anything else goes to tiny_http at `internal`
+fn handle_connection(
+    stream: TcpStream,
+    internal: SocketAddr,
+    state: &Mutex<ScanState>,
+    allow_origin: Option<&str>,
+) {
+    if stream.set_read_timeout(Some(REQUEST_TIMEOUT)).is_err() {
+        return;
+    }
+    match peek_request_line(&stream) {
+        Some(line) if is_event_stream(&line) => handle_event_stream(stream, state, allow_origin),
+        Some(_) if stream.set_read_timeout(None).is_ok() => relay(stream, internal),
+        _ => {}
+    }
+}
+
+/// Wait for the request line without consuming it, so the connection can
+/// still be handed on whole. Gives up on clients that close or stay silent.
+fn peek_request_line(stream: &TcpStream) -> Option<String> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut buffer = [0; 1024];
+    loop {
+        let peeked = stream.peek(&mut buffer).ok()?;
+        let data = &buffer[..peeked];
+        if peeked == 0 {
+            return None;
+        }
+        // A line too long for `/v1/events`, or never finished, is tiny_http's
+        // to answer
+        let end = data.windows(2).position(|pair| pair == b"\r\n");
+        if end.is_some() || peeked == buffer.len() || Instant::now() >= deadline {
+            let line = &data[..end.unwrap_or(peeked)];
+            return Some(String::from_utf8_lossy(line).into_owned());
+        }
+        // Peeking returns at once while any data is buffered
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn is_event_stream(request_line: &str) -> bool {
+    let mut parts = request_line.split(' ');
+    parts.next() == Some("GET")
+        && parts
+            .next()
+            .is_some_and(|target| target.split('?').next() == Some("/v1/events"))
+}
+
+/// Pass a connection through to tiny_http and its responses back, until
+/// either side closes
+fn relay(client: TcpStream, internal: SocketAddr) {
+    let Ok(mut upstream) = TcpStream::connect(internal) else {
+        return;
+    };
+    let (Ok(mut client_reader), Ok(mut upstream_writer)) =
+        (client.try_clone(), upstream.try_clone())
+    else {
+        return;
+    };
+    let requests = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    let mut client = client;
+    let _ = io::copy(&mut upstream, &mut client);
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = requests.join();
+}
+
+fn handle_request(request: Request, state: &Mutex<ScanState>, allow_origin: Option<&str>) {
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.url().to_string(), String::new()),
+    };
+
+    let response = if *request.method() != Method::Get {
+        json_response(
+            405,
+            &ErrorBody {
+                error: "method not allowed",
+            },
+        )
+    } else if path == "/v1/events" {
+        // Only reachable on a kept-alive connection; upgrades are taken
+        // before a connection reaches tiny_http
+        json_response(
+            426,
+            &ErrorBody {
+                error: "websocket upgrade required",
+            },
+        )
+        .with_header(header("Sec-WebSocket-Version", "13"))
+    } else {
+        let state = state.lock().unwrap();
+        match path.as_str() {
+            "/v1/detect" => json_response(200, &state.detection),
+            "/v1/windows" => json_response(200, &state.windows),
+            "/v1/health" => json_response(200, &state.health.current()),
+            "/v1/history" => {
+                let limit = query_param(&query, "limit")
+                    .and_then(|limit| limit.parse::<usize>().ok())
+                    .unwrap_or(state.history.len());
+                let skip = state.history.len().saturating_sub(limit);
+                let history: Vec<_> = state.history.iter().skip(skip).collect();
+                json_response(200, &history)
+            }
+            _ => json_response(404, &ErrorBody { error: "not found" }),
+        }
+    };
+
+    let response = match allow_origin {
+        Some(origin) => response.with_header(header("Access-Control-Allow-Origin", origin)),
+        None => response,
+    };
+
+    // The client may have disconnected; nothing useful to do about it
+    let _ = request.respond(response);
+}
+
+/// Upgrade `/v1/events` to a WebSocket and stream state-change events to
+/// it. A page may only connect if it is the `--allow-origin` one, so other
+/// websites open in the user's browser cannot watch the detector.
+fn handle_event_stream(stream: TcpStream, state: &Mutex<ScanState>, allow_origin: Option<&str>) {
+    // The error type is tungstenite's
+    #[allow(clippy::result_large_err)]
+    let check_origin = |request: &HandshakeRequest, response: HandshakeResponse| {
+        let Some(origin) = request.headers().get("Origin") else {
+            return Ok(response);
+        };
+        match allow_origin {
+            Some(allowed) if allowed == "*" || origin.as_bytes() == allowed.as_bytes() => {
+                Ok(response)
+            }
+            _ => Err(error_response(403, "origin not allowed")),
+        }
+    };
+
+    let mut socket = match tungstenite::accept_hdr(&stream, check_origin) {
+        Ok(socket) => socket,
+        // Not a WebSocket handshake, or one for another protocol version
+        Err(HandshakeError::Failure(tungstenite::Error::Protocol(_))) => {
+            upgrade_required(&stream);
+            return;
+        }
+        // Refused by the origin check, which tungstenite has answered
+        Err(_) => return,
+    };
+    if stream
+        .set_read_timeout(Some(WEBSOCKET_READ_TIMEOUT))
+        .is_err()
+    {
+        return;
+    }
+
+    let events = state.lock().unwrap().subscribe();
+    stream_events(&mut socket, &events);
+}
+
+fn error_response(status: u16, error: &str) -> ErrorResponse {
+    let body = serde_json::to_string_pretty(&ErrorBody { error }).unwrap();
+    tungstenite::http::Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Content-Length", body.len())
+        .body(Some(body))
+        .unwrap()
+}
+
+/// Answer a request that tungstenite could not upgrade; it leaves these
+/// unanswered
+fn upgrade_required(mut stream: &TcpStream) {
+    let body = serde_json::to_vec_pretty(&ErrorBody {
+        error: "websocket upgrade required",
+    })
+    .unwrap();
+    let head = format!(
+        "HTTP/1.1 426 Upgrade Required\r\n\
+         Upgrade: websocket\r\n\
+         Sec-WebSocket-Version: 13\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream
+        .write_all(head.as_bytes())
+        .and_then(|()| stream.write_all(&body));
+}
+
+/// Send events to a client while reading its frames, until either side
+/// closes. Reads time out after `WEBSOCKET_READ_TIMEOUT` to let events out.
+fn stream_events<S: std::io::Read + std::io::Write>(
+    socket: &mut WebSocket<S>,
+    events: &Receiver<String>,
+) {
+    let mut last_sent = Instant::now();
+    loop {
+        match socket.read() {
+            // tungstenite queues the reply; flushing completes the handshake
+            Ok(Message::Close(_)) => {
+                let _ = socket.flush();
+                return;
+            }
+            // Pongs, and anything else a client sends, need no answer
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+
+        loop {
+            let message = match events.try_recv() {
+                Ok(event) => Message::Text(event),
+                Err(TryRecvError::Empty) if last_sent.elapsed() >= WEBSOCKET_PING_INTERVAL => {
+                    Message::Ping(Vec::new())
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return,
+            };
+            if socket.send(message).is_err() {
+                return;
+            }
+            last_sent = Instant::now();
+        }
+    }
+}
+
+pub fn json_response<T: Serialize>(status: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let data = serde_json::to_vec_pretty(body).unwrap();
+    Response::from_data(data)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
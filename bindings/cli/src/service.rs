@@ -0,0 +1,219 @@
+//! launchd integration (`service` subcommand).
+//!
+//! Installs a per-user LaunchAgent that runs `cluely-detector monitor` at
+//! login and restarts it if it exits. The agent reads the same configuration
+//! file as an interactive `monitor`, so notifiers configured there apply to
+//! the background service too.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use plist::{Dictionary, Value};
+
+/// launchd job label, also used for the plist file name
+pub const LABEL: &str = "io.nocluely.cluely-detector";
+
+/// Where the agent's files live for the current user
+pub struct ServicePaths {
+    pub plist: PathBuf,
+    pub stdout_log: PathBuf,
+    pub stderr_log: PathBuf,
+}
+
+impl ServicePaths {
+    pub fn new() -> Result<Self, String> {
+        let home = dirs::home_dir().ok_or("Could not determine the home directory")?;
+        let logs = home.join("Library").join("Logs").join("cluely-detector");
+        Ok(Self {
+            plist: home
+                .join("Library")
+                .join("LaunchAgents")
+                .join(format!("{}.plist", LABEL)),
+            stdout_log: logs.join("monitor.log"),
+            stderr_log: logs.join("monitor.err.log"),
+        })
+    }
+}
+
+/// State of the agent as reported by launchd
+pub struct ServiceStatus {
+    pub installed: bool,
+    pub loaded: bool,
+    pub pid: Option<u32>,
+    pub last_exit_code: Option<String>,
+}
+
+/// launchd domain for the logged-in user's GUI session
+pub fn domain() -> String {
+    format!("gui/{}", unsafe { libc::getuid() })
+}
+
+fn service_target() -> String {
+    format!("{}/{}", domain(), LABEL)
+}
+
+pub fn launchctl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("launchctl")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run launchctl: {}", e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "launchctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn agent_plist(
+    program: &Path,
+    config: Option<&Path>,
+    interval: Option<u64>,
+    schedule: Option<&str>,
+    watchdog: bool,
+    os_log: bool,
+    paths: &ServicePaths,
+) -> Value {
+    let mut arguments = vec![Value::from(program.display().to_string())];
+    if let Some(config) = config {
+        arguments.push(Value::from("--config"));
+        arguments.push(Value::from(config.display().to_string()));
+    }
+    arguments.push(Value::from("monitor"));
+    // Lets `cluely-detector status` check on the agent without a scan of its own
+    arguments.push(Value::from("--health"));
+    // With neither, `monitor` uses the configuration file's schedule or its default interval
+    if let Some(interval) = interval {
+        arguments.extend([Value::from("--interval"), Value::from(interval.to_string())]);
+    }
+    if let Some(schedule) = schedule {
+        arguments.extend([Value::from("--schedule"), Value::from(schedule)]);
+    }
+    if watchdog {
+        arguments.push(Value::from("--watchdog"));
+    }
+    if os_log {
+        arguments.push(Value::from("--os-log"));
+    }
+
+    // Log files are not terminals; keep ANSI escapes out of them
+    let mut environment = Dictionary::new();
+    environment.insert("NO_COLOR".to_string(), Value::from("1"));
+
+    let mut agent = Dictionary::new();
+    agent.insert("Label".to_string(), Value::from(LABEL));
+    agent.insert("ProgramArguments".to_string(), Value::Array(arguments));
+    agent.insert("RunAtLoad".to_string(), Value::Boolean(true));
+    agent.insert("KeepAlive".to_string(), Value::Boolean(true));
+    agent.insert("ProcessType".to_string(), Value::from("Background"));
+    agent.insert(
+        "EnvironmentVariables".to_string(),
+        Value::Dictionary(environment),
+    );
+    agent.insert(
+        "StandardOutPath".to_string(),
+        Value::from(paths.stdout_log.display().to_string()),
+    );
+    agent.insert(
+        "StandardErrorPath".to_string(),
+        Value::from(paths.stderr_log.display().to_string()),
+    );
+    Value::Dictionary(agent)
+}
+
+/// Write the LaunchAgent plist and load it, replacing any existing agent
+pub fn install(
+    config: Option<&Path>,
+    interval: Option<u64>,
+    schedule: Option<&str>,
+    watchdog: bool,
+    os_log: bool,
+) -> Result<ServicePaths, String> {
+    let paths = ServicePaths::new()?;
+    let program = std::env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(|e| format!("Could not locate the cluely-detector binary: {}", e))?;
+    let config = config
+        .map(|config| {
+            fs::canonicalize(config)
+                .map_err(|e| format!("Failed to read {}: {}", config.display(), e))
+        })
+        .transpose()?;
+
+    for dir in [paths.plist.parent(), paths.stdout_log.parent()]
+        .into_iter()
+        .flatten()
+    {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    // bootstrap refuses to load a label that is already loaded
+    if is_loaded() {
+        launchctl(&["bootout", &service_target()])?;
+    }
+
+    agent_plist(
+        &program,
+        config.as_deref(),
+        interval,
+        schedule,
+        watchdog,
+        os_log,
+        &paths,
+    )
+    .to_file_xml(&paths.plist)
+    .map_err(|e| format!("Failed to write {}: {}", paths.plist.display(), e))?;
+
+    launchctl(&["bootstrap", &domain(), &paths.plist.display().to_string()])?;
+    Ok(paths)
+}
+
+/// Unload the agent and delete its plist. Log files are kept.
+pub fn uninstall() -> Result<ServicePaths, String> {
+    let paths = ServicePaths::new()?;
+
+    if is_loaded() {
+        launchctl(&["bootout", &service_target()])?;
+    }
+
+    if paths.plist.exists() {
+        fs::remove_file(&paths.plist)
+            .map_err(|e| format!("Failed to remove {}: {}", paths.plist.display(), e))?;
+    }
+
+    Ok(paths)
+}
+
+fn is_loaded() -> bool {
+    launchctl(&["print", &service_target()]).is_ok()
+}
+
+/// Query launchd for the agent's state
+pub fn status() -> Result<(ServicePaths, ServiceStatus), String> {
+    let paths = ServicePaths::new()?;
+    let mut status = ServiceStatus {
+        installed: paths.plist.exists(),
+        loaded: false,
+        pid: None,
+        last_exit_code: None,
+    };
+
+    if let Ok(description) = launchctl(&["print", &service_target()]) {
+        status.loaded = true;
+        for line in description.lines() {
+            match line.trim().split_once(" = ") {
+                Some(("pid", pid)) => status.pid = pid.parse().ok(),
+                Some(("last exit code", code)) => status.last_exit_code = Some(code.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    Ok((paths, status))
+}
@@ -0,0 +1,126 @@
+//! Summary of one monitor run.
+//!
+//! While `monitor` runs it counts scans, failed scans and detection
+//! episodes, from a detection being reported until it is reported gone.
+//! When it stops the summary is printed and saved next to the monitor state
+//! file, replacing the previous run's, so what happened during an unattended
+//! session can still be read afterwards.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Where the last run's summary is saved
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("no-cluely").join("last-session.json"))
+}
+
+/// What happened during one monitor run
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub started: DateTime<Utc>,
+    pub stopped: DateTime<Utc>,
+    pub runtime_seconds: i64,
+    pub scans: u64,
+    /// Scans that could not read the window list
+    pub failed_scans: u64,
+    /// Times Cluely was reported detected, counting a detection that was
+    /// still going on when monitoring started
+    pub episodes: u64,
+    pub longest_episode_seconds: i64,
+    /// First and last scan that saw Cluely, confirmed or not
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Whether Cluely was still reported when monitoring stopped
+    pub detected_at_exit: bool,
+}
+
+impl SessionSummary {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Counters of a running monitor
+pub struct Session {
+    started: DateTime<Utc>,
+    scans: u64,
+    failed_scans: u64,
+    episodes: u64,
+    longest_episode: chrono::Duration,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+    /// When the reported detection began, while there is one
+    episode_since: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    /// Start counting; `detected` if monitoring resumes a reported detection
+    pub fn start(detected: bool) -> Self {
+        let started = Utc::now();
+        Self {
+            started,
+            scans: 0,
+            failed_scans: 0,
+            episodes: u64::from(detected),
+            longest_episode: chrono::Duration::zero(),
+            first_seen: None,
+            last_seen: None,
+            episode_since: detected.then_some(started),
+        }
+    }
+
+    pub fn failed(&mut self) {
+        self.failed_scans += 1;
+    }
+
+    /// Count a scan that `seen` Cluely or not, after which the reported
+    /// state is `reported`
+    pub fn scanned(&mut self, seen: bool, reported: bool) {
+        let now = Utc::now();
+        self.scans += 1;
+        if seen {
+            self.first_seen.get_or_insert(now);
+            self.last_seen = Some(now);
+        }
+        match self.episode_since {
+            None if reported => {
+                self.episodes += 1;
+                self.episode_since = Some(now);
+            }
+            Some(since) if !reported => {
+                self.longest_episode = self.longest_episode.max(now - since);
+                self.episode_since = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// The summary as of now, with an ongoing episode ending now
+    pub fn finish(&self) -> SessionSummary {
+        let stopped = Utc::now();
+        let longest_episode = match self.episode_since {
+            Some(since) => self.longest_episode.max(stopped - since),
+            None => self.longest_episode,
+        };
+        SessionSummary {
+            started: self.started,
+            stopped,
+            runtime_seconds: (stopped - self.started).num_seconds(),
+            scans: self.scans,
+            failed_scans: self.failed_scans,
+            episodes: self.episodes,
+            longest_episode_seconds: longest_episode.num_seconds(),
+            first_seen: self.first_seen,
+            last_seen: self.last_seen,
+            detected_at_exit: self.episode_since.is_some(),
+        }
+    }
+}
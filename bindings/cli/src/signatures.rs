@@ -0,0 +1,389 @@
+//! Signature bundles (`signatures` subcommand).
+//!
+//! Cluely changes its bundle IDs and window titles more often than the
+//! detector is released, so signatures are also published on their own: a
+//! bundle is a TOML file of `[[signature]]` tables like the configuration's,
+//! with a version number and a detached Ed25519 signature made with the
+//! release key. `signatures update` installs a bundle only if its signature
+//! verifies, every pattern compiles and it is newer than the installed one.
+//!
+//! The installed bundle and its signature live in `signatures/current`
+//! under the configuration directory. A new bundle is written to a staging
+//! directory and renamed into place, and the bundle it replaces is kept in
+//! `signatures/previous` for `signatures rollback`.
+//!
+//! Machines that cannot reach the feed take the same files by hand:
+//! `signatures export` copies the installed bundle and its signature out,
+//! and `signatures import` checks and installs them like an update.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use no_cluely_driver::SignatureSet;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{self, config_dir, SignatureConfig, SignatureUpdatesConfig};
+use crate::update;
+
+/// Where bundles are fetched from unless `[signature_updates]` says otherwise
+pub const DEFAULT_URL: &str =
+    "https://github.com/terminalsin/no-cluely/releases/latest/download/signatures.toml";
+
+const BUNDLE_FILE: &str = "signatures.toml";
+const SIGNATURE_FILE: &str = "signatures.toml.sig";
+
+/// Largest bundle accepted; real ones are a few kilobytes
+const MAX_BUNDLE_SIZE: u64 = 1024 * 1024;
+
+/// A published set of signatures
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    /// Increases with every bundle published; an update never installs an
+    /// older one, so a stale signed bundle cannot be replayed
+    pub version: u64,
+    /// When the bundle was published
+    pub released: Option<NaiveDate>,
+    #[serde(default, rename = "signature")]
+    pub signatures: Vec<SignatureConfig>,
+}
+
+impl Bundle {
+    fn parse(contents: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(contents).map_err(|_| "not UTF-8 text".to_string())?;
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// The bundle's signatures, compiled
+    pub fn compile(&self) -> Result<Vec<no_cluely_driver::Signature>, String> {
+        config::compile_signatures(&self.signatures)
+    }
+}
+
+/// Any TOML file with `[[signature]]` tables: a bundle, the configuration
+/// file or one written for testing
+#[derive(Deserialize)]
+struct SignatureFile {
+    #[serde(default, rename = "signature")]
+    signatures: Vec<SignatureConfig>,
+}
+
+/// The `[[signature]]` entries of the file at `path`, compiled
+pub fn load_file(path: &Path) -> Result<Vec<no_cluely_driver::Signature>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: SignatureFile =
+        toml::from_str(&contents).map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+    config::compile_signatures(&file.signatures)
+        .map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+/// What `signatures update` or `signatures import` found or did
+pub enum UpdateOutcome {
+    UpToDate {
+        version: u64,
+    },
+    Installed {
+        bundle: Bundle,
+        /// Version of the bundle it replaced, if one was installed
+        replaced: Option<u64>,
+    },
+    /// Passed every check, but was not installed because of `dry_run`
+    Verified {
+        bundle: Bundle,
+        /// Version of the bundle it would replace, if one is installed
+        replaced: Option<u64>,
+    },
+}
+
+/// Directory holding the installed and previous bundles
+fn signatures_dir() -> Result<PathBuf, String> {
+    config_dir()
+        .map(|dir| dir.join("signatures"))
+        .ok_or_else(|| "Could not determine the configuration directory".to_string())
+}
+
+fn read_bundle(dir: &Path) -> Result<Option<Bundle>, String> {
+    let path = dir.join(BUNDLE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents =
+        fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Bundle::parse(&contents).map(Some).map_err(|e| {
+        format!(
+            "Invalid signature bundle {}: {} (run 'cluely-detector signatures rollback')",
+            path.display(),
+            e
+        )
+    })
+}
+
+/// The installed bundle, if any
+pub fn installed() -> Result<Option<Bundle>, String> {
+    match config_dir() {
+        Some(dir) => read_bundle(&dir.join("signatures").join("current")),
+        None => Ok(None),
+    }
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let mut contents = Vec::new();
+    update::get(url)?
+        .into_reader()
+        .take(MAX_BUNDLE_SIZE + 1)
+        .read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+    if contents.len() as u64 > MAX_BUNDLE_SIZE {
+        return Err(format!("{} is larger than a signature bundle can be", url));
+    }
+    Ok(contents)
+}
+
+fn verifying_key(settings: &SignatureUpdatesConfig) -> Result<VerifyingKey, String> {
+    match &settings.public_key {
+        Some(key) => update::parse_public_key(key)
+            .map_err(|e| format!("signature_updates public_key is invalid: {}", e)),
+        None => update::verifying_key(),
+    }
+}
+
+/// Write the bundle to a staging directory and swap it in for the installed
+/// one, which becomes the previous bundle. If the swap fails halfway the
+/// installed bundle is put back.
+fn install(dir: &Path, bundle: &[u8], signature: &[u8]) -> Result<(), String> {
+    let current = dir.join("current");
+    let previous = dir.join("previous");
+    let staging = dir.join(format!(".staging-{}", std::process::id()));
+
+    let staged = fs::create_dir_all(&staging)
+        .and_then(|_| fs::write(staging.join(BUNDLE_FILE), bundle))
+        .and_then(|_| fs::write(staging.join(SIGNATURE_FILE), signature));
+    if let Err(e) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("Failed to write to {}: {}", dir.display(), e));
+    }
+
+    let swap = || -> std::io::Result<()> {
+        if current.exists() {
+            if previous.exists() {
+                fs::remove_dir_all(&previous)?;
+            }
+            fs::rename(&current, &previous)?;
+        }
+        if let Err(e) = fs::rename(&staging, &current) {
+            if previous.exists() && !current.exists() {
+                let _ = fs::rename(&previous, &current);
+            }
+            return Err(e);
+        }
+        Ok(())
+    };
+    swap().map_err(|e| {
+        let _ = fs::remove_dir_all(&staging);
+        format!(
+            "Failed to install the signature bundle in {}: {}",
+            dir.display(),
+            e
+        )
+    })
+}
+
+/// Check a bundle from `source` against its signature and install it if it
+/// is newer than the installed one, or regardless with `force`. With
+/// `dry_run`, stop short of installing it.
+fn accept(
+    key: &VerifyingKey,
+    source: &str,
+    contents: &[u8],
+    signature: &[u8],
+    force: bool,
+    dry_run: bool,
+) -> Result<UpdateOutcome, String> {
+    let signature = Signature::from_slice(signature)
+        .map_err(|_| format!("{}.sig is not an Ed25519 signature", source))?;
+    key.verify(contents, &signature).map_err(|_| {
+        format!(
+            "Signature check failed for {}; the bundle was discarded",
+            source
+        )
+    })?;
+
+    let bundle = Bundle::parse(contents)
+        .map_err(|e| format!("Invalid signature bundle {}: {}", source, e))?;
+    bundle.compile().map_err(|e| {
+        format!(
+            "Signature bundle {} was not installed: {}",
+            bundle.version, e
+        )
+    })?;
+
+    let replaced = installed().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "installed signature bundle is unreadable; replacing it");
+        None
+    });
+    let replaced = replaced.map(|bundle| bundle.version);
+    if let Some(version) = replaced.filter(|version| !force && *version >= bundle.version) {
+        return Ok(UpdateOutcome::UpToDate { version });
+    }
+    if dry_run {
+        return Ok(UpdateOutcome::Verified { bundle, replaced });
+    }
+
+    install(&signatures_dir()?, contents, &signature.to_bytes())?;
+    tracing::info!(
+        version = bundle.version,
+        source,
+        "signature bundle installed"
+    );
+    Ok(UpdateOutcome::Installed { bundle, replaced })
+}
+
+/// Fetch the latest bundle and install it if it is newer than the installed
+/// one, or regardless with `force`. `url` overrides the configured one.
+pub fn update(
+    settings: &SignatureUpdatesConfig,
+    url: Option<&str>,
+    force: bool,
+    dry_run: bool,
+) -> Result<UpdateOutcome, String> {
+    // Fail before downloading anything if the bundle could not be verified
+    let key = verifying_key(settings)?;
+    let url = url.or(settings.url.as_deref()).unwrap_or(DEFAULT_URL);
+
+    let contents = download(url)?;
+    let signature = download(&format!("{}.sig", url))?;
+    accept(&key, url, &contents, &signature, force, dry_run)
+}
+
+/// The signature that goes with the bundle at `path`
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Install the bundle at `path`, signed in `path` plus ".sig", as `update`
+/// would after downloading it
+pub fn import(
+    settings: &SignatureUpdatesConfig,
+    path: &Path,
+    force: bool,
+    dry_run: bool,
+) -> Result<UpdateOutcome, String> {
+    let key = verifying_key(settings)?;
+    let read = |path: &Path| {
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    let contents = read(path)?;
+    let signature = read(&signature_path(path))?;
+    accept(
+        &key,
+        &path.display().to_string(),
+        &contents,
+        &signature,
+        force,
+        dry_run,
+    )
+}
+
+/// Copy the installed bundle and its signature to `path` and `path` plus
+/// ".sig", for `import` on a machine that cannot reach the feed. Returns
+/// the version exported.
+pub fn export(path: &Path) -> Result<u64, String> {
+    let current = signatures_dir()?.join("current");
+    let bundle = read_bundle(&current)?
+        .ok_or("No signature bundle is installed; run 'cluely-detector signatures update' first")?;
+    let copy = |from: &Path, to: &Path| {
+        fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to write {}: {}", to.display(), e))
+    };
+    copy(&current.join(BUNDLE_FILE), path)?;
+    copy(&current.join(SIGNATURE_FILE), &signature_path(path))?;
+    Ok(bundle.version)
+}
+
+/// The installed bundle as `signatures show` reports it
+#[derive(Debug, Serialize)]
+pub struct InstalledBundle {
+    pub version: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub released: Option<NaiveDate>,
+    /// SHA-256 of the bundle file, to compare with the published one
+    pub sha256: String,
+    pub path: PathBuf,
+    pub signatures: Vec<SignatureConfig>,
+}
+
+/// The signatures scans currently match against
+#[derive(Debug, Serialize)]
+pub struct Ruleset {
+    /// Number of signatures compiled into the detector
+    pub builtin: usize,
+    /// None if no bundle has been installed
+    pub bundle: Option<InstalledBundle>,
+    /// `[[signature]]` entries from the configuration file
+    pub configured: Vec<SignatureConfig>,
+}
+
+/// The active ruleset, given the configuration's own signatures
+pub fn active(configured: &[SignatureConfig]) -> Result<Ruleset, String> {
+    let current = signatures_dir()?.join("current");
+    let bundle = match read_bundle(&current)? {
+        Some(bundle) => {
+            let path = current.join(BUNDLE_FILE);
+            let contents =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            Some(InstalledBundle {
+                version: bundle.version,
+                released: bundle.released,
+                sha256: hex::encode(Sha256::digest(&contents)),
+                path,
+                signatures: bundle.signatures,
+            })
+        }
+        None => None,
+    };
+    Ok(Ruleset {
+        builtin: SignatureSet::builtin().signatures().len(),
+        bundle,
+        configured: configured.to_vec(),
+    })
+}
+
+/// Swap the installed bundle with the previous one, returning the versions
+/// now installed and kept as previous
+pub fn rollback() -> Result<(u64, Option<u64>), String> {
+    let dir = signatures_dir()?;
+    let current = dir.join("current");
+    let previous = dir.join("previous");
+    let restored =
+        read_bundle(&previous)?.ok_or("There is no previous signature bundle to roll back to")?;
+    let replaced = read_bundle(&current)
+        .ok()
+        .flatten()
+        .map(|bundle| bundle.version);
+
+    let swapping = dir.join(format!(".rollback-{}", std::process::id()));
+    let swap = || -> std::io::Result<()> {
+        if current.exists() {
+            fs::rename(&current, &swapping)?;
+        }
+        if let Err(e) = fs::rename(&previous, &current) {
+            let _ = fs::rename(&swapping, &current);
+            return Err(e);
+        }
+        if swapping.exists() {
+            fs::rename(&swapping, &previous)?;
+        }
+        Ok(())
+    };
+    swap().map_err(|e| format!("Failed to roll back in {}: {}", dir.display(), e))?;
+    tracing::info!(version = restored.version, "signature bundle rolled back");
+    Ok((restored.version, replaced))
+}
@@ -0,0 +1,215 @@
+//! Fake Cluely for end-to-end testing (`simulate` subcommand).
+//!
+//! The detector can only be trusted on a machine if it finds Cluely there,
+//! so this stands one up: a copy of this binary named "Cluely", which opens
+//! a window hidden from screen capture and floating above normal windows,
+//! the two techniques Cluely relies on. The copy does nothing else, and
+//! quits on its own after a time limit even if this process dies first.
+
+use std::env;
+use std::ffi::c_void;
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+use std::process::{self, Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use no_cluely_driver::{detect_cluely_detailed_rust, get_cluely_processes_rust, WindowInfo};
+
+use crate::doctor::Check;
+use crate::objc::{class, nsstring, objc_msgSend, sel, send, send_bool, send_id, send_isize, Id, Sel};
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+/// Name of the fake process, and so the owner of its window
+const SIMULATED_NAME: &str = "Cluely";
+const SIMULATED_TITLE: &str = "Cluely (simulated by cluely-detector)";
+/// How long to wait for the fake process and window to show up
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const NS_APPLICATION_ACTIVATION_POLICY_ACCESSORY: isize = 1;
+const NS_WINDOW_STYLE_MASK_TITLED: usize = 1 << 0;
+const NS_BACKING_STORE_BUFFERED: usize = 2;
+const NS_WINDOW_SHARING_NONE: isize = 0;
+const NS_FLOATING_WINDOW_LEVEL: isize = 3;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NSRect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+/// A running fake Cluely. Dropping it quits the process and deletes the copy.
+pub struct Simulation {
+    child: Child,
+    dir: PathBuf,
+    window: bool,
+}
+
+impl Simulation {
+    /// Start the fake process, with a window unless `window` is false. It
+    /// quits by itself after `lifetime`.
+    pub fn start(window: bool, lifetime: Duration) -> Result<Self, String> {
+        let exe = env::current_exe().map_err(|e| format!("Cannot find this executable: {}", e))?;
+        let dir = env::temp_dir().join(format!("cluely-detector-simulate-{}", process::id()));
+        fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        let fake = dir.join(SIMULATED_NAME);
+        fs::copy(&exe, &fake).map_err(|e| format!("Cannot copy {} to {}: {}", exe.display(), fake.display(), e))?;
+
+        let mut command = Command::new(&fake);
+        command
+            .arg("simulate")
+            .arg("--host")
+            .arg(lifetime.as_secs().max(1).to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null());
+        if !window {
+            command.arg("--no-window");
+        }
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                return Err(format!("Cannot start {}: {}", fake.display(), e));
+            }
+        };
+        Ok(Self { child, dir, window })
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Scan until the fake process (and window) are found or the startup
+    /// timeout passes, and report what the detector saw
+    pub fn verify(&mut self) -> Vec<Check> {
+        let pid = self.child.id() as i32;
+        let started = Instant::now();
+        loop {
+            let process_found = get_cluely_processes_rust().iter().any(|process| process.pid == pid);
+            let (_, windows) = detect_cluely_detailed_rust();
+            let window = windows.into_iter().find(|window| window.owner == SIMULATED_NAME);
+            let complete = process_found && (window.is_some() || !self.window);
+            if complete || started.elapsed() >= STARTUP_TIMEOUT {
+                return self.checks(process_found, window);
+            }
+            if let Ok(Some(status)) = self.child.try_wait() {
+                return vec![Check::fail(
+                    "Simulation",
+                    format!("The fake Cluely process exited early ({})", status),
+                    "Run from a logged-in GUI session, not over SSH",
+                )];
+            }
+            thread::sleep(Duration::from_millis(250));
+        }
+    }
+
+    fn checks(&self, process_found: bool, window: Option<WindowInfo>) -> Vec<Check> {
+        let pid = self.child.id();
+        let mut checks = vec![if process_found {
+            Check::ok("Process", format!("Found \"{}\" (pid {})", SIMULATED_NAME, pid))
+        } else {
+            Check::fail(
+                "Process",
+                format!("\"{}\" (pid {}) was not found in the process table", SIMULATED_NAME, pid),
+                "Report this as a bug, with the output of `cluely-detector doctor`",
+            )
+        }];
+        if !self.window {
+            return checks;
+        }
+
+        let Some(window) = window else {
+            checks.push(Check::fail(
+                "Window",
+                "The fake Cluely window was not found in the window list",
+                "Run `cluely-detector doctor` to check this session can see windows",
+            ));
+            return checks;
+        };
+        checks.push(Check::ok("Window", format!("Found window {}", window.window_id)));
+        checks.push(if window.sharing_state == 0 {
+            Check::ok("Capture evasion", "Detected the window hiding from screen capture")
+        } else {
+            Check::fail(
+                "Capture evasion",
+                format!("Window reports sharing state {}, expected 0", window.sharing_state),
+                "Report this as a bug; this macOS version may handle sharing types differently",
+            )
+        });
+        checks.push(if window.layer > 0 {
+            Check::ok("Elevated layer", format!("Detected the window at layer {}", window.layer))
+        } else {
+            Check::fail(
+                "Elevated layer",
+                format!("Window reports layer {}, expected above 0", window.layer),
+                "Report this as a bug",
+            )
+        });
+        checks
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Body of the fake process: open the window, or just wait, until
+/// `lifetime` runs out. Never returns.
+pub fn host(window: bool, lifetime: Duration) -> ! {
+    if !window {
+        thread::sleep(lifetime);
+        process::exit(0);
+    }
+
+    unsafe {
+        let app = send(class("NSApplication"), "sharedApplication");
+        // No Dock icon or menu bar, like Cluely's overlay
+        send_isize(app, "setActivationPolicy:", NS_APPLICATION_ACTIVATION_POLICY_ACCESSORY);
+
+        let init: unsafe extern "C" fn(Id, Sel, NSRect, usize, usize, bool) -> Id =
+            mem::transmute(objc_msgSend as *const c_void);
+        let window = init(
+            send(class("NSWindow"), "alloc"),
+            sel("initWithContentRect:styleMask:backing:defer:"),
+            NSRect {
+                x: 200.0,
+                y: 200.0,
+                width: 360.0,
+                height: 120.0,
+            },
+            NS_WINDOW_STYLE_MASK_TITLED,
+            NS_BACKING_STORE_BUFFERED,
+            false,
+        );
+        send_id(window, "setTitle:", nsstring(SIMULATED_TITLE));
+        send_isize(window, "setSharingType:", NS_WINDOW_SHARING_NONE);
+        send_isize(window, "setLevel:", NS_FLOATING_WINDOW_LEVEL);
+        send_bool(window, "setReleasedWhenClosed:", false);
+        send(window, "orderFrontRegardless");
+
+        let schedule: unsafe extern "C" fn(Id, Sel, f64, Id, Sel, Id, bool) -> Id =
+            mem::transmute(objc_msgSend as *const c_void);
+        schedule(
+            class("NSTimer"),
+            sel("scheduledTimerWithTimeInterval:target:selector:userInfo:repeats:"),
+            lifetime.as_secs_f64(),
+            app,
+            sel("terminate:"),
+            std::ptr::null_mut(),
+            false,
+        );
+
+        send(app, "run");
+    }
+    process::exit(0);
+}
@@ -0,0 +1,184 @@
+//! Full-machine scan snapshots and comparisons between them.
+//!
+//! A snapshot records everything the detector can see — Cluely windows,
+//! processes and files on disk — so two snapshots can be diffed, e.g. before
+//! and after an uninstall to confirm nothing was left behind.
+
+use std::collections::HashSet;
+use std::fs;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use no_cluely_driver::{
+    detect_cluely_detailed_rust, get_cluely_persistence_rust, get_cluely_processes_rust,
+    ClueLyDetectionResult, WindowInfo,
+};
+
+use crate::config::config_dir;
+use crate::output::{DetectionPayload, PersistencePayload, ProcessPayload, WindowPayload};
+
+/// Everything found in one scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub hostname: String,
+    pub detection: DetectionPayload,
+    pub windows: Vec<WindowPayload>,
+    pub processes: Vec<ProcessPayload>,
+    pub persistence: Vec<PersistencePayload>,
+}
+
+impl Snapshot {
+    /// Scan this machine now
+    pub fn take() -> Self {
+        let (result, windows) = detect_cluely_detailed_rust();
+        Self::from_scan(&result, &windows)
+    }
+
+    /// Complete a window scan that was already taken with processes and files on disk
+    pub fn from_scan(result: &ClueLyDetectionResult, windows: &[WindowInfo]) -> Self {
+        Self {
+            hostname: hostname::get()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            detection: DetectionPayload::from_result(result),
+            windows: windows.iter().map(WindowPayload::from_window).collect(),
+            processes: get_cluely_processes_rust()
+                .iter()
+                .map(ProcessPayload::from_process)
+                .collect(),
+            persistence: get_cluely_persistence_rust()
+                .iter()
+                .map(PersistencePayload::from_item)
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| format!("{} is not a scan snapshot: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Where the baseline snapshot is kept
+pub fn baseline_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("baseline.json"))
+}
+
+/// Load the saved baseline
+pub fn load_baseline() -> Result<Snapshot, String> {
+    match baseline_path() {
+        Some(path) if path.exists() => Snapshot::load(&path),
+        _ => Err(
+            "No baseline saved; run 'cluely-detector baseline save' on a known-good machine first"
+                .to_string(),
+        ),
+    }
+}
+
+pub fn describe_window(window: &WindowPayload) -> String {
+    format!(
+        "#{} {} (sharing state: {}, layer: {})",
+        window.window_id, window.owner, window.sharing_state, window.layer
+    )
+}
+
+pub fn describe_process(process: &ProcessPayload) -> String {
+    format!("{} (pid {}) {}", process.name, process.pid, process.path)
+}
+
+pub fn describe_persistence(item: &PersistencePayload) -> String {
+    format!("{}: {}", item.kind, item.path)
+}
+
+/// Items present in only one of two snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct Changes<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+}
+
+impl<T: Clone> Changes<T> {
+    fn between<K: Eq + Hash>(before: &[T], after: &[T], key: impl Fn(&T) -> K) -> Self {
+        let before_keys: HashSet<K> = before.iter().map(&key).collect();
+        let after_keys: HashSet<K> = after.iter().map(&key).collect();
+        Self {
+            added: after
+                .iter()
+                .filter(|item| !before_keys.contains(&key(item)))
+                .cloned()
+                .collect(),
+            removed: before
+                .iter()
+                .filter(|item| !after_keys.contains(&key(item)))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Differences between two snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    pub before: String,
+    pub after: String,
+    pub windows: Changes<WindowPayload>,
+    pub processes: Changes<ProcessPayload>,
+    pub persistence: Changes<PersistencePayload>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty() && self.processes.is_empty() && self.persistence.is_empty()
+    }
+
+    /// Descriptions of everything present only in the later snapshot
+    pub fn additions(&self) -> Vec<String> {
+        let windows = self
+            .windows
+            .added
+            .iter()
+            .map(|window| format!("Window {}", describe_window(window)));
+        let processes = self
+            .processes
+            .added
+            .iter()
+            .map(|process| format!("Process {}", describe_process(process)));
+        let persistence = self.persistence.added.iter().map(describe_persistence);
+        windows.chain(processes).chain(persistence).collect()
+    }
+}
+
+pub fn diff(before: &Snapshot, after: &Snapshot) -> SnapshotDiff {
+    SnapshotDiff {
+        before: before.detection.timestamp.clone(),
+        after: after.detection.timestamp.clone(),
+        // Window ids are reassigned whenever the app restarts
+        windows: Changes::between(&before.windows, &after.windows, |window| {
+            (window.owner.clone(), window.sharing_state, window.layer)
+        }),
+        // PIDs change between runs; the executable identifies the process
+        processes: Changes::between(&before.processes, &after.processes, |process| {
+            (process.name.clone(), process.path.clone())
+        }),
+        persistence: Changes::between(&before.persistence, &after.persistence, |item| {
+            item.path.clone()
+        }),
+    }
+}
@@ -0,0 +1,150 @@
+//! Monitor state kept across restarts.
+//!
+//! `monitor` saves its last reported detection state and running totals
+//! after every scan, next to the history database. After a reboot or crash
+//! it resumes from the saved state instead of announcing an unchanged
+//! detection again, and `stats` reports the totals over all runs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Default state file location
+pub fn default_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("no-cluely").join("monitor-state.json"))
+}
+
+/// Saved monitor state and cumulative counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorState {
+    /// Whether the last reported state was "detected"
+    pub detected: bool,
+    /// Whether that state was relative to a baseline (`monitor --baseline`)
+    pub baseline: bool,
+    /// When the last reported state began
+    pub state_since: Option<DateTime<Utc>>,
+    /// When the monitor last scanned
+    pub last_scan: Option<DateTime<Utc>>,
+    /// When a monitor first ran on this machine
+    pub first_started: Option<DateTime<Utc>>,
+    /// Number of times `monitor` was started
+    pub runs: u64,
+    pub total_scans: u64,
+    /// Number of times Cluely was reported as detected
+    pub total_detections: u64,
+    /// Seconds spent monitoring, over all runs
+    pub uptime_seconds: u64,
+    /// Seconds during which Cluely was reported as detected
+    pub detected_seconds: u64,
+    /// Process ID of a `monitor --watchdog` while it runs; still set after
+    /// a run that was killed or crashed instead of stopping
+    pub running_pid: Option<u32>,
+}
+
+impl MonitorState {
+    /// Read the state file; None if it doesn't exist yet
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("{} is not a monitor state file: {}", path.display(), e))
+    }
+
+    /// Write the state file, replacing it atomically so a crash mid-write
+    /// can't leave a truncated file behind
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self).unwrap())
+            .and_then(|_| fs::rename(&temp, path))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// The state of a running monitor, saved after every scan
+pub struct StateTracker {
+    path: PathBuf,
+    pub state: MonitorState,
+    /// The state a previous run left behind without stopping cleanly
+    pub unfinished: Option<MonitorState>,
+    /// Time not yet added to the totals
+    last_tick: Instant,
+}
+
+impl StateTracker {
+    /// Load the saved state for a new monitor run. A state saved in the
+    /// other mode (with or without `--baseline`) is not resumed, but its
+    /// totals are kept.
+    pub fn start(path: PathBuf, baseline: bool) -> Result<Self, String> {
+        let mut state = MonitorState::load(&path)?.unwrap_or_default();
+        let unfinished = state.running_pid.take().map(|pid| MonitorState {
+            running_pid: Some(pid),
+            ..state.clone()
+        });
+        if state.baseline != baseline {
+            state.detected = false;
+            state.state_since = None;
+            state.baseline = baseline;
+        }
+        state.runs += 1;
+        state.first_started.get_or_insert_with(Utc::now);
+        Ok(Self {
+            path,
+            state,
+            unfinished,
+            last_tick: Instant::now(),
+        })
+    }
+
+    /// Leave this run's process ID in the state file until it stops, so a
+    /// later run can tell that it was killed
+    pub fn watch(&mut self) {
+        self.state.running_pid = Some(std::process::id());
+    }
+
+    /// Add the time since the last scan to the totals
+    fn tick(&mut self) {
+        let elapsed = self.last_tick.elapsed().as_secs();
+        // Keep the fractional second for the next tick
+        self.last_tick += std::time::Duration::from_secs(elapsed);
+        self.state.uptime_seconds += elapsed;
+        if self.state.detected {
+            self.state.detected_seconds += elapsed;
+        }
+    }
+
+    /// Record a completed scan; `reported` is the state the monitor now
+    /// reports and `changed` whether this scan changed it
+    pub fn scanned(&mut self, reported: bool, changed: bool) -> Result<(), String> {
+        self.tick();
+        let now = Utc::now();
+        self.state.total_scans += 1;
+        self.state.last_scan = Some(now);
+        if changed || self.state.state_since.is_none() {
+            self.state.state_since = Some(now);
+        }
+        if changed && reported {
+            self.state.total_detections += 1;
+        }
+        self.state.detected = reported;
+        self.state.save(&self.path)
+    }
+
+    /// Save the time since the last scan when monitoring stops
+    pub fn finish(&mut self) -> Result<(), String> {
+        self.tick();
+        self.state.running_pid = None;
+        self.state.save(&self.path)
+    }
+}
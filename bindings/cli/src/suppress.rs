@@ -0,0 +1,151 @@
+//! Suppressed evasion scan findings (`suppressions.toml`).
+//!
+//! `ignore.toml` drops every finding of an app. A suppression is narrower:
+//! one rule for the windows of the owners it names, such as the nearly
+//! invisible overlay of a proctoring tool that is otherwise still checked.
+//! Each needs a justification and may expire, so an audit can see what was
+//! deliberately ignored, why, and until when. Suppressed findings are left
+//! out of the result but still counted and listed.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use no_cluely_driver::{normalize, EvasionScan, Rule};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_dir;
+
+/// One `[[suppression]]` entry
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suppression {
+    /// ID of the rule whose findings are suppressed (see `rules list`)
+    pub rule: String,
+    /// Window owner names it applies to, matched whole
+    pub owners: Vec<String>,
+    /// Last day the suppression applies; it never expires if absent
+    pub expires: Option<NaiveDate>,
+    /// Why the finding is acceptable, for whoever audits the machine
+    pub justification: String,
+}
+
+impl Suppression {
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.is_some_and(|expires| expires < today)
+    }
+
+    fn covers(&self, rule: Rule, owner: &str) -> bool {
+        let owner = normalize::fold(owner);
+        self.rule == rule.id()
+            && self
+                .owners
+                .iter()
+                .any(|suppressed| normalize::fold(suppressed) == owner)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SuppressionFile {
+    suppression: Vec<Suppression>,
+}
+
+/// A finding left out of a result, with the reason it was
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressedFinding {
+    pub window_id: i32,
+    pub owner: String,
+    pub rule_id: &'static str,
+    pub message: String,
+    pub justification: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<NaiveDate>,
+}
+
+/// Path of the suppressions file, if there is a home directory
+pub fn path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("suppressions.toml"))
+}
+
+/// Every suppression in the suppressions file, expired or not; empty if
+/// there is no file
+pub fn load() -> Result<Vec<Suppression>, String> {
+    let Some(path) = path().filter(|path| path.exists()) else {
+        return Ok(Vec::new());
+    };
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let file: SuppressionFile = toml::from_str(&contents)
+        .map_err(|e| format!("Invalid suppressions file {}: {}", path.display(), e))?;
+    for suppression in &file.suppression {
+        let problem = if Rule::from_id(&suppression.rule).is_none() {
+            Some(format!(
+                "there is no rule \"{}\"; run 'cluely-detector rules list' to see them",
+                suppression.rule
+            ))
+        } else if suppression.owners.is_empty() {
+            Some(format!(
+                "the \"{}\" suppression names no owners",
+                suppression.rule
+            ))
+        } else if suppression.justification.trim().is_empty() {
+            Some(format!(
+                "the \"{}\" suppression needs a justification",
+                suppression.rule
+            ))
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            return Err(format!(
+                "Invalid suppressions file {}: {}",
+                path.display(),
+                problem
+            ));
+        }
+    }
+    Ok(file.suppression)
+}
+
+/// Remove the findings `suppressions` cover from `scan`, ignoring those that
+/// expired before `today`, and return them. A window left without findings
+/// is no longer flagged.
+pub fn apply(
+    scan: &mut EvasionScan,
+    suppressions: &[Suppression],
+    today: NaiveDate,
+) -> Vec<SuppressedFinding> {
+    let active: Vec<&Suppression> = suppressions
+        .iter()
+        .filter(|suppression| !suppression.is_expired(today))
+        .collect();
+    if active.is_empty() {
+        return Vec::new();
+    }
+
+    let mut suppressed = Vec::new();
+    for finding in &mut scan.findings {
+        let window = &finding.window;
+        finding.techniques.retain(|technique| {
+            let Some(suppression) = active
+                .iter()
+                .find(|suppression| suppression.covers(technique.rule_id, &window.owner))
+            else {
+                return true;
+            };
+            suppressed.push(SuppressedFinding {
+                window_id: window.window_id,
+                owner: window.owner.clone(),
+                rule_id: technique.rule_id.id(),
+                message: technique.message.clone(),
+                justification: suppression.justification.clone(),
+                expires: suppression.expires,
+            });
+            false
+        });
+    }
+    scan.findings
+        .retain(|finding| !finding.techniques.is_empty());
+    suppressed
+}
@@ -0,0 +1,122 @@
+//! How times are shown (`--timezone`, `--timestamp-format`).
+//!
+//! Times are kept in UTC and converted only when written out. The zone
+//! applies to everything: text output, reports, notifications and the
+//! timestamps in JSON, which stay RFC 3339 but carry the zone's offset. A
+//! custom format applies to text for people and to CSV exports; JSON keeps
+//! RFC 3339 so that it still parses as a date-time. Files the detector keeps
+//! for itself, such as the monitor state and the history database, stay in
+//! UTC.
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+/// Format of times shown to people unless `--timestamp-format` is given
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S %Z";
+
+/// The zone times are shown in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zone {
+    Utc,
+    /// The Mac's own time zone
+    Local,
+    /// An IANA zone such as `Europe/Berlin`
+    Named(Tz),
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        if value.eq_ignore_ascii_case("utc") {
+            Ok(Zone::Utc)
+        } else if value.eq_ignore_ascii_case("local") {
+            Ok(Zone::Local)
+        } else {
+            value.parse::<Tz>().map(Zone::Named).map_err(|_| {
+                format!(
+                    "Unknown time zone '{}': use local, UTC or an IANA name such as Europe/Berlin",
+                    value
+                )
+            })
+        }
+    }
+}
+
+/// Check a `--timestamp-format` value; chrono only notices an invalid
+/// specifier when formatting, and panics then
+pub fn parse_format(value: &str) -> Result<String, String> {
+    if StrftimeItems::new(value).any(|item| item == Item::Error) {
+        return Err(format!(
+            "Invalid timestamp format '{}': see strftime(3) for the % specifiers",
+            value
+        ));
+    }
+    Ok(value.to_string())
+}
+
+struct Style {
+    zone: Zone,
+    /// None for [`DEFAULT_FORMAT`], without changing CSV exports
+    format: Option<String>,
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Set the zone and format for the rest of the run
+pub fn configure(zone: Zone, format: Option<String>) {
+    let _ = STYLE.set(Style { zone, format });
+}
+
+fn style() -> &'static Style {
+    STYLE.get_or_init(|| Style {
+        zone: Zone::Utc,
+        format: None,
+    })
+}
+
+/// `time` for people, in the configured zone and format
+pub fn display(time: DateTime<Utc>) -> String {
+    let format = style().format.as_deref().unwrap_or(DEFAULT_FORMAT);
+    match style().zone {
+        Zone::Utc => time.format(format).to_string(),
+        Zone::Local => time.with_timezone(&Local).format(format).to_string(),
+        Zone::Named(zone) => time.with_timezone(&zone).format(format).to_string(),
+    }
+}
+
+/// The current time for people, as on monitor lines
+pub fn now() -> String {
+    display(Utc::now())
+}
+
+/// `time` as RFC 3339 in the configured zone, for JSON
+pub fn rfc3339(time: DateTime<Utc>) -> String {
+    match style().zone {
+        Zone::Utc => time.to_rfc3339(),
+        Zone::Local => time.with_timezone(&Local).to_rfc3339(),
+        Zone::Named(zone) => time.with_timezone(&zone).to_rfc3339(),
+    }
+}
+
+/// An RFC 3339 time from a payload, for people; anything else is returned
+/// unchanged
+pub fn reformat(rfc3339: &str) -> String {
+    DateTime::parse_from_rfc3339(rfc3339).map_or_else(
+        |_| rfc3339.to_string(),
+        |time| display(time.with_timezone(&Utc)),
+    )
+}
+
+/// An RFC 3339 time for a CSV export: in the custom format if one was
+/// given, otherwise left as RFC 3339
+pub fn for_export(rfc3339: &str) -> String {
+    match style().format {
+        Some(_) => reformat(rfc3339),
+        None => rfc3339.to_string(),
+    }
+}
@@ -0,0 +1,156 @@
+//! Detection trends from the scan history (`stats`).
+//!
+//! One scan says whether Cluely is running now. The history says how often
+//! it ran over the past weeks, at what time of day and for how long, which
+//! is what shows a pattern such as Cluely appearing for every interview.
+//! Episodes are the sessions `history timeline` reconstructs; days and hours
+//! are in local time.
+
+use chrono::{DateTime, Local, NaiveDate, Timelike};
+use serde::Serialize;
+
+use crate::history::{self, ScanRecord};
+
+/// Days `stats` looks back over by default
+pub const DEFAULT_DAYS: u32 = 30;
+
+/// Longest gap between scans within one episode, as for `history timeline`
+const MAX_GAP_SECONDS: i64 = 300;
+
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Activity on one day
+#[derive(Debug, Clone, Serialize)]
+pub struct DayTrend {
+    pub date: NaiveDate,
+    /// Detection episodes that began that day
+    pub episodes: usize,
+    pub scans: usize,
+    pub detected_scans: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Trends {
+    /// First and last day covered, both included
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub scans: usize,
+    pub episodes: usize,
+    pub average_episode_seconds: Option<i64>,
+    pub longest_episode_seconds: Option<i64>,
+    /// Scans that saw Cluely, by hour of the day
+    pub detections_by_hour: Vec<usize>,
+    /// Hours with at least half as many detections as the busiest one, as
+    /// ranges such as "09:00-12:00"
+    pub active_hours: Vec<String>,
+    /// Oldest first, including days without scans
+    pub per_day: Vec<DayTrend>,
+    /// Episodes per day, one character each
+    pub sparkline: String,
+}
+
+impl Trends {
+    /// Days on which at least one episode began
+    pub fn days_with_detections(&self) -> usize {
+        self.per_day.iter().filter(|day| day.episodes > 0).count()
+    }
+}
+
+fn local_time(rfc3339: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(rfc3339)
+        .ok()
+        .map(|time| time.with_timezone(&Local))
+}
+
+/// Trends over the `days` days up to and including `to`, from `scans` in
+/// chronological order
+pub fn compute(scans: &[ScanRecord], to: NaiveDate, days: u32) -> Trends {
+    let from = to - chrono::Duration::days(i64::from(days.max(1)) - 1);
+    let mut per_day: Vec<DayTrend> = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| DayTrend {
+            date,
+            episodes: 0,
+            scans: 0,
+            detected_scans: 0,
+        })
+        .collect();
+    let day_count = per_day.len();
+    let day_index = |time: DateTime<Local>| {
+        let offset = (time.date_naive() - from).num_days();
+        usize::try_from(offset)
+            .ok()
+            .filter(|index| *index < day_count)
+    };
+
+    let mut detections_by_hour = vec![0; 24];
+    let mut counted = 0;
+    for scan in scans {
+        let Some(time) = local_time(&scan.detection.timestamp) else {
+            continue;
+        };
+        let Some(index) = day_index(time) else {
+            continue;
+        };
+        counted += 1;
+        per_day[index].scans += 1;
+        if scan.detection.detected {
+            per_day[index].detected_scans += 1;
+            detections_by_hour[time.hour() as usize] += 1;
+        }
+    }
+
+    let mut durations = Vec::new();
+    for episode in history::timeline(scans, chrono::Duration::seconds(MAX_GAP_SECONDS)) {
+        if let Some(index) = local_time(&episode.first_seen).and_then(day_index) {
+            per_day[index].episodes += 1;
+            durations.push(episode.duration_seconds);
+        }
+    }
+
+    Trends {
+        from,
+        to,
+        scans: counted,
+        episodes: durations.len(),
+        average_episode_seconds: (!durations.is_empty())
+            .then(|| durations.iter().sum::<i64>() / durations.len() as i64),
+        longest_episode_seconds: durations.iter().copied().max(),
+        active_hours: active_hours(&detections_by_hour),
+        detections_by_hour,
+        sparkline: sparkline(per_day.iter().map(|day| day.episodes)),
+        per_day,
+    }
+}
+
+/// The busiest hours, merged into ranges
+fn active_hours(by_hour: &[usize]) -> Vec<String> {
+    let peak = by_hour.iter().copied().max().unwrap_or(0);
+    if peak == 0 {
+        return Vec::new();
+    }
+    let busy: Vec<bool> = by_hour.iter().map(|count| count * 2 >= peak).collect();
+    let mut ranges = Vec::new();
+    let mut hour = 0;
+    while hour < busy.len() {
+        if !busy[hour] {
+            hour += 1;
+            continue;
+        }
+        let start = hour;
+        while hour < busy.len() && busy[hour] {
+            hour += 1;
+        }
+        ranges.push(format!("{:02}:00-{:02}:00", start, hour % 24));
+    }
+    ranges
+}
+
+/// One bar per value, scaled to the largest; only zero gets the lowest bar
+fn sparkline(values: impl Iterator<Item = usize> + Clone) -> String {
+    let peak = values.clone().max().unwrap_or(0).max(1);
+    values
+        .map(|value| BARS[(value * (BARS.len() - 1)).div_ceil(peak)])
+        .collect()
+}
@@ -0,0 +1,212 @@
+//! Self-update from GitHub releases (`self-update` subcommand).
+//!
+//! The release workflow signs a manifest of each release's version and the
+//! SHA-256 of its binary with Ed25519, and the matching public key is
+//! compiled in from `CLUELY_RELEASE_PUBLIC_KEY` (hex). A download is only
+//! installed if the manifest's signature verifies, the binary matches its
+//! hash, and the signed version is the release's own and not older than the
+//! running one; a signature over the binary alone would let any older
+//! signed release pass as a newer one. The running binary is replaced with
+//! a rename so it is never left half-written.
+
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/terminalsin/no-cluely/releases/latest";
+const BINARY_ASSET: &str = "cluely-detector";
+const MANIFEST_ASSET: &str = "cluely-detector.manifest.json";
+const MANIFEST_SIGNATURE_ASSET: &str = "cluely-detector.manifest.json.sig";
+
+/// Public key release binaries are signed with; builds without one cannot self-update
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("CLUELY_RELEASE_PUBLIC_KEY");
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// What the release workflow signs for each release
+#[derive(Deserialize)]
+struct Manifest {
+    /// Version of the binary, without a leading "v"
+    version: String,
+    /// SHA-256 of the binary as hex
+    sha256: String,
+}
+
+/// What `self-update` found or did
+pub enum UpdateOutcome {
+    UpToDate { version: String },
+    Available { version: String },
+    Updated { version: String, path: PathBuf },
+}
+
+/// Numeric version components, ignoring a leading "v" and any pre-release suffix
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// An Ed25519 public key written as 64 hex digits
+pub fn parse_public_key(key: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = hex::decode(key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("not 64 hex digits")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+/// The key release binaries and signature bundles are signed with
+pub fn verifying_key() -> Result<VerifyingKey, String> {
+    let key = RELEASE_PUBLIC_KEY
+        .ok_or("This build has no release signing key, so updates cannot be verified")?;
+    parse_public_key(key)
+        .map_err(|e| format!("The built-in release signing key is malformed: {}", e))
+}
+
+pub fn get(url: &str) -> Result<ureq::Response, String> {
+    ureq::get(url)
+        .timeout(Duration::from_secs(60))
+        .set(
+            "User-Agent",
+            concat!("cluely-detector/", env!("CARGO_PKG_VERSION")),
+        )
+        .call()
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))
+}
+
+fn download(release: &Release, name: &str) -> Result<Vec<u8>, String> {
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == name)
+        .ok_or_else(|| format!("Release {} has no {} asset", release.tag_name, name))?;
+
+    let mut bytes = Vec::new();
+    get(&asset.browser_download_url)?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to download {}: {}", name, e))?;
+    Ok(bytes)
+}
+
+/// Write `contents` next to `target` and rename it into place
+fn replace_atomically(target: &Path, contents: &[u8]) -> Result<(), String> {
+    let dir = target
+        .parent()
+        .ok_or("The binary has no parent directory")?;
+    let staging = dir.join(format!(".{}.update-{}", BINARY_ASSET, std::process::id()));
+
+    let result = fs::write(&staging, contents)
+        .and_then(|_| fs::set_permissions(&staging, fs::Permissions::from_mode(0o755)))
+        .and_then(|_| fs::rename(&staging, target));
+
+    result.map_err(|e| {
+        let _ = fs::remove_file(&staging);
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                format!("Cannot write to {} (re-run with sudo)", dir.display())
+            }
+            _ => format!("Failed to replace {}: {}", target.display(), e),
+        }
+    })
+}
+
+/// The release's manifest, once its signature verifies and it names the
+/// release's own version, not older than the running one
+fn verified_manifest(
+    key: &VerifyingKey,
+    release: &Release,
+    version: &str,
+    force: bool,
+) -> Result<Manifest, String> {
+    let manifest = download(release, MANIFEST_ASSET)?;
+    let signature = download(release, MANIFEST_SIGNATURE_ASSET)?;
+    let signature = Signature::from_slice(&signature)
+        .map_err(|_| format!("{} is not an Ed25519 signature", MANIFEST_SIGNATURE_ASSET))?;
+    key.verify(&manifest, &signature).map_err(|_| {
+        format!(
+            "Signature check failed for the {} {} manifest; nothing was installed",
+            BINARY_ASSET, version
+        )
+    })?;
+    let manifest: Manifest = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("{} is malformed: {}", MANIFEST_ASSET, e))?;
+
+    let signed = manifest.version.trim_start_matches('v');
+    if parse_version(signed) != parse_version(version) {
+        return Err(format!(
+            "Release {} carries the signed manifest of version {}; nothing was installed",
+            release.tag_name, signed
+        ));
+    }
+    let running = parse_version(env!("CARGO_PKG_VERSION"));
+    let signed_version = parse_version(signed);
+    // --force reinstalls the running version, but never goes back
+    if signed_version < running || (!force && signed_version == running) {
+        return Err(format!(
+            "Release {} would downgrade cluely-detector {} to {}; nothing was installed",
+            release.tag_name,
+            env!("CARGO_PKG_VERSION"),
+            signed
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Check for a newer release and, unless `check_only`, install it
+pub fn run(check_only: bool, force: bool) -> Result<UpdateOutcome, String> {
+    let body = get(LATEST_RELEASE_URL)?
+        .into_string()
+        .map_err(|e| format!("Failed to read the release list: {}", e))?;
+    let release: Release = serde_json::from_str(&body)
+        .map_err(|e| format!("Unexpected response from GitHub: {}", e))?;
+    let version = release.tag_name.trim_start_matches('v').to_string();
+
+    if !force && parse_version(&version) <= parse_version(env!("CARGO_PKG_VERSION")) {
+        return Ok(UpdateOutcome::UpToDate { version });
+    }
+    if check_only {
+        return Ok(UpdateOutcome::Available { version });
+    }
+
+    // Fail before downloading anything if the update could not be verified
+    let key = verifying_key()?;
+
+    let manifest = verified_manifest(&key, &release, &version, force)?;
+    let binary = download(&release, BINARY_ASSET)?;
+    if !hex::encode(Sha256::digest(&binary)).eq_ignore_ascii_case(manifest.sha256.trim()) {
+        return Err(format!(
+            "{} {} does not match its signed SHA-256; the download was discarded",
+            BINARY_ASSET, version
+        ));
+    }
+
+    let path = std::env::current_exe()
+        .and_then(fs::canonicalize)
+        .map_err(|e| format!("Could not locate the running binary: {}", e))?;
+    replace_atomically(&path, &binary)?;
+
+    Ok(UpdateOutcome::Updated { version, path })
+}
@@ -0,0 +1,158 @@
+//! Tamper detection for `monitor --watchdog`.
+//!
+//! Monitoring software that kills its detector is itself a finding. The
+//! monitor catches SIGTERM and SIGHUP to learn which process sent them:
+//! launchd stopping the service is routine, anything else is reported.
+//! SIGKILL cannot be caught, so the monitor also leaves its process ID in
+//! the state file and clears it when it stops; launchd's KeepAlive restarts
+//! a killed service, and the new run finds the ID left behind and reports
+//! the kill.
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use chrono::{DateTime, TimeZone, Utc};
+use no_cluely_driver::get_cluely_processes_rust;
+
+use crate::state::MonitorState;
+use crate::timestamp;
+
+/// Cleared by the signal handler to stop the monitor loop
+static RUNNING: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+/// Process that sent the last termination signal; 0 if none was received
+static SENDER: AtomicI32 = AtomicI32::new(0);
+
+/// launchd, which stops agents at logout and on `launchctl bootout`
+const LAUNCHD_PID: i32 = 1;
+
+extern "C" fn on_terminate(
+    _signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    _context: *mut libc::c_void,
+) {
+    // Only atomics here: this runs in signal context
+    if let Some(info) = unsafe { info.as_ref() } {
+        SENDER.store(unsafe { info.si_pid() }, Ordering::SeqCst);
+    }
+    if let Some(running) = RUNNING.get() {
+        running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Stop the monitor on SIGTERM or SIGHUP by clearing `running`, noting who
+/// sent the signal
+pub fn catch_termination(running: Arc<AtomicBool>) -> Result<(), String> {
+    let _ = RUNNING.set(running);
+    for signal in [libc::SIGTERM, libc::SIGHUP] {
+        let installed = unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_terminate as *const () as usize;
+            action.sa_flags = libc::SA_SIGINFO;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, std::ptr::null_mut())
+        };
+        if installed != 0 {
+            return Err(format!(
+                "Failed to handle signal {}: {}",
+                signal,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A process that stopped the monitor
+#[derive(Debug, Clone)]
+pub struct Sender {
+    pub pid: i32,
+    /// Command name; None if it has already exited
+    pub name: Option<String>,
+    pub is_cluely: bool,
+}
+
+impl Sender {
+    /// What to report
+    pub fn describe(&self) -> String {
+        let name = self.name.as_deref().unwrap_or("an exited process");
+        if self.is_cluely {
+            format!(
+                "The Cluely monitor was terminated by Cluely itself ({}, pid {})",
+                name, self.pid
+            )
+        } else {
+            format!(
+                "The Cluely monitor was terminated by {} (pid {})",
+                name, self.pid
+            )
+        }
+    }
+}
+
+/// The process that sent a termination signal, unless it was launchd, the
+/// kernel or the shell the monitor runs in stopping it as usual
+pub fn unexpected_sender() -> Option<Sender> {
+    let pid = SENDER.load(Ordering::SeqCst);
+    let routine = [0, LAUNCHD_PID, std::process::id() as i32, unsafe {
+        libc::getppid()
+    }];
+    if routine.contains(&pid) {
+        return None;
+    }
+    Some(Sender {
+        pid,
+        name: process_name(pid),
+        is_cluely: get_cluely_processes_rust()
+            .iter()
+            .any(|process| process.pid == pid),
+    })
+}
+
+fn process_name(pid: i32) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// When the Mac last started
+fn boot_time() -> Option<DateTime<Utc>> {
+    let output = Command::new("sysctl")
+        .args(["-n", "kern.boottime"])
+        .output()
+        .map_err(|error| tracing::warn!(%error, "sysctl could not be run"))
+        .ok()?;
+    // { sec = 1718000000, usec = 123456 } Mon Jun 10 08:13:20 2024
+    let text = String::from_utf8_lossy(&output.stdout);
+    let seconds = text.split("sec = ").nth(1)?.split(',').next()?.trim();
+    Utc.timestamp_opt(seconds.parse().ok()?, 0).single()
+}
+
+/// What to report about a previous run that left `previous` behind without
+/// stopping: None if it is still running or the Mac restarted since
+pub fn describe_unfinished(previous: &MonitorState) -> Option<String> {
+    let pid = previous.running_pid? as i32;
+    // Another monitor sharing the state file
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return None;
+    }
+    let last_scan = previous.last_scan?;
+    if boot_time().is_some_and(|booted| last_scan < booted) {
+        tracing::info!(pid, "previous monitor ran until the Mac restarted");
+        return None;
+    }
+    Some(format!(
+        "The previous Cluely monitor (pid {}) was killed or crashed after its last scan at {}{}",
+        pid,
+        timestamp::display(last_scan),
+        if previous.detected {
+            ", while Cluely was detected"
+        } else {
+            ""
+        }
+    ))
+}
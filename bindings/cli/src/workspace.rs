@@ -0,0 +1,98 @@
+//! App launch and quit notifications from NSWorkspace.
+//!
+//! `monitor` uses these to scan soon after an app starts or stops instead of
+//! waiting out its interval. Notifications are delivered on the main run
+//! loop, so the monitor waits by running the run loop rather than sleeping.
+
+use std::ffi::{c_void, CString};
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::objc::{
+    class, class_addMethod, nsstring, objc_allocateClassPair, objc_msgSend, objc_registerClassPair,
+    sel, send, Id, Sel,
+};
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    static kCFRunLoopDefaultMode: *const c_void;
+    fn CFRunLoopRunInMode(
+        mode: *const c_void,
+        seconds: f64,
+        return_after_source_handled: bool,
+    ) -> i32;
+}
+
+/// CFRunLoopRunInMode result when the mode has no sources or timers
+const K_CF_RUN_LOOP_RUN_FINISHED: i32 = 1;
+
+const NOTIFICATIONS: [&str; 2] = [
+    "NSWorkspaceDidLaunchApplicationNotification",
+    "NSWorkspaceDidTerminateApplicationNotification",
+];
+
+/// Set by the observer, cleared by [`take_app_event`]
+static APP_EVENT: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn app_event(_this: Id, _cmd: Sel, _notification: Id) {
+    APP_EVENT.store(true, Ordering::SeqCst);
+}
+
+/// Start listening for app launches and quits. Must be called on the main
+/// thread, which must then wait with [`wait`].
+pub fn observe_app_events() {
+    unsafe {
+        let name = CString::new("CluelyWorkspaceObserver").unwrap();
+        let observer_class = objc_allocateClassPair(class("NSObject"), name.as_ptr(), 0);
+        if observer_class.is_null() {
+            // Already registered by an earlier call
+            return;
+        }
+        let types = CString::new("v@:@").unwrap();
+        class_addMethod(
+            observer_class,
+            sel("appEvent:"),
+            app_event as *const c_void,
+            types.as_ptr(),
+        );
+        objc_registerClassPair(observer_class);
+
+        let observer = send(send(observer_class, "alloc"), "init");
+        let center = send(
+            send(class("NSWorkspace"), "sharedWorkspace"),
+            "notificationCenter",
+        );
+        let add_observer: unsafe extern "C" fn(Id, Sel, Id, Sel, Id, Id) =
+            mem::transmute(objc_msgSend as *const c_void);
+        for notification in NOTIFICATIONS {
+            add_observer(
+                center,
+                sel("addObserver:selector:name:object:"),
+                observer,
+                sel("appEvent:"),
+                nsstring(notification),
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
+
+/// Whether an app launched or quit since the last call
+pub fn take_app_event() -> bool {
+    APP_EVENT.swap(false, Ordering::SeqCst)
+}
+
+/// Run the main run loop for up to `timeout`, returning early once a
+/// notification has been handled
+pub fn wait(timeout: Duration) {
+    let result = unsafe { CFRunLoopRunInMode(kCFRunLoopDefaultMode, timeout.as_secs_f64(), true) };
+    // With no run loop sources to wait on, CFRunLoopRunInMode returns at once
+    if result == K_CF_RUN_LOOP_RUN_FINISHED {
+        thread::sleep(timeout);
+    }
+}
@@ -0,0 +1,20 @@
+//! Stand-in for `workspace` on platforms without NSWorkspace.
+//!
+//! No app launch or quit is ever reported, so `monitor` scans on its
+//! interval alone.
+
+use std::thread;
+use std::time::Duration;
+
+/// Nothing to observe here
+pub fn observe_app_events() {}
+
+/// Always false: there are no app notifications to take
+pub fn take_app_event() -> bool {
+    false
+}
+
+/// Sleep for `timeout`
+pub fn wait(timeout: Duration) {
+    thread::sleep(timeout);
+}
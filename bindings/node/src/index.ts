@@ -15,6 +15,10 @@ const CluelyDetectionResultStruct = koffi.struct("ClueLyDetectionResult", {
   screen_capture_evasion_count: "uint32",
   elevated_layer_count: "uint32",
   max_layer_detected: "int32",
+  last_scan_duration_us: "uint64",
+  ruleset_version: "uint32",
+  rules_fired: "uint32",
+  signatures_version: "uint64",
 });
 
 // Locate the dynamic library - try multiple locations
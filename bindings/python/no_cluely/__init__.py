@@ -60,7 +60,7 @@ def _find_library() -> str:
 
     raise FileNotFoundError(
         "Could not find libno_cluely_driver.dylib. "
-        "Please ensure the Rust library is built: cargo build --lib --release"
+        "Please ensure the Rust library is built: cargo build --lib --release --features ffi"
     )
 
 
@@ -79,6 +79,10 @@ class _ClueLyDetectionResult(ctypes.Structure):
         ("screen_capture_evasion_count", ctypes.c_uint32),
         ("elevated_layer_count", ctypes.c_uint32),
         ("max_layer_detected", ctypes.c_int32),
+        ("last_scan_duration_us", ctypes.c_uint64),
+        ("ruleset_version", ctypes.c_uint32),
+        ("rules_fired", ctypes.c_uint32),
+        ("signatures_version", ctypes.c_uint64),
     ]
 
 
@@ -18,8 +18,21 @@ typedef struct {
     uint32_t screen_capture_evasion_count; // Windows using sharing_state = 0
     uint32_t elevated_layer_count;       // Windows using layer > 0
     int32_t max_layer_detected;          // Highest layer number found
+    uint64_t last_scan_duration_us;      // Time the scan took, in microseconds
+    uint32_t ruleset_version;            // Version of the detection rules used
+    uint32_t rules_fired;                // Bits of the rules that fired (see below)
+    uint64_t signatures_version;         // Signature bundle version; 0 for built-in only
 } ClueLyDetectionResult;
 
+/**
+ * Bits of ClueLyDetectionResult.rules_fired.
+ */
+typedef enum {
+    CLUELY_RULE_CLUELY_SIGNATURE = 1 << 0,       // A window matched a Cluely signature
+    CLUELY_RULE_SCREEN_CAPTURE_EVASION = 1 << 1, // A window is hidden from screen capture
+    CLUELY_RULE_ELEVATED_LAYER = 1 << 2,         // A window sits above normal windows
+} ClueLyRule;
+
 /**
  * Simple check if Cluely employee monitoring software is running.
  * 
@@ -56,6 +69,46 @@ void free_cluely_report(char* report_ptr);
  */
 uint32_t get_cluely_window_count(void);
 
+/**
+ * Settings for cluely_monitor_create().
+ */
+typedef struct {
+    bool on_screen_only;                 // Only scan windows currently on screen
+    bool exclude_desktop_elements;       // Skip desktop picture and icon windows
+    bool incremental;                    // Only parse windows new since the last poll
+    const char* const* owner_patterns;   // Extra owner name patterns; "^..." is a regex
+    uint32_t owner_pattern_count;        // Number of entries in owner_patterns
+} ClueLyMonitorConfig;
+
+/**
+ * Long-lived monitor that keeps its configuration and window cache
+ * between polls. Safe to poll from any thread.
+ */
+typedef struct ClueLyMonitorHandle ClueLyMonitorHandle;
+
+/**
+ * Create a monitor. Free it with cluely_monitor_destroy().
+ * 
+ * @param config Monitor settings, or NULL for the defaults
+ * @return New monitor, or NULL if an owner pattern is invalid
+ */
+ClueLyMonitorHandle* cluely_monitor_create(const ClueLyMonitorConfig* config);
+
+/**
+ * Scan with the monitor's configuration.
+ * 
+ * @param monitor Monitor returned by cluely_monitor_create()
+ * @return ClueLyDetectionResult of this scan
+ */
+ClueLyDetectionResult cluely_monitor_poll(const ClueLyMonitorHandle* monitor);
+
+/**
+ * Free a monitor created by cluely_monitor_create().
+ * 
+ * @param monitor Monitor to free; must not be used afterwards
+ */
+void cluely_monitor_destroy(ClueLyMonitorHandle* monitor);
+
 #ifdef __cplusplus
 }
 #endif
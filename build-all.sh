@@ -174,7 +174,7 @@ if [ "$BUILD_RUST" = true ]; then
     print_section "Building Rust Library"
     
     print_status "Building release version..."
-    cargo build --lib --release
+    cargo build --lib --release --features ffi
     
     # Copy to packages
     cp target/release/libno_cluely_driver.dylib packages/
@@ -192,8 +192,8 @@ if [ "$BUILD_CLI" = true ]; then
     print_status "Building CLI with Cargo..."
     cargo build --release
     
-    # Copy to packages
-    cp target/release/cluely-detector ../../packages/
+    # Copy to packages (the workspace builds into the root target directory)
+    cp ../../target/release/cluely-detector ../../packages/
     
     cd ../..
     
@@ -250,7 +250,7 @@ if [ "$BUILD_PYTHON" = true ]; then
         if [ ! -f "../../target/release/libno_cluely_driver.dylib" ]; then
             print_status "Rust library not found. Building it now..."
             cd ../..
-            cargo build --lib --release
+            cargo build --lib --release --features ffi
             cd bindings/python
         fi
         
@@ -0,0 +1,74 @@
+//! CPU use of processes with hidden windows.
+//!
+//! A process hiding a window from capture may be a dormant stub waiting for
+//! a meeting, or it may be reading the screen. Capturing, encoding and
+//! transcribing cost CPU the whole time, so an owner that keeps using a
+//! steady share of a core is most likely recording. A single busy sample
+//! proves nothing (a launch, a burst of drawing); only a run of them counts.
+
+/// What a process's recent CPU use says it is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ActivityLevel {
+    /// Next to no CPU in the latest sample
+    Dormant,
+    Active,
+    /// Capture-level CPU over the whole run of samples
+    Capturing,
+}
+
+impl ActivityLevel {
+    pub fn label(self) -> &'static str {
+        match self {
+            ActivityLevel::Dormant => "dormant",
+            ActivityLevel::Active => "active",
+            ActivityLevel::Capturing => "capturing",
+        }
+    }
+}
+
+/// When CPU use counts as capturing. Percentages are of one core, so a
+/// process busy on two cores uses 200%.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ActivityRule {
+    /// Use at or above this in every one of the last `sustained_samples`
+    /// samples is capturing
+    pub capture_percent: f64,
+    /// Use below this in the latest sample is dormant
+    pub dormant_percent: f64,
+    pub sustained_samples: usize,
+}
+
+impl Default for ActivityRule {
+    fn default() -> Self {
+        Self {
+            capture_percent: 10.0,
+            dormant_percent: 1.0,
+            sustained_samples: 3,
+        }
+    }
+}
+
+impl ActivityRule {
+    /// Judge a process by its CPU use per sample, oldest first
+    pub fn level(&self, samples: &[f64]) -> ActivityLevel {
+        let Some(&latest) = samples.last() else {
+            return ActivityLevel::Dormant;
+        };
+        let run = self.sustained_samples.max(1);
+        if samples.len() >= run
+            && samples[samples.len() - run..]
+                .iter()
+                .all(|&percent| percent >= self.capture_percent)
+        {
+            ActivityLevel::Capturing
+        } else if latest < self.dormant_percent {
+            ActivityLevel::Dormant
+        } else {
+            ActivityLevel::Active
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Apps whose findings are never reported.
+//!
+//! An organization may deploy a tool that hides its windows from capture
+//! with everyone's knowledge, or even one matching the Cluely signatures.
+//! Allowing it by bundle ID, code signing Team ID or owner name suppresses
+//! what it alone would trigger, while every other window is still scanned.
+
+use crate::normalize;
+
+/// Apps never reported, by any of these
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AllowList {
+    /// Bundle IDs, compared case-insensitively with the whole ID. Helpers
+    /// nested in the app, whose IDs extend the app's (`<id>.helper`), are
+    /// allowed along with it.
+    pub bundle_ids: Vec<String>,
+    /// Team IDs of the developer certificate an app is signed with, e.g.
+    /// `ABCDE12345`; harder to fake than a name or bundle ID
+    pub team_ids: Vec<String>,
+    /// Owner (process) names, compared case-insensitively with the whole
+    /// name
+    pub owners: Vec<String>,
+}
+
+impl AllowList {
+    pub fn is_empty(&self) -> bool {
+        self.bundle_ids.is_empty() && self.team_ids.is_empty() && self.owners.is_empty()
+    }
+
+    /// Whether matching needs bundle IDs
+    pub fn uses_bundle_ids(&self) -> bool {
+        !self.bundle_ids.is_empty()
+    }
+
+    /// Whether matching needs Team IDs
+    pub fn uses_team_ids(&self) -> bool {
+        !self.team_ids.is_empty()
+    }
+
+    /// Whether an app is allowed, given whatever was read of it
+    pub fn allows(&self, owner: &str, bundle_id: Option<&str>, team_id: Option<&str>) -> bool {
+        let owner = normalize::fold(owner);
+        self.owners
+            .iter()
+            .any(|allowed| normalize::fold(allowed) == owner)
+            || bundle_id.is_some_and(|bundle_id| {
+                let bundle_id = bundle_id.to_ascii_lowercase();
+                self.bundle_ids.iter().any(|allowed| {
+                    let allowed = allowed.trim().to_ascii_lowercase();
+                    bundle_id == allowed
+                        || bundle_id
+                            .strip_prefix(&allowed)
+                            .is_some_and(|rest| rest.starts_with('.'))
+                })
+            })
+            || team_id.is_some_and(|team_id| {
+                self.team_ids
+                    .iter()
+                    .any(|allowed| allowed.trim() == team_id)
+            })
+    }
+}
@@ -0,0 +1,88 @@
+//! Files that belong to Cluely, judged from a path a process has open.
+//!
+//! A renamed process can hide its name, but not what it works on: to run,
+//! Cluely opens its bundle, its support directory, its databases, the
+//! recordings it writes and the shared memory frames are passed through.
+//! A path counts when it matches the built-in signatures, so a handle into
+//! `~/Library/Application Support/Cluely` gives the process away whatever it
+//! is called.
+
+use crate::is_cluely_process;
+
+/// Extensions of audio, video and screenshot files
+const RECORDING_EXTENSIONS: &[&str] = &[
+    "mp4", "mov", "m4v", "webm", "mkv", "m4a", "wav", "caf", "aac", "mp3", "opus", "png", "jpg",
+    "jpeg", "heic",
+];
+/// Extensions of SQLite databases and their journals
+const DATABASE_EXTENSIONS: &[&str] = &[
+    "db",
+    "sqlite",
+    "sqlite3",
+    "db-wal",
+    "db-shm",
+    "db-journal",
+    "sqlite-wal",
+    "sqlite-shm",
+];
+/// Directories Chromium keeps its databases in
+const DATABASE_DIRECTORIES: &[&str] = &["/indexeddb/", "/local storage/", "/databases/"];
+
+/// What a Cluely file a process has open is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ArtifactKind {
+    /// Part of the app bundle
+    AppBundle,
+    /// A database or its journal
+    Database,
+    /// An audio or video recording, or a screenshot
+    Recording,
+    /// A shared memory object, the way captured frames are handed between
+    /// processes
+    CaptureBuffer,
+    /// Anything else, e.g. in its support, cache or log directory
+    SupportFile,
+}
+
+impl ArtifactKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ArtifactKind::AppBundle => "app bundle",
+            ArtifactKind::Database => "database",
+            ArtifactKind::Recording => "recording",
+            ArtifactKind::CaptureBuffer => "capture buffer",
+            ArtifactKind::SupportFile => "support file",
+        }
+    }
+
+    /// What the file at `path` is, if it belongs to Cluely
+    pub fn of_file(path: &str) -> Option<Self> {
+        if !is_cluely_process(path) {
+            return None;
+        }
+        let lowercase = path.to_lowercase();
+        let file_name = lowercase.rsplit('/').next().unwrap_or_default();
+        let extension = file_name.rsplit_once('.').map(|(_, extension)| extension);
+        let kind = if extension.is_some_and(|extension| RECORDING_EXTENSIONS.contains(&extension)) {
+            ArtifactKind::Recording
+        } else if extension.is_some_and(|extension| DATABASE_EXTENSIONS.contains(&extension))
+            || DATABASE_DIRECTORIES
+                .iter()
+                .any(|directory| lowercase.contains(directory))
+        {
+            ArtifactKind::Database
+        } else if lowercase.contains(".app/") {
+            ArtifactKind::AppBundle
+        } else {
+            ArtifactKind::SupportFile
+        };
+        Some(kind)
+    }
+
+    /// What the shared memory object `name` is, if it belongs to Cluely
+    pub fn of_shared_memory(name: &str) -> Option<Self> {
+        is_cluely_process(name).then_some(ArtifactKind::CaptureBuffer)
+    }
+}
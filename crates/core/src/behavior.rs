@@ -0,0 +1,121 @@
+//! Behavioral detection of Cluely, whatever it is called.
+//!
+//! A renamed Cluely still has to behave like Cluely: a floating panel kept
+//! above other windows, hidden from screen capture, on screen while a
+//! meeting is running. No ordinary app needs all of that at once, so a
+//! window combining every trait is reported as a Cluely-like overlay even
+//! when no signature matches its name.
+//!
+//! Meetings are recognised by window title, which other apps only expose
+//! with Screen Recording permission. Without it no meeting is seen and the
+//! rule never fires.
+
+use crate::scanner::{ScannedWindow, WindowBounds};
+use crate::{Finding, Rule};
+
+/// Window titles that show a meeting in progress: the owner to look for
+/// (empty for any owner, e.g. a browser), text in the title, and the name
+/// to report
+const MEETING_WINDOWS: &[(&str, &str, &str)] = &[
+    ("zoom.us", "zoom meeting", "Zoom"),
+    ("zoom.us", "zoom webinar", "Zoom"),
+    ("microsoft teams", "meeting", "Microsoft Teams"),
+    ("microsoft teams", "call with", "Microsoft Teams"),
+    ("webex", "meeting", "Webex"),
+    ("slack", "huddle", "Slack"),
+    ("", "meet - ", "Google Meet"),
+    ("", "meet – ", "Google Meet"),
+];
+
+/// Smallest window that can show an overlay's content
+const MIN_OVERLAY_WIDTH: f64 = 100.0;
+const MIN_OVERLAY_HEIGHT: f64 = 30.0;
+
+/// Why a window was judged to behave like Cluely's overlay
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OverlayEvidence {
+    /// Window layer; above 0 keeps it in front of normal windows
+    pub layer: i32,
+    pub bounds: WindowBounds,
+    /// The meeting app on screen at the same time
+    pub meeting_app: String,
+}
+
+impl OverlayEvidence {
+    /// One-line summary for reports
+    pub fn describe(&self) -> String {
+        format!(
+            "Cluely-like overlay: hidden from screen capture, floating at layer {}, \
+             {}x{} at ({}, {}), during a {} meeting",
+            self.layer,
+            self.bounds.width,
+            self.bounds.height,
+            self.bounds.x,
+            self.bounds.y,
+            self.meeting_app
+        )
+    }
+
+    /// The evidence as an evasion scan finding
+    pub fn finding(&self) -> Finding {
+        Finding::new(Rule::CluelyLikeOverlay, self.describe())
+            .with("layer", self.layer)
+            .with("x", self.bounds.x)
+            .with("y", self.bounds.y)
+            .with("width", self.bounds.width)
+            .with("height", self.bounds.height)
+            .with("meeting_app", self.meeting_app.as_str())
+    }
+}
+
+/// The meeting app with a meeting window on screen, if any
+pub(crate) fn active_meeting(windows: &[ScannedWindow]) -> Option<&'static str> {
+    meeting_window(windows).map(|(_, app)| app)
+}
+
+/// The frontmost on-screen meeting window, and its meeting app
+pub fn meeting_window(windows: &[ScannedWindow]) -> Option<(&ScannedWindow, &'static str)> {
+    windows
+        .iter()
+        .filter(|window| window.is_onscreen)
+        .find_map(|window| {
+            let owner = window.owner.to_lowercase();
+            let title = window.name.to_lowercase();
+            MEETING_WINDOWS
+                .iter()
+                .find(|&&(meeting_owner, meeting_title, _)| {
+                    owner.contains(meeting_owner) && title.contains(meeting_title)
+                })
+                .map(|&(_, _, app)| (window, app))
+        })
+}
+
+/// Evidence that `window` is a Cluely-like overlay, given the meeting
+/// running alongside it
+pub(crate) fn overlay_evidence(
+    window: &ScannedWindow,
+    meeting_app: &'static str,
+) -> Option<OverlayEvidence> {
+    // Meeting apps float their own controls above a shared screen
+    let owner = window.owner.to_lowercase();
+    if MEETING_WINDOWS
+        .iter()
+        .any(|&(meeting_owner, _, _)| !meeting_owner.is_empty() && owner.contains(meeting_owner))
+    {
+        return None;
+    }
+
+    let bounds = window.bounds?;
+    let is_overlay = window.sharing_state == 0
+        && window.layer > 0
+        && window.is_onscreen
+        && bounds.width >= MIN_OVERLAY_WIDTH
+        && bounds.height >= MIN_OVERLAY_HEIGHT;
+
+    is_overlay.then(|| OverlayEvidence {
+        layer: window.layer,
+        bounds,
+        meeting_app: meeting_app.to_string(),
+    })
+}
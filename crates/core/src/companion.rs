@@ -0,0 +1,82 @@
+//! What each process of an app installation is for.
+//!
+//! Cluely is an Electron app, so one installation runs as several
+//! processes: the app itself, renderer and GPU helpers, a Squirrel updater
+//! (ShipIt) and a Crashpad crash reporter. Only the app and its renderers
+//! own windows; the rest stay running, or are started again by a launch
+//! agent, after every window is closed. Helpers are matched by name, and
+//! updaters and crash reporters by name or by the framework they run from.
+
+use crate::normalize;
+
+/// Folded names and path parts of crash reporters
+const CRASH_REPORTER_PATTERNS: &[&str] = &["crashpad", "crash reporter", "crashreporter"];
+/// Folded names and path parts of updaters
+const UPDATER_PATTERNS: &[&str] = &[
+    "shipit",
+    "updater",
+    "autoupdate",
+    "squirrel.framework",
+    "sparkle.framework",
+];
+/// Folded names of helpers: Electron's "<App> Helper (Renderer)", "(GPU)",
+/// "(Plugin)" and so on
+const HELPER_PATTERNS: &[&str] = &["helper"];
+
+/// What a process does for the app it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ProcessRole {
+    /// The app itself
+    #[default]
+    Main,
+    /// A renderer, GPU or other helper the app starts
+    Helper,
+    /// Downloads and installs new versions
+    Updater,
+    /// Uploads crash reports
+    CrashReporter,
+}
+
+impl ProcessRole {
+    /// Every role, the app first
+    pub const ALL: [ProcessRole; 4] = [
+        ProcessRole::Main,
+        ProcessRole::Helper,
+        ProcessRole::Updater,
+        ProcessRole::CrashReporter,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProcessRole::Main => "app",
+            ProcessRole::Helper => "helper",
+            ProcessRole::Updater => "updater",
+            ProcessRole::CrashReporter => "crash reporter",
+        }
+    }
+
+    /// Judge a process by its name and executable path (empty if unknown)
+    pub fn of(name: &str, path: &str) -> Self {
+        let name = normalize::fold(name);
+        let path = normalize::fold(path);
+        let matches = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .any(|pattern| name.contains(pattern) || path.contains(pattern))
+        };
+
+        // Checked before helpers: Chromium names its reporter
+        // "chrome_crashpad_handler", and Squirrel's lives in a helper bundle
+        if matches(CRASH_REPORTER_PATTERNS) {
+            ProcessRole::CrashReporter
+        } else if matches(UPDATER_PATTERNS) {
+            ProcessRole::Updater
+        } else if HELPER_PATTERNS.iter().any(|pattern| name.contains(pattern)) {
+            ProcessRole::Helper
+        } else {
+            ProcessRole::Main
+        }
+    }
+}
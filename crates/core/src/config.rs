@@ -0,0 +1,66 @@
+//! Scan configuration.
+
+use crate::{AllowList, SignatureError, SignatureSet, TargetSpec, TransparencyRule, WatchTarget};
+
+/// Which side of the reference window to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum WindowOrder {
+    /// Windows in front of the reference window
+    Above,
+    /// Windows behind the reference window
+    Below,
+}
+
+/// Limits the window list to on-screen windows in front of or behind
+/// another window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelativeWindow {
+    pub window_id: u32,
+    pub order: WindowOrder,
+    /// Also list the reference window itself
+    pub including: bool,
+}
+
+/// Which windows a scan asks the window server for. The default lists
+/// every window, which finds Cluely wherever it hides; narrower lists are
+/// cheaper to produce and parse but can miss windows.
+#[derive(Debug, Clone, Default)]
+pub struct DetectorConfig {
+    /// Only windows currently on screen. Skips minimized windows, windows
+    /// on other Spaces and ordered-out helper windows.
+    pub on_screen_only: bool,
+    /// Leave out the desktop picture and desktop icon windows
+    pub exclude_desktop_elements: bool,
+    /// Only windows in front of or behind this window; implies
+    /// `on_screen_only`
+    pub relative_to: Option<RelativeWindow>,
+    /// What counts as a Cluely window; the built-in signatures by default
+    pub signatures: SignatureSet,
+    /// Have the evasion scan also flag owners named within this many edits
+    /// of a known tool ("Cluey", "Culely"), as low-confidence findings
+    pub fuzzy_max_distance: Option<usize>,
+    /// When the evasion scan flags nearly invisible windows
+    pub transparency: TransparencyRule,
+    /// Have the evasion scan read the UI of each flagged window into
+    /// `WindowFinding::content`. On macOS this needs Accessibility
+    /// permission, and is skipped without it.
+    pub inspect_content: bool,
+    /// Apps left out of every finding, e.g. a disclosed tool the
+    /// organization deploys itself
+    pub allow: AllowList,
+    /// Apps watched for besides Cluely (see `watch_target`)
+    pub targets: Vec<WatchTarget>,
+}
+
+impl DetectorConfig {
+    /// Watch for another app of concern. Its windows are analyzed like
+    /// Cluely's: flagged by the evasion scan whatever they do, and counted
+    /// by the Cluely scans and `Monitor`.
+    pub fn watch_target(&mut self, spec: TargetSpec) -> Result<(), SignatureError> {
+        self.targets.push(WatchTarget::new(spec)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! What a window shows, as read from its accessibility tree.
+//!
+//! An overlay with no title and a generic owner says little about itself,
+//! but the labels on its buttons and the text it displays usually do. A
+//! backend collects those strings into a `WindowContent`, and `summary`
+//! condenses them into one line for reports.
+
+/// Most strings kept per kind; the rest only set `truncated`
+pub const MAX_CONTENT_ITEMS: usize = 20;
+/// Longest string shown in a summary, in characters
+const SUMMARY_TEXT_LENGTH: usize = 40;
+/// Strings of each kind shown in a summary
+const SUMMARY_ITEMS: usize = 5;
+
+/// Text read from a window's UI elements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowContent {
+    pub buttons: Vec<String>, // Button titles, in tree order, without repeats
+    pub text: Vec<String>,    // Static text and text field values
+    /// Some elements were not read or not kept
+    pub truncated: bool,
+}
+
+impl WindowContent {
+    pub fn is_empty(&self) -> bool {
+        self.buttons.is_empty() && self.text.is_empty()
+    }
+
+    /// Keep a button title; blank and repeated titles are dropped
+    pub fn add_button(&mut self, title: &str) {
+        Self::add(&mut self.buttons, title, &mut self.truncated);
+    }
+
+    /// Keep a piece of displayed text; blank and repeated text is dropped
+    pub fn add_text(&mut self, text: &str) {
+        Self::add(&mut self.text, text, &mut self.truncated);
+    }
+
+    fn add(items: &mut Vec<String>, value: &str, truncated: &mut bool) {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        if value.is_empty() || items.contains(&value) {
+            return;
+        }
+        if items.len() >= MAX_CONTENT_ITEMS {
+            *truncated = true;
+            return;
+        }
+        items.push(value);
+    }
+
+    /// One line describing the window, e.g.
+    /// `buttons "Ask AI", "Hide"; text "Listening…"`. Empty if nothing was read.
+    pub fn summary(&self) -> String {
+        let quoted = |items: &[String]| {
+            let mut shown: Vec<String> = items
+                .iter()
+                .take(SUMMARY_ITEMS)
+                .map(|item| format!("\"{}\"", shorten(item)))
+                .collect();
+            if items.len() > SUMMARY_ITEMS {
+                shown.push(format!("{} more", items.len() - SUMMARY_ITEMS));
+            }
+            shown.join(", ")
+        };
+
+        let mut parts = Vec::new();
+        if !self.buttons.is_empty() {
+            parts.push(format!("buttons {}", quoted(&self.buttons)));
+        }
+        if !self.text.is_empty() {
+            parts.push(format!("text {}", quoted(&self.text)));
+        }
+        parts.join("; ")
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= SUMMARY_TEXT_LENGTH {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(SUMMARY_TEXT_LENGTH - 1).collect();
+    short.push('…');
+    short
+}
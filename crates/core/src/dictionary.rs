@@ -0,0 +1,133 @@
+//! Window dictionaries, independent of the backend that reads them.
+//!
+//! The window server describes each window as a dictionary of loosely
+//! typed values. A backend exposes one through `WindowDictionary`, and
+//! `parse_window` turns it into a `ScannedWindow`. Every getter returns
+//! None for a missing key, a value of another type or a number that does
+//! not fit, so malformed input yields default fields rather than a panic.
+
+use crate::scanner::{ScannedWindow, WindowBounds};
+
+/// A key in a window dictionary or in its bounds dictionary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowKey {
+    OwnerName,
+    OwnerPid,
+    SharingState,
+    Layer,
+    Number,
+    Name,
+    Alpha,
+    IsOnscreen,
+    Bounds,
+    StoreType,
+    BackingType,
+    BoundsX,
+    BoundsY,
+    BoundsWidth,
+    BoundsHeight,
+}
+
+impl WindowKey {
+    pub const ALL: [WindowKey; 15] = [
+        WindowKey::OwnerName,
+        WindowKey::OwnerPid,
+        WindowKey::SharingState,
+        WindowKey::Layer,
+        WindowKey::Number,
+        WindowKey::Name,
+        WindowKey::Alpha,
+        WindowKey::IsOnscreen,
+        WindowKey::Bounds,
+        WindowKey::StoreType,
+        WindowKey::BackingType,
+        WindowKey::BoundsX,
+        WindowKey::BoundsY,
+        WindowKey::BoundsWidth,
+        WindowKey::BoundsHeight,
+    ];
+
+    /// The key as CGWindowListCopyWindowInfo spells it
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowKey::OwnerName => "kCGWindowOwnerName",
+            WindowKey::OwnerPid => "kCGWindowOwnerPID",
+            WindowKey::SharingState => "kCGWindowSharingState",
+            WindowKey::Layer => "kCGWindowLayer",
+            WindowKey::Number => "kCGWindowNumber",
+            WindowKey::Name => "kCGWindowName",
+            WindowKey::Alpha => "kCGWindowAlpha",
+            WindowKey::IsOnscreen => "kCGWindowIsOnscreen",
+            WindowKey::Bounds => "kCGWindowBounds",
+            WindowKey::StoreType => "kCGWindowStoreType",
+            WindowKey::BackingType => "kCGWindowBackingType",
+            WindowKey::BoundsX => "X",
+            WindowKey::BoundsY => "Y",
+            WindowKey::BoundsWidth => "Width",
+            WindowKey::BoundsHeight => "Height",
+        }
+    }
+}
+
+/// Typed reads from one window dictionary (Rust API)
+pub trait WindowDictionary: Sized {
+    fn string(&self, key: WindowKey) -> Option<String>;
+    /// None if the value is not a number or does not fit an i32 exactly
+    fn int(&self, key: WindowKey) -> Option<i32>;
+    fn float(&self, key: WindowKey) -> Option<f64>;
+    fn bool(&self, key: WindowKey) -> Option<bool>;
+    fn dictionary(&self, key: WindowKey) -> Option<Self>;
+}
+
+fn get_window_bounds(dict: &impl WindowDictionary) -> Option<WindowBounds> {
+    let bounds_dict = dict.dictionary(WindowKey::Bounds)?;
+
+    Some(WindowBounds {
+        x: bounds_dict.float(WindowKey::BoundsX).unwrap_or_default(),
+        y: bounds_dict.float(WindowKey::BoundsY).unwrap_or_default(),
+        width: bounds_dict
+            .float(WindowKey::BoundsWidth)
+            .unwrap_or_default(),
+        height: bounds_dict
+            .float(WindowKey::BoundsHeight)
+            .unwrap_or_default(),
+    })
+}
+
+/// Read every property the scanner uses from a window dictionary (Rust API)
+pub fn parse_window(window_dict: &impl WindowDictionary) -> ScannedWindow {
+    let int = |key| window_dict.int(key).unwrap_or_default();
+    let name = window_dict.string(WindowKey::Name).unwrap_or_default();
+    let owner = window_dict.string(WindowKey::OwnerName).unwrap_or_default();
+    let owner_pid = int(WindowKey::OwnerPid);
+    let window_id = int(WindowKey::Number);
+    let layer = int(WindowKey::Layer);
+    let is_onscreen = window_dict.bool(WindowKey::IsOnscreen).unwrap_or_default();
+    let alpha = window_dict.float(WindowKey::Alpha).unwrap_or_default();
+    let sharing_state = int(WindowKey::SharingState);
+    let store_type = int(WindowKey::StoreType);
+    let backing_type = int(WindowKey::BackingType);
+    let bounds = get_window_bounds(window_dict);
+
+    let is_hidden = !is_onscreen || alpha < 0.1 || layer < 0;
+
+    ScannedWindow {
+        name: if name.is_empty() {
+            "<No Title>".to_string()
+        } else {
+            name
+        },
+        owner,
+        owner_pid,
+        window_id,
+        layer,
+        is_onscreen,
+        alpha,
+        is_hidden,
+        sharing_state,
+        store_type,
+        backing_type,
+        bounds,
+        target: None,
+    }
+}
@@ -0,0 +1,139 @@
+//! Displays, and which windows sit on them.
+//!
+//! With more than one screen attached, a presenter usually shares only one
+//! of them. An overlay parked on another display never shows up in the
+//! share, so where Cluely's windows sit matters as much as what they are.
+
+use std::collections::BTreeMap;
+
+use crate::scanner::{ScannedWindow, WindowBounds};
+
+/// Windows that meeting apps show only while a screen is being shared: the
+/// owner to look for (empty for any owner, e.g. a browser) and text in the
+/// title
+const SHARE_INDICATORS: &[(&str, &str)] = &[
+    ("zoom.us", "share toolbar"),
+    ("zoom.us", "share statusbar"),
+    ("microsoft teams", "sharing control bar"),
+    ("", "is sharing your screen"),
+];
+
+/// An active display, in global display coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Display {
+    pub id: u32,
+    pub bounds: WindowBounds,
+    pub is_main: bool,
+}
+
+impl Display {
+    /// Area of `bounds` that lies on this display
+    pub(crate) fn overlap(&self, bounds: &WindowBounds) -> f64 {
+        let width = (self.bounds.x + self.bounds.width).min(bounds.x + bounds.width)
+            - self.bounds.x.max(bounds.x);
+        let height = (self.bounds.y + self.bounds.height).min(bounds.y + bounds.height)
+            - self.bounds.y.max(bounds.y);
+        width.max(0.0) * height.max(0.0)
+    }
+}
+
+/// The Cluely windows on one display
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayOverlays {
+    pub display: Display,
+    pub cluely_window_ids: Vec<i32>,
+}
+
+/// Where Cluely's windows sit across the attached displays
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayReport {
+    pub displays: Vec<DisplayOverlays>,
+    /// The display being shared: given by the caller, or found from a
+    /// meeting app's sharing toolbar. None if no screen share is seen.
+    pub shared_display: Option<u32>,
+    /// Cluely windows on a display other than the shared one
+    pub unshared_overlays: Vec<i32>,
+}
+
+impl DisplayReport {
+    /// Whether Cluely sits on a display the meeting cannot see
+    pub fn overlay_hidden_from_share(&self) -> bool {
+        !self.unshared_overlays.is_empty()
+    }
+}
+
+/// The display holding most of `bounds`, if any part of it is on screen
+pub(crate) fn display_for(displays: &[Display], bounds: &WindowBounds) -> Option<u32> {
+    displays
+        .iter()
+        .map(|display| (display.id, display.overlap(bounds)))
+        .filter(|&(_, area)| area > 0.0)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(id, _)| id)
+}
+
+/// The display a meeting app's sharing toolbar sits on
+fn find_shared_display(windows: &[ScannedWindow], displays: &[Display]) -> Option<u32> {
+    windows
+        .iter()
+        .filter(|window| window.is_onscreen)
+        .filter(|window| {
+            let owner = window.owner.to_lowercase();
+            let title = window.name.to_lowercase();
+            SHARE_INDICATORS
+                .iter()
+                .any(|&(indicator_owner, indicator_title)| {
+                    owner.contains(indicator_owner) && title.contains(indicator_title)
+                })
+        })
+        .find_map(|window| display_for(displays, window.bounds.as_ref()?))
+}
+
+/// Place each on-screen Cluely window on one of `displays`.
+/// `shared_display` overrides the display found from sharing toolbars.
+pub fn analyze_displays(
+    windows: &[ScannedWindow],
+    displays: &[Display],
+    shared_display: Option<u32>,
+) -> DisplayReport {
+    let shared_display = shared_display.or_else(|| find_shared_display(windows, displays));
+
+    let mut by_display: BTreeMap<u32, Vec<i32>> = BTreeMap::new();
+    for window in windows {
+        if !window.is_onscreen || !window.is_cluely_related() {
+            continue;
+        }
+        let Some(id) = window
+            .bounds
+            .as_ref()
+            .and_then(|bounds| display_for(displays, bounds))
+        else {
+            continue;
+        };
+        by_display.entry(id).or_default().push(window.window_id);
+    }
+
+    let unshared_overlays = match shared_display {
+        Some(shared) => by_display
+            .iter()
+            .filter(|&(&id, _)| id != shared)
+            .flat_map(|(_, window_ids)| window_ids.iter().copied())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    DisplayReport {
+        displays: displays
+            .iter()
+            .map(|&display| DisplayOverlays {
+                display,
+                cluely_window_ids: by_display.remove(&display.id).unwrap_or_default(),
+            })
+            .collect(),
+        shared_display,
+        unshared_overlays,
+    }
+}
@@ -0,0 +1,101 @@
+//! Structured findings of the evasion scan.
+//!
+//! Each technique a window uses is reported as the `Rule` that caught it, a
+//! message for people and the values the rule looked at. Automation should
+//! branch on the rule and read the evidence; the message may be reworded
+//! between releases.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Rule;
+
+/// A value a rule looked at
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Evidence {
+    Integer(i64),
+    Number(f64),
+    Text(String),
+}
+
+impl From<i32> for Evidence {
+    fn from(value: i32) -> Self {
+        Evidence::Integer(value.into())
+    }
+}
+
+impl From<u32> for Evidence {
+    fn from(value: u32) -> Self {
+        Evidence::Integer(value.into())
+    }
+}
+
+impl From<usize> for Evidence {
+    fn from(value: usize) -> Self {
+        Evidence::Integer(value as i64)
+    }
+}
+
+impl From<f64> for Evidence {
+    fn from(value: f64) -> Self {
+        Evidence::Number(value)
+    }
+}
+
+impl From<&str> for Evidence {
+    fn from(value: &str) -> Self {
+        Evidence::Text(value.to_string())
+    }
+}
+
+impl From<String> for Evidence {
+    fn from(value: String) -> Self {
+        Evidence::Text(value)
+    }
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Evidence::Integer(value) => write!(f, "{}", value),
+            Evidence::Number(value) => write!(f, "{}", value),
+            Evidence::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+/// A technique a window uses, as one rule saw it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Finding {
+    /// The rule that fired; serialized as its stable ID
+    pub rule_id: Rule,
+    /// One-line description for reports
+    pub message: String,
+    /// The values behind the finding, by name, e.g. `layer` or `alpha`
+    pub evidence: BTreeMap<String, Evidence>,
+}
+
+impl Finding {
+    pub fn new(rule_id: Rule, message: impl Into<String>) -> Self {
+        Self {
+            rule_id,
+            message: message.into(),
+            evidence: BTreeMap::new(),
+        }
+    }
+
+    /// Record `value` as evidence under `key`
+    pub fn with(mut self, key: &str, value: impl Into<Evidence>) -> Self {
+        self.evidence.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
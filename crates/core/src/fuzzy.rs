@@ -0,0 +1,78 @@
+//! Near-miss matching of monitoring tool names.
+//!
+//! A lazy rebrand ("Cluey", "Cluelly", "Culely") slips past the signatures,
+//! which only match names containing the real one. The fuzzy matcher
+//! compares each word of an owner name with the known tool names by edit
+//! distance. A close but inexact name is only a hint, so the evasion scan
+//! reports it with low confidence instead of as a detection.
+
+use crate::normalize;
+
+/// Known monitoring tools: the folded name to compare with, and the name to
+/// report
+const KNOWN_NAMES: &[(&str, &str)] = &[("cluely", "Cluely")];
+
+/// A word of a name that is close to a known tool name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FuzzyMatch {
+    pub tool: &'static str,
+    pub word: String,
+    pub distance: usize,
+}
+
+/// The closest known tool name within `max_distance` edits of a word in
+/// `name`. Exact matches are left to the signatures. The distance is also
+/// capped at a third of the tool name's length, since beyond that almost any
+/// short word comes close.
+pub(crate) fn near_miss(name: &str, max_distance: usize) -> Option<FuzzyMatch> {
+    let folded = normalize::fold(name);
+    folded
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            KNOWN_NAMES.iter().filter_map(move |&(known, tool)| {
+                let limit = max_distance.min(known.len() / 3);
+                let distance = edit_distance(word, known, limit)?;
+                (distance > 0).then(|| FuzzyMatch {
+                    tool,
+                    word: word.to_string(),
+                    distance,
+                })
+            })
+        })
+        .min_by_key(|found| found.distance)
+}
+
+/// Optimal string alignment distance between `a` and `b`: insertions,
+/// deletions, substitutions and swaps of adjacent characters each cost one.
+/// `None` if it is more than `limit`.
+fn edit_distance(a: &str, b: &str, limit: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > limit {
+        return None;
+    }
+
+    // Rows i - 2, i - 1 and i of the distance table
+    let mut two_back = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(two_back[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut two_back, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= limit).then_some(distance)
+}
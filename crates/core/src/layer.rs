@@ -0,0 +1,116 @@
+//! Window layer classes.
+//!
+//! A window's layer is a raw CGWindowLevel number. The window server
+//! assigns names to a handful of levels (normal, floating, status bar,
+//! screen saver, ...); a layer belongs to the highest named level at or
+//! below it.
+
+// Reserved levels at each end of the range, as defined in CGWindowLevel.h
+const K_CG_NUM_RESERVED_BASE_WINDOW_LEVELS: i32 = 5;
+const K_CG_NUM_RESERVED_WINDOW_LEVELS: i32 = 16;
+const K_CG_MINIMUM_WINDOW_LEVEL: i32 = i32::MIN + K_CG_NUM_RESERVED_BASE_WINDOW_LEVELS;
+const K_CG_MAXIMUM_WINDOW_LEVEL: i32 = i32::MAX - K_CG_NUM_RESERVED_WINDOW_LEVELS;
+
+/// The named levels and their classes, sorted by level. These are the
+/// values CGWindowLevel.h defines and `CGWindowLevelForKey` returns; they
+/// have not changed since the window server introduced them. Where two
+/// names share a level (floating and torn-off menus) only one is listed.
+const NAMED_LEVELS: &[(i32, WindowLayerClass)] = &[
+    (K_CG_MINIMUM_WINDOW_LEVEL + 20, WindowLayerClass::Desktop),
+    (
+        K_CG_MINIMUM_WINDOW_LEVEL + 40,
+        WindowLayerClass::DesktopIcon,
+    ),
+    (0, WindowLayerClass::Normal),
+    (3, WindowLayerClass::Floating),
+    (8, WindowLayerClass::ModalPanel),
+    (19, WindowLayerClass::Utility),
+    (20, WindowLayerClass::Dock),
+    (24, WindowLayerClass::MainMenu),
+    (25, WindowLayerClass::StatusBar),
+    (101, WindowLayerClass::PopUpMenu),
+    (102, WindowLayerClass::Overlay),
+    (200, WindowLayerClass::Help),
+    (500, WindowLayerClass::Dragging),
+    (1000, WindowLayerClass::ScreenSaver),
+    (1500, WindowLayerClass::AboveScreenCapture),
+    (K_CG_MAXIMUM_WINDOW_LEVEL - 1, WindowLayerClass::Cursor),
+];
+
+/// What a window's layer is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum WindowLayerClass {
+    Desktop,
+    DesktopIcon,
+    BelowNormal, // Between the desktop icons and normal windows
+    Normal,
+    Floating, // Palettes, torn-off menus and always-on-top panels
+    ModalPanel,
+    Utility,
+    Dock,
+    MainMenu,
+    StatusBar,
+    PopUpMenu,
+    Overlay,
+    Help,
+    Dragging,
+    ScreenSaver,
+    /// Above the screen saver, where accessibility tools draw. Screen
+    /// sharing apps composite their own indicators below this level, so an
+    /// app window here covers everything they show.
+    AboveScreenCapture,
+    Cursor,
+}
+
+impl WindowLayerClass {
+    /// The class of a raw layer number
+    pub fn of(layer: i32) -> Self {
+        let normal = level_of(WindowLayerClass::Normal);
+        let desktop_icon = level_of(WindowLayerClass::DesktopIcon);
+        if layer > desktop_icon && layer < normal {
+            return WindowLayerClass::BelowNormal;
+        }
+
+        NAMED_LEVELS
+            .iter()
+            .rev()
+            .find(|&&(level, _)| level <= layer)
+            .map_or(WindowLayerClass::Desktop, |&(_, class)| class)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WindowLayerClass::Desktop => "desktop",
+            WindowLayerClass::DesktopIcon => "desktop-icon",
+            WindowLayerClass::BelowNormal => "below-normal",
+            WindowLayerClass::Normal => "normal",
+            WindowLayerClass::Floating => "floating",
+            WindowLayerClass::ModalPanel => "modal-panel",
+            WindowLayerClass::Utility => "utility",
+            WindowLayerClass::Dock => "dock",
+            WindowLayerClass::MainMenu => "main-menu",
+            WindowLayerClass::StatusBar => "status-bar",
+            WindowLayerClass::PopUpMenu => "pop-up-menu",
+            WindowLayerClass::Overlay => "overlay",
+            WindowLayerClass::Help => "help",
+            WindowLayerClass::Dragging => "dragging",
+            WindowLayerClass::ScreenSaver => "screen-saver",
+            WindowLayerClass::AboveScreenCapture => "above-screen-capture",
+            WindowLayerClass::Cursor => "cursor",
+        }
+    }
+
+    /// Whether the layer keeps the window in front of normal app windows
+    pub fn is_elevated(self) -> bool {
+        self > WindowLayerClass::Normal
+    }
+}
+
+fn level_of(class: WindowLayerClass) -> i32 {
+    NAMED_LEVELS
+        .iter()
+        .find(|&&(_, named)| named == class)
+        .map_or(0, |&(level, _)| level)
+}
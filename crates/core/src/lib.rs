@@ -0,0 +1,235 @@
+//! Platform-independent detection rules for no-cluely.
+//!
+//! Everything here works on window properties that have already been read:
+//! signatures, evasion techniques, layer classes and display geometry. The
+//! macOS backend (`no-cluely-macos`) reads those properties from the window
+//! server, and `no-cluely-driver` puts the two together.
+
+use std::time::Instant;
+
+mod activity;
+mod allow;
+mod artifact;
+mod behavior;
+mod companion;
+mod config;
+mod content;
+mod dictionary;
+mod display;
+mod finding;
+mod fuzzy;
+mod layer;
+pub mod normalize;
+mod placement;
+mod rules;
+mod scanner;
+mod signature;
+mod target;
+mod transparency;
+
+pub use activity::{ActivityLevel, ActivityRule};
+pub use allow::AllowList;
+pub use artifact::ArtifactKind;
+pub use behavior::{meeting_window, OverlayEvidence};
+pub use companion::ProcessRole;
+pub use config::{DetectorConfig, RelativeWindow, WindowOrder};
+pub use content::{WindowContent, MAX_CONTENT_ITEMS};
+pub use dictionary::{parse_window, WindowDictionary, WindowKey};
+pub use display::{analyze_displays, Display, DisplayOverlays, DisplayReport};
+pub use finding::{Evidence, Finding};
+pub use layer::WindowLayerClass;
+pub use placement::{placement, Placement, WindowPlacement};
+pub use rules::{Rule, RULESET_VERSION};
+pub use scanner::{
+    analyze_windows, capture_evasion, suspicious_processes, CaptureEvasionApp,
+    CaptureEvasionReport, CaptureSignal, Confidence, EvasionScan, ScannedWindow, SuspiciousProcess,
+    WindowBounds, WindowFinding,
+};
+pub use signature::{Pattern, Signature, SignatureError, SignatureSet, Subject};
+pub use target::{TargetSpec, WatchTarget};
+pub use transparency::TransparencyRule;
+
+/// Detailed detection result with evasion techniques
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClueLyDetectionResult {
+    pub is_detected: bool,
+    pub window_count: u32,
+    pub screen_capture_evasion_count: u32, // Windows avoiding screen capture
+    pub elevated_layer_count: u32,         // Windows using elevated layers
+    pub max_layer_detected: i32,           // Highest layer number found
+    pub last_scan_duration_us: u64,        // Time the scan took, in microseconds
+    /// `RULESET_VERSION` of the rules that produced this result; 0 in
+    /// results recorded before it was kept
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ruleset_version: u32,
+    /// Bits of the `Rule`s that fired (see `fired_rules`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules_fired: u32,
+    /// Version of the signature bundle in use; 0 for the built-in
+    /// signatures only
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub signatures_version: u64,
+}
+
+impl Default for ClueLyDetectionResult {
+    fn default() -> Self {
+        Self {
+            is_detected: false,
+            window_count: 0,
+            screen_capture_evasion_count: 0,
+            elevated_layer_count: 0,
+            max_layer_detected: 0,
+            last_scan_duration_us: 0,
+            ruleset_version: RULESET_VERSION,
+            rules_fired: 0,
+            signatures_version: 0,
+        }
+    }
+}
+
+impl ClueLyDetectionResult {
+    /// An empty result of a scan matching `signatures`
+    pub fn new(signatures: &SignatureSet) -> Self {
+        Self {
+            signatures_version: signatures.version(),
+            ..Self::default()
+        }
+    }
+
+    /// The rules that fired, in bit order
+    pub fn fired_rules(&self) -> Vec<Rule> {
+        Rule::in_mask(self.rules_fired)
+    }
+
+    /// How long the scan that produced this result took
+    pub fn last_scan_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.last_scan_duration_us)
+    }
+
+    /// Add a Cluely window to the detection counts
+    pub fn add_window(&mut self, window: &WindowInfo) {
+        self.is_detected = true;
+        self.window_count += 1;
+        self.rules_fired |= Rule::CluelySignature.bit();
+
+        // Check for specific evasion techniques
+        if window.sharing_state == 0 {
+            self.screen_capture_evasion_count += 1;
+            self.rules_fired |= Rule::ScreenCaptureEvasion.bit();
+        }
+
+        if window.layer > 0 {
+            self.elevated_layer_count += 1;
+            self.rules_fired |= Rule::ElevatedLayer.bit();
+            if window.layer > self.max_layer_detected {
+                self.max_layer_detected = window.layer;
+            }
+        }
+    }
+}
+
+/// Reasons a scan could not be performed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum DetectionError {
+    /// The window server returned no window list, e.g. when running outside
+    /// a logged-in GUI session (over SSH or as a system daemon)
+    WindowListUnavailable,
+    /// This platform has no backend yet, so nothing can be scanned
+    PlatformUnsupported,
+    /// The scan needs root, e.g. to read other users' processes
+    PermissionDenied,
+}
+
+impl std::fmt::Display for DetectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectionError::WindowListUnavailable => {
+                write!(f, "the window list is not available in this session")
+            }
+            DetectionError::PlatformUnsupported => {
+                write!(f, "scanning is not supported on this platform yet")
+            }
+            DetectionError::PermissionDenied => write!(f, "this scan needs root privileges"),
+        }
+    }
+}
+
+impl std::error::Error for DetectionError {}
+
+/// Window information for detailed analysis
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowInfo {
+    pub owner: String,
+    pub window_id: i32,
+    pub sharing_state: i32, // 0 = hidden from screen capture
+    pub layer: i32,         // > 0 = elevated above normal windows
+    pub alpha: f64,         // Opacity, 0.0 (invisible) to 1.0
+}
+
+impl WindowInfo {
+    /// What the window's layer is used for
+    pub fn layer_class(&self) -> WindowLayerClass {
+        WindowLayerClass::of(self.layer)
+    }
+}
+
+/// Whether a process or file name matches the built-in signatures
+pub fn is_cluely_process(name: &str) -> bool {
+    signature::builtin()
+        .matching(&Subject::owner(name))
+        .is_some()
+}
+
+/// Domain Cluely's web app is served from
+pub const CLUELY_WEB_DOMAIN: &str = "cluely.com";
+
+/// Whether a URL points at Cluely's web app: `cluely.com` or any subdomain
+pub fn is_cluely_url(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = host.split(':').next().unwrap_or_default();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    host == CLUELY_WEB_DOMAIN
+        || host
+            .strip_suffix(CLUELY_WEB_DOMAIN)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Run Cluely detection over windows read earlier, such as a recorded
+/// fixture, instead of the live window list (Rust API)
+pub fn detect_cluely_in_rust(
+    windows: &[ScannedWindow],
+) -> (ClueLyDetectionResult, Vec<WindowInfo>) {
+    let started = Instant::now();
+    let mut result = ClueLyDetectionResult::default();
+    let cluely_windows: Vec<WindowInfo> = windows
+        .iter()
+        .filter(|window| is_cluely_process(&window.owner))
+        .map(|window| WindowInfo {
+            owner: window.owner.clone(),
+            window_id: window.window_id,
+            sharing_state: window.sharing_state,
+            layer: window.layer,
+            alpha: window.alpha,
+        })
+        .collect();
+    for window in &cluely_windows {
+        result.add_window(window);
+    }
+    result.last_scan_duration_us = started.elapsed().as_micros() as u64;
+    (result, cluely_windows)
+}
+
+/// Scan windows read earlier, such as a recorded fixture, for evasion
+/// techniques (Rust API)
+pub fn scan_windows_rust(windows: Vec<ScannedWindow>, include_system: bool) -> EvasionScan {
+    analyze_windows(windows, &[], include_system, &DetectorConfig::default())
+}
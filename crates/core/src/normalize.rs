@@ -0,0 +1,107 @@
+//! Name folding for signature matching.
+//!
+//! Cluely could rename itself "Cluеly" with a Cyrillic `е`, "Ｃｌｕｅｌｙ" in
+//! fullwidth letters, or slip a zero-width space into its name; each looks
+//! the same on screen but defeats a plain substring check. Names are
+//! compared after NFKC normalization (which folds fullwidth and other
+//! compatibility forms), lowercasing, removing invisible characters and
+//! mapping common lookalikes from other scripts to the Latin letter they
+//! imitate.
+//!
+//! Lookalike mapping is for substring patterns only. It turns `0` into `o`
+//! and `1` into `l`, so a regex such as `^cluely[0-9]+$` could never match
+//! folded text; regexes are matched against [`lower`] text instead, which
+//! has every step but that one.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters from other scripts, and digits, that pass for a Latin letter
+const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'),
+    ('в', 'b'),
+    ('с', 'c'),
+    ('ԁ', 'd'),
+    ('е', 'e'),
+    ('ё', 'e'),
+    ('һ', 'h'),
+    ('н', 'h'),
+    ('і', 'i'),
+    ('ї', 'i'),
+    ('ј', 'j'),
+    ('к', 'k'),
+    ('ӏ', 'l'),
+    ('м', 'm'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ԛ', 'q'),
+    ('ѕ', 's'),
+    ('т', 't'),
+    ('у', 'y'),
+    ('ү', 'y'),
+    ('ԝ', 'w'),
+    ('х', 'x'),
+    // Greek
+    ('α', 'a'),
+    ('ε', 'e'),
+    ('η', 'n'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('τ', 't'),
+    ('υ', 'u'),
+    ('χ', 'x'),
+    ('γ', 'y'),
+    // Latin
+    ('ı', 'i'),
+    ('ǀ', 'l'),
+    // Digits
+    ('0', 'o'),
+    ('1', 'l'),
+];
+
+/// Zero-width and other format characters that render as nothing
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}' | '\u{034F}' | '\u{180E}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}' | '\u{FEFF}'
+    )
+}
+
+fn unconfuse(c: char) -> char {
+    CONFUSABLES
+        .iter()
+        .find(|(confusable, _)| *confusable == c)
+        .map_or(c, |(_, latin)| *latin)
+}
+
+/// Whether `c` is read as the Latin letter it looks like in folded text
+pub fn is_confusable(c: char) -> bool {
+    unconfuse(c) != c
+}
+
+/// `text` normalized, lowercased and without invisible characters, but
+/// with lookalikes kept: the form regexes are matched against
+pub fn lower(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_ascii_lowercase();
+    }
+
+    text.nfkc()
+        .filter(|c| !is_invisible(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Map the lookalikes in [`lower`] text to Latin letters
+pub fn unconfuse_lower(lower: &str) -> String {
+    lower.chars().map(unconfuse).collect()
+}
+
+/// `text` in the form substring patterns are matched against
+pub fn fold(text: &str) -> String {
+    unconfuse_lower(&lower(text))
+}
@@ -0,0 +1,83 @@
+//! Where windows sit: on which display, how far off screen, and whether
+//! they follow the user to every Space.
+//!
+//! A window can be kept out of a screen share without touching its sharing
+//! state, by parking it beyond the edge of every display until it is
+//! needed. What counts as "beyond the edge" depends on the displays that
+//! are attached, so positions are judged against their actual geometry.
+
+use crate::display::{self, Display};
+use crate::scanner::{ScannedWindow, WindowBounds};
+
+/// Where a window sits relative to the attached displays
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Placement {
+    /// Mostly on this display; `visible_fraction` is the share of the
+    /// window's area that lies on any display
+    OnScreen {
+        display_id: u32,
+        visible_fraction: f64,
+    },
+    /// Entirely off every display, `distance` points from the nearest one
+    OffScreen { distance: f64 },
+}
+
+impl Placement {
+    /// Off screen by more than the size of the display arrangement: too far
+    /// out to be a window sliding in or straddling a display edge
+    pub fn is_parked_off_screen(&self, displays: &[Display]) -> bool {
+        let span = displays
+            .iter()
+            .map(|display| display.bounds.width.max(display.bounds.height))
+            .fold(0.0, f64::max);
+        matches!(*self, Placement::OffScreen { distance } if distance > span)
+    }
+}
+
+/// Placement details for one window
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowPlacement {
+    pub window: ScannedWindow,
+    /// None if the window has no bounds or no display is attached
+    pub placement: Option<Placement>,
+    /// Shown on every Space, following the user wherever they switch
+    pub on_all_spaces: bool,
+}
+
+/// Where `bounds` sits relative to `displays`; None without displays
+pub fn placement(bounds: &WindowBounds, displays: &[Display]) -> Option<Placement> {
+    if displays.is_empty() {
+        return None;
+    }
+
+    let Some(display_id) = display::display_for(displays, bounds) else {
+        let distance = displays
+            .iter()
+            .map(|display| distance_between(&display.bounds, bounds))
+            .fold(f64::INFINITY, f64::min);
+        return Some(Placement::OffScreen { distance });
+    };
+
+    let area = bounds.width * bounds.height;
+    let visible: f64 = displays.iter().map(|display| display.overlap(bounds)).sum();
+    Some(Placement::OnScreen {
+        display_id,
+        visible_fraction: if area > 0.0 {
+            (visible / area).min(1.0)
+        } else {
+            1.0
+        },
+    })
+}
+
+/// Gap between two rectangles; 0 if they touch or overlap
+fn distance_between(a: &WindowBounds, b: &WindowBounds) -> f64 {
+    let dx = (a.x - (b.x + b.width)).max(b.x - (a.x + a.width)).max(0.0);
+    let dy = (a.y - (b.y + b.height))
+        .max(b.y - (a.y + a.height))
+        .max(0.0);
+    dx.hypot(dy)
+}
@@ -0,0 +1,242 @@
+//! The rules behind a detection result, and the version of the ruleset.
+//!
+//! A result records which rules fired as a bit mask and which ruleset
+//! produced it, so a result read back long after the scan can be
+//! interpreted against the exact detection logic of the time. The IDs are
+//! stable: a rule that changes what it matches gets a new ID, and
+//! `RULESET_VERSION` goes up whenever a rule is added, removed or changed.
+//!
+//! The first three rules are the ones a `ClueLyDetectionResult` records.
+//! The rest only fire in the evasion scan, which looks at every window, and
+//! name the `Finding`s it reports.
+
+/// Version of the built-in detection rules
+pub const RULESET_VERSION: u32 = 2;
+
+/// A detection rule
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Rule {
+    /// A window's owner, title or bundle ID matched a Cluely signature
+    CluelySignature = 1 << 0,
+    /// A Cluely window is hidden from screen capture (sharing state 0)
+    ScreenCaptureEvasion = 1 << 1,
+    /// A Cluely window sits above normal windows (layer above 0)
+    ElevatedLayer = 1 << 2,
+    /// A window belongs to an app watched with `DetectorConfig::watch_target`
+    WatchedApp = 1 << 3,
+    /// A window sits at or above the screen capture layer
+    AboveScreenCapture = 1 << 4,
+    /// A window is on screen but almost fully transparent
+    NearlyInvisible = 1 << 5,
+    /// A window is parked well clear of every display
+    OffScreen = 1 << 6,
+    /// A titled, on-screen window has no width or height
+    ZeroSize = 1 << 7,
+    /// A titled window is less than a pixel wide or high
+    SubPixelSize = 1 << 8,
+    /// A window is on an extremely deep layer
+    DeepLayer = 1 << 9,
+    /// A window's title calls it hidden, invisible or stealth
+    StealthTitle = 1 << 10,
+    /// A window is shown on every Space
+    AllSpaces = 1 << 11,
+    /// A window stays in place during Mission Control
+    Stationary = 1 << 12,
+    /// A window behaves like Cluely's overlay during a meeting
+    CluelyLikeOverlay = 1 << 13,
+    /// An owner name is a near miss of a known tool's name
+    RenamedTool = 1 << 14,
+    /// A hidden Cluely process is using capture-level CPU
+    ActiveCapture = 1 << 15,
+}
+
+impl Rule {
+    /// Every rule, in bit order
+    pub const ALL: [Rule; 16] = [
+        Rule::CluelySignature,
+        Rule::ScreenCaptureEvasion,
+        Rule::ElevatedLayer,
+        Rule::WatchedApp,
+        Rule::AboveScreenCapture,
+        Rule::NearlyInvisible,
+        Rule::OffScreen,
+        Rule::ZeroSize,
+        Rule::SubPixelSize,
+        Rule::DeepLayer,
+        Rule::StealthTitle,
+        Rule::AllSpaces,
+        Rule::Stationary,
+        Rule::CluelyLikeOverlay,
+        Rule::RenamedTool,
+        Rule::ActiveCapture,
+    ];
+
+    /// The rule's bit in a mask of rules, such as
+    /// `ClueLyDetectionResult::rules_fired`
+    pub fn bit(self) -> u32 {
+        self as u32
+    }
+
+    /// Whether the rule can fire in a `ClueLyDetectionResult`; the others
+    /// only produce evasion scan findings
+    pub fn in_detection_result(self) -> bool {
+        matches!(
+            self,
+            Rule::CluelySignature | Rule::ScreenCaptureEvasion | Rule::ElevatedLayer
+        )
+    }
+
+    /// Stable identifier, for JSON output and anything that stores results
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::CluelySignature => "cluely-signature",
+            Rule::ScreenCaptureEvasion => "screen-capture-evasion",
+            Rule::ElevatedLayer => "elevated-layer",
+            Rule::WatchedApp => "watched-app",
+            Rule::AboveScreenCapture => "above-screen-capture",
+            Rule::NearlyInvisible => "nearly-invisible",
+            Rule::OffScreen => "off-screen",
+            Rule::ZeroSize => "zero-size",
+            Rule::SubPixelSize => "sub-pixel-size",
+            Rule::DeepLayer => "deep-layer",
+            Rule::StealthTitle => "stealth-title",
+            Rule::AllSpaces => "all-spaces",
+            Rule::Stationary => "stationary",
+            Rule::CluelyLikeOverlay => "cluely-like-overlay",
+            Rule::RenamedTool => "renamed-tool",
+            Rule::ActiveCapture => "active-capture",
+        }
+    }
+
+    /// What the rule flags, in a line
+    pub fn summary(self) -> &'static str {
+        match self {
+            Rule::CluelySignature => "A window belongs to Cluely",
+            Rule::ScreenCaptureEvasion => "A Cluely window is hidden from screen capture",
+            Rule::ElevatedLayer => "A Cluely window floats above normal windows",
+            Rule::WatchedApp => "A window belongs to a watched app",
+            Rule::AboveScreenCapture => "A window sits above the screen capture layer",
+            Rule::NearlyInvisible => "A window is almost fully transparent",
+            Rule::OffScreen => "A window is parked off screen",
+            Rule::ZeroSize => "A named window has no size",
+            Rule::SubPixelSize => "A named window is smaller than a pixel",
+            Rule::DeepLayer => "A window is buried on an extremely deep layer",
+            Rule::StealthTitle => "A window's title says it is hidden",
+            Rule::AllSpaces => "A window follows the user to every Space",
+            Rule::Stationary => "A window stays on screen during Mission Control",
+            Rule::CluelyLikeOverlay => "A window behaves like Cluely's overlay",
+            Rule::RenamedTool => "An app's name is close to a known tool's",
+            Rule::ActiveCapture => "A hidden Cluely process is capturing the screen",
+        }
+    }
+
+    /// What the rule checks and why it matters
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Rule::CluelySignature => {
+                "Fires for every window whose owner name, title or bundle ID matches a \
+                 Cluely signature: the built-in ones (any owner containing \"cluely\" or \
+                 \"clue.ly\") and any added from a signature bundle or configuration. \
+                 Every other rule only looks at windows this rule matched, and a result \
+                 counts as a detection when it fires."
+            }
+            Rule::ScreenCaptureEvasion => {
+                "Fires when a Cluely window has sharing state 0, which asks the window \
+                 server to leave it out of screenshots, screen recordings and screen \
+                 sharing. The window stays visible to the person at the Mac while anyone \
+                 watching a shared screen sees nothing."
+            }
+            Rule::ElevatedLayer => {
+                "Fires when a Cluely window is on a window layer above 0, where it floats \
+                 over normal app windows, including full-screen meeting and exam apps. \
+                 The result keeps the highest layer seen; layers at or above the \
+                 screen-saver level are the most aggressive. The evasion scan reports \
+                 it for watched apps' windows too."
+            }
+            Rule::WatchedApp => {
+                "Fires in the evasion scan for every window of an app added with \
+                 DetectorConfig::watch_target, matched by name, bundle ID or Team ID. \
+                 Its windows are checked with the same capture, layer and transparency \
+                 rules as Cluely's."
+            }
+            Rule::AboveScreenCapture => {
+                "Fires when a window is at or above the layer the window server \
+                 composites screen captures at. Sharing apps draw their own indicators \
+                 below it, so such a window covers everything else on a shared screen."
+            }
+            Rule::NearlyInvisible => {
+                "Fires when an on-screen window is almost fully transparent, by the \
+                 configured transparency rule. Such a window is invisible in a shared \
+                 screen but can still be made opaque at any moment."
+            }
+            Rule::OffScreen => {
+                "Fires when a window lies well clear of every display, where it can be \
+                 kept running without ever being seen. Windows straddling a display edge \
+                 do not count; without display information only extreme coordinates do."
+            }
+            Rule::ZeroSize => {
+                "Fires when an on-screen window with a title has no width or no height. \
+                 A named content window has no reason to be empty."
+            }
+            Rule::SubPixelSize => {
+                "Fires when a titled window that is not a system window is less than a \
+                 pixel wide or high, so it is on screen without anything being visible."
+            }
+            Rule::DeepLayer => {
+                "Fires when a window that is not part of the desktop is on a layer below \
+                 -100000, far beneath anything a screen shows."
+            }
+            Rule::StealthTitle => {
+                "Fires when a window's title contains \"hidden\", \"invisible\" or \
+                 \"stealth\"."
+            }
+            Rule::AllSpaces => {
+                "Fires when a flagged window is shown on every Space, so it stays in view \
+                 whichever desktop the user switches to. Overlays use this to follow a \
+                 full-screen meeting."
+            }
+            Rule::Stationary => {
+                "Fires when a flagged window is left in place by Mission Control instead \
+                 of being shuffled away with the other windows, as overlays are."
+            }
+            Rule::CluelyLikeOverlay => {
+                "Fires when a window no signature matches is hidden from screen capture, \
+                 floats above normal windows and is large enough to show content while a \
+                 meeting window is on screen. No ordinary app needs all of that at once. \
+                 Meetings are only seen with Screen Recording permission."
+            }
+            Rule::RenamedTool => {
+                "Fires when a word of an owner name is within a few edits of a known \
+                 tool's name without matching it, such as \"Cluey\". It is only a hint, \
+                 so the finding has low confidence."
+            }
+            Rule::ActiveCapture => {
+                "Fires while monitoring when a Cluely process with a hidden window keeps \
+                 using as much CPU as screen capture takes, so it is recording rather \
+                 than waiting."
+            }
+        }
+    }
+
+    /// The rule with this identifier
+    pub fn from_id(id: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.id() == id)
+    }
+
+    /// The rules whose bits are set in `mask`, in bit order
+    pub fn in_mask(mask: u32) -> Vec<Rule> {
+        Rule::ALL
+            .into_iter()
+            .filter(|rule| mask & rule.bit() != 0)
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
@@ -0,0 +1,665 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::behavior::{self, OverlayEvidence};
+use crate::content::WindowContent;
+use crate::display::Display;
+use crate::{
+    fuzzy, normalize, placement, DetectorConfig, Finding, Rule, TransparencyRule, WindowLayerClass,
+};
+
+// How far out a window must sit to count as parked off screen when no
+// display geometry is known, e.g. for windows from a recorded fixture
+const FALLBACK_OFF_SCREEN_POSITION: f64 = 50000.0;
+
+/// Window position and size in global display coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Every property the generic scanner reads for a window, of any owner
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScannedWindow {
+    pub name: String, // "<No Title>" when the title is empty or hidden
+    pub owner: String,
+    pub owner_pid: i32,
+    pub window_id: i32,
+    pub layer: i32,
+    pub is_onscreen: bool,
+    pub alpha: f64,
+    pub is_hidden: bool, // Off-screen, nearly transparent or below the desktop
+    pub sharing_state: i32,
+    pub store_type: i32,
+    pub backing_type: i32,
+    pub bounds: Option<WindowBounds>,
+    /// Name of the watched app (`DetectorConfig::watch_target`) that owns
+    /// the window; set by the backend
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub target: Option<String>,
+}
+
+/// Window properties that can keep a window out of screen recordings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum CaptureSignal {
+    SharingDisabled, // sharing_state 0
+    NegativeLayer,   // Below the normal window layer
+    LowAlpha,        // Mostly transparent
+}
+
+impl CaptureSignal {
+    pub fn label(self) -> &'static str {
+        match self {
+            CaptureSignal::SharingDisabled => "sharing-disabled",
+            CaptureSignal::NegativeLayer => "negative-layer",
+            CaptureSignal::LowAlpha => "low-alpha",
+        }
+    }
+}
+
+/// A process whose windows show capture signals
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspiciousProcess {
+    pub pid: i32,
+    pub name: String,
+    pub is_cluely: bool,
+    pub signals: Vec<CaptureSignal>, // Every signal seen across its windows
+    pub windows: Vec<ScannedWindow>, // Only the windows that show a signal
+}
+
+/// How sure a finding is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Confidence {
+    Low,  // Only a name close to a known tool's
+    High, // An evasion technique seen on the window itself
+}
+
+impl Confidence {
+    pub fn label(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// An app with windows excluded from screen capture
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureEvasionApp {
+    pub pid: i32,
+    pub name: String,
+    pub is_cluely: bool,
+    pub window_ids: Vec<i32>,
+}
+
+/// Every non-system app hiding windows from screen capture, whether or not
+/// it is Cluely
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaptureEvasionReport {
+    pub apps: Vec<CaptureEvasionApp>, // Sorted by pid
+    pub total_windows: usize,         // Windows scanned
+}
+
+impl CaptureEvasionReport {
+    pub fn evasion_detected(&self) -> bool {
+        !self.apps.is_empty()
+    }
+
+    /// Capture-excluded windows across all apps
+    pub fn window_count(&self) -> usize {
+        self.apps.iter().map(|app| app.window_ids.len()).sum()
+    }
+}
+
+/// A window that uses at least one screen sharing evasion technique
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowFinding {
+    pub window: ScannedWindow,
+    pub is_cluely: bool,
+    pub techniques: Vec<Finding>,
+    pub confidence: Confidence,
+    /// Set when the window behaves like Cluely's overlay, whatever its name
+    pub cluely_like: Option<OverlayEvidence>,
+    /// What the window shows, when the backend was asked to read it
+    /// (`DetectorConfig::inspect_content`) and could
+    pub content: Option<WindowContent>,
+    /// Shown on every Space (canJoinAllSpaces), so it stays in view
+    /// whichever desktop the user switches to
+    pub follows_user_across_spaces: bool,
+    /// Left in place by Mission Control (stationary) rather than shuffled
+    /// away with the other windows
+    pub stationary: bool,
+}
+
+impl WindowFinding {
+    /// Record how the window behaves across Spaces, as read by the backend.
+    /// Both are strong overlay indicators, so each becomes a technique.
+    pub fn set_space_behavior(&mut self, follows_user_across_spaces: bool, stationary: bool) {
+        self.follows_user_across_spaces = follows_user_across_spaces;
+        self.stationary = stationary;
+        if follows_user_across_spaces {
+            self.techniques.push(Finding::new(
+                Rule::AllSpaces,
+                "Joins every Space, following the user across desktops",
+            ));
+        }
+        if stationary {
+            self.techniques.push(Finding::new(
+                Rule::Stationary,
+                "Stays on screen above Mission Control",
+            ));
+        }
+    }
+}
+
+/// Result of scanning every window on the system
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvasionScan {
+    pub windows: Vec<ScannedWindow>,
+    pub findings: Vec<WindowFinding>,
+    pub system_analysis: Vec<String>, // Patterns across the whole window set
+}
+
+impl EvasionScan {
+    pub fn evasion_detected(&self) -> bool {
+        !self.findings.is_empty()
+    }
+
+    pub fn cluely_detected(&self) -> bool {
+        self.findings.iter().any(|finding| finding.is_cluely)
+    }
+}
+
+impl ScannedWindow {
+    fn has_title(&self) -> bool {
+        !self.name.is_empty() && self.name != "<No Title>"
+    }
+
+    /// Screen sharing evasion techniques this window uses. Off-screen
+    /// positions are judged against `displays`; without any, only extreme
+    /// coordinates count. `transparency` decides which faint windows count
+    /// as cloaked.
+    #[allow(clippy::collapsible_if)]
+    pub fn evasion_techniques(
+        &self,
+        displays: &[Display],
+        transparency: &TransparencyRule,
+    ) -> Vec<Finding> {
+        let mut evasion_techniques = Vec::new();
+
+        // Special handling for Cluely - it's inherently designed for monitoring/evasion
+        if self.is_cluely_related() {
+            evasion_techniques.push(
+                Finding::new(
+                    Rule::CluelySignature,
+                    "Cluely employee monitoring software detected",
+                )
+                .with("owner", self.owner.as_str()),
+            );
+            evasion_techniques.extend(self.tool_techniques("Cluely", transparency));
+            return evasion_techniques;
+        }
+        // Apps the user watches for get the same treatment
+        if let Some(target) = &self.target {
+            evasion_techniques.push(
+                Finding::new(Rule::WatchedApp, format!("Watched app {} detected", target))
+                    .with("app", target.as_str()),
+            );
+            evasion_techniques.extend(self.tool_techniques(target, transparency));
+            return evasion_techniques;
+        }
+
+        // For non-Cluely processes, use conservative detection
+        if let Some(bounds) = self.bounds {
+            // 1. Off-screen positioning trick (common evasion technique)
+            // Only flag windows parked well clear of every display, not ones
+            // straddling a display edge
+            let parked_off_screen = match placement::placement(&bounds, displays) {
+                Some(placement) => placement.is_parked_off_screen(displays),
+                None => {
+                    bounds.x.abs() > FALLBACK_OFF_SCREEN_POSITION
+                        || bounds.y.abs() > FALLBACK_OFF_SCREEN_POSITION
+                }
+            };
+            if parked_off_screen {
+                evasion_techniques.push(
+                    Finding::new(Rule::OffScreen, "Extreme off-screen positioning detected")
+                        .with("x", bounds.x)
+                        .with("y", bounds.y),
+                );
+            }
+
+            // 2. Zero-dimension windows that should have content
+            if (bounds.width == 0.0 || bounds.height == 0.0) && self.is_onscreen && self.has_title()
+            {
+                evasion_techniques.push(
+                    Finding::new(Rule::ZeroSize, "Named window with zero dimensions")
+                        .with("width", bounds.width)
+                        .with("height", bounds.height),
+                );
+            }
+
+            // 3. Sub-pixel dimensions for named windows (suspicious for content windows)
+            if (bounds.width > 0.0 && bounds.width < 1.0)
+                || (bounds.height > 0.0 && bounds.height < 1.0)
+            {
+                if self.has_title() && !self.is_system_window() {
+                    evasion_techniques.push(
+                        Finding::new(
+                            Rule::SubPixelSize,
+                            "Sub-pixel dimensions for content window",
+                        )
+                        .with("width", bounds.width)
+                        .with("height", bounds.height),
+                    );
+                }
+            }
+        }
+
+        // 4. Layer manipulation - only flag extreme cases that are clearly evasive
+        if self.layer < -100000 && !self.is_system_background_window() {
+            evasion_techniques.push(
+                Finding::new(Rule::DeepLayer, "Extremely deep layer positioning")
+                    .with("layer", self.layer),
+            );
+        }
+
+        // 5. Layers above screen capture cover everything sharing apps composite
+        if self.layer_class() == WindowLayerClass::AboveScreenCapture {
+            evasion_techniques.push(
+                Finding::new(
+                    Rule::AboveScreenCapture,
+                    format!("Window layered above screen capture: {}", self.layer),
+                )
+                .with("layer", self.layer),
+            );
+        }
+
+        // 6. Detect windows that are trying to be invisible during screen sharing
+        let name_lower = self.name.to_lowercase();
+        if name_lower.contains("hidden")
+            || name_lower.contains("invisible")
+            || name_lower.contains("stealth")
+        {
+            evasion_techniques.push(
+                Finding::new(Rule::StealthTitle, "Explicitly hidden/stealth window")
+                    .with("title", self.name.as_str()),
+            );
+        }
+
+        // 7. Detect apps that have suspiciously transparent windows
+        if transparency.is_invisible(self.is_onscreen, self.alpha)
+            && (self.has_title() || !transparency.require_title)
+            && self.bounds.is_some_and(|bounds| {
+                bounds.width >= transparency.min_width && bounds.height >= transparency.min_height
+            })
+            && !transparency.allows(&self.owner)
+        {
+            evasion_techniques.push(
+                Finding::new(
+                    Rule::NearlyInvisible,
+                    format!("Nearly invisible content window: alpha {:.3}", self.alpha),
+                )
+                .with("alpha", self.alpha),
+            );
+        }
+
+        evasion_techniques
+    }
+
+    /// Techniques of a known tool's window, each naming `tool`
+    fn tool_techniques(&self, tool: &str, transparency: &TransparencyRule) -> Vec<Finding> {
+        let mut techniques = Vec::new();
+        if self.sharing_state == 0 {
+            techniques.push(
+                Finding::new(
+                    Rule::ScreenCaptureEvasion,
+                    format!("{} window configured to avoid screen capture", tool),
+                )
+                .with("app", tool)
+                .with("sharing_state", self.sharing_state),
+            );
+        }
+        if self.layer > 0 {
+            techniques.push(
+                Finding::new(
+                    Rule::ElevatedLayer,
+                    format!("{} using elevated window layer: {}", tool, self.layer),
+                )
+                .with("app", tool)
+                .with("layer", self.layer),
+            );
+        }
+        if self.layer_class() == WindowLayerClass::AboveScreenCapture {
+            techniques.push(
+                Finding::new(
+                    Rule::AboveScreenCapture,
+                    format!(
+                        "{} window layered above screen capture: {}",
+                        tool, self.layer
+                    ),
+                )
+                .with("app", tool)
+                .with("layer", self.layer),
+            );
+        }
+        if transparency.is_invisible(self.is_onscreen, self.alpha) {
+            techniques.push(
+                Finding::new(
+                    Rule::NearlyInvisible,
+                    format!("{} window nearly invisible: alpha {:.3}", tool, self.alpha),
+                )
+                .with("app", tool)
+                .with("alpha", self.alpha),
+            );
+        }
+        techniques
+    }
+
+    /// Signals that this window may be hidden from screen capture. Much
+    /// broader than `evasion_techniques`; plenty of legitimate windows match.
+    pub fn capture_signals(&self) -> Vec<CaptureSignal> {
+        let mut signals = Vec::new();
+        if self.sharing_state == 0 {
+            signals.push(CaptureSignal::SharingDisabled);
+        }
+        if self.layer < 0 {
+            signals.push(CaptureSignal::NegativeLayer);
+        }
+        if self.alpha < 0.5 {
+            signals.push(CaptureSignal::LowAlpha);
+        }
+        signals
+    }
+
+    /// What the window's layer is used for
+    pub fn layer_class(&self) -> WindowLayerClass {
+        WindowLayerClass::of(self.layer)
+    }
+
+    /// System windows that normally have sharing_state 0
+    pub fn is_system_window(&self) -> bool {
+        // Menu bar and overlay layers, except the ones above screen capture
+        // that no system window needs
+        let system_layer =
+            self.layer >= 20 && self.layer_class() != WindowLayerClass::AboveScreenCapture;
+        self.is_system_process() || self.name == "Menubar" || system_layer
+    }
+
+    /// Windows owned by a macOS system process
+    fn is_system_process(&self) -> bool {
+        let system_processes = [
+            "Window Server",
+            "Dock",
+            "SystemUIServer",
+            "Control Center",
+            "Spotlight",
+            "Notification Center",
+            "loginwindow",
+            "Finder",
+            "TextInputMenuAgent",
+            "Universal Control",
+            "CursorUIViewService",
+            "Open and Save Panel Service",
+            "Accessibility",
+            "Wi-Fi",
+            "Displays",
+            "Wallpaper",
+        ];
+
+        system_processes
+            .iter()
+            .any(|&process| self.owner.contains(process))
+    }
+
+    /// Background/wallpaper windows
+    fn is_system_background_window(&self) -> bool {
+        let at_origin = self
+            .bounds
+            .is_some_and(|bounds| bounds.x == 0.0 && bounds.y == 0.0);
+
+        self.name.contains("Wallpaper")
+            || self.owner == "Dock"
+            || self.owner.contains("Wallpaper")
+            || (self.owner == "Window Server" && self.layer < -1000000)
+            // Finder often manages the desktop background with very deep layers
+            || (self.owner == "Finder" && self.layer < -1000000 && at_origin)
+    }
+
+    /// Whether the window belongs to Cluely, judged by title and owner
+    pub fn is_cluely_related(&self) -> bool {
+        // Folded so lookalike characters can't disguise the names
+        let name_lower = normalize::fold(&self.name);
+        let owner_lower = normalize::fold(&self.owner);
+
+        // Look for actual Cluely processes; the detector's own windows are
+        // left out by the backend, by process rather than by name
+        name_lower.contains("cluely") ||
+        owner_lower.contains("cluely") ||
+        name_lower.contains("clue.ly") ||
+        owner_lower.contains("clue.ly") ||
+        owner_lower.contains("com.cluely") ||
+        owner_lower.contains("io.cluely") ||
+        owner_lower.contains("co.cluely") ||
+        // Be more specific about these generic terms
+        (owner_lower.contains("cluely helper") || owner_lower.contains("cluely agent")) ||
+        // Only flag productivity + clue if it's very specific
+        (name_lower == "productivity monitor" && owner_lower.contains("clue"))
+    }
+}
+
+/// Patterns that only show up across the whole window set
+fn analyze_window_set(windows: &[ScannedWindow]) -> Vec<String> {
+    let mut analysis = Vec::new();
+
+    let total_windows = windows.len();
+    let hidden_count = windows.iter().filter(|w| w.is_hidden).count();
+
+    // Only flag if the hidden ratio is extremely high (macOS systems have many legitimate hidden windows)
+    // Increase threshold to be more conservative
+    if total_windows > 0 && hidden_count as f64 / total_windows as f64 > 0.85 {
+        analysis.push("Extremely high ratio of hidden windows detected".to_string());
+    }
+
+    // Look for coordinated window manipulation, but be more conservative
+    let mut same_owner_groups: HashMap<String, usize> = HashMap::new();
+    for window in windows {
+        if !window.owner.is_empty() && !window.is_system_window() {
+            *same_owner_groups.entry(window.owner.clone()).or_insert(0) += 1;
+        }
+    }
+
+    // Only flag non-system processes with very high window counts
+    // Increase threshold to reduce false positives
+    for (owner, count) in same_owner_groups {
+        if count > 20 &&
+           !owner.contains("com.apple") &&
+           !owner.contains("Apple") &&
+           !owner.contains("Messages") && // Messages legitimately creates multiple conversation windows
+           !owner.contains("Chrome")
+        {
+            // Chrome legitimately creates many tab windows
+            analysis.push(format!("Suspicious multi-window pattern from: {owner}"));
+        }
+    }
+
+    analysis
+}
+
+/// Look for evasion techniques in windows that have already been read, on
+/// `displays` (empty if unknown), with the rules `config` sets. System
+/// windows are skipped unless `include_system` is set.
+pub fn analyze_windows(
+    windows: Vec<ScannedWindow>,
+    displays: &[Display],
+    include_system: bool,
+    config: &DetectorConfig,
+) -> EvasionScan {
+    let meeting_app = behavior::active_meeting(&windows);
+    let findings = windows
+        .iter()
+        .filter_map(|window| {
+            let is_cluely = window.is_cluely_related();
+            let known = is_cluely || window.target.is_some();
+            // Judged before system windows are skipped, since an overlay
+            // may float at a system window layer
+            let cluely_like = meeting_app
+                .filter(|_| !known && !window.is_system_process())
+                .and_then(|meeting_app| behavior::overlay_evidence(window, meeting_app));
+            if !include_system && window.is_system_window() && cluely_like.is_none() {
+                return None;
+            }
+
+            let mut techniques = window.evasion_techniques(displays, &config.transparency);
+            if let Some(evidence) = &cluely_like {
+                techniques.push(evidence.finding());
+            }
+            let confidence = if techniques.is_empty() {
+                Confidence::Low
+            } else {
+                Confidence::High
+            };
+            let near_miss = config
+                .fuzzy_max_distance
+                .filter(|_| !known)
+                .and_then(|max_distance| fuzzy::near_miss(&window.owner, max_distance));
+            if let Some(near_miss) = near_miss {
+                techniques.push(
+                    Finding::new(
+                        Rule::RenamedTool,
+                        format!(
+                            "Possible renamed instance of {}: \"{}\" is {} edit(s) away",
+                            near_miss.tool, near_miss.word, near_miss.distance
+                        ),
+                    )
+                    .with("tool", near_miss.tool)
+                    .with("word", near_miss.word)
+                    .with("distance", near_miss.distance),
+                );
+            }
+
+            if !techniques.is_empty() {
+                tracing::trace!(
+                    window_id = window.window_id,
+                    owner = %window.owner,
+                    rules = ?techniques.iter().map(|finding| finding.rule_id.id()).collect::<Vec<_>>(),
+                    confidence = confidence.label(),
+                    "evasion finding"
+                );
+            }
+            (!techniques.is_empty()).then(|| WindowFinding {
+                window: window.clone(),
+                is_cluely,
+                techniques,
+                confidence,
+                cluely_like,
+                content: None,
+                follows_user_across_spaces: false,
+                stationary: false,
+            })
+        })
+        .collect::<Vec<_>>();
+    let system_analysis = analyze_window_set(&windows);
+    tracing::debug!(
+        windows = windows.len(),
+        findings = findings.len(),
+        meeting = meeting_app,
+        "evasion scan complete"
+    );
+
+    EvasionScan {
+        windows,
+        findings,
+        system_analysis,
+    }
+}
+
+/// Group the windows that show capture signals by owning process, sorted by pid.
+/// System windows are skipped unless `include_system` is set.
+pub fn suspicious_processes(
+    windows: Vec<ScannedWindow>,
+    include_system: bool,
+) -> Vec<SuspiciousProcess> {
+    let mut processes: BTreeMap<i32, SuspiciousProcess> = BTreeMap::new();
+
+    for window in windows {
+        if window.owner.is_empty() || (!include_system && window.is_system_window()) {
+            continue;
+        }
+        let signals = window.capture_signals();
+        if signals.is_empty() {
+            continue;
+        }
+
+        let process = processes
+            .entry(window.owner_pid)
+            .or_insert_with(|| SuspiciousProcess {
+                pid: window.owner_pid,
+                name: window.owner.clone(),
+                is_cluely: false,
+                signals: Vec::new(),
+                windows: Vec::new(),
+            });
+        process.is_cluely |= window.is_cluely_related();
+        process.signals.extend(signals);
+        process.windows.push(window);
+    }
+
+    processes
+        .into_values()
+        .map(|mut process| {
+            process.signals.sort();
+            process.signals.dedup();
+            process
+        })
+        .collect()
+}
+
+/// Group the windows with sharing_state 0 by owning process, skipping
+/// system processes, which hide plenty of windows legitimately
+pub fn capture_evasion(windows: &[ScannedWindow]) -> CaptureEvasionReport {
+    let mut apps: BTreeMap<i32, CaptureEvasionApp> = BTreeMap::new();
+
+    for window in windows {
+        if window.sharing_state != 0 || window.owner.is_empty() || window.is_system_process() {
+            continue;
+        }
+
+        let app = apps
+            .entry(window.owner_pid)
+            .or_insert_with(|| CaptureEvasionApp {
+                pid: window.owner_pid,
+                name: window.owner.clone(),
+                is_cluely: false,
+                window_ids: Vec::new(),
+            });
+        app.is_cluely |= window.is_cluely_related();
+        app.window_ids.push(window.window_id);
+    }
+
+    tracing::debug!(
+        windows = windows.len(),
+        apps = apps.len(),
+        "capture evasion scan complete"
+    );
+    CaptureEvasionReport {
+        apps: apps.into_values().collect(),
+        total_windows: windows.len(),
+    }
+}
@@ -0,0 +1,244 @@
+//! Signatures that identify Cluely by owner name, window title or bundle ID.
+//!
+//! A pattern that starts with `^` is an anchored regular expression; any
+//! other pattern matches as a substring. Both ignore case, so
+//! `^cluely( helper| agent)?$` matches "Cluely Helper" but not "Cluely Notes
+//! Exporter". Regexes are compiled when a signature is built, never per
+//! window.
+//!
+//! Substrings are compared against folded text (`normalize::fold`), with
+//! lookalikes such as a Cyrillic `е` or the digit `1` read as the Latin
+//! letter they imitate. Regexes are compared against `normalize::lower`
+//! text, without that mapping, so that digits and character classes mean
+//! what they say: `^cluely[0-9]+$` matches "Cluely2024". A regex that
+//! should also catch lookalikes has to spell them out.
+
+use std::sync::OnceLock;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::normalize;
+
+/// Text that one field of a signature must match
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    /// Text containing this substring once both are folded
+    Contains(String),
+    /// Text matching this anchored expression, without folding lookalikes
+    Regex(Regex),
+}
+
+impl Pattern {
+    /// Compile `pattern`: an anchored regex if it starts with `^`, otherwise
+    /// a substring
+    pub fn parse(pattern: &str) -> Result<Self, SignatureError> {
+        if !pattern.starts_with('^') {
+            return Ok(Pattern::Contains(normalize::fold(pattern)));
+        }
+
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Pattern::Regex)
+            .map_err(|e| SignatureError {
+                pattern: pattern.to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Whether `lower` text (see `normalize::lower`) matches
+    pub fn matches(&self, lower: &str) -> bool {
+        match self {
+            Pattern::Contains(needle) => {
+                normalize::unconfuse_lower(lower).contains(needle.as_str())
+            }
+            Pattern::Regex(regex) => regex.is_match(lower),
+        }
+    }
+}
+
+/// A pattern that failed to compile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid pattern `{}`: {}", self.pattern, self.message)
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Identifies a Cluely window. Every pattern given must match; fields
+/// without a pattern are not checked.
+#[derive(Debug, Clone)]
+pub struct Signature {
+    pub name: String,
+    pub owner: Option<Pattern>,
+    pub title: Option<Pattern>,
+    pub bundle_id: Option<Pattern>,
+}
+
+impl Signature {
+    /// A signature that matches nothing until patterns are added
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            owner: None,
+            title: None,
+            bundle_id: None,
+        }
+    }
+
+    /// Match the name of the process that owns the window
+    pub fn owner(mut self, pattern: &str) -> Result<Self, SignatureError> {
+        self.owner = Some(Pattern::parse(pattern)?);
+        Ok(self)
+    }
+
+    /// Match the window title
+    pub fn title(mut self, pattern: &str) -> Result<Self, SignatureError> {
+        self.title = Some(Pattern::parse(pattern)?);
+        Ok(self)
+    }
+
+    /// Match the bundle ID of the app that owns the window
+    pub fn bundle_id(mut self, pattern: &str) -> Result<Self, SignatureError> {
+        self.bundle_id = Some(Pattern::parse(pattern)?);
+        Ok(self)
+    }
+
+    fn matches(&self, subject: &Subject) -> bool {
+        let fields = [
+            (&self.owner, Some(subject.owner.as_str())),
+            (&self.title, subject.title.as_deref()),
+            (&self.bundle_id, subject.bundle_id.as_deref()),
+        ];
+
+        let mut checked = false;
+        for (pattern, text) in fields {
+            let Some(pattern) = pattern else {
+                continue;
+            };
+            if !text.is_some_and(|text| pattern.matches(text)) {
+                return false;
+            }
+            checked = true;
+        }
+        checked
+    }
+}
+
+/// The signatures a scan matches windows against. The default set holds the
+/// built-in Cluely signatures.
+#[derive(Debug, Clone)]
+pub struct SignatureSet {
+    signatures: Vec<Signature>,
+    version: u64,
+}
+
+impl SignatureSet {
+    /// The built-in signatures: any owner name containing "cluely" or
+    /// "clue.ly"
+    pub fn builtin() -> Self {
+        Self {
+            signatures: vec![
+                Signature {
+                    owner: Some(Pattern::Contains("cluely".to_string())),
+                    ..Signature::new("Cluely")
+                },
+                Signature {
+                    owner: Some(Pattern::Contains("clue.ly".to_string())),
+                    ..Signature::new("Cluely")
+                },
+            ],
+            version: 0,
+        }
+    }
+
+    /// A set holding only `signatures`, without the built-in ones
+    pub fn new(signatures: Vec<Signature>) -> Self {
+        Self {
+            signatures,
+            version: 0,
+        }
+    }
+
+    pub fn push(&mut self, signature: Signature) {
+        self.signatures.push(signature);
+    }
+
+    /// Version of the signature bundle these signatures came from; 0 if
+    /// they did not come from one
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Record the version of the signature bundle added to this set, so
+    /// results name it
+    pub fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
+    pub fn signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    /// The first signature matching `subject`. Names and titles are
+    /// Cluely's to choose, so nothing is exempt by name here; the backend
+    /// leaves out the detector's own processes by PID and executable.
+    pub fn matching(&self, subject: &Subject) -> Option<&Signature> {
+        self.signatures
+            .iter()
+            .find(|signature| signature.matches(subject))
+    }
+
+    /// Whether matching needs window titles
+    pub fn uses_titles(&self) -> bool {
+        self.signatures
+            .iter()
+            .any(|signature| signature.title.is_some())
+    }
+
+    /// Whether matching needs bundle IDs
+    pub fn uses_bundle_ids(&self) -> bool {
+        self.signatures
+            .iter()
+            .any(|signature| signature.bundle_id.is_some())
+    }
+}
+
+impl Default for SignatureSet {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// The built-in signatures, compiled once for the life of the process
+pub(crate) fn builtin() -> &'static SignatureSet {
+    static BUILTIN: OnceLock<SignatureSet> = OnceLock::new();
+    BUILTIN.get_or_init(SignatureSet::builtin)
+}
+
+/// The text a signature is matched against (see `normalize::lower`).
+/// A field that was not read is `None` and fails any pattern for it.
+#[derive(Debug, Clone, Default)]
+pub struct Subject {
+    pub owner: String,
+    pub title: Option<String>,
+    pub bundle_id: Option<String>,
+}
+
+impl Subject {
+    /// A subject with only an owner name
+    pub fn owner(owner: &str) -> Self {
+        Self {
+            owner: normalize::lower(owner),
+            title: None,
+            bundle_id: None,
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Apps watched for besides Cluely.
+//!
+//! A school or employer may have its own app of concern, such as a
+//! particular proctoring or overlay tool. A `TargetSpec` names it by owner
+//! name pattern, bundle ID or signing Team ID; once added with
+//! `DetectorConfig::watch_target`, its windows go through the same analysis
+//! as Cluely's and are counted by the Cluely scans too.
+
+use crate::normalize;
+use crate::signature::{Pattern, SignatureError};
+
+/// An app to watch for, matched by any of its patterns or IDs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TargetSpec {
+    /// Shown in findings, e.g. "ExamShield"
+    pub name: String,
+    /// Owner (process) name patterns: an anchored regex if it starts with
+    /// `^`, otherwise a substring, compared as signature patterns are
+    pub name_patterns: Vec<String>,
+    /// Bundle IDs, compared case-insensitively; apps nested inside one
+    /// (`<id>.helper`) match too
+    pub bundle_ids: Vec<String>,
+    /// Team IDs of the developer certificate the app is signed with
+    pub team_ids: Vec<String>,
+}
+
+impl TargetSpec {
+    /// A target that matches nothing until patterns or IDs are added
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A `TargetSpec` with its patterns compiled
+#[derive(Debug, Clone)]
+pub struct WatchTarget {
+    pub name: String,
+    name_patterns: Vec<Pattern>,
+    bundle_ids: Vec<String>,
+    team_ids: Vec<String>,
+}
+
+impl WatchTarget {
+    /// Compile `spec`'s patterns
+    pub fn new(spec: TargetSpec) -> Result<Self, SignatureError> {
+        let name_patterns = spec
+            .name_patterns
+            .iter()
+            .map(|pattern| Pattern::parse(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            name: spec.name,
+            name_patterns,
+            bundle_ids: spec
+                .bundle_ids
+                .iter()
+                .map(|id| id.trim().to_ascii_lowercase())
+                .collect(),
+            team_ids: spec
+                .team_ids
+                .iter()
+                .map(|id| id.trim().to_string())
+                .collect(),
+        })
+    }
+
+    /// Whether matching needs bundle IDs
+    pub fn uses_bundle_ids(&self) -> bool {
+        !self.bundle_ids.is_empty()
+    }
+
+    /// Whether matching needs Team IDs
+    pub fn uses_team_ids(&self) -> bool {
+        !self.team_ids.is_empty()
+    }
+
+    /// Whether an app is this target, given whatever was read of it
+    pub fn matches(&self, owner: &str, bundle_id: Option<&str>, team_id: Option<&str>) -> bool {
+        let owner = normalize::lower(owner);
+        self.name_patterns
+            .iter()
+            .any(|pattern| pattern.matches(&owner))
+            || bundle_id.is_some_and(|bundle_id| {
+                let bundle_id = bundle_id.to_ascii_lowercase();
+                self.bundle_ids.iter().any(|target| {
+                    bundle_id == *target
+                        || bundle_id
+                            .strip_prefix(target.as_str())
+                            .is_some_and(|rest| rest.starts_with('.'))
+                })
+            })
+            || team_id.is_some_and(|team_id| self.team_ids.iter().any(|target| target == team_id))
+    }
+}
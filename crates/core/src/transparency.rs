@@ -0,0 +1,53 @@
+//! Transparency cloaking.
+//!
+//! A window faded to near-zero opacity stays on screen and is still
+//! captured, so it passes every sharing-state check, yet nobody watching
+//! the share can see it. Overlays fade themselves out while a share runs
+//! and back in for the person presenting.
+
+use crate::normalize;
+
+/// When a nearly invisible window counts as cloaked
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TransparencyRule {
+    /// Windows at or below this opacity (0.0 to 1.0) are invisible
+    pub max_alpha: f64,
+    /// Smallest window that can show content; smaller ones are tracking
+    /// areas and other invisible helpers
+    pub min_width: f64,
+    pub min_height: f64,
+    /// Only flag windows with a title, which content windows have
+    pub require_title: bool,
+    /// Owners never flagged, compared case-insensitively with the whole
+    /// owner name. Cluely's windows are flagged regardless.
+    pub allowed_owners: Vec<String>,
+}
+
+impl Default for TransparencyRule {
+    fn default() -> Self {
+        Self {
+            max_alpha: 0.01,
+            min_width: 0.0,
+            min_height: 0.0,
+            require_title: true,
+            allowed_owners: Vec::new(),
+        }
+    }
+}
+
+impl TransparencyRule {
+    /// Whether an on-screen window at `alpha` is too faint to see
+    pub fn is_invisible(&self, is_onscreen: bool, alpha: f64) -> bool {
+        is_onscreen && alpha <= self.max_alpha
+    }
+
+    /// Whether `owner` is on the allow-list
+    pub fn allows(&self, owner: &str) -> bool {
+        let owner = normalize::fold(owner);
+        self.allowed_owners
+            .iter()
+            .any(|allowed| normalize::fold(allowed) == owner)
+    }
+}
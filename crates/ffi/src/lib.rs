@@ -0,0 +1,428 @@
+//! The C API for no-cluely, linked into `libno_cluely_driver` by its `ffi`
+//! feature.
+//!
+//! The plain C functions are stateless: every call builds its configuration
+//! from scratch and forgets what it saw. A monitor handle keeps custom
+//! signatures and the incremental detector's window cache between polls.
+//! Each handle is guarded by a mutex, so it may be polled from any thread.
+//!
+//! No function here lets a panic unwind into its caller. Each body runs
+//! under `guard`, which turns a panic into the function's fallback value:
+//! an empty result, a null pointer or `ClueLyStatus::Panicked`. The `try_*`
+//! functions also say why a scan failed, where the older functions can
+//! only return an empty result.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use no_cluely_core::{ClueLyDetectionResult, DetectionError, DetectorConfig, Signature};
+use no_cluely_macos::{
+    cluely_report_rust, detect_cluely_rust, try_cluely_report_rust, try_detect_cluely_rust,
+    verify_self_integrity_rust, Monitor, SignatureStatus,
+};
+
+/// Outcome of a `try_*` C function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClueLyStatus {
+    Ok = 0,
+    /// The window server returned no window list (no GUI session)
+    WindowListUnavailable = 1,
+    /// A required pointer was null, or a pattern did not compile
+    InvalidArgument = 2,
+    /// The library hit a bug; the call was abandoned
+    Panicked = 3,
+    /// This platform has no backend yet
+    PlatformUnsupported = 4,
+    /// The scan needs root
+    PermissionDenied = 5,
+}
+
+impl From<DetectionError> for ClueLyStatus {
+    fn from(error: DetectionError) -> Self {
+        match error {
+            DetectionError::WindowListUnavailable => ClueLyStatus::WindowListUnavailable,
+            DetectionError::PlatformUnsupported => ClueLyStatus::PlatformUnsupported,
+            DetectionError::PermissionDenied => ClueLyStatus::PermissionDenied,
+        }
+    }
+}
+
+/// Run `body`, returning `fallback` instead if it panics
+fn guard<T>(function: &str, fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| {
+        tracing::error!(function, "panic caught at the C boundary");
+        fallback
+    })
+}
+
+/// `text` as a C string, dropping any NUL characters it contains
+fn to_c_string(text: String) -> CString {
+    let mut bytes = text.into_bytes();
+    bytes.retain(|&byte| byte != 0);
+    CString::new(bytes).unwrap_or_default()
+}
+
+/// C API - Main detection function (for compatibility)
+///
+/// # Safety
+/// This function is safe to call from Swift/C
+#[no_mangle]
+pub extern "C" fn detect_cluely() -> ClueLyDetectionResult {
+    guard(
+        "detect_cluely",
+        ClueLyDetectionResult::default(),
+        detect_cluely_rust,
+    )
+}
+
+/// C API - Detection that reports why a scan failed. Writes the result to
+/// `out` only on `ClueLyStatus::Ok`.
+///
+/// # Safety
+/// `out` must be null or point to writable memory for a
+/// `ClueLyDetectionResult`. A null `out` returns `InvalidArgument`.
+#[no_mangle]
+pub unsafe extern "C" fn try_detect_cluely(out: *mut ClueLyDetectionResult) -> ClueLyStatus {
+    guard("try_detect_cluely", ClueLyStatus::Panicked, || {
+        if out.is_null() {
+            return ClueLyStatus::InvalidArgument;
+        }
+        match try_detect_cluely_rust() {
+            Ok(result) => {
+                out.write(result);
+                ClueLyStatus::Ok
+            }
+            Err(e) => e.into(),
+        }
+    })
+}
+
+/// C API - Simple boolean check (for compatibility)
+///
+/// # Safety
+/// This function is safe to call from Swift/C
+#[no_mangle]
+pub extern "C" fn is_cluely_running() -> c_int {
+    guard("is_cluely_running", 0, || {
+        if detect_cluely_rust().is_detected {
+            1
+        } else {
+            0
+        }
+    })
+}
+
+/// C API - Get window count (for compatibility)
+///
+/// # Safety
+/// This function is safe to call from Swift/C
+#[no_mangle]
+pub extern "C" fn get_cluely_window_count() -> u32 {
+    guard("get_cluely_window_count", 0, || {
+        detect_cluely_rust().window_count
+    })
+}
+
+/// Generate a detailed text report of Cluely detection
+/// Returns a pointer to a C string that must be freed with free_cluely_report,
+/// or null if the report could not be built
+///
+/// # Safety
+/// This function is safe to call from Swift/C
+/// The returned string must be freed with free_cluely_report
+#[no_mangle]
+pub extern "C" fn get_cluely_report() -> *mut c_char {
+    guard("get_cluely_report", std::ptr::null_mut(), || {
+        to_c_string(cluely_report_rust()).into_raw()
+    })
+}
+
+/// C API - Report that says why a scan failed. On `ClueLyStatus::Ok`,
+/// writes a string to `out` that must be freed with free_cluely_report;
+/// otherwise writes null.
+///
+/// # Safety
+/// `out` must be null or point to writable memory for a pointer. A null
+/// `out` returns `InvalidArgument`.
+#[no_mangle]
+pub unsafe extern "C" fn try_get_cluely_report(out: *mut *mut c_char) -> ClueLyStatus {
+    if out.is_null() {
+        return ClueLyStatus::InvalidArgument;
+    }
+    out.write(std::ptr::null_mut());
+    guard(
+        "try_get_cluely_report",
+        ClueLyStatus::Panicked,
+        || match try_cluely_report_rust() {
+            Ok(report) => {
+                out.write(to_c_string(report).into_raw());
+                ClueLyStatus::Ok
+            }
+            Err(e) => e.into(),
+        },
+    )
+}
+
+/// Free memory allocated by get_cluely_report
+///
+/// # Safety
+/// This function is safe to call from Swift/C
+/// Only call this with pointers returned by get_cluely_report
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn free_cluely_report(ptr: *mut c_char) {
+    guard("free_cluely_report", (), || {
+        if !ptr.is_null() {
+            unsafe {
+                let _ = CString::from_raw(ptr);
+            }
+        }
+    })
+}
+
+/// How the detector's binary matches its code signature
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClueLySignatureStatus {
+    Valid = 0,
+    Unsigned = 1,
+    /// The file no longer matches its signature
+    Invalid = 2,
+    /// The signature could not be checked
+    Unchecked = 3,
+}
+
+/// Self-integrity check written by `verify_self_integrity`
+#[repr(C)]
+pub struct ClueLyIntegrityResult {
+    /// No sign that the detector was patched or replaced
+    pub is_intact: bool,
+    pub signature: ClueLySignatureStatus,
+    /// 1 if the running code matches its signature, 0 if not, -1 if unsigned
+    pub running_code_valid: i8,
+    /// The file changed since the first check in this process
+    pub modified: bool,
+    /// SHA-256 of the binary as NUL-terminated hex; empty if unreadable
+    pub sha256: [c_char; 65],
+}
+
+/// C API - Check the detector's own binary for tampering: its code
+/// signature, its hash against the first check in this process, and the
+/// running code. Call it once at startup so later calls can tell whether
+/// the file was replaced. Writes to `out` only on `ClueLyStatus::Ok`.
+///
+/// # Safety
+/// `out` must be null or point to writable memory for a
+/// `ClueLyIntegrityResult`. A null `out` returns `InvalidArgument`.
+#[no_mangle]
+pub unsafe extern "C" fn verify_self_integrity(out: *mut ClueLyIntegrityResult) -> ClueLyStatus {
+    guard("verify_self_integrity", ClueLyStatus::Panicked, || {
+        if out.is_null() {
+            return ClueLyStatus::InvalidArgument;
+        }
+        let report = match verify_self_integrity_rust() {
+            Ok(report) => report,
+            Err(e) => return e.into(),
+        };
+        let mut sha256 = [0 as c_char; 65];
+        for (slot, byte) in sha256
+            .iter_mut()
+            .zip(report.sha256.as_deref().unwrap_or_default().bytes())
+        {
+            *slot = byte as c_char;
+        }
+        out.write(ClueLyIntegrityResult {
+            is_intact: report.is_intact(),
+            signature: match report.signature {
+                SignatureStatus::Valid => ClueLySignatureStatus::Valid,
+                SignatureStatus::Unsigned => ClueLySignatureStatus::Unsigned,
+                SignatureStatus::Invalid => ClueLySignatureStatus::Invalid,
+                SignatureStatus::Unchecked => ClueLySignatureStatus::Unchecked,
+            },
+            running_code_valid: report.running_code_valid.map_or(-1, i8::from),
+            modified: report.modified,
+            sha256,
+        });
+        ClueLyStatus::Ok
+    })
+}
+
+/// Monitor settings passed to `cluely_monitor_create`
+#[repr(C)]
+pub struct ClueLyMonitorConfig {
+    /// Only scan windows currently on screen
+    pub on_screen_only: bool,
+    /// Leave out the desktop picture and desktop icon windows
+    pub exclude_desktop_elements: bool,
+    /// Only parse windows that are new since the previous poll
+    pub incremental: bool,
+    /// Owner name patterns added to the built-in signatures; a pattern
+    /// starting with `^` is an anchored regex. May be null if the count is 0.
+    pub owner_patterns: *const *const c_char,
+    pub owner_pattern_count: u32,
+}
+
+/// A monitor owned by C code
+pub struct ClueLyMonitorHandle {
+    monitor: Mutex<Monitor>,
+}
+
+/// Read `config` into detector settings; None if a pattern is null, not
+/// UTF-8 or does not compile
+unsafe fn detector_config(config: &ClueLyMonitorConfig) -> Option<DetectorConfig> {
+    let mut detector = DetectorConfig {
+        on_screen_only: config.on_screen_only,
+        exclude_desktop_elements: config.exclude_desktop_elements,
+        ..Default::default()
+    };
+
+    let patterns = match config.owner_pattern_count {
+        0 => &[][..],
+        count if !config.owner_patterns.is_null() => {
+            std::slice::from_raw_parts(config.owner_patterns, count as usize)
+        }
+        _ => return None,
+    };
+    for (i, &pattern) in patterns.iter().enumerate() {
+        if pattern.is_null() {
+            return None;
+        }
+        let pattern = CStr::from_ptr(pattern).to_str().ok()?;
+        let signature = Signature::new(format!("Custom signature {}", i + 1))
+            .owner(pattern)
+            .map_err(|e| tracing::warn!(error = %e, "invalid owner pattern"))
+            .ok()?;
+        detector.signatures.push(signature);
+    }
+    Some(detector)
+}
+
+/// C API - Create a monitor. A null `config` uses the defaults. Returns
+/// null if a pattern in `config` is invalid.
+///
+/// # Safety
+/// `config` must be null or point to a valid `ClueLyMonitorConfig` whose
+/// `owner_patterns` holds `owner_pattern_count` NUL-terminated strings.
+/// The handle must be freed with `cluely_monitor_destroy`.
+#[no_mangle]
+pub unsafe extern "C" fn cluely_monitor_create(
+    config: *const ClueLyMonitorConfig,
+) -> *mut ClueLyMonitorHandle {
+    guard("cluely_monitor_create", std::ptr::null_mut(), || {
+        let (detector, incremental) = match config.as_ref() {
+            Some(config) => match detector_config(config) {
+                Some(detector) => (detector, config.incremental),
+                None => return std::ptr::null_mut(),
+            },
+            None => (DetectorConfig::default(), false),
+        };
+
+        let monitor = if incremental {
+            Monitor::incremental(detector)
+        } else {
+            Monitor::with_config(detector)
+        };
+        Box::into_raw(Box::new(ClueLyMonitorHandle {
+            monitor: Mutex::new(monitor),
+        }))
+    })
+}
+
+/// C API - Scan with a monitor and return the result. A scan that cannot
+/// see any windows, even after retrying, returns the previous result.
+///
+/// # Safety
+/// `handle` must be null or a handle from `cluely_monitor_create` that has
+/// not been destroyed. A null handle returns an empty result.
+#[no_mangle]
+pub unsafe extern "C" fn cluely_monitor_poll(
+    handle: *const ClueLyMonitorHandle,
+) -> ClueLyDetectionResult {
+    guard(
+        "cluely_monitor_poll",
+        ClueLyDetectionResult::default(),
+        || {
+            let Some(handle) = handle.as_ref() else {
+                return ClueLyDetectionResult::default();
+            };
+            let mut monitor = lock(handle);
+            if let Err(e) = monitor.poll() {
+                tracing::warn!(error = %e, "monitor scan failed");
+            }
+            monitor.result()
+        },
+    )
+}
+
+/// C API - Scan with a monitor, reporting why a scan failed. Writes the
+/// result to `out` only on `ClueLyStatus::Ok`.
+///
+/// # Safety
+/// `handle` must be null or a live handle from `cluely_monitor_create`,
+/// and `out` null or writable memory for a `ClueLyDetectionResult`. A
+/// null `handle` or `out` returns `InvalidArgument`.
+#[no_mangle]
+pub unsafe extern "C" fn cluely_monitor_try_poll(
+    handle: *const ClueLyMonitorHandle,
+    out: *mut ClueLyDetectionResult,
+) -> ClueLyStatus {
+    guard("cluely_monitor_try_poll", ClueLyStatus::Panicked, || {
+        let Some(handle) = handle.as_ref() else {
+            return ClueLyStatus::InvalidArgument;
+        };
+        if out.is_null() {
+            return ClueLyStatus::InvalidArgument;
+        }
+        let mut monitor = lock(handle);
+        match monitor.poll() {
+            Ok(_) => {
+                out.write(monitor.result());
+                ClueLyStatus::Ok
+            }
+            Err(e) => e.into(),
+        }
+    })
+}
+
+/// C API - Polls in a row that failed to scan, 0 after a successful one.
+/// While it is above 0 the monitor's result is stale, not clean.
+///
+/// # Safety
+/// `handle` must be null or a live handle from `cluely_monitor_create`. A
+/// null handle returns 0.
+#[no_mangle]
+pub unsafe extern "C" fn cluely_monitor_consecutive_failures(
+    handle: *const ClueLyMonitorHandle,
+) -> u32 {
+    guard("cluely_monitor_consecutive_failures", 0, || {
+        handle
+            .as_ref()
+            .map_or(0, |handle| lock(handle).consecutive_failures())
+    })
+}
+
+/// Lock a handle's monitor. A panic caught mid-poll poisons the lock, but
+/// the monitor is left in a usable state and the next poll rescans.
+fn lock(handle: &ClueLyMonitorHandle) -> std::sync::MutexGuard<'_, Monitor> {
+    handle
+        .monitor
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// C API - Free a monitor
+///
+/// # Safety
+/// `handle` must be null or a handle from `cluely_monitor_create`, and must
+/// not be used again or be in use on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn cluely_monitor_destroy(handle: *mut ClueLyMonitorHandle) {
+    guard("cluely_monitor_destroy", (), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
+}
@@ -0,0 +1,97 @@
+//! Reading flagged windows' UI through the Accessibility API.
+//!
+//! The window server says where a window is and how it is drawn, but not
+//! what it shows. For windows the scan flags, the owning app's accessibility
+//! tree is walked for button titles and displayed text, which usually name
+//! an overlay that has no title of its own. This needs the Accessibility
+//! permission (Privacy & Security > Accessibility); without it nothing is
+//! read.
+
+use std::collections::VecDeque;
+
+use no_cluely_core::{EvasionScan, WindowContent};
+
+use crate::sys::{self, AXElement, CFKey};
+
+// Attribute names, from AXAttributeConstants.h
+static AX_WINDOWS: CFKey = CFKey::new("AXWindows");
+static AX_CHILDREN: CFKey = CFKey::new("AXChildren");
+static AX_ROLE: CFKey = CFKey::new("AXRole");
+static AX_TITLE: CFKey = CFKey::new("AXTitle");
+static AX_VALUE: CFKey = CFKey::new("AXValue");
+static AX_DESCRIPTION: CFKey = CFKey::new("AXDescription");
+
+/// How long one app may take to answer a single request
+const MESSAGING_TIMEOUT_SECONDS: f32 = 0.5;
+/// Elements read per window before giving up, so huge trees (a browser
+/// window, a long list) stay cheap
+const MAX_ELEMENTS: usize = 400;
+/// Levels below the window that are read
+const MAX_DEPTH: usize = 12;
+
+/// Read what `window_id`, owned by `pid`, shows. None if the app does not
+/// expose the window, or nothing readable was found.
+fn window_content(pid: i32, window_id: u32) -> Option<WindowContent> {
+    let app = AXElement::application(pid, MESSAGING_TIMEOUT_SECONDS)?;
+    let window = app
+        .elements(&AX_WINDOWS)
+        .into_iter()
+        .find(|window| window.window_id() == Some(window_id))?;
+
+    let mut content = WindowContent::default();
+    let mut queue = VecDeque::from([(window, 0)]);
+    let mut read = 0;
+    while let Some((element, depth)) = queue.pop_front() {
+        if read == MAX_ELEMENTS {
+            content.truncated = true;
+            break;
+        }
+        read += 1;
+
+        match element.string(&AX_ROLE).as_deref() {
+            Some("AXButton" | "AXMenuButton" | "AXPopUpButton" | "AXCheckBox") => {
+                // Icon-only buttons carry their label as a description
+                if let Some(title) = element
+                    .string(&AX_TITLE)
+                    .filter(|title| !title.trim().is_empty())
+                    .or_else(|| element.string(&AX_DESCRIPTION))
+                {
+                    content.add_button(&title);
+                }
+            }
+            Some("AXStaticText" | "AXTextField" | "AXTextArea" | "AXHeading") => {
+                if let Some(text) = element.string(&AX_VALUE) {
+                    content.add_text(&text);
+                }
+            }
+            _ => {}
+        }
+
+        if depth < MAX_DEPTH {
+            queue.extend(
+                element
+                    .elements(&AX_CHILDREN)
+                    .into_iter()
+                    .map(|child| (child, depth + 1)),
+            );
+        }
+    }
+
+    tracing::trace!(pid, window_id, elements = read, "read window content");
+    (!content.is_empty()).then_some(content)
+}
+
+/// Fill in `content` for every finding in `scan`
+pub(crate) fn inspect_findings(scan: &mut EvasionScan) {
+    if scan.findings.is_empty() {
+        return;
+    }
+    if !sys::accessibility_access() {
+        tracing::warn!("no Accessibility permission; window content not read");
+        return;
+    }
+    for finding in &mut scan.findings {
+        let window = &finding.window;
+        finding.content = window_content(window.owner_pid, window.window_id as u32);
+    }
+}
@@ -0,0 +1,149 @@
+//! CPU use of processes with windows hidden from capture, sampled from scan
+//! to scan and judged by `ActivityRule`.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use no_cluely_core::{
+    ActivityLevel, ActivityRule, CaptureSignal, DetectionError, DetectorConfig, ScannedWindow,
+};
+
+use crate::{sys, window_list};
+
+/// CPU use of a process with hidden windows, over the last scans
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessActivity {
+    pub pid: i32,
+    pub name: String,
+    pub is_cluely: bool,
+    /// Its windows hidden from screen capture
+    pub hidden_windows: usize,
+    /// Use since the previous scan, in percent of one core
+    pub cpu_percent: f64,
+    pub level: ActivityLevel,
+}
+
+impl ProcessActivity {
+    /// One-line summary for logs and alerts
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (pid {}) is {}: {:.1}% CPU with {} window(s) hidden from capture",
+            self.name,
+            self.pid,
+            self.level.label(),
+            self.cpu_percent,
+            self.hidden_windows
+        )
+    }
+}
+
+/// What was last read of a process
+struct Sampled {
+    cpu_time: Duration,
+    at: Instant,
+    /// CPU use per scan, oldest first
+    samples: VecDeque<f64>,
+}
+
+/// Samples the CPU use of every process with a window hidden from capture,
+/// each time it is polled. A process is reported from its second sighting,
+/// once there is an interval to measure.
+pub struct ActivityMonitor {
+    rule: ActivityRule,
+    processes: HashMap<i32, Sampled>,
+}
+
+impl Default for ActivityMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActivityMonitor {
+    pub fn new() -> Self {
+        Self::with_rule(ActivityRule::default())
+    }
+
+    pub fn with_rule(rule: ActivityRule) -> Self {
+        Self {
+            rule,
+            processes: HashMap::new(),
+        }
+    }
+
+    /// Scan every window and sample the owners of hidden ones
+    pub fn poll(&mut self) -> Result<Vec<ProcessActivity>, DetectionError> {
+        let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+        Ok(self.observe(&windows))
+    }
+
+    /// Sample the owners of hidden windows among `windows`, sorted by pid
+    pub fn observe(&mut self, windows: &[ScannedWindow]) -> Vec<ProcessActivity> {
+        let mut owners: HashMap<i32, Vec<&ScannedWindow>> = HashMap::new();
+        for window in windows.iter().filter(|window| {
+            !window.is_system_window()
+                && window
+                    .capture_signals()
+                    .contains(&CaptureSignal::SharingDisabled)
+        }) {
+            owners.entry(window.owner_pid).or_default().push(window);
+        }
+
+        let now = Instant::now();
+        let history = self.rule.sustained_samples.max(1);
+        let mut activity = Vec::new();
+        let mut sampled = HashMap::with_capacity(owners.len());
+        for (pid, hidden) in owners {
+            // Exited, or another user's
+            let Some(cpu_time) = sys::pid_cpu_time(pid) else {
+                continue;
+            };
+            let mut current = Sampled {
+                cpu_time,
+                at: now,
+                samples: VecDeque::new(),
+            };
+            if let Some(previous) = self.processes.remove(&pid) {
+                let elapsed = now.duration_since(previous.at).as_secs_f64();
+                // A lower total means the pid was reused by a new process
+                if elapsed > 0.0 && cpu_time >= previous.cpu_time {
+                    let used = (cpu_time - previous.cpu_time).as_secs_f64();
+                    current.samples = previous.samples;
+                    current.samples.push_back(used / elapsed * 100.0);
+                    if current.samples.len() > history {
+                        current.samples.pop_front();
+                    }
+
+                    let samples = current.samples.make_contiguous();
+                    activity.push(ProcessActivity {
+                        pid,
+                        name: hidden[0].owner.clone(),
+                        is_cluely: hidden.iter().any(|window| window.is_cluely_related()),
+                        hidden_windows: hidden.len(),
+                        cpu_percent: samples[samples.len() - 1],
+                        level: self.rule.level(samples),
+                    });
+                }
+            }
+            sampled.insert(pid, current);
+        }
+        self.processes = sampled;
+
+        activity.sort_by_key(|process| process.pid);
+        for process in &activity {
+            tracing::debug!(
+                pid = process.pid,
+                cpu_percent = process.cpu_percent,
+                level = process.level.label(),
+                "process activity"
+            );
+        }
+        activity
+    }
+
+    /// Forget every process, so sampling starts over
+    pub fn reset(&mut self) {
+        self.processes.clear();
+    }
+}
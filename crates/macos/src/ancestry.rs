@@ -0,0 +1,107 @@
+//! Where a process came from, judged from its chain of parents.
+//!
+//! Every process descends from launchd (pid 1). One that launchd started
+//! directly is either an app opened through LaunchServices (Finder, the
+//! Dock, `open`, a login item) or a background job from a launch agent or
+//! daemon. launchd tells them apart in the `XPC_SERVICE_NAME` it sets:
+//! `application.<bundle id>...` for apps, the job's label otherwise. A
+//! process with a shell above it came from the command line, and any other
+//! parent started it itself, which is how a helper starts an agent, or how
+//! an agent hides under an innocent-looking parent.
+
+use crate::sys;
+
+const LAUNCHD_PID: i32 = 1;
+/// Parents that mean a process was started by hand
+const COMMAND_LINE_PARENTS: &[&str] = &[
+    "zsh", "bash", "sh", "fish", "dash", "tcsh", "csh", "ksh", "login", "sshd", "Terminal",
+    "iTerm2",
+];
+/// Parents followed before giving up, in case of a loop
+const MAX_PARENTS: usize = 32;
+
+/// A process above another in its parent chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParentProcess {
+    pub pid: i32,
+    pub name: String, // Empty if it could not be read
+}
+
+/// How a process was started
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum LaunchOrigin {
+    /// Opened as an app through LaunchServices
+    App,
+    /// Run by launchd as a background job from a launch agent or daemon
+    Service { label: String },
+    /// Started from a shell or terminal
+    CommandLine,
+    /// Started by another process, named here
+    Spawned { parent: String },
+    /// Started by launchd, but its environment could not be read (another
+    /// user's process)
+    Unknown,
+}
+
+impl LaunchOrigin {
+    /// Human-readable description used in reports
+    pub fn describe(&self) -> String {
+        match self {
+            LaunchOrigin::App => "opened as an app".to_string(),
+            LaunchOrigin::Service { label } => format!("launchd job {}", label),
+            LaunchOrigin::CommandLine => "started from a shell".to_string(),
+            LaunchOrigin::Spawned { parent } => format!("spawned by {}", parent),
+            LaunchOrigin::Unknown => "started by launchd".to_string(),
+        }
+    }
+}
+
+/// `pid`'s parents, nearest first and ending at launchd
+pub(crate) fn parents(pid: i32) -> Vec<ParentProcess> {
+    let mut parents = Vec::new();
+    let mut current = pid;
+    while current != LAUNCHD_PID && parents.len() < MAX_PARENTS {
+        // Exited, or another user's process
+        let Some(parent) = sys::pid_parent(current).filter(|&parent| parent > 0) else {
+            break;
+        };
+        parents.push(ParentProcess {
+            pid: parent,
+            name: sys::pid_name(parent).unwrap_or_default(),
+        });
+        current = parent;
+    }
+    parents
+}
+
+/// How `pid` was started, given its `parents`
+pub(crate) fn launch_origin(pid: i32, parents: &[ParentProcess]) -> LaunchOrigin {
+    let Some(parent) = parents.first() else {
+        return LaunchOrigin::Unknown;
+    };
+    if parent.pid == LAUNCHD_PID {
+        let service = sys::pid_environment(pid).and_then(|environment| {
+            environment
+                .into_iter()
+                .find_map(|entry| entry.strip_prefix("XPC_SERVICE_NAME=").map(str::to_string))
+        });
+        return match service {
+            Some(name) if name.starts_with("application.") => LaunchOrigin::App,
+            // "0" when launchd has no job for it
+            Some(label) if label != "0" => LaunchOrigin::Service { label },
+            _ => LaunchOrigin::Unknown,
+        };
+    }
+    if parents
+        .iter()
+        .any(|parent| COMMAND_LINE_PARENTS.contains(&parent.name.as_str()))
+    {
+        return LaunchOrigin::CommandLine;
+    }
+    LaunchOrigin::Spawned {
+        parent: parent.name.clone(),
+    }
+}
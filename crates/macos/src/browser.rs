@@ -0,0 +1,144 @@
+//! Cluely's web app open in a browser tab.
+//!
+//! Tabs are listed through each browser's AppleScript dictionary with
+//! `osascript`. Only browsers that are already running are asked, since
+//! addressing an app from AppleScript launches it. The first time, macOS
+//! asks the user to allow this process to control the browser (Privacy &
+//! Security > Automation); until then, or if refused, that browser's tabs
+//! are simply left out.
+
+use std::process::Command;
+
+use no_cluely_core::is_cluely_url;
+
+use crate::sys;
+
+/// A browser whose tabs can be listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Browser {
+    Safari,
+    Chrome,
+}
+
+impl Browser {
+    pub const ALL: [Browser; 2] = [Browser::Safari, Browser::Chrome];
+
+    /// Human-readable name used in reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            Browser::Safari => "Safari",
+            Browser::Chrome => "Google Chrome",
+        }
+    }
+
+    /// Name of the browser's main process
+    fn process_name(&self) -> &'static str {
+        match self {
+            Browser::Safari => "Safari",
+            Browser::Chrome => "Google Chrome",
+        }
+    }
+
+    /// AppleScript printing each tab as its URL, a tab character and its
+    /// title, one per line. Safari calls the title `name`.
+    fn script(&self) -> &'static str {
+        match self {
+            Browser::Safari => {
+                r#"tell application id "com.apple.Safari"
+    set output to ""
+    repeat with w in windows
+        repeat with t in tabs of w
+            set output to output & (URL of t as text) & tab & (name of t as text) & linefeed
+        end repeat
+    end repeat
+    return output
+end tell"#
+            }
+            Browser::Chrome => {
+                r#"tell application id "com.google.Chrome"
+    set output to ""
+    repeat with w in windows
+        repeat with t in tabs of w
+            set output to output & (URL of t as text) & tab & (title of t as text) & linefeed
+        end repeat
+    end repeat
+    return output
+end tell"#
+            }
+        }
+    }
+}
+
+/// An open browser tab showing Cluely's web app
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrowserTab {
+    pub browser: Browser,
+    pub url: String,
+    pub title: String,
+}
+
+fn is_running(process_name: &str) -> bool {
+    sys::list_pids()
+        .into_iter()
+        .any(|pid| sys::pid_name(pid).as_deref() == Some(process_name))
+}
+
+/// Every tab open in `browser`, as (URL, title); empty if it could not be asked
+fn list_tabs(browser: Browser) -> Vec<(String, String)> {
+    let output = match Command::new("osascript")
+        .arg("-e")
+        .arg(browser.script())
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            tracing::warn!(%error, "osascript could not be run");
+            return Vec::new();
+        }
+    };
+    if !output.status.success() {
+        // Most often error -1743: not allowed to control the browser
+        tracing::warn!(
+            browser = browser.label(),
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "browser tabs could not be listed"
+        );
+        return Vec::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(url, title)| (url.to_string(), title.to_string()))
+        .collect()
+}
+
+/// Tabs showing Cluely's web app in every running browser
+pub(crate) fn find_cluely_tabs() -> Vec<BrowserTab> {
+    let mut tabs = Vec::new();
+    for browser in Browser::ALL {
+        if !is_running(browser.process_name()) {
+            continue;
+        }
+        let listed = list_tabs(browser);
+        tracing::debug!(
+            browser = browser.label(),
+            tabs = listed.len(),
+            "listed browser tabs"
+        );
+        tabs.extend(
+            listed
+                .into_iter()
+                .filter(|(url, _)| is_cluely_url(url))
+                .map(|(url, title)| BrowserTab {
+                    browser,
+                    url,
+                    title,
+                }),
+        );
+    }
+    tabs
+}
@@ -0,0 +1,67 @@
+use crate::sys;
+use crate::window_list::K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW;
+
+// Constants
+const K_CG_WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING: u32 = 1 << 0;
+const K_CG_WINDOW_IMAGE_BEST_RESOLUTION: u32 = 1 << 3;
+
+/// Pixels of a single captured window
+#[derive(Debug, Clone)]
+pub struct WindowImage {
+    pub window_id: u32,
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>, // Straight (not premultiplied) alpha, row-major
+}
+
+impl WindowImage {
+    /// Whether every pixel is fully transparent. macOS returns such an
+    /// image for windows that exclude themselves from screen capture.
+    pub fn is_blank(&self) -> bool {
+        self.rgba.chunks_exact(4).all(|pixel| pixel[3] == 0)
+    }
+}
+
+/// Convert one 32-bit premultiplied pixel to straight RGBA
+fn unpremultiply(r: u8, g: u8, b: u8, a: u8) -> [u8; 4] {
+    if a == 0 {
+        return [0, 0, 0, 0];
+    }
+    let scale = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+    [scale(r), scale(g), scale(b), a]
+}
+
+/// Capture a single window, including the parts covered by other windows.
+/// Returns None if the window is gone or the image is not 32-bit.
+pub(crate) fn capture_window(window_id: u32) -> Option<WindowImage> {
+    let Some(image) = sys::create_window_image(
+        window_id,
+        K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW,
+        K_CG_WINDOW_IMAGE_BOUNDS_IGNORE_FRAMING | K_CG_WINDOW_IMAGE_BEST_RESOLUTION,
+    ) else {
+        tracing::debug!(window_id, "window could not be captured");
+        return None;
+    };
+
+    let (width, height) = (image.width, image.height);
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in image.bytes().chunks(image.bytes_per_row) {
+        for pixel in row[..width * 4].chunks_exact(4) {
+            // Window images are premultiplied-first: BGRA in memory when
+            // little-endian, ARGB otherwise
+            let converted = if image.little_endian {
+                unpremultiply(pixel[2], pixel[1], pixel[0], pixel[3])
+            } else {
+                unpremultiply(pixel[1], pixel[2], pixel[3], pixel[0])
+            };
+            rgba.extend_from_slice(&converted);
+        }
+    }
+
+    Some(WindowImage {
+        window_id,
+        width: width as u32,
+        height: height as u32,
+        rgba,
+    })
+}
@@ -0,0 +1,93 @@
+//! The window server's own record of each window (`private-cgs` feature).
+//!
+//! `kCGWindowSharingState` in the window list is a snapshot the window
+//! server publishes, and newer ways of keeping a window out of captures do
+//! not always show up in it. The private CGS calls used here ask the window
+//! server directly for a window's sharing state, level, alpha, owner and
+//! tags. They are undocumented, the tag bits come from reverse-engineered
+//! headers, and Apple can change any of it in a release, which is why this
+//! is off by default. Every value is optional, and a call that fails reads
+//! as unknown rather than as a finding.
+
+use no_cluely_core::ScannedWindow;
+
+use crate::placement::{K_CGS_IGNORE_FOR_EXPOSE_TAG_BIT, K_CGS_STICKY_TAG_BIT};
+use crate::sys;
+
+// Window tag bit, from the reverse-engineered CGSWindow.h
+const K_CGS_FLOATING_WINDOW_TAG_BIT: u32 = 1 << 1;
+
+/// What the window server reports for one window
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowServerAttributes {
+    pub window_id: u32,
+    pub sharing_state: Option<i32>, // 0 = excluded from capture
+    pub level: Option<i32>,
+    pub alpha: Option<f32>,
+    pub owner_pid: Option<i32>,
+    pub tags: Option<[u32; 2]>,
+}
+
+impl WindowServerAttributes {
+    fn tag(&self, bit: u32) -> Option<bool> {
+        self.tags.map(|tags| tags[0] & bit != 0)
+    }
+
+    /// Kept out of screen captures and screen sharing
+    pub fn capture_excluded(&self) -> Option<bool> {
+        self.sharing_state.map(|state| state == 0)
+    }
+
+    /// Floats above the app's other windows
+    pub fn floating(&self) -> Option<bool> {
+        self.tag(K_CGS_FLOATING_WINDOW_TAG_BIT)
+    }
+
+    /// Left in place by Mission Control (stationary)
+    pub fn stationary(&self) -> Option<bool> {
+        self.tag(K_CGS_IGNORE_FOR_EXPOSE_TAG_BIT)
+    }
+
+    /// Shown on every Space
+    pub fn on_all_spaces(&self) -> Option<bool> {
+        self.tag(K_CGS_STICKY_TAG_BIT)
+    }
+}
+
+pub(crate) fn window_server_attributes(window_id: u32) -> WindowServerAttributes {
+    WindowServerAttributes {
+        window_id,
+        sharing_state: sys::window_sharing_state(window_id),
+        level: sys::window_level(window_id),
+        alpha: sys::window_alpha(window_id),
+        owner_pid: sys::window_owner_pid(window_id),
+        tags: sys::window_tags(window_id),
+    }
+}
+
+/// The window server's sharing state for a window, where it disagrees with
+/// the `listed` one from the window list, so the detection rules see
+/// capture exclusion the window list does not report
+pub(crate) fn sharing_state(window_id: i32, listed: i32) -> i32 {
+    let Some(state) = sys::window_sharing_state(window_id as u32) else {
+        return listed;
+    };
+    if state != listed {
+        tracing::debug!(
+            window_id,
+            listed,
+            window_server = state,
+            "sharing state differs from the window list"
+        );
+    }
+    state
+}
+
+/// `windows`, each with the window server's `sharing_state`
+pub(crate) fn apply_sharing_state(mut windows: Vec<ScannedWindow>) -> Vec<ScannedWindow> {
+    for window in &mut windows {
+        window.sharing_state = sharing_state(window.window_id, window.sharing_state);
+    }
+    windows
+}
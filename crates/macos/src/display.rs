@@ -0,0 +1,39 @@
+//! Reading the active displays from Core Graphics.
+
+use no_cluely_core::{Display, WindowBounds};
+
+use crate::sys;
+
+// Far more displays than a Mac can drive
+const MAX_DISPLAYS: usize = 32;
+
+/// Every active display, main display first
+pub(crate) fn active_displays() -> Vec<Display> {
+    let ids = match sys::active_display_ids(MAX_DISPLAYS) {
+        Ok(ids) => ids,
+        Err(error) => {
+            tracing::warn!(error, "could not list displays");
+            return Vec::new();
+        }
+    };
+
+    let main = sys::main_display_id();
+    let mut displays: Vec<Display> = ids
+        .into_iter()
+        .map(|id| {
+            let rect = sys::display_bounds(id);
+            Display {
+                id,
+                bounds: WindowBounds {
+                    x: rect.origin.x,
+                    y: rect.origin.y,
+                    width: rect.size.width,
+                    height: rect.size.height,
+                },
+                is_main: id == main,
+            }
+        })
+        .collect();
+    displays.sort_by_key(|display| !display.is_main);
+    displays
+}
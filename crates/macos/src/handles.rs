@@ -0,0 +1,130 @@
+//! Processes holding Cluely files open.
+//!
+//! Each candidate's open descriptors are read with `proc_pidfdinfo` and
+//! their paths judged by `ArtifactKind`. Candidates are Cluely's own
+//! processes and every process with a window showing a capture signal;
+//! with `all_processes`, every process this user can read. Other users'
+//! processes need root.
+
+use std::collections::BTreeMap;
+
+use no_cluely_core::{
+    is_cluely_process, suspicious_processes, ArtifactKind, DetectionError, DetectorConfig,
+};
+
+use crate::sys::{self, OpenFile};
+use crate::{process, window_list};
+
+/// A Cluely file a process has open
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArtifactHandle {
+    /// Path of the file, or name of the shared memory object
+    pub path: String,
+    pub kind: ArtifactKind,
+}
+
+/// A process with Cluely files open
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessHandles {
+    pub pid: i32,
+    pub name: String,
+    pub path: String, // Empty if the executable path is not readable
+    /// Whether its name or executable path matches the signatures; if not,
+    /// the handles are all that give it away
+    pub is_cluely: bool,
+    /// Sorted by kind, then path
+    pub handles: Vec<ArtifactHandle>,
+}
+
+impl ProcessHandles {
+    /// Whether any handle is to a recording or capture buffer
+    pub fn capturing(&self) -> bool {
+        self.handles.iter().any(|handle| {
+            matches!(
+                handle.kind,
+                ArtifactKind::Recording | ArtifactKind::CaptureBuffer
+            )
+        })
+    }
+}
+
+/// The Cluely files `pid` has open; empty if it has none or cannot be read
+fn artifact_handles(pid: i32) -> Vec<ArtifactHandle> {
+    let mut handles: Vec<ArtifactHandle> = sys::pid_open_files(pid)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|file| {
+            let (path, kind) = match file {
+                OpenFile::File(path) => {
+                    let kind = ArtifactKind::of_file(&path)?;
+                    (path, kind)
+                }
+                OpenFile::SharedMemory(name) => {
+                    let kind = ArtifactKind::of_shared_memory(&name)?;
+                    (name, kind)
+                }
+            };
+            Some(ArtifactHandle { path, kind })
+        })
+        .collect();
+    handles.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+    // A file opened twice is one handle as far as the report goes
+    handles.dedup();
+    handles
+}
+
+/// Every candidate process with a Cluely file open, sorted by pid
+pub(crate) fn find_artifact_handles(
+    all_processes: bool,
+) -> Result<Vec<ProcessHandles>, DetectionError> {
+    let candidates: Vec<i32> = if all_processes {
+        if !sys::PLATFORM_SUPPORTED {
+            return Err(DetectionError::PlatformUnsupported);
+        }
+        sys::list_pids()
+    } else {
+        let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+        let mut pids: Vec<i32> = suspicious_processes(windows, false)
+            .into_iter()
+            .map(|process| process.pid)
+            .chain(
+                process::find_cluely_processes()
+                    .into_iter()
+                    .map(|process| process.pid),
+            )
+            .collect();
+        pids.sort_unstable();
+        pids.dedup();
+        pids
+    };
+
+    let found: BTreeMap<i32, Vec<ArtifactHandle>> = candidates
+        .into_iter()
+        // The monitor keeps its history open under `no-cluely`
+        .filter(|&pid| pid > 0 && !process::is_detector(pid))
+        .map(|pid| (pid, artifact_handles(pid)))
+        .filter(|(_, handles)| !handles.is_empty())
+        .collect();
+
+    let processes: Vec<ProcessHandles> = found
+        .into_iter()
+        .map(|(pid, handles)| {
+            let (name, path) = process::process_names(pid).unwrap_or_default();
+            ProcessHandles {
+                pid,
+                is_cluely: is_cluely_process(&name) || is_cluely_process(&path),
+                name,
+                path,
+                handles,
+            }
+        })
+        .collect();
+    tracing::debug!(
+        all_processes,
+        matched = processes.len(),
+        "open handle scan complete"
+    );
+    Ok(processes)
+}
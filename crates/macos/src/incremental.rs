@@ -0,0 +1,116 @@
+//! Cluely detection that carries window state from one scan to the next.
+//!
+//! A window's owner never changes, so once a window has been classified only
+//! its ID and owner PID need to be read on later scans. Only new windows, or
+//! IDs that now belong to another process, are parsed in full, and only
+//! Cluely windows have their sharing state and layer re-read. On systems with
+//! hundreds of windows this cuts most of the work out of a monitor loop.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use no_cluely_core::{ClueLyDetectionResult, DetectionError, DetectorConfig, WindowInfo};
+
+use crate::matcher::WindowMatcher;
+use crate::sys::Dictionary;
+use crate::window_list::{
+    copy_window_list, WINDOW_ALPHA, WINDOW_LAYER, WINDOW_NUMBER, WINDOW_OWNER_NAME,
+    WINDOW_OWNER_PID, WINDOW_SHARING_STATE,
+};
+
+/// What is remembered about a window between scans
+struct KnownWindow {
+    owner_pid: i32,
+    /// The owner name, for Cluely windows only
+    cluely_owner: Option<String>,
+}
+
+/// Detects Cluely like `detect_cluely_detailed_rust`, but only parses the
+/// windows that appeared since the previous scan (Rust API)
+#[derive(Default)]
+pub struct IncrementalDetector {
+    config: DetectorConfig,
+    known: HashMap<i32, KnownWindow>,
+}
+
+impl IncrementalDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan only the windows `config` selects
+    pub fn with_config(config: DetectorConfig) -> Self {
+        Self {
+            config,
+            known: HashMap::new(),
+        }
+    }
+
+    /// Scan, treating an unavailable window list as a clean result
+    pub fn detect(&mut self) -> (ClueLyDetectionResult, Vec<WindowInfo>) {
+        self.try_detect().unwrap_or_default()
+    }
+
+    /// Scan, reporting a scan that could not see any windows as an error
+    pub fn try_detect(
+        &mut self,
+    ) -> Result<(ClueLyDetectionResult, Vec<WindowInfo>), DetectionError> {
+        let _span = tracing::debug_span!("incremental_scan").entered();
+        let started = Instant::now();
+        let mut cluely_windows = Vec::new();
+        let mut result = ClueLyDetectionResult::new(&self.config.signatures);
+        let mut seen = HashMap::with_capacity(self.known.len());
+        let mut parsed = 0;
+        let mut matcher = WindowMatcher::new(&self.config);
+        // Titles change, so title signatures need every window re-read
+        let reread = self.config.signatures.uses_titles();
+
+        let window_list = copy_window_list(&self.config)?;
+        for window_dict in window_list.windows() {
+            let window_id = window_dict.int(&WINDOW_NUMBER).unwrap_or_default();
+            let owner_pid = window_dict.int(&WINDOW_OWNER_PID).unwrap_or_default();
+            let window = match self.known.remove(&window_id) {
+                // Window IDs can be reused once a window closes
+                Some(known) if known.owner_pid == owner_pid && !reread => known,
+                _ => {
+                    parsed += 1;
+                    classify(&mut matcher, &window_dict, owner_pid)
+                }
+            };
+
+            if let Some(owner) = &window.cluely_owner {
+                let window_info = WindowInfo {
+                    owner: owner.clone(),
+                    window_id,
+                    sharing_state: window_dict.int(&WINDOW_SHARING_STATE).unwrap_or_default(),
+                    layer: window_dict.int(&WINDOW_LAYER).unwrap_or_default(),
+                    alpha: window_dict.float(&WINDOW_ALPHA).unwrap_or_default(),
+                };
+                result.add_window(&window_info);
+                cluely_windows.push(window_info);
+            }
+            seen.insert(window_id, window);
+        }
+
+        // Anything not seen this time has closed
+        self.known = seen;
+
+        result.last_scan_duration_us = started.elapsed().as_micros() as u64;
+        tracing::debug!(
+            total_windows = self.known.len(),
+            parsed,
+            cluely_windows = result.window_count,
+            duration_ms = result.last_scan_duration_us as f64 / 1000.0,
+            "incremental window scan complete"
+        );
+        Ok((result, cluely_windows))
+    }
+}
+
+fn classify(matcher: &mut WindowMatcher, window_dict: &Dictionary, owner_pid: i32) -> KnownWindow {
+    let owner = window_dict.string(&WINDOW_OWNER_NAME).unwrap_or_default();
+    KnownWindow {
+        owner_pid,
+        cluely_owner: matcher.is_cluely(window_dict, &owner).then_some(owner),
+    }
+}
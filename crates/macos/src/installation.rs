@@ -0,0 +1,132 @@
+//! Cluely's processes grouped by the installation they run from.
+//!
+//! Closing every Cluely window does not stop Cluely: its updater, crash
+//! reporter and helpers can keep running, and a window count of zero hides
+//! them. Each process is grouped under the outermost app bundle in its
+//! executable path (helpers and updaters live in bundles nested inside the
+//! app's own), so one installation reads as one entry, windows and all.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use no_cluely_core::{DetectionError, DetectorConfig, ProcessRole};
+
+use crate::process::{self, ProcessInfo};
+use crate::window_list;
+
+/// One copy of Cluely and every process running from it
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Installation {
+    /// The app bundle, e.g. `/Applications/Cluely.app`; None for processes
+    /// that run from outside any bundle
+    pub bundle: Option<PathBuf>,
+    /// Its running processes, the app first, then by pid
+    pub processes: Vec<ProcessInfo>,
+    /// Windows owned by any of its processes
+    pub window_count: usize,
+}
+
+impl Installation {
+    /// How many of its processes have `role`
+    pub fn count(&self, role: ProcessRole) -> usize {
+        self.processes
+            .iter()
+            .filter(|process| process.role == role)
+            .count()
+    }
+
+    /// Processes are running but none of them has a window
+    pub fn windowless(&self) -> bool {
+        self.window_count == 0 && !self.processes.is_empty()
+    }
+
+    /// One-line summary for logs and reports, e.g. "0 windows, 3 processes
+    /// (1 updater, 2 helpers)"
+    pub fn describe(&self) -> String {
+        let roles: Vec<String> = ProcessRole::ALL
+            .into_iter()
+            .filter(|&role| role != ProcessRole::Main)
+            .filter_map(|role| {
+                let count = self.count(role);
+                (count > 0).then(|| {
+                    format!(
+                        "{} {}{}",
+                        count,
+                        role.label(),
+                        if count == 1 { "" } else { "s" }
+                    )
+                })
+            })
+            .collect();
+        let mut summary = format!(
+            "{} window{}, {} process{}",
+            self.window_count,
+            if self.window_count == 1 { "" } else { "s" },
+            self.processes.len(),
+            if self.processes.len() == 1 { "" } else { "es" }
+        );
+        if !roles.is_empty() {
+            summary.push_str(&format!(" ({})", roles.join(", ")));
+        }
+        summary
+    }
+}
+
+/// The outermost app bundle in `path`
+fn outer_bundle(path: &str) -> Option<&str> {
+    Some(&path[..path.find(".app/")? + ".app".len()])
+}
+
+/// Every running Cluely process, grouped by installation and sorted by
+/// bundle path
+pub(crate) fn find_installations() -> Result<Vec<Installation>, DetectionError> {
+    let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+    let processes = process::find_cluely_processes();
+
+    // A process outside any bundle belongs with the nearest parent inside
+    // one, e.g. a renamed agent a helper started
+    let bundles: HashMap<i32, String> = processes
+        .iter()
+        .filter_map(|process| Some((process.pid, outer_bundle(&process.path)?.to_string())))
+        .collect();
+    let mut grouped: BTreeMap<Option<String>, Vec<ProcessInfo>> = BTreeMap::new();
+    for process in processes {
+        let bundle = bundles.get(&process.pid).cloned().or_else(|| {
+            process
+                .parents
+                .iter()
+                .find_map(|parent| bundles.get(&parent.pid).cloned())
+        });
+        grouped.entry(bundle).or_default().push(process);
+    }
+
+    let installations: Vec<Installation> = grouped
+        .into_iter()
+        .map(|(bundle, mut processes)| {
+            processes.sort_by_key(|process| (process.role, process.pid));
+            let window_count = windows
+                .iter()
+                .filter(|window| {
+                    processes
+                        .iter()
+                        .any(|process| process.pid == window.owner_pid)
+                })
+                .count();
+            Installation {
+                bundle: bundle.map(PathBuf::from),
+                processes,
+                window_count,
+            }
+        })
+        .collect();
+    for installation in &installations {
+        tracing::debug!(
+            bundle = ?installation.bundle,
+            processes = installation.processes.len(),
+            windows = installation.window_count,
+            "installation"
+        );
+    }
+    Ok(installations)
+}
@@ -0,0 +1,186 @@
+//! Whether the detector's own code has been tampered with.
+//!
+//! Monitoring software that wants to go unseen can patch or replace the
+//! detector instead of hiding from it. The check looks at the file the
+//! detector's code was loaded from (the executable, or the library an app
+//! embeds it through): its code signature must still be valid, its contents
+//! must not change while the process runs, and the running code must still
+//! match the signature it was loaded with.
+//!
+//! A valid signature on its own proves little: whoever patches the binary
+//! before it starts can strip the signature or sign it again ad hoc. Builds
+//! made with `CLUELY_TEAM_ID` set to the Team ID releases are signed with
+//! expect that signature, and report anything else, including an unsigned
+//! file or one whose signature could not be checked, as tampering.
+
+use std::sync::OnceLock;
+
+use no_cluely_core::DetectionError;
+
+use crate::signing::hex;
+use crate::sys::{self, SignatureCheck};
+
+/// Team ID this build's releases are signed with; unset for local builds,
+/// which then only check that the file does not change
+const EXPECTED_TEAM_ID: Option<&str> = option_env!("CLUELY_TEAM_ID");
+
+/// How the detector's file matches its code signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SignatureStatus {
+    Valid,
+    /// Not signed, as with a local build on Intel; nothing to check against
+    Unsigned,
+    /// Signed, but the file no longer matches the signature
+    Invalid,
+    /// The signature could not be checked, as when the file is gone
+    Unchecked,
+}
+
+/// The detector's own code, as checked by `verify_self_integrity_rust`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegrityReport {
+    /// The executable or library the detector's code was loaded from
+    pub path: String,
+    /// SHA-256 of the file as 64 lowercase hex digits; None if it could not
+    /// be read, as when it was deleted
+    pub sha256: Option<String>,
+    /// Whether the file differs from what the first check in this process
+    /// found
+    pub modified: bool,
+    pub signature: SignatureStatus,
+    /// Whether the running process still matches its signature; None if
+    /// it is unsigned
+    pub running_code_valid: Option<bool>,
+    /// Team ID the file is signed with, if any
+    pub team_id: Option<String>,
+    /// Team ID this build expects to be signed with; None for a build
+    /// without one, such as a local build
+    pub expected_team_id: Option<String>,
+    /// CDHash as 40 lowercase hex digits; None if unsigned
+    pub cdhash: Option<String>,
+}
+
+impl IntegrityReport {
+    /// No sign of tampering. Without an expected Team ID, an unsigned build
+    /// is intact as long as it does not change.
+    pub fn is_intact(&self) -> bool {
+        self.problems().is_empty()
+    }
+
+    /// What points to tampering, one sentence each
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.sha256.is_none() {
+            problems.push(format!("{} could not be read", self.path));
+        }
+        if self.modified {
+            problems.push(format!(
+                "{} changed while the detector was running",
+                self.path
+            ));
+        }
+        if self.signature == SignatureStatus::Invalid {
+            problems.push(format!(
+                "{} no longer matches its code signature",
+                self.path
+            ));
+        }
+        if self.running_code_valid == Some(false) {
+            problems.push("The running code no longer matches its signature".to_string());
+        }
+
+        let Some(expected) = &self.expected_team_id else {
+            return problems;
+        };
+        match self.signature {
+            SignatureStatus::Unsigned => problems.push(format!(
+                "{} is not code signed, but this build is signed by Team ID {}",
+                self.path, expected
+            )),
+            SignatureStatus::Unchecked => problems.push(format!(
+                "The code signature of {} could not be checked",
+                self.path
+            )),
+            SignatureStatus::Valid if self.team_id.as_ref() != Some(expected) => {
+                problems.push(format!(
+                    "{} is signed by {} instead of Team ID {}",
+                    self.path,
+                    self.team_id.as_ref().map_or(
+                        "no team (ad hoc)".to_string(),
+                        |team_id| format!("Team ID {}", team_id)
+                    ),
+                    expected
+                ))
+            }
+            _ => {}
+        }
+        if self.running_code_valid.is_none() {
+            problems.push("The signature of the running code could not be checked".to_string());
+        }
+        problems
+    }
+}
+
+/// What the first check in this process found the file's hash to be
+static FIRST_SHA256: OnceLock<Option<String>> = OnceLock::new();
+
+pub(crate) fn verify_self_integrity() -> Result<IntegrityReport, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+    // Any function of this crate is in the file the detector was loaded from
+    let path = sys::image_path(verify_self_integrity as *const () as *const _)
+        .ok_or(DetectionError::PlatformUnsupported)?;
+
+    let sha256 = std::fs::read(&path)
+        .map_err(|error| tracing::warn!(path = %path, %error, "could not read own binary"))
+        .ok()
+        .and_then(|contents| sys::sha256(&contents))
+        .map(|digest| hex(&digest));
+    let first = FIRST_SHA256.get_or_init(|| sha256.clone());
+    let modified = first.is_some() && *first != sha256;
+
+    let signature = match sys::check_signature(&path) {
+        Some(SignatureCheck::Valid) => SignatureStatus::Valid,
+        Some(SignatureCheck::Unsigned) => SignatureStatus::Unsigned,
+        Some(SignatureCheck::Invalid(status)) => {
+            tracing::warn!(path = %path, status, "code signature check failed");
+            SignatureStatus::Invalid
+        }
+        None => SignatureStatus::Unchecked,
+    };
+    let running_code_valid = match sys::check_own_signature() {
+        Some(SignatureCheck::Valid) => Some(true),
+        Some(SignatureCheck::Invalid(status)) => {
+            tracing::warn!(status, "running code signature check failed");
+            Some(false)
+        }
+        Some(SignatureCheck::Unsigned) | None => None,
+    };
+    let (team_id, cdhash) = sys::code_signature(&path)
+        .map(|signature| {
+            (
+                signature.team_id,
+                signature.cdhash.map(|cdhash| hex(&cdhash)),
+            )
+        })
+        .unwrap_or_default();
+
+    let report = IntegrityReport {
+        path,
+        sha256,
+        modified,
+        signature,
+        running_code_valid,
+        team_id,
+        expected_team_id: EXPECTED_TEAM_ID
+            .filter(|team_id| !team_id.is_empty())
+            .map(str::to_string),
+        cdhash,
+    };
+    tracing::debug!(path = %report.path, intact = report.is_intact(), "self-integrity check complete");
+    Ok(report)
+}
@@ -0,0 +1,502 @@
+//! The macOS backend for no-cluely.
+//!
+//! Reads windows, displays, processes and files from the running system and
+//! judges them with the rules in `no-cluely-core`. Every call into the
+//! system frameworks goes through `sys`.
+//!
+//! On other platforms the crate still builds, against a stub `sys` that
+//! reads nothing: scans fail with `DetectionError::PlatformUnsupported`
+//! and lookups come back empty.
+
+use std::time::Instant;
+
+use no_cluely_core::{
+    analyze_displays, analyze_windows, capture_evasion, placement, suspicious_processes,
+    CaptureEvasionReport, ClueLyDetectionResult, DetectionError, DetectorConfig, Display,
+    DisplayReport, EvasionScan, Placement, SuspiciousProcess, WindowBounds, WindowInfo,
+    WindowPlacement,
+};
+
+mod accessibility;
+mod activity;
+mod ancestry;
+mod browser;
+mod capture;
+#[cfg(feature = "private-cgs")]
+mod cgs;
+mod display;
+mod handles;
+mod incremental;
+mod installation;
+mod integrity;
+mod matcher;
+mod monitor;
+mod permissions;
+mod persistence;
+mod placement;
+mod process;
+mod recorders;
+mod sessions;
+mod signing;
+#[cfg_attr(not(target_os = "macos"), path = "sys_stub.rs")]
+mod sys;
+mod tracking;
+mod window_list;
+
+pub use activity::{ActivityMonitor, ProcessActivity};
+pub use ancestry::{LaunchOrigin, ParentProcess};
+pub use browser::{Browser, BrowserTab};
+pub use capture::WindowImage;
+#[cfg(feature = "private-cgs")]
+pub use cgs::WindowServerAttributes;
+pub use handles::{ArtifactHandle, ProcessHandles};
+pub use incremental::IncrementalDetector;
+pub use installation::Installation;
+pub use integrity::{IntegrityReport, SignatureStatus};
+pub use monitor::{DetectionEvent, Monitor, MonitorThread};
+pub use permissions::{Permission, PermissionGrant};
+pub use persistence::{PersistenceAudit, PersistenceItem, PersistenceKind, UserPersistence};
+pub use process::ProcessInfo;
+pub use recorders::{ScreenRecorder, ScreenRecorders};
+pub use sessions::UserSession;
+pub use signing::SignedBinary;
+pub use tracking::{WindowBehavior, WindowTracker};
+
+use matcher::{AllowChecker, WindowMatcher};
+use window_list::{
+    copy_window_list, WINDOW_ALPHA, WINDOW_LAYER, WINDOW_NUMBER, WINDOW_OWNER_NAME,
+    WINDOW_SHARING_STATE,
+};
+
+/// Scan every window for evasion techniques. System windows are skipped
+/// unless `include_system` is set; window dictionaries are parsed on the
+/// rayon thread pool if `parallel` is set.
+fn scan_all(
+    config: &DetectorConfig,
+    include_system: bool,
+    parallel: bool,
+) -> Result<EvasionScan, DetectionError> {
+    let _span = tracing::debug_span!("evasion_scan", include_system).entered();
+    let windows = window_list::scan_all_windows(config, parallel)?;
+    let displays = display::active_displays();
+    let mut scan = analyze_windows(windows, &displays, include_system, config);
+    let mut allow = AllowChecker::new(&config.allow);
+    scan.findings
+        .retain(|finding| !allow.allows(finding.window.owner_pid, &finding.window.owner));
+    placement::mark_space_behavior(&mut scan);
+    if config.inspect_content {
+        accessibility::inspect_findings(&mut scan);
+    }
+    Ok(scan)
+}
+
+/// Processes with windows that show capture signals, sorted by pid
+fn find_suspicious_processes(
+    include_system: bool,
+) -> Result<Vec<SuspiciousProcess>, DetectionError> {
+    let _span = tracing::debug_span!("process_scan", include_system).entered();
+    let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+    Ok(suspicious_processes(windows, include_system))
+}
+
+/// Apps with windows excluded from screen capture
+fn find_capture_evasion() -> Result<CaptureEvasionReport, DetectionError> {
+    let _span = tracing::debug_span!("capture_evasion_scan").entered();
+    let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+    Ok(capture_evasion(&windows))
+}
+
+pub(crate) fn analyze_cluely_windows(
+    config: &DetectorConfig,
+) -> (Vec<WindowInfo>, ClueLyDetectionResult) {
+    try_analyze_cluely_windows(config).unwrap_or_default()
+}
+
+pub(crate) fn try_analyze_cluely_windows(
+    config: &DetectorConfig,
+) -> Result<(Vec<WindowInfo>, ClueLyDetectionResult), DetectionError> {
+    let _span = tracing::debug_span!("cluely_scan").entered();
+    let started = Instant::now();
+    let mut cluely_windows = Vec::new();
+    let mut result = ClueLyDetectionResult::new(&config.signatures);
+    let mut matcher = WindowMatcher::new(config);
+
+    let window_list = copy_window_list(config)?;
+    let total_windows = window_list.len();
+    for window_dict in window_list.windows() {
+        let owner = window_dict.string(&WINDOW_OWNER_NAME).unwrap_or_default();
+        if matcher.is_cluely(&window_dict, &owner) {
+            let window_id = window_dict.int(&WINDOW_NUMBER).unwrap_or_default();
+            let sharing_state = window_dict.int(&WINDOW_SHARING_STATE).unwrap_or_default();
+            #[cfg(feature = "private-cgs")]
+            let sharing_state = cgs::sharing_state(window_id, sharing_state);
+            let layer = window_dict.int(&WINDOW_LAYER).unwrap_or_default();
+            let alpha = window_dict.float(&WINDOW_ALPHA).unwrap_or_default();
+            tracing::trace!(window_id, %owner, sharing_state, layer, alpha, "Cluely window");
+
+            let window_info = WindowInfo {
+                owner,
+                window_id,
+                sharing_state,
+                layer,
+                alpha,
+            };
+            result.add_window(&window_info);
+            cluely_windows.push(window_info);
+        }
+    }
+
+    result.last_scan_duration_us = started.elapsed().as_micros() as u64;
+    tracing::debug!(
+        total_windows,
+        cluely_windows = result.window_count,
+        screen_capture_evasion = result.screen_capture_evasion_count,
+        elevated_layer = result.elevated_layer_count,
+        duration_ms = result.last_scan_duration_us as f64 / 1000.0,
+        "window scan complete"
+    );
+    Ok((cluely_windows, result))
+}
+
+/// Main detection function - returns detailed result
+/// This is the primary Rust API for detection
+pub fn detect_cluely_rust() -> ClueLyDetectionResult {
+    let (_, result) = analyze_cluely_windows(&DetectorConfig::default());
+    result
+}
+
+/// Like `detect_cluely_rust`, but reports a scan that could not see any
+/// windows as an error instead of a clean result (Rust API)
+pub fn try_detect_cluely_rust() -> Result<ClueLyDetectionResult, DetectionError> {
+    try_analyze_cluely_windows(&DetectorConfig::default()).map(|(_, result)| result)
+}
+
+/// Like `try_detect_cluely_rust`, but scanning the windows `config` selects.
+/// Returns the Cluely windows found as well (Rust API)
+pub fn detect_cluely_with_rust(
+    config: &DetectorConfig,
+) -> Result<(ClueLyDetectionResult, Vec<WindowInfo>), DetectionError> {
+    try_analyze_cluely_windows(config).map(|(windows, result)| (result, windows))
+}
+
+/// Get details of every Cluely window currently open (Rust API)
+pub fn get_cluely_windows_rust() -> Vec<WindowInfo> {
+    let (windows, _) = analyze_cluely_windows(&DetectorConfig::default());
+    windows
+}
+
+/// Detection result and window details from a single scan (Rust API)
+pub fn detect_cluely_detailed_rust() -> (ClueLyDetectionResult, Vec<WindowInfo>) {
+    let (windows, result) = analyze_cluely_windows(&DetectorConfig::default());
+    (result, windows)
+}
+
+/// Get every running Cluely process, including ones without windows (Rust API)
+pub fn get_cluely_processes_rust() -> Vec<ProcessInfo> {
+    process::find_cluely_processes()
+}
+
+/// Get every running Cluely process grouped by the installation it runs
+/// from, with the number of windows each installation owns, so updaters,
+/// crash reporters and helpers left running without a window show up
+/// (Rust API)
+pub fn get_cluely_installations_rust() -> Result<Vec<Installation>, DetectionError> {
+    installation::find_installations()
+}
+
+/// Get the executable of every running Cluely process with its signing
+/// Team ID, signing ID and CDHash, for blocking it by signature (Rust API)
+pub fn get_cluely_binaries_rust() -> Result<Vec<SignedBinary>, DetectionError> {
+    signing::find_cluely_binaries()
+}
+
+/// Check the detector's own binary for tampering: its code signature, its
+/// hash against the first check in this process, and the running code.
+/// Call it once at startup so later calls can tell whether the file was
+/// replaced (Rust API)
+pub fn verify_self_integrity_rust() -> Result<IntegrityReport, DetectionError> {
+    integrity::verify_self_integrity()
+}
+
+/// Get every process holding Cluely files open: its bundle, support files,
+/// databases, recordings or shared memory, which gives a process away even
+/// under another name. Looks at Cluely's processes and those with windows
+/// showing capture signals, or every process with `all_processes`; other
+/// users' processes need root (Rust API)
+pub fn get_artifact_handles_rust(
+    all_processes: bool,
+) -> Result<Vec<ProcessHandles>, DetectionError> {
+    handles::find_artifact_handles(all_processes)
+}
+
+/// Get every user logged in to a GUI session, including ones switched out
+/// with fast user switching, and every user running Cluely, with their
+/// Cluely processes. Needs root (Rust API)
+pub fn scan_sessions_rust() -> Result<Vec<UserSession>, DetectionError> {
+    sessions::scan_sessions()
+}
+
+/// Get browser tabs open on Cluely's web app, in Safari and Chrome. macOS
+/// asks the user to allow this the first time; refused browsers are left
+/// out (Rust API)
+pub fn get_cluely_browser_tabs_rust() -> Vec<BrowserTab> {
+    browser::find_cluely_tabs()
+}
+
+/// Get Cluely files left on disk: the app bundle, launch agents/daemons,
+/// support files, preferences and caches for the current user (Rust API)
+pub fn get_cluely_persistence_rust() -> Vec<PersistenceItem> {
+    let home = std::env::var_os("HOME").map(std::path::PathBuf::from);
+    persistence::find_cluely_persistence(home.as_deref())
+}
+
+/// Get Cluely files left on disk machine-wide and under every user's home
+/// directory, for auditing a shared or newly acquired Mac. Needs root (Rust API)
+pub fn audit_persistence_rust() -> Result<PersistenceAudit, DetectionError> {
+    persistence::audit_persistence()
+}
+
+/// What the window server itself reports for a window, through private
+/// CGS calls; needs the `private-cgs` feature (Rust API)
+#[cfg(feature = "private-cgs")]
+pub fn window_server_attributes_rust(window_id: u32) -> WindowServerAttributes {
+    cgs::window_server_attributes(window_id)
+}
+
+/// Whether this process has Accessibility permission, which
+/// `DetectorConfig::inspect_content` needs to read window content (Rust API)
+pub fn has_accessibility_access_rust() -> bool {
+    sys::accessibility_access()
+}
+
+/// Get every app granted Screen Recording permission, and which of them are
+/// running and so can be recording the screen now. Grants are only readable
+/// with Full Disk Access (Rust API)
+pub fn get_screen_recorders_rust() -> Result<ScreenRecorders, DetectionError> {
+    recorders::find_screen_recorders()
+}
+
+/// Whether this process has Screen Recording permission. Without it, window
+/// titles of other apps are hidden from the window list (Rust API)
+pub fn has_screen_capture_access_rust() -> bool {
+    sys::screen_capture_access()
+}
+
+/// Scan every window, of any app, for screen sharing evasion techniques.
+/// System windows are left out of the findings unless `include_system` is set (Rust API)
+pub fn scan_all_rust(include_system: bool) -> Result<EvasionScan, DetectionError> {
+    scan_all(&DetectorConfig::default(), include_system, false)
+}
+
+/// Like `scan_all_rust`, but scanning the windows `config` selects (Rust API)
+pub fn scan_all_with_rust(
+    config: &DetectorConfig,
+    include_system: bool,
+) -> Result<EvasionScan, DetectionError> {
+    scan_all(config, include_system, false)
+}
+
+/// Like `scan_all_rust`, but parses window dictionaries on the rayon thread
+/// pool. Only pays off with several hundred windows; below that the thread
+/// handoff costs more than it saves. Needs the `parallel` feature (Rust API)
+#[cfg(feature = "parallel")]
+pub fn scan_all_parallel_rust(include_system: bool) -> Result<EvasionScan, DetectionError> {
+    scan_all(&DetectorConfig::default(), include_system, true)
+}
+
+/// Get every active display, main display first (Rust API)
+pub fn active_displays_rust() -> Vec<Display> {
+    display::active_displays()
+}
+
+/// Find which display each on-screen Cluely window sits on, and which of
+/// them are on a display other than the one being shared. The shared
+/// display is found from meeting apps' sharing toolbars unless
+/// `shared_display` names it (Rust API)
+pub fn analyze_displays_rust(shared_display: Option<u32>) -> Result<DisplayReport, DetectionError> {
+    let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+    let displays = display::active_displays();
+    let report = analyze_displays(&windows, &displays, shared_display);
+    tracing::debug!(
+        displays = displays.len(),
+        shared_display = report.shared_display,
+        unshared_overlays = report.unshared_overlays.len(),
+        "display analysis complete"
+    );
+    Ok(report)
+}
+
+/// Where `bounds` sits relative to the active displays: on which display
+/// and how much of it is visible, or how far off screen. None if no display
+/// is attached (Rust API)
+pub fn window_placement_rust(bounds: &WindowBounds) -> Option<Placement> {
+    placement(bounds, &display::active_displays())
+}
+
+/// Get the placement of every window, judged against the active displays,
+/// and whether it is shown on every Space. System windows are skipped
+/// unless `include_system` is set (Rust API)
+pub fn analyze_placement_rust(
+    include_system: bool,
+) -> Result<Vec<WindowPlacement>, DetectionError> {
+    placement::analyze_placement(include_system)
+}
+
+/// Whether a window is shown on every Space, following the user wherever
+/// they switch. Read from its window server tags; false if they cannot be
+/// read (Rust API)
+pub fn is_window_on_all_spaces_rust(window_id: u32) -> bool {
+    placement::is_on_all_spaces(window_id)
+}
+
+/// Get every non-system app with windows excluded from screen capture
+/// (sharing_state 0), regardless of its name. Empty if the window list is
+/// not available (Rust API)
+pub fn detect_capture_evasion_rust() -> CaptureEvasionReport {
+    find_capture_evasion().unwrap_or_default()
+}
+
+/// Like `detect_capture_evasion_rust`, but reports a scan that could not see
+/// any windows as an error (Rust API)
+pub fn try_detect_capture_evasion_rust() -> Result<CaptureEvasionReport, DetectionError> {
+    find_capture_evasion()
+}
+
+/// Get every process with windows that opt out of screen sharing, sit below
+/// the normal layer or are mostly transparent (Rust API)
+pub fn get_suspicious_processes_rust(
+    include_system: bool,
+) -> Result<Vec<SuspiciousProcess>, DetectionError> {
+    find_suspicious_processes(include_system)
+}
+
+/// Capture the contents of a window, even if other windows cover it.
+/// Needs Screen Recording permission; without it other apps' windows come
+/// back without their contents (Rust API)
+pub fn capture_window_image_rust(window_id: u32) -> Option<WindowImage> {
+    capture::capture_window(window_id)
+}
+
+/// Simple boolean check function for Rust API
+pub fn is_cluely_running_rust() -> bool {
+    let result = detect_cluely_rust();
+    result.is_detected
+}
+
+/// Get the number of Cluely windows detected (Rust API)
+pub fn get_cluely_window_count_rust() -> u32 {
+    let result = detect_cluely_rust();
+    result.window_count
+}
+
+/// A detailed text report of Cluely detection, as `get_cluely_report`
+/// returns it (Rust API)
+pub fn cluely_report_rust() -> String {
+    let (windows, result) = analyze_cluely_windows(&DetectorConfig::default());
+    format_report(&windows, &result)
+}
+
+/// Like `cluely_report_rust`, but reports a scan that could not see any
+/// windows as an error instead of a clean report (Rust API)
+pub fn try_cluely_report_rust() -> Result<String, DetectionError> {
+    let (windows, result) = try_analyze_cluely_windows(&DetectorConfig::default())?;
+    Ok(format_report(&windows, &result))
+}
+
+fn format_report(windows: &[WindowInfo], result: &ClueLyDetectionResult) -> String {
+    let mut report = String::new();
+
+    if result.is_detected {
+        report.push_str("🚨 CLUELY EMPLOYEE MONITORING DETECTED\n");
+        report.push_str("=====================================\n\n");
+
+        report.push_str("📊 Summary:\n");
+        report.push_str(&format!(
+            "   • Total Cluely windows: {}\n",
+            result.window_count
+        ));
+        report.push_str(&format!(
+            "   • Screen capture evasion: {}\n",
+            result.screen_capture_evasion_count
+        ));
+        report.push_str(&format!(
+            "   • Elevated layer usage: {}\n",
+            result.elevated_layer_count
+        ));
+        if result.max_layer_detected > 0 {
+            report.push_str(&format!(
+                "   • Highest layer detected: {}\n",
+                result.max_layer_detected
+            ));
+        }
+        report.push('\n');
+
+        report.push_str("🔍 Evasion Techniques Detected:\n");
+        if result.screen_capture_evasion_count > 0 {
+            report.push_str(&format!(
+                "   ⚠️  {} window(s) configured to avoid screen capture\n",
+                result.screen_capture_evasion_count
+            ));
+        }
+        if result.elevated_layer_count > 0 {
+            report.push_str(&format!(
+                "   ⚠️  {} window(s) using elevated display layers\n",
+                result.elevated_layer_count
+            ));
+        }
+        report.push('\n');
+
+        report.push_str("📋 Window Details:\n");
+        for (i, window) in windows.iter().enumerate() {
+            report.push_str(&format!(
+                "   {}. Window ID: {} [{}]\n",
+                i + 1,
+                window.window_id,
+                window.owner
+            ));
+            report.push_str(&format!(
+                "      - Sharing State: {} {}\n",
+                window.sharing_state,
+                if window.sharing_state == 0 {
+                    "(avoiding screen capture)"
+                } else {
+                    "(normal)"
+                }
+            ));
+            report.push_str(&format!(
+                "      - Layer: {} {}\n",
+                window.layer,
+                if window.layer > 0 {
+                    "(elevated - potential overlay)"
+                } else {
+                    "(normal)"
+                }
+            ));
+
+            let mut techniques = Vec::new();
+            if window.sharing_state == 0 {
+                techniques.push("Screen capture evasion");
+            }
+            if window.layer > 0 {
+                techniques.push("Elevated layer positioning");
+            }
+
+            if !techniques.is_empty() {
+                report.push_str(&format!("      - Techniques: {}\n", techniques.join(", ")));
+            }
+            report.push('\n');
+        }
+
+        report.push_str("⚠️  WARNING:\n");
+        report.push_str("   This software is designed to monitor employee activity\n");
+        report.push_str("   while remaining hidden during screen sharing sessions.\n");
+        report.push_str("   Your activities may be recorded even when sharing your screen.\n");
+    } else {
+        report.push_str("✅ NO CLUELY MONITORING DETECTED\n");
+        report.push_str("================================\n\n");
+        report.push_str("No Cluely employee monitoring software found.\n");
+        report.push_str("Your system appears to be free from this monitoring tool.\n");
+    }
+
+    report
+}
@@ -0,0 +1,150 @@
+//! Matching live window dictionaries against signatures, watched apps and
+//! the allow-list.
+
+use std::collections::HashMap;
+
+use no_cluely_core::{normalize, AllowList, DetectorConfig, SignatureSet, Subject, WatchTarget};
+
+use crate::process;
+use crate::sys::{self, Dictionary};
+use crate::window_list::{WINDOW_NAME, WINDOW_OWNER_PID};
+
+/// Matches window dictionaries against a signature set, reading titles and
+/// bundle IDs only when some signature needs them. Bundle IDs are looked up
+/// once per process.
+pub(crate) struct WindowMatcher<'a> {
+    signatures: &'a SignatureSet,
+    read_titles: bool,
+    read_bundle_ids: bool,
+    bundle_ids: HashMap<i32, Option<String>>,
+    targets: TargetMatcher<'a>,
+    allow: AllowChecker<'a>,
+}
+
+impl<'a> WindowMatcher<'a> {
+    pub(crate) fn new(config: &'a DetectorConfig) -> Self {
+        let signatures = &config.signatures;
+        Self {
+            signatures,
+            read_titles: signatures.uses_titles(),
+            read_bundle_ids: signatures.uses_bundle_ids(),
+            bundle_ids: HashMap::new(),
+            targets: TargetMatcher::new(&config.targets),
+            allow: AllowChecker::new(&config.allow),
+        }
+    }
+
+    /// Whether the window owned by `owner` matches a signature or a watched
+    /// app, and is not allowed
+    pub(crate) fn is_cluely(&mut self, window_dict: &Dictionary, owner: &str) -> bool {
+        let mut subject = Subject::owner(owner);
+        if self.read_titles {
+            let title = window_dict.string(&WINDOW_NAME).unwrap_or_default();
+            subject.title = Some(normalize::lower(&title));
+        }
+        if self.read_bundle_ids {
+            let pid = window_dict.int(&WINDOW_OWNER_PID).unwrap_or_default();
+            subject.bundle_id = self
+                .bundle_ids
+                .entry(pid)
+                .or_insert_with(|| process::bundle_id(pid).map(|id| normalize::lower(&id)))
+                .clone();
+        }
+
+        let pid = window_dict.int(&WINDOW_OWNER_PID).unwrap_or_default();
+        let matched = match self.signatures.matching(&subject) {
+            Some(signature) => signature.name.as_str(),
+            None => match self.targets.target(pid, owner) {
+                Some(target) => target,
+                None => return false,
+            },
+        };
+        // Only read the executable of windows that matched
+        if process::is_detector(pid) {
+            return false;
+        }
+        if self.allow.allows(pid, owner) {
+            tracing::trace!(signature = %matched, %owner, "allowed");
+            return false;
+        }
+        tracing::trace!(signature = %matched, %owner, "signature matched");
+        true
+    }
+}
+
+/// Finds which watched app owns a window, reading bundle and Team IDs only
+/// when some target needs them. Each process is looked up once.
+pub(crate) struct TargetMatcher<'a> {
+    targets: &'a [WatchTarget],
+    matched: HashMap<i32, Option<&'a str>>,
+}
+
+impl<'a> TargetMatcher<'a> {
+    pub(crate) fn new(targets: &'a [WatchTarget]) -> Self {
+        Self {
+            targets,
+            matched: HashMap::new(),
+        }
+    }
+
+    /// Name of the target the process `pid`, named `owner`, is
+    pub(crate) fn target(&mut self, pid: i32, owner: &str) -> Option<&'a str> {
+        if self.targets.is_empty() {
+            return None;
+        }
+        let targets = self.targets;
+        *self.matched.entry(pid).or_insert_with(|| {
+            let bundle_id = if targets.iter().any(WatchTarget::uses_bundle_ids) {
+                process::bundle_id(pid)
+            } else {
+                None
+            };
+            let team_id = if targets.iter().any(WatchTarget::uses_team_ids) {
+                sys::pid_path(pid).and_then(|path| sys::team_identifier(&path))
+            } else {
+                None
+            };
+            targets
+                .iter()
+                .find(|target| target.matches(owner, bundle_id.as_deref(), team_id.as_deref()))
+                .map(|target| target.name.as_str())
+        })
+    }
+}
+
+/// Checks window owners against an allow-list, reading bundle and Team IDs
+/// only when the list has any. Each process is looked up once.
+pub(crate) struct AllowChecker<'a> {
+    list: &'a AllowList,
+    allowed: HashMap<i32, bool>,
+}
+
+impl<'a> AllowChecker<'a> {
+    pub(crate) fn new(list: &'a AllowList) -> Self {
+        Self {
+            list,
+            allowed: HashMap::new(),
+        }
+    }
+
+    /// Whether the process `pid`, named `owner`, is allowed
+    pub(crate) fn allows(&mut self, pid: i32, owner: &str) -> bool {
+        if self.list.is_empty() {
+            return false;
+        }
+        let list = self.list;
+        *self.allowed.entry(pid).or_insert_with(|| {
+            let bundle_id = if list.uses_bundle_ids() {
+                process::bundle_id(pid)
+            } else {
+                None
+            };
+            let team_id = if list.uses_team_ids() {
+                sys::pid_path(pid).and_then(|path| sys::team_identifier(&path))
+            } else {
+                None
+            };
+            list.allows(owner, bundle_id.as_deref(), team_id.as_deref())
+        })
+    }
+}
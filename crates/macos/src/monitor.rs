@@ -0,0 +1,380 @@
+//! Detection events over a series of scans.
+//!
+//! A monitor loop wants to know what changed, not just whether Cluely is
+//! there: which windows opened and closed, and when one of them stopped
+//! being captured or moved to a higher layer. `Monitor` keeps the windows
+//! from its last scan and turns each new scan into events.
+//!
+//! With `watch_permissions`, it also reports apps newly allowed to record
+//! the screen or monitor input, which catches a monitoring tool when it is
+//! set up rather than the first time it runs.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use no_cluely_core::{ClueLyDetectionResult, DetectionError, DetectorConfig, WindowInfo};
+
+use crate::permissions::{self, Permission, PermissionGrant};
+use crate::{try_analyze_cluely_windows, IncrementalDetector};
+
+/// Something that changed between two scans
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DetectionEvent {
+    /// Cluely was found after a scan that found nothing
+    Detected(ClueLyDetectionResult),
+    /// Cluely's last window closed
+    Cleared,
+    WindowAppeared(WindowInfo),
+    WindowDisappeared(WindowInfo),
+    /// A window's sharing state, layer or opacity changed
+    StateChanged {
+        before: WindowInfo,
+        after: WindowInfo,
+    },
+    /// An app was allowed Screen Recording or Input Monitoring (with
+    /// `Monitor::watch_permissions`)
+    PermissionGranted(PermissionGrant),
+    /// The window list could not be read, even after retrying, so the
+    /// monitor cannot tell whether Cluely is there; the last state is kept
+    ScanFailed {
+        error: DetectionError,
+        /// Failed polls in a row, this one included
+        consecutive_failures: u32,
+    },
+}
+
+impl DetectionEvent {
+    /// One-line summary for logs and alerts
+    pub fn describe(&self) -> String {
+        match self {
+            DetectionEvent::Detected(result) => {
+                format!("Cluely detected with {} window(s)", result.window_count)
+            }
+            DetectionEvent::Cleared => "Cluely no longer detected".to_string(),
+            DetectionEvent::WindowAppeared(window) => {
+                format!("{} appeared", describe_window(window))
+            }
+            DetectionEvent::WindowDisappeared(window) => {
+                format!("{} closed", describe_window(window))
+            }
+            DetectionEvent::StateChanged { before, after } => {
+                let mut changes = Vec::new();
+                if before.sharing_state != after.sharing_state {
+                    changes.push(format!(
+                        "sharing state {} -> {}",
+                        before.sharing_state, after.sharing_state
+                    ));
+                }
+                if before.layer != after.layer {
+                    changes.push(format!("layer {} -> {}", before.layer, after.layer));
+                }
+                if before.alpha != after.alpha {
+                    changes.push(format!("alpha {:.3} -> {:.3}", before.alpha, after.alpha));
+                }
+                format!(
+                    "{} window {} changed: {}",
+                    after.owner,
+                    after.window_id,
+                    changes.join(", ")
+                )
+            }
+            DetectionEvent::PermissionGranted(grant) => {
+                format!("{} was allowed {}", grant.client, grant.permission.label())
+            }
+            DetectionEvent::ScanFailed {
+                error,
+                consecutive_failures,
+            } => format!("Scan failed: {} ({} in a row)", error, consecutive_failures),
+        }
+    }
+}
+
+fn describe_window(window: &WindowInfo) -> String {
+    format!(
+        "{} window {} (sharing state {}, layer {}, alpha {:.3})",
+        window.owner, window.window_id, window.sharing_state, window.layer, window.alpha
+    )
+}
+
+/// Allowed grants as of the last read, to tell which ones are new
+#[derive(Default)]
+struct PermissionWatch {
+    /// Whether the database has been looked at yet
+    checked: bool,
+    /// When it had last changed at that point
+    modified: Option<SystemTime>,
+    /// None until the grants are first read
+    allowed: Option<BTreeSet<(Permission, String)>>,
+}
+
+/// Extra attempts at a scan the window server refused before the poll
+/// counts as failed
+const SCAN_RETRIES: u32 = 2;
+/// Pause before the first retry, doubled before each one after
+const RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
+enum Scanner {
+    Full(DetectorConfig),
+    Incremental(IncrementalDetector),
+}
+
+/// Scans for Cluely on demand and reports what changed since the previous
+/// scan (Rust API)
+pub struct Monitor {
+    scanner: Scanner,
+    result: ClueLyDetectionResult,
+    windows: BTreeMap<i32, WindowInfo>,
+    permissions: Option<PermissionWatch>,
+    subscribers: Vec<Sender<DetectionEvent>>,
+    consecutive_failures: u32,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::with_config(DetectorConfig::default())
+    }
+
+    /// Scan the windows `config` selects, reading the whole list each time
+    pub fn with_config(config: DetectorConfig) -> Self {
+        Self::with_scanner(Scanner::Full(config))
+    }
+
+    /// Like `with_config`, but only parse windows that are new since the
+    /// previous scan; see `IncrementalDetector`
+    pub fn incremental(config: DetectorConfig) -> Self {
+        Self::with_scanner(Scanner::Incremental(IncrementalDetector::with_config(
+            config,
+        )))
+    }
+
+    fn with_scanner(scanner: Scanner) -> Self {
+        Self {
+            scanner,
+            result: ClueLyDetectionResult::default(),
+            windows: BTreeMap::new(),
+            permissions: None,
+            subscribers: Vec::new(),
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Also report apps allowed Screen Recording or Input Monitoring after
+    /// the next poll. Grants are only readable with Full Disk Access;
+    /// without it no such events are reported.
+    pub fn watch_permissions(&mut self) {
+        self.permissions
+            .get_or_insert_with(PermissionWatch::default);
+    }
+
+    /// A channel that receives every event from later polls, for event
+    /// loops that would rather receive than call back. A subscriber that
+    /// drops its receiver is removed on the next poll.
+    pub fn subscribe(&mut self) -> Receiver<DetectionEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Poll every `interval` on a background thread, delivering events to
+    /// subscribers, until the returned handle is stopped or dropped
+    pub fn spawn(mut self, interval: Duration) -> MonitorThread {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || {
+            loop {
+                if let Err(e) = self.poll() {
+                    tracing::warn!(error = %e, "monitor scan failed");
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+            self
+        });
+        MonitorThread {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Scan once, retrying with backoff while the window list is
+    /// unavailable, as it briefly is at the login window or while the
+    /// displays sleep
+    fn scan(&mut self) -> Result<(ClueLyDetectionResult, Vec<WindowInfo>), DetectionError> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let scanned = match &mut self.scanner {
+                Scanner::Full(config) => {
+                    try_analyze_cluely_windows(config).map(|(windows, result)| (result, windows))
+                }
+                Scanner::Incremental(detector) => detector.try_detect(),
+            };
+            match scanned {
+                Err(DetectionError::WindowListUnavailable) if attempt < SCAN_RETRIES => {
+                    attempt += 1;
+                    tracing::debug!(attempt, "window list unavailable; retrying");
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                scanned => return scanned,
+            }
+        }
+    }
+
+    /// Scan and report what changed. The first scan reports every Cluely
+    /// window as appeared. A scan that fails even after retrying leaves the
+    /// last state in place and sends subscribers a `ScanFailed` event.
+    pub fn poll(&mut self) -> Result<Vec<DetectionEvent>, DetectionError> {
+        let (result, windows) = match self.scan() {
+            Ok(scanned) => {
+                self.consecutive_failures = 0;
+                scanned
+            }
+            Err(error) => {
+                self.consecutive_failures += 1;
+                let event = DetectionEvent::ScanFailed {
+                    error,
+                    consecutive_failures: self.consecutive_failures,
+                };
+                tracing::debug!(event = %event.describe(), "detection event");
+                self.publish(&[event]);
+                return Err(error);
+            }
+        };
+
+        let mut events = Vec::new();
+        if result.is_detected && !self.result.is_detected {
+            events.push(DetectionEvent::Detected(result));
+        }
+
+        let mut previous = std::mem::take(&mut self.windows);
+        for window in windows {
+            match previous.remove(&window.window_id) {
+                None => events.push(DetectionEvent::WindowAppeared(window.clone())),
+                // Window IDs can be reused once a window closes
+                Some(before) if before.owner != window.owner => {
+                    events.push(DetectionEvent::WindowDisappeared(before));
+                    events.push(DetectionEvent::WindowAppeared(window.clone()));
+                }
+                Some(before) if before != window => {
+                    events.push(DetectionEvent::StateChanged {
+                        before,
+                        after: window.clone(),
+                    });
+                }
+                Some(_) => {}
+            }
+            self.windows.insert(window.window_id, window);
+        }
+        events.extend(
+            previous
+                .into_values()
+                .map(DetectionEvent::WindowDisappeared),
+        );
+
+        if !result.is_detected && self.result.is_detected {
+            events.push(DetectionEvent::Cleared);
+        }
+        self.result = result;
+
+        if let Some(watch) = &mut self.permissions {
+            events.extend(
+                new_grants(watch)
+                    .into_iter()
+                    .map(DetectionEvent::PermissionGranted),
+            );
+        }
+
+        for event in &events {
+            tracing::debug!(event = %event.describe(), "detection event");
+        }
+        self.publish(&events);
+        Ok(events)
+    }
+
+    /// Send `events` to every subscriber, dropping those that have gone
+    fn publish(&mut self, events: &[DetectionEvent]) {
+        self.subscribers.retain(|subscriber| {
+            events
+                .iter()
+                .all(|event| subscriber.send(event.clone()).is_ok())
+        });
+    }
+
+    /// The result of the last successful scan
+    pub fn result(&self) -> ClueLyDetectionResult {
+        self.result
+    }
+
+    /// The Cluely windows found by the last successful scan
+    pub fn windows(&self) -> impl Iterator<Item = &WindowInfo> {
+        self.windows.values()
+    }
+
+    /// Polls in a row that failed to scan; 0 after a successful one. While
+    /// it is above 0 the monitor is blind, not clean.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+}
+
+/// Grants allowed since the last read. The first read only records what is
+/// already allowed. The database is only read when it has changed on disk,
+/// so an unreadable one is not retried (and warned about) on every poll.
+fn new_grants(watch: &mut PermissionWatch) -> Vec<PermissionGrant> {
+    let modified = permissions::database_modified();
+    if watch.checked && modified == watch.modified {
+        return Vec::new();
+    }
+    watch.checked = true;
+    watch.modified = modified;
+    let Some(grants) = permissions::read_grants(&Permission::ALL) else {
+        return Vec::new();
+    };
+
+    let allowed: Vec<PermissionGrant> = grants.into_iter().filter(|grant| grant.allowed).collect();
+    let keys = allowed
+        .iter()
+        .map(|grant| (grant.permission, grant.client.clone()))
+        .collect();
+    match watch.allowed.replace(keys) {
+        Some(before) => allowed
+            .into_iter()
+            .filter(|grant| !before.contains(&(grant.permission, grant.client.clone())))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// A `Monitor` polling on its own thread
+pub struct MonitorThread {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<Monitor>>,
+}
+
+impl MonitorThread {
+    /// Stop polling and get the monitor back, with its subscribers
+    pub fn stop(mut self) -> Monitor {
+        self.stop.take();
+        let thread = self.thread.take().expect("monitor thread already stopped");
+        thread.join().expect("monitor thread panicked")
+    }
+}
+
+impl Drop for MonitorThread {
+    fn drop(&mut self) {
+        // Closing the channel wakes the thread, which then exits
+        self.stop.take();
+    }
+}
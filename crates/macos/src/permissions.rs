@@ -0,0 +1,135 @@
+//! Privacy permission grants, from the system TCC database.
+//!
+//! Screen Recording and Input Monitoring are what a monitoring tool needs
+//! to see the screen and read keystrokes, and both are granted per app in
+//! System Settings. macOS records the grants in the system TCC database,
+//! read here with `sqlite3`. Reading it needs Full Disk Access; without it
+//! no grants are seen.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::SystemTime;
+
+/// The system-wide TCC database, where these grants are kept
+const TCC_DATABASE: &str = "/Library/Application Support/com.apple.TCC/TCC.db";
+/// Its write-ahead log, which changes before the database file does
+const TCC_DATABASE_WAL: &str = "/Library/Application Support/com.apple.TCC/TCC.db-wal";
+
+// `auth_value` for an allowed client (0 = denied)
+const AUTH_VALUE_ALLOWED: u32 = 2;
+
+/// A privacy permission granted per app in System Settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Permission {
+    ScreenRecording,
+    InputMonitoring,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 2] = [Permission::ScreenRecording, Permission::InputMonitoring];
+
+    /// Name shown in System Settings
+    pub fn label(&self) -> &'static str {
+        match self {
+            Permission::ScreenRecording => "Screen Recording",
+            Permission::InputMonitoring => "Input Monitoring",
+        }
+    }
+
+    /// The TCC service the permission is stored under
+    fn service(&self) -> &'static str {
+        match self {
+            Permission::ScreenRecording => "kTCCServiceScreenCapture",
+            Permission::InputMonitoring => "kTCCServiceListenEvent",
+        }
+    }
+
+    fn from_service(service: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|permission| permission.service() == service)
+    }
+}
+
+/// One app's entry for a permission
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PermissionGrant {
+    pub permission: Permission,
+    /// Bundle ID, or executable path for apps without a bundle
+    pub client: String,
+    /// Whether the grant is switched on in System Settings
+    pub allowed: bool,
+    /// When the grant was last changed, in seconds since the Unix epoch
+    pub last_modified: Option<u64>,
+}
+
+impl PermissionGrant {
+    /// Whether `client` is an executable path rather than a bundle ID
+    pub fn is_path(&self) -> bool {
+        self.client.starts_with('/')
+    }
+}
+
+/// When the grants last changed on disk, to skip reading them when they
+/// have not. None if the database does not exist.
+pub(crate) fn database_modified() -> Option<SystemTime> {
+    [TCC_DATABASE, TCC_DATABASE_WAL]
+        .into_iter()
+        .filter_map(|path| Path::new(path).metadata().ok()?.modified().ok())
+        .max()
+}
+
+/// Every entry for `permissions`; None if the database cannot be read
+pub(crate) fn read_grants(permissions: &[Permission]) -> Option<Vec<PermissionGrant>> {
+    if !Path::new(TCC_DATABASE).exists() {
+        return None;
+    }
+    let services: Vec<String> = permissions
+        .iter()
+        .map(|permission| format!("'{}'", permission.service()))
+        .collect();
+    let query = format!(
+        "SELECT service, client, auth_value, last_modified FROM access WHERE service IN ({})",
+        services.join(", ")
+    );
+
+    let output = match Command::new("sqlite3")
+        .args(["-readonly", "-separator", "\t", TCC_DATABASE, &query])
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) => {
+            tracing::warn!(%error, "sqlite3 could not be run");
+            return None;
+        }
+    };
+    if !output.status.success() {
+        // "authorization denied" without Full Disk Access
+        tracing::warn!(
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "permission grants could not be read"
+        );
+        return None;
+    }
+
+    let grants = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split('\t');
+            let permission = Permission::from_service(columns.next()?)?;
+            let client = columns.next()?.to_string();
+            let auth_value = columns.next()?.parse::<u32>().ok()?;
+            let last_modified = columns.next().and_then(|value| value.parse().ok());
+            Some(PermissionGrant {
+                permission,
+                client,
+                allowed: auth_value == AUTH_VALUE_ALLOWED,
+                last_modified,
+            })
+        })
+        .collect();
+    Some(grants)
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use no_cluely_core::{is_cluely_process, DetectionError};
+
+use crate::sys;
+
+/// Kind of on-disk artifact an installation leaves behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum PersistenceKind {
+    Application,
+    LaunchAgent,
+    LaunchDaemon,
+    PrivilegedHelper,
+    ApplicationSupport,
+    Preferences,
+    Caches,
+    Logs,
+    SavedState,
+}
+
+impl PersistenceKind {
+    /// Human-readable name used in reports
+    pub fn label(&self) -> &'static str {
+        match self {
+            PersistenceKind::Application => "Application",
+            PersistenceKind::LaunchAgent => "Launch agent",
+            PersistenceKind::LaunchDaemon => "Launch daemon",
+            PersistenceKind::PrivilegedHelper => "Privileged helper",
+            PersistenceKind::ApplicationSupport => "Application support",
+            PersistenceKind::Preferences => "Preferences",
+            PersistenceKind::Caches => "Caches",
+            PersistenceKind::Logs => "Logs",
+            PersistenceKind::SavedState => "Saved application state",
+        }
+    }
+}
+
+/// A Cluely file or directory found on disk
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistenceItem {
+    pub kind: PersistenceKind,
+    pub path: PathBuf,
+}
+
+/// Cluely files under one user's home directory
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserPersistence {
+    pub user: String,
+    pub home: PathBuf,
+    pub items: Vec<PersistenceItem>,
+}
+
+impl UserPersistence {
+    /// Whether anything of Cluely's was found for this user
+    pub fn installed(&self) -> bool {
+        !self.items.is_empty()
+    }
+}
+
+/// Cluely files across the whole machine: the machine-wide locations, and
+/// every user's home directory
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistenceAudit {
+    pub system: Vec<PersistenceItem>,
+    pub users: Vec<UserPersistence>,
+}
+
+impl PersistenceAudit {
+    pub fn installed(&self) -> bool {
+        !self.system.is_empty() || self.users.iter().any(UserPersistence::installed)
+    }
+}
+
+/// Where macOS keeps home directories
+const USERS_DIR: &str = "/Users";
+
+// Machine-wide locations
+const SYSTEM_LOCATIONS: &[(&str, PersistenceKind)] = &[
+    ("/Applications", PersistenceKind::Application),
+    ("/Library/LaunchAgents", PersistenceKind::LaunchAgent),
+    ("/Library/LaunchDaemons", PersistenceKind::LaunchDaemon),
+    (
+        "/Library/PrivilegedHelperTools",
+        PersistenceKind::PrivilegedHelper,
+    ),
+    (
+        "/Library/Application Support",
+        PersistenceKind::ApplicationSupport,
+    ),
+    ("/Library/Preferences", PersistenceKind::Preferences),
+];
+
+// Locations relative to a home directory
+const USER_LOCATIONS: &[(&str, PersistenceKind)] = &[
+    ("Applications", PersistenceKind::Application),
+    ("Library/LaunchAgents", PersistenceKind::LaunchAgent),
+    (
+        "Library/Application Support",
+        PersistenceKind::ApplicationSupport,
+    ),
+    ("Library/Preferences", PersistenceKind::Preferences),
+    ("Library/Caches", PersistenceKind::Caches),
+    ("Library/HTTPStorages", PersistenceKind::Caches),
+    ("Library/WebKit", PersistenceKind::Caches),
+    ("Library/Logs", PersistenceKind::Logs),
+    (
+        "Library/Saved Application State",
+        PersistenceKind::SavedState,
+    ),
+];
+
+/// Files the detector itself writes in the locations above, matched by
+/// their exact name
+const DETECTOR_FILES: &[&str] = &[
+    "io.nocluely.cluely-detector.plist",
+    "no-cluely",
+    "cluely-detector",
+];
+
+fn scan_location(dir: &Path, kind: PersistenceKind, items: &mut Vec<PersistenceItem>) {
+    // Missing or unreadable directories simply have nothing to report
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if is_cluely_process(&name) && !DETECTOR_FILES.contains(&name.as_str()) {
+            tracing::trace!(path = %entry.path().display(), kind = kind.label(), "Cluely file");
+            items.push(PersistenceItem {
+                kind,
+                path: entry.path(),
+            });
+        }
+    }
+}
+
+fn scan_system(items: &mut Vec<PersistenceItem>) {
+    for (dir, kind) in SYSTEM_LOCATIONS {
+        scan_location(Path::new(dir), *kind, items);
+    }
+}
+
+fn scan_home(home: &Path, items: &mut Vec<PersistenceItem>) {
+    for (dir, kind) in USER_LOCATIONS {
+        scan_location(&home.join(dir), *kind, items);
+    }
+}
+
+/// Cluely artifacts in the machine-wide locations and the given home directory
+pub(crate) fn find_cluely_persistence(home: Option<&Path>) -> Vec<PersistenceItem> {
+    let mut items = Vec::new();
+
+    scan_system(&mut items);
+    if let Some(home) = home {
+        scan_home(home, &mut items);
+    }
+
+    items.sort_by(|a, b| a.path.cmp(&b.path));
+    tracing::debug!(found = items.len(), "persistence scan complete");
+    items
+}
+
+/// Cluely artifacts in the machine-wide locations and in every home
+/// directory under /Users. Other users' Library folders are private, so
+/// this needs root.
+pub(crate) fn audit_persistence() -> Result<PersistenceAudit, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+    if sys::effective_uid() != 0 {
+        return Err(DetectionError::PermissionDenied);
+    }
+
+    let mut system = Vec::new();
+    scan_system(&mut system);
+    system.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut users: Vec<UserPersistence> = fs::read_dir(USERS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter_map(|entry| {
+            let user = entry.file_name().to_string_lossy().into_owned();
+            // Shared is a drop folder every account can write to, not a user
+            if user.starts_with('.') || user == "Shared" {
+                return None;
+            }
+            let home = entry.path();
+            let mut items = Vec::new();
+            scan_home(&home, &mut items);
+            items.sort_by(|a, b| a.path.cmp(&b.path));
+            Some(UserPersistence { user, home, items })
+        })
+        .collect();
+    users.sort_by(|a, b| a.user.cmp(&b.user));
+
+    tracing::debug!(
+        system = system.len(),
+        users = users.len(),
+        installed_for = users.iter().filter(|user| user.installed()).count(),
+        "persistence audit complete"
+    );
+    Ok(PersistenceAudit { system, users })
+}
@@ -0,0 +1,52 @@
+//! Window placement against the live displays and Spaces.
+
+use no_cluely_core::{placement, DetectionError, DetectorConfig, EvasionScan, WindowPlacement};
+
+use crate::{display, sys, window_list};
+
+// Set on windows that appear on every Space (canJoinAllSpaces)
+pub(crate) const K_CGS_STICKY_TAG_BIT: u32 = 1 << 11;
+// Set on windows Mission Control leaves in place (stationary)
+pub(crate) const K_CGS_IGNORE_FOR_EXPOSE_TAG_BIT: u32 = 1 << 7;
+
+/// Whether the window appears on every Space, read from its window server
+/// tags. False if the tags cannot be read.
+pub(crate) fn is_on_all_spaces(window_id: u32) -> bool {
+    sys::window_tags(window_id).is_some_and(|tags| tags[0] & K_CGS_STICKY_TAG_BIT != 0)
+}
+
+/// Read the Space behavior of every flagged window in `scan`
+pub(crate) fn mark_space_behavior(scan: &mut EvasionScan) {
+    for finding in &mut scan.findings {
+        let Some(tags) = sys::window_tags(finding.window.window_id as u32) else {
+            continue;
+        };
+        finding.set_space_behavior(
+            tags[0] & K_CGS_STICKY_TAG_BIT != 0,
+            tags[0] & K_CGS_IGNORE_FOR_EXPOSE_TAG_BIT != 0,
+        );
+    }
+}
+
+/// Placement of every window; system windows are skipped unless
+/// `include_system` is set
+pub(crate) fn analyze_placement(
+    include_system: bool,
+) -> Result<Vec<WindowPlacement>, DetectionError> {
+    let _span = tracing::debug_span!("placement_scan", include_system).entered();
+    let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+    let displays = display::active_displays();
+
+    Ok(windows
+        .into_iter()
+        .filter(|window| include_system || !window.is_system_window())
+        .map(|window| WindowPlacement {
+            placement: window
+                .bounds
+                .as_ref()
+                .and_then(|bounds| placement(bounds, &displays)),
+            on_all_spaces: is_on_all_spaces(window.window_id as u32),
+            window,
+        })
+        .collect())
+}
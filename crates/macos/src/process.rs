@@ -0,0 +1,103 @@
+use std::sync::OnceLock;
+
+use no_cluely_core::{is_cluely_process, ProcessRole};
+
+use crate::ancestry::{self, LaunchOrigin, ParentProcess};
+use crate::sys;
+
+/// A running process
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub name: String,
+    pub path: String, // Empty if the executable path is not readable
+    /// What it does for its app: the app itself, a helper, an updater...
+    pub role: ProcessRole,
+    /// Parent processes, nearest first and ending at launchd
+    pub parents: Vec<ParentProcess>,
+    pub origin: LaunchOrigin,
+}
+
+impl ProcessInfo {
+    /// Read where `pid` came from
+    fn new(pid: i32, name: String, path: String) -> Self {
+        let parents = ancestry::parents(pid);
+        let origin = ancestry::launch_origin(pid, &parents);
+        Self {
+            pid,
+            role: ProcessRole::of(&name, &path),
+            name,
+            path,
+            parents,
+            origin,
+        }
+    }
+}
+
+/// Name and executable path of `pid`
+pub(crate) fn process_names(pid: i32) -> Option<(String, String)> {
+    let (path, name) = match (sys::pid_path(pid), sys::pid_name(pid)) {
+        // Process exited or belongs to another user
+        (None, None) => return None,
+        (path, name) => (path.unwrap_or_default(), name.unwrap_or_default()),
+    };
+
+    let name = if name.is_empty() {
+        path.rsplit('/').next().unwrap_or_default().to_string()
+    } else {
+        name
+    };
+
+    Some((name, path))
+}
+
+/// The bundle ID of the app a process runs from, if it runs from one
+pub(crate) fn bundle_id(pid: i32) -> Option<String> {
+    let path = sys::pid_path(pid)?;
+
+    // Helpers sit in bundles nested inside the app's own
+    // (Cluely.app/Contents/Frameworks/Cluely Helper.app/...); the innermost
+    // bundle is the process's
+    let bundle_path = &path[..path.rfind(".app/")? + ".app".len()];
+    sys::bundle_identifier(bundle_path)
+}
+
+/// Whether `pid` is this process, or another one running the same
+/// executable, such as the menu bar app while `check` runs.
+///
+/// Names are Cluely's to choose, so the detector is told apart by what it
+/// cannot fake. A copy of the binary elsewhere, as `simulate` starts, is
+/// not the detector.
+pub(crate) fn is_detector(pid: i32) -> bool {
+    static OWN_PATH: OnceLock<Option<String>> = OnceLock::new();
+    if pid == std::process::id() as i32 {
+        return true;
+    }
+    let own_path = OWN_PATH.get_or_init(|| sys::pid_path(std::process::id() as i32));
+    own_path
+        .as_ref()
+        .is_some_and(|own_path| sys::pid_path(pid).as_ref() == Some(own_path))
+}
+
+/// Every running process whose name or executable path looks like Cluely
+pub(crate) fn find_cluely_processes() -> Vec<ProcessInfo> {
+    let pids = sys::list_pids();
+
+    let processes: Vec<ProcessInfo> = pids
+        .iter()
+        .copied()
+        .filter(|&pid| pid > 0)
+        .filter_map(|pid| Some((pid, process_names(pid)?)))
+        .filter(|(_, (name, path))| is_cluely_process(name) || is_cluely_process(path))
+        .filter(|(pid, _)| !is_detector(*pid))
+        // Ancestry is only read for the few that match
+        .map(|(pid, (name, path))| ProcessInfo::new(pid, name, path))
+        .collect();
+    tracing::debug!(
+        scanned = pids.len(),
+        matched = processes.len(),
+        "process scan complete"
+    );
+    processes
+}
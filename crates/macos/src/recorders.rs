@@ -0,0 +1,143 @@
+//! Apps that can record the screen.
+//!
+//! macOS has no public way to ask which app is capturing the screen right
+//! now. An app that holds the Screen Recording grant and is running can
+//! start capturing at any moment without the user noticing, so that is what
+//! is reported as recording. Grants come from `permissions`, and are only
+//! readable with Full Disk Access.
+
+use std::collections::HashMap;
+
+use no_cluely_core::{meeting_window, DetectionError, DetectorConfig};
+
+use crate::permissions::{self, Permission, PermissionGrant};
+use crate::{process, sys, window_list};
+
+/// An app with a Screen Recording entry
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScreenRecorder {
+    /// Bundle ID, or executable path for apps without a bundle
+    pub client: String,
+    pub name: String,
+    /// Whether the grant is switched on in System Settings
+    pub allowed: bool,
+    /// When the grant was last changed, in seconds since the Unix epoch
+    pub last_modified: Option<u64>,
+    /// Running processes of the app, sorted
+    pub pids: Vec<i32>,
+    /// The app shows the meeting currently on screen
+    pub meeting_app: bool,
+}
+
+impl ScreenRecorder {
+    /// Allowed to record and running, so it can be capturing now
+    pub fn recording(&self) -> bool {
+        self.allowed && !self.pids.is_empty()
+    }
+}
+
+/// Every app with a Screen Recording entry
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScreenRecorders {
+    /// Recording apps first, then by name
+    pub recorders: Vec<ScreenRecorder>,
+    /// False if the TCC database could not be read (no Full Disk Access),
+    /// in which case `recorders` is empty
+    pub grants_readable: bool,
+}
+
+impl ScreenRecorders {
+    /// Apps that can be recording now, other than the meeting app
+    pub fn unexpected(&self) -> impl Iterator<Item = &ScreenRecorder> {
+        self.recorders
+            .iter()
+            .filter(|recorder| recorder.recording() && !recorder.meeting_app)
+    }
+}
+
+/// Running processes of `grant`'s app, by executable path or bundle ID
+fn running_pids(grant: &PermissionGrant, bundle_ids: &HashMap<i32, Option<String>>) -> Vec<i32> {
+    let mut pids: Vec<i32> = if grant.is_path() {
+        sys::list_pids()
+            .into_iter()
+            .filter(|&pid| sys::pid_path(pid).as_deref() == Some(grant.client.as_str()))
+            .collect()
+    } else {
+        bundle_ids
+            .iter()
+            .filter(|(_, id)| id.as_deref() == Some(grant.client.as_str()))
+            .map(|(&pid, _)| pid)
+            .collect()
+    };
+    pids.sort_unstable();
+    pids
+}
+
+/// Every app with a Screen Recording entry, and whether it is running
+pub(crate) fn find_screen_recorders() -> Result<ScreenRecorders, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+    let grants = match permissions::read_grants(&[Permission::ScreenRecording]) {
+        Some(grants) => grants,
+        None => return Ok(ScreenRecorders::default()),
+    };
+
+    let own_pid = std::process::id() as i32;
+    let bundle_ids: HashMap<i32, Option<String>> = sys::list_pids()
+        .into_iter()
+        .filter(|&pid| pid > 0 && pid != own_pid)
+        .map(|pid| (pid, process::bundle_id(pid)))
+        .collect();
+    // Without a window list (no GUI session) no meeting is seen
+    let meeting_pid = window_list::scan_all_windows(&DetectorConfig::default(), false)
+        .ok()
+        .and_then(|windows| meeting_window(&windows).map(|(window, _)| window.owner_pid));
+
+    let mut recorders: Vec<ScreenRecorder> = grants
+        .into_iter()
+        .map(|grant| {
+            let pids = running_pids(&grant, &bundle_ids);
+            let name = pids
+                .first()
+                .and_then(|&pid| sys::pid_name(pid))
+                .unwrap_or_else(|| {
+                    if grant.is_path() {
+                        grant
+                            .client
+                            .rsplit('/')
+                            .next()
+                            .unwrap_or_default()
+                            .to_string()
+                    } else {
+                        grant.client.clone()
+                    }
+                });
+            ScreenRecorder {
+                meeting_app: meeting_pid.is_some_and(|pid| pids.contains(&pid)),
+                client: grant.client,
+                name,
+                allowed: grant.allowed,
+                last_modified: grant.last_modified,
+                pids,
+            }
+        })
+        .collect();
+    recorders.sort_by(|a, b| {
+        b.recording()
+            .cmp(&a.recording())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    tracing::debug!(
+        grants = recorders.len(),
+        recording = recorders.iter().filter(|r| r.recording()).count(),
+        "screen recorder scan complete"
+    );
+    Ok(ScreenRecorders {
+        recorders,
+        grants_readable: true,
+    })
+}
@@ -0,0 +1,89 @@
+//! Cluely in other users' login sessions.
+//!
+//! With fast user switching several users can be logged in at once, each in
+//! a GUI session of their own. The window list only covers the session this
+//! process runs in, so other sessions are scanned through the process table,
+//! which only root can read in full. Each GUI session has a loginwindow
+//! process running as its user.
+
+use std::collections::BTreeMap;
+
+use no_cluely_core::DetectionError;
+
+use crate::process::{self, ProcessInfo};
+use crate::sys;
+
+/// A user account that is logged in to a GUI session, runs Cluely, or both
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserSession {
+    pub uid: u32,
+    pub user: String, // Empty if the account has no name
+    /// The user has a GUI session, on screen or switched out
+    pub logged_in: bool,
+    /// The user's session is the one on screen
+    pub on_console: bool,
+    pub cluely_processes: Vec<ProcessInfo>,
+}
+
+impl UserSession {
+    fn new(uid: u32, console_uid: Option<u32>) -> Self {
+        Self {
+            uid,
+            user: sys::user_name(uid).unwrap_or_default(),
+            logged_in: false,
+            on_console: console_uid == Some(uid),
+            cluely_processes: Vec::new(),
+        }
+    }
+
+    pub fn cluely_detected(&self) -> bool {
+        !self.cluely_processes.is_empty()
+    }
+}
+
+/// Every logged-in user, and every other user running Cluely, by uid
+pub(crate) fn scan_sessions() -> Result<Vec<UserSession>, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+    // Without root, other users' processes are silently left out
+    if sys::effective_uid() != 0 {
+        return Err(DetectionError::PermissionDenied);
+    }
+
+    let console_uid = sys::console_uid();
+    let mut sessions: BTreeMap<u32, UserSession> = BTreeMap::new();
+
+    for pid in sys::list_pids() {
+        if sys::pid_name(pid).as_deref() != Some("loginwindow") {
+            continue;
+        }
+        // At the login screen, loginwindow runs as root
+        if let Some(uid) = sys::pid_uid(pid).filter(|&uid| uid != 0) {
+            sessions
+                .entry(uid)
+                .or_insert_with(|| UserSession::new(uid, console_uid))
+                .logged_in = true;
+        }
+    }
+
+    for process in process::find_cluely_processes() {
+        // Exited since it was listed
+        let Some(uid) = sys::pid_uid(process.pid) else {
+            continue;
+        };
+        sessions
+            .entry(uid)
+            .or_insert_with(|| UserSession::new(uid, console_uid))
+            .cluely_processes
+            .push(process);
+    }
+
+    tracing::debug!(
+        users = sessions.len(),
+        detected = sessions.values().filter(|s| s.cluely_detected()).count(),
+        "session scan complete"
+    );
+    Ok(sessions.into_values().collect())
+}
@@ -0,0 +1,85 @@
+//! How Cluely's running executables are signed.
+//!
+//! Execution control tools such as Santa block a binary by its signature
+//! rather than its path, which a copy or rename would change. Each Cluely
+//! process's executable is read once for its signing Team ID (every app from
+//! the same developer), signing ID (one app) and CDHash (one exact build).
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use no_cluely_core::DetectionError;
+
+use crate::{process, sys};
+
+/// A Cluely executable and how it is signed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignedBinary {
+    pub path: String,
+    /// Running processes started from it
+    pub pids: Vec<i32>,
+    /// Team ID of the developer certificate; None if ad-hoc signed
+    pub team_id: Option<String>,
+    /// Signing identifier, usually the bundle ID, e.g. `com.cluely.app`
+    pub signing_id: Option<String>,
+    /// CDHash as 40 lowercase hex digits; None if unsigned
+    pub cdhash: Option<String>,
+}
+
+impl SignedBinary {
+    pub fn is_signed(&self) -> bool {
+        self.cdhash.is_some()
+    }
+}
+
+/// `bytes` as lowercase hex digits
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+/// The executable of every running Cluely process, sorted by path
+pub(crate) fn find_cluely_binaries() -> Result<Vec<SignedBinary>, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+    let mut pids_by_path: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    for process in process::find_cluely_processes() {
+        if !process.path.is_empty() {
+            pids_by_path
+                .entry(process.path)
+                .or_default()
+                .push(process.pid);
+        }
+    }
+
+    let binaries: Vec<SignedBinary> = pids_by_path
+        .into_iter()
+        .map(|(path, pids)| {
+            let signature = sys::code_signature(&path);
+            let (team_id, signing_id, cdhash) = match signature {
+                Some(signature) => (
+                    signature.team_id,
+                    signature.identifier,
+                    signature.cdhash.map(|cdhash| hex(&cdhash)),
+                ),
+                None => {
+                    tracing::warn!(path = %path, "could not read code signature");
+                    (None, None, None)
+                }
+            };
+            SignedBinary {
+                path,
+                pids,
+                team_id,
+                signing_id,
+                cdhash,
+            }
+        })
+        .collect();
+    tracing::debug!(binaries = binaries.len(), "code signature scan complete");
+    Ok(binaries)
+}
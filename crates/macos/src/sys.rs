@@ -0,0 +1,1287 @@
+//! Every call into Core Foundation, Core Graphics and libproc.
+//!
+//! The rest of the crate only sees the safe wrappers below. Objects this
+//! code creates or copies are released when their wrapper is dropped, and
+//! every value read from a dictionary has its type checked before it is
+//! used, so a window server that hands back something unexpected yields a
+//! missing value rather than a bad read. New bindings belong here too.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr::{self, NonNull};
+use std::sync::OnceLock;
+
+/// Whether this build can read anything from the running system
+pub(crate) const PLATFORM_SUPPORTED: bool = true;
+
+type CFTypeRef = *const c_void;
+
+#[repr(C)]
+struct CFRange {
+    location: isize,
+    length: isize,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct CGPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct CGSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub(crate) struct CGRect {
+    pub origin: CGPoint,
+    pub size: CGSize,
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFRelease(cf_type: CFTypeRef);
+    fn CFRetain(cf_type: CFTypeRef) -> CFTypeRef;
+    fn CFGetTypeID(cf_type: CFTypeRef) -> usize;
+
+    fn CFArrayGetTypeID() -> usize;
+    fn CFArrayGetCount(array: CFTypeRef) -> isize;
+    fn CFArrayGetValueAtIndex(array: CFTypeRef, index: isize) -> CFTypeRef;
+
+    fn CFDictionaryGetTypeID() -> usize;
+    fn CFDictionaryGetValue(dict: CFTypeRef, key: CFTypeRef) -> CFTypeRef;
+
+    fn CFStringGetTypeID() -> usize;
+    fn CFStringCreateWithCString(
+        allocator: CFTypeRef,
+        c_str: *const c_char,
+        encoding: u32,
+    ) -> CFTypeRef;
+    fn CFStringGetCStringPtr(string: CFTypeRef, encoding: u32) -> *const c_char;
+    fn CFStringGetLength(string: CFTypeRef) -> isize;
+    fn CFStringGetMaximumSizeForEncoding(length: isize, encoding: u32) -> isize;
+    fn CFStringGetBytes(
+        string: CFTypeRef,
+        range: CFRange,
+        encoding: u32,
+        loss_byte: u8,
+        is_external_representation: bool,
+        buffer: *mut u8,
+        max_buffer_length: isize,
+        used_buffer_length: *mut isize,
+    ) -> isize;
+
+    fn CFNumberGetTypeID() -> usize;
+    fn CFNumberGetValue(number: CFTypeRef, number_type: c_int, value_ptr: *mut c_void) -> bool;
+
+    fn CFBooleanGetTypeID() -> usize;
+    fn CFBooleanGetValue(boolean: CFTypeRef) -> bool;
+
+    fn CFDataGetTypeID() -> usize;
+    fn CFDataGetLength(data: CFTypeRef) -> isize;
+    fn CFDataGetBytePtr(data: CFTypeRef) -> *const u8;
+
+    fn CFURLCreateFromFileSystemRepresentation(
+        allocator: CFTypeRef,
+        buffer: *const u8,
+        buffer_length: isize,
+        is_directory: bool,
+    ) -> CFTypeRef;
+    fn CFBundleCreate(allocator: CFTypeRef, bundle_url: CFTypeRef) -> CFTypeRef;
+    fn CFBundleGetIdentifier(bundle: CFTypeRef) -> CFTypeRef;
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    static CGRectNull: CGRect;
+
+    fn CGWindowListCopyWindowInfo(option: u32, relative_window_id: u32) -> CFTypeRef;
+    fn CGWindowListCreateImage(
+        screen_bounds: CGRect,
+        list_option: u32,
+        window_id: u32,
+        image_option: u32,
+    ) -> CFTypeRef;
+    fn CGImageGetWidth(image: CFTypeRef) -> usize;
+    fn CGImageGetHeight(image: CFTypeRef) -> usize;
+    fn CGImageGetBitsPerPixel(image: CFTypeRef) -> usize;
+    fn CGImageGetBytesPerRow(image: CFTypeRef) -> usize;
+    fn CGImageGetBitmapInfo(image: CFTypeRef) -> u32;
+    fn CGImageGetDataProvider(image: CFTypeRef) -> CFTypeRef;
+    fn CGDataProviderCopyData(provider: CFTypeRef) -> CFTypeRef;
+
+    fn CGGetActiveDisplayList(
+        max_displays: u32,
+        active_displays: *mut u32,
+        display_count: *mut u32,
+    ) -> i32;
+    fn CGDisplayBounds(display: u32) -> CGRect;
+    fn CGMainDisplayID() -> u32;
+
+    fn CGPreflightScreenCaptureAccess() -> bool;
+
+    // Private window server calls, exported by CoreGraphics on every macOS
+    // release since 10.5
+    fn CGSMainConnectionID() -> i32;
+    fn CGSGetWindowTags(connection: i32, window_id: u32, tags: *mut u32, tag_size: i32) -> i32;
+}
+
+// More private window server calls, only with the `private-cgs` feature.
+// They read the window server's own record of a window rather than the
+// snapshot in the window list.
+#[cfg(feature = "private-cgs")]
+extern "C" {
+    fn CGSGetWindowSharingState(connection: i32, window_id: u32, state: *mut i32) -> i32;
+    fn CGSGetWindowLevel(connection: i32, window_id: u32, level: *mut i32) -> i32;
+    fn CGSGetWindowAlpha(connection: i32, window_id: u32, alpha: *mut f32) -> i32;
+    fn CGSGetWindowOwner(connection: i32, window_id: u32, owner: *mut i32) -> i32;
+    fn CGSConnectionGetPID(connection: i32, pid: *mut i32) -> i32;
+}
+
+// Accessibility (HIServices, inside ApplicationServices)
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn AXUIElementGetTypeID() -> usize;
+    fn AXUIElementCreateApplication(pid: c_int) -> CFTypeRef;
+    fn AXUIElementCopyAttributeValue(
+        element: CFTypeRef,
+        attribute: CFTypeRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
+    fn AXUIElementSetMessagingTimeout(element: CFTypeRef, timeout_seconds: f32) -> i32;
+    // Private, but the only way from an AX window to its window server ID;
+    // exported since macOS 10.5
+    fn _AXUIElementGetWindow(element: CFTypeRef, window_id: *mut u32) -> i32;
+}
+
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn SecStaticCodeCreateWithPath(path: CFTypeRef, flags: u32, static_code: *mut CFTypeRef)
+        -> i32;
+    fn SecCodeCopySigningInformation(
+        code: CFTypeRef,
+        flags: u32,
+        information: *mut CFTypeRef,
+    ) -> i32;
+    fn SecStaticCodeCheckValidity(code: CFTypeRef, flags: u32, requirement: CFTypeRef) -> i32;
+    fn SecCodeCopySelf(flags: u32, code: *mut CFTypeRef) -> i32;
+    fn SecCodeCheckValidity(code: CFTypeRef, flags: u32, requirement: CFTypeRef) -> i32;
+}
+
+// CommonCrypto (part of libSystem)
+extern "C" {
+    fn CC_SHA256(data: *const c_void, length: u32, digest: *mut u8) -> *mut u8;
+}
+
+#[repr(C)]
+struct DlInfo {
+    dli_fname: *const c_char,
+    dli_fbase: *mut c_void,
+    dli_sname: *const c_char,
+    dli_saddr: *mut c_void,
+}
+
+// dyld (part of libSystem)
+extern "C" {
+    fn dladdr(address: *const c_void, info: *mut DlInfo) -> c_int;
+}
+
+// libproc (part of libSystem)
+extern "C" {
+    fn proc_listallpids(buffer: *mut c_void, buffer_size: c_int) -> c_int;
+    fn proc_pidpath(pid: c_int, buffer: *mut c_void, buffer_size: u32) -> c_int;
+    fn proc_name(pid: c_int, buffer: *mut c_void, buffer_size: u32) -> c_int;
+    fn proc_pidinfo(
+        pid: c_int,
+        flavor: c_int,
+        arg: u64,
+        buffer: *mut c_void,
+        buffer_size: c_int,
+    ) -> c_int;
+    fn proc_pidfdinfo(
+        pid: c_int,
+        fd: c_int,
+        flavor: c_int,
+        buffer: *mut c_void,
+        buffer_size: c_int,
+    ) -> c_int;
+}
+
+// sysctl (part of libSystem)
+extern "C" {
+    fn sysctl(
+        name: *mut c_int,
+        name_length: u32,
+        old: *mut c_void,
+        old_length: *mut usize,
+        new: *mut c_void,
+        new_length: usize,
+    ) -> c_int;
+}
+
+// Mach time (part of libSystem)
+extern "C" {
+    fn mach_timebase_info(info: *mut MachTimebaseInfo) -> c_int;
+}
+
+// User accounts (part of libSystem)
+extern "C" {
+    fn geteuid() -> u32;
+    fn getpwuid_r(
+        uid: u32,
+        pwd: *mut Passwd,
+        buffer: *mut c_char,
+        buffer_size: usize,
+        result: *mut *mut Passwd,
+    ) -> c_int;
+}
+
+/// `struct proc_bsdinfo` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct ProcBsdInfo {
+    flags: u32,
+    status: u32,
+    xstatus: u32,
+    pid: u32,
+    ppid: u32,
+    uid: u32,
+    gid: u32,
+    ruid: u32,
+    rgid: u32,
+    svuid: u32,
+    svgid: u32,
+    rfu_1: u32,
+    comm: [c_char; 16],
+    name: [c_char; 32],
+    nfiles: u32,
+    pgid: u32,
+    pjobc: u32,
+    e_tdev: u32,
+    e_tpgid: u32,
+    nice: i32,
+    start_tvsec: u64,
+    start_tvusec: u64,
+}
+
+/// `struct proc_taskinfo` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct ProcTaskInfo {
+    virtual_size: u64,
+    resident_size: u64,
+    total_user: u64,   // Mach absolute time units
+    total_system: u64, // Mach absolute time units
+    threads_user: u64,
+    threads_system: u64,
+    policy: i32,
+    faults: i32,
+    pageins: i32,
+    cow_faults: i32,
+    messages_sent: i32,
+    messages_received: i32,
+    syscalls_mach: i32,
+    syscalls_unix: i32,
+    csw: i32,
+    threadnum: i32,
+    numrunning: i32,
+    priority: i32,
+}
+
+/// `struct proc_fdinfo` from sys/proc_info.h
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ProcFdInfo {
+    fd: i32,
+    fd_type: u32,
+}
+
+/// `struct proc_fileinfo` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct ProcFileInfo {
+    open_flags: u32,
+    status: u32,
+    offset: i64,
+    file_type: i32,
+    guard_flags: u32,
+}
+
+/// `struct vinfo_stat` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct VinfoStat {
+    dev: u32,
+    mode: u16,
+    nlink: u16,
+    ino: u64,
+    uid: u32,
+    gid: u32,
+    atime: i64,
+    atimensec: i64,
+    mtime: i64,
+    mtimensec: i64,
+    ctime: i64,
+    ctimensec: i64,
+    birthtime: i64,
+    birthtimensec: i64,
+    size: i64,
+    blocks: i64,
+    blksize: i32,
+    flags: u32,
+    gen: u32,
+    rdev: u32,
+    qspare: [i64; 2],
+}
+
+/// `struct vnode_fdinfowithpath` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct VnodeFdInfoWithPath {
+    file: ProcFileInfo,
+    stat: VinfoStat,
+    vnode_type: i32,
+    pad: i32,
+    fsid: [i32; 2],
+    path: [c_char; MAXPATHLEN],
+}
+
+/// `struct pshm_fdinfo` from sys/proc_info.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct PshmFdInfo {
+    file: ProcFileInfo,
+    stat: VinfoStat,
+    mapped_address: u64,
+    name: [c_char; MAXPATHLEN],
+}
+
+/// `struct mach_timebase_info` from mach/mach_time.h
+#[repr(C)]
+#[derive(Default)]
+struct MachTimebaseInfo {
+    numer: u32,
+    denom: u32,
+}
+
+/// `struct passwd` from pwd.h
+#[repr(C)]
+#[allow(dead_code)] // Laid out in full; only some fields are read
+struct Passwd {
+    pw_name: *mut c_char,
+    pw_passwd: *mut c_char,
+    pw_uid: u32,
+    pw_gid: u32,
+    pw_change: i64,
+    pw_class: *mut c_char,
+    pw_gecos: *mut c_char,
+    pw_dir: *mut c_char,
+    pw_shell: *mut c_char,
+    pw_expire: i64,
+}
+
+// Constants
+const K_CF_STRING_ENCODING_UTF8: u32 = 0x08000100;
+const K_CF_NUMBER_INT_TYPE: c_int = 9;
+const K_CF_NUMBER_FLOAT64_TYPE: c_int = 6;
+const K_CG_BITMAP_BYTE_ORDER_MASK: u32 = 0x7000;
+const K_CG_BITMAP_BYTE_ORDER_32_LITTLE: u32 = 2 << 12;
+const K_SEC_CS_SIGNING_INFORMATION: u32 = 1 << 1;
+const PROC_PIDPATHINFO_MAXSIZE: usize = 4096;
+const PROC_NAME_MAXSIZE: usize = 256;
+const PROC_PIDTBSDINFO: c_int = 3;
+const PROC_PIDTASKINFO: c_int = 4;
+const PROC_PIDLISTFDS: c_int = 1;
+const PROC_PIDFDVNODEPATHINFO: c_int = 2;
+const PROC_PIDFDPSHMINFO: c_int = 5;
+const PROX_FDTYPE_VNODE: u32 = 1;
+const PROX_FDTYPE_PSHM: u32 = 5;
+const MAXPATHLEN: usize = 1024;
+const CTL_KERN: c_int = 1;
+const KERN_ARGMAX: c_int = 8;
+const KERN_PROCARGS2: c_int = 49;
+
+/// A Core Foundation object this code holds a reference to, released on
+/// drop
+struct Owned(NonNull<c_void>);
+
+impl Owned {
+    /// Take over the reference returned by a Create or Copy call; None if
+    /// the call returned null
+    ///
+    /// # Safety
+    /// `object` must be null or a reference the caller owns.
+    unsafe fn from_create(object: CFTypeRef) -> Option<Self> {
+        NonNull::new(object as *mut c_void).map(Self)
+    }
+
+    fn as_ptr(&self) -> CFTypeRef {
+        self.0.as_ptr()
+    }
+}
+
+impl Drop for Owned {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.as_ptr()) }
+    }
+}
+
+/// Whether `value` is a non-null object of the CF type `type_id`
+fn has_type(value: CFTypeRef, type_id: usize) -> bool {
+    !value.is_null() && unsafe { CFGetTypeID(value) } == type_id
+}
+
+/// A new CFString holding `s`; None if `s` contains a NUL
+fn create_cfstring(s: &str) -> Option<Owned> {
+    let c_str = CString::new(s).ok()?;
+    unsafe {
+        Owned::from_create(CFStringCreateWithCString(
+            ptr::null(),
+            c_str.as_ptr(),
+            K_CF_STRING_ENCODING_UTF8,
+        ))
+    }
+}
+
+/// A dictionary key. Its CFString is created on first use and kept for the
+/// life of the process, rather than created and released on every lookup.
+pub(crate) struct CFKey {
+    name: &'static str,
+    cf_string: OnceLock<Option<KeyString>>,
+}
+
+struct KeyString(Owned);
+
+// CFStrings are immutable, so one can be used from any thread
+unsafe impl Send for KeyString {}
+unsafe impl Sync for KeyString {}
+
+impl CFKey {
+    pub(crate) const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            cf_string: OnceLock::new(),
+        }
+    }
+
+    fn get(&self) -> Option<CFTypeRef> {
+        self.cf_string
+            .get_or_init(|| create_cfstring(self.name).map(KeyString))
+            .as_ref()
+            .map(|key| key.0.as_ptr())
+    }
+}
+
+thread_local! {
+    /// Conversion buffer for strings CoreFoundation can't hand out directly.
+    /// Grows to fit the longest string converted on this thread.
+    static STRING_BUFFER: RefCell<Vec<u8>> = RefCell::new(vec![0; 1024]);
+}
+
+/// The contents of a CFString; None if it could not be converted whole
+///
+/// # Safety
+/// `cf_string` must be a valid CFString.
+unsafe fn cfstring_to_string(cf_string: CFTypeRef) -> Option<String> {
+    let c_str_ptr = CFStringGetCStringPtr(cf_string, K_CF_STRING_ENCODING_UTF8);
+    if !c_str_ptr.is_null() {
+        return Some(CStr::from_ptr(c_str_ptr).to_string_lossy().into_owned());
+    }
+
+    // Size the buffer for the whole string, so long titles aren't cut off
+    // and multibyte characters are never split
+    let length = CFStringGetLength(cf_string);
+    let max_size = usize::try_from(CFStringGetMaximumSizeForEncoding(
+        length,
+        K_CF_STRING_ENCODING_UTF8,
+    ))
+    .ok()?;
+
+    STRING_BUFFER.with_borrow_mut(|buffer| {
+        if buffer.len() < max_size {
+            buffer.resize(max_size, 0);
+        }
+
+        let mut used: isize = 0;
+        let converted = CFStringGetBytes(
+            cf_string,
+            CFRange {
+                location: 0,
+                length,
+            },
+            K_CF_STRING_ENCODING_UTF8,
+            0,
+            false,
+            buffer.as_mut_ptr(),
+            buffer.len() as isize,
+            &mut used,
+        );
+
+        let used = usize::try_from(used)
+            .ok()
+            .filter(|&used| used <= buffer.len())?;
+        (converted == length).then(|| String::from_utf8_lossy(&buffer[..used]).into_owned())
+    })
+}
+
+/// A dictionary inside a `WindowList`, valid for as long as the list
+#[derive(Clone, Copy)]
+pub(crate) struct Dictionary<'a> {
+    dict: NonNull<c_void>,
+    _list: PhantomData<&'a WindowList>,
+}
+
+// The window list and the dictionaries in it are immutable CoreFoundation
+// objects, which may be read from several threads at once. Nothing is
+// retained or released through a Dictionary, and the borrow keeps the list
+// alive until every dictionary is done with.
+unsafe impl Send for Dictionary<'_> {}
+unsafe impl Sync for Dictionary<'_> {}
+
+impl<'a> Dictionary<'a> {
+    /// Wrap `value` if it is a dictionary
+    ///
+    /// # Safety
+    /// `value` must be null or a valid CF object that outlives `'a`.
+    unsafe fn checked(value: CFTypeRef) -> Option<Self> {
+        has_type(value, CFDictionaryGetTypeID()).then(|| Self {
+            dict: NonNull::new_unchecked(value as *mut c_void),
+            _list: PhantomData,
+        })
+    }
+
+    /// The value for `key`, if present and of the CF type `type_id`
+    fn value(&self, key: &CFKey, type_id: usize) -> Option<CFTypeRef> {
+        let value = unsafe { CFDictionaryGetValue(self.dict.as_ptr(), key.get()?) };
+        has_type(value, type_id).then_some(value)
+    }
+
+    pub(crate) fn string(&self, key: &CFKey) -> Option<String> {
+        let value = self.value(key, unsafe { CFStringGetTypeID() })?;
+        unsafe { cfstring_to_string(value) }
+    }
+
+    /// The value for `key` if it is a number that fits an i32 exactly
+    pub(crate) fn int(&self, key: &CFKey) -> Option<i32> {
+        let value = self.value(key, unsafe { CFNumberGetTypeID() })?;
+        let mut result: i32 = 0;
+        let exact = unsafe {
+            CFNumberGetValue(
+                value,
+                K_CF_NUMBER_INT_TYPE,
+                &mut result as *mut i32 as *mut c_void,
+            )
+        };
+        exact.then_some(result)
+    }
+
+    pub(crate) fn float(&self, key: &CFKey) -> Option<f64> {
+        let value = self.value(key, unsafe { CFNumberGetTypeID() })?;
+        let mut result: f64 = 0.0;
+        // A lossy conversion (a 64-bit integer) still stores the nearest
+        // double, which is what callers want
+        unsafe {
+            CFNumberGetValue(
+                value,
+                K_CF_NUMBER_FLOAT64_TYPE,
+                &mut result as *mut f64 as *mut c_void,
+            );
+        }
+        Some(result)
+    }
+
+    pub(crate) fn bool(&self, key: &CFKey) -> Option<bool> {
+        let value = self.value(key, unsafe { CFBooleanGetTypeID() })?;
+        Some(unsafe { CFBooleanGetValue(value) })
+    }
+
+    pub(crate) fn dictionary(&self, key: &CFKey) -> Option<Dictionary<'a>> {
+        let value = unsafe { CFDictionaryGetValue(self.dict.as_ptr(), key.get()?) };
+        unsafe { Dictionary::checked(value) }
+    }
+}
+
+/// A snapshot of the window server's window list
+pub(crate) struct WindowList {
+    array: Owned,
+}
+
+impl WindowList {
+    /// Copy the window list for `option` relative to `relative_window_id`;
+    /// None if the window server returns nothing
+    pub(crate) fn copy(option: u32, relative_window_id: u32) -> Option<Self> {
+        let array =
+            unsafe { Owned::from_create(CGWindowListCopyWindowInfo(option, relative_window_id))? };
+        Some(Self { array })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        let count = unsafe { CFArrayGetCount(self.array.as_ptr()) };
+        usize::try_from(count).unwrap_or(0)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The window dictionaries, front to back. Entries that are not
+    /// dictionaries are skipped.
+    pub(crate) fn windows(&self) -> impl Iterator<Item = Dictionary<'_>> {
+        (0..self.len()).filter_map(move |i| unsafe {
+            Dictionary::checked(CFArrayGetValueAtIndex(self.array.as_ptr(), i as isize))
+        })
+    }
+}
+
+/// The pixel data of a 32-bit window image
+pub(crate) struct ImageData {
+    data: Owned,
+    pub width: usize,
+    pub height: usize,
+    pub bytes_per_row: usize,
+    /// Pixels are stored little-endian (BGRA in memory) rather than ARGB
+    pub little_endian: bool,
+}
+
+impl ImageData {
+    /// `bytes_per_row * height` bytes, at least `width * 4` per row
+    pub(crate) fn bytes(&self) -> &[u8] {
+        unsafe {
+            std::slice::from_raw_parts(
+                CFDataGetBytePtr(self.data.as_ptr()),
+                self.bytes_per_row * self.height,
+            )
+        }
+    }
+}
+
+/// Capture `window_id` with `CGWindowListCreateImage`. None if the window
+/// is gone, or the image is empty, not 32 bits per pixel, or shorter than
+/// its own dimensions.
+pub(crate) fn create_window_image(
+    window_id: u32,
+    list_option: u32,
+    image_option: u32,
+) -> Option<ImageData> {
+    unsafe {
+        let image = Owned::from_create(CGWindowListCreateImage(
+            CGRectNull,
+            list_option,
+            window_id,
+            image_option,
+        ))?;
+        let image = image.as_ptr();
+
+        let width = CGImageGetWidth(image);
+        let height = CGImageGetHeight(image);
+        let bytes_per_row = CGImageGetBytesPerRow(image);
+        let little_endian = CGImageGetBitmapInfo(image) & K_CG_BITMAP_BYTE_ORDER_MASK
+            == K_CG_BITMAP_BYTE_ORDER_32_LITTLE;
+        if width == 0
+            || height == 0
+            || CGImageGetBitsPerPixel(image) != 32
+            || bytes_per_row < width.checked_mul(4)?
+        {
+            return None;
+        }
+
+        let provider = CGImageGetDataProvider(image);
+        if provider.is_null() {
+            return None;
+        }
+        let data = Owned::from_create(CGDataProviderCopyData(provider))?;
+        let length = usize::try_from(CFDataGetLength(data.as_ptr())).ok()?;
+        if length < bytes_per_row.checked_mul(height)? || CFDataGetBytePtr(data.as_ptr()).is_null()
+        {
+            return None;
+        }
+
+        Some(ImageData {
+            data,
+            width,
+            height,
+            bytes_per_row,
+            little_endian,
+        })
+    }
+}
+
+/// IDs of up to `max` active displays, or the CGError if they could not be
+/// listed
+pub(crate) fn active_display_ids(max: usize) -> Result<Vec<u32>, i32> {
+    let mut ids = vec![0u32; max];
+    let mut count = 0u32;
+    let error = unsafe { CGGetActiveDisplayList(max as u32, ids.as_mut_ptr(), &mut count) };
+    if error != 0 {
+        return Err(error);
+    }
+    ids.truncate(count as usize);
+    Ok(ids)
+}
+
+pub(crate) fn main_display_id() -> u32 {
+    unsafe { CGMainDisplayID() }
+}
+
+/// A display's bounds in global coordinates
+pub(crate) fn display_bounds(display_id: u32) -> CGRect {
+    unsafe { CGDisplayBounds(display_id) }
+}
+
+/// Whether this process may capture the screen, without prompting
+pub(crate) fn screen_capture_access() -> bool {
+    unsafe { CGPreflightScreenCaptureAccess() }
+}
+
+/// Whether this process may read other apps' UI through the Accessibility
+/// API, without prompting
+pub(crate) fn accessibility_access() -> bool {
+    unsafe { AXIsProcessTrusted() }
+}
+
+/// An element in another app's accessibility tree
+pub(crate) struct AXElement(Owned);
+
+// AXUIElements are immutable references to another process's UI; messages
+// sent through them are serialized by the Accessibility API
+unsafe impl Send for AXElement {}
+
+impl AXElement {
+    /// The application element for `pid`. Messages to it, and to every
+    /// element read through it, give up after `timeout_seconds`, so a hung
+    /// app cannot stall a scan.
+    pub(crate) fn application(pid: i32, timeout_seconds: f32) -> Option<Self> {
+        let element = Self(unsafe { Owned::from_create(AXUIElementCreateApplication(pid))? });
+        unsafe { AXUIElementSetMessagingTimeout(element.0.as_ptr(), timeout_seconds) };
+        Some(element)
+    }
+
+    /// The value of `attribute`, owned by the caller; None on any AX error
+    fn copy_attribute(&self, attribute: &CFKey) -> Option<Owned> {
+        let mut value: CFTypeRef = ptr::null();
+        let error =
+            unsafe { AXUIElementCopyAttributeValue(self.0.as_ptr(), attribute.get()?, &mut value) };
+        if error != 0 {
+            return None;
+        }
+        unsafe { Owned::from_create(value) }
+    }
+
+    /// The value of `attribute` if it is a string
+    pub(crate) fn string(&self, attribute: &CFKey) -> Option<String> {
+        let value = self.copy_attribute(attribute)?;
+        unsafe {
+            if !has_type(value.as_ptr(), CFStringGetTypeID()) {
+                return None;
+            }
+            cfstring_to_string(value.as_ptr())
+        }
+    }
+
+    /// The elements in `attribute` if it is an array; entries that are not
+    /// elements are skipped
+    pub(crate) fn elements(&self, attribute: &CFKey) -> Vec<AXElement> {
+        let Some(array) = self.copy_attribute(attribute) else {
+            return Vec::new();
+        };
+        unsafe {
+            if !has_type(array.as_ptr(), CFArrayGetTypeID()) {
+                return Vec::new();
+            }
+            (0..CFArrayGetCount(array.as_ptr()))
+                .map(|i| CFArrayGetValueAtIndex(array.as_ptr(), i))
+                .filter(|&value| has_type(value, AXUIElementGetTypeID()))
+                // The array holds the only reference, so each kept element
+                // gets one of its own
+                .filter_map(|value| Owned::from_create(CFRetain(value)).map(AXElement))
+                .collect()
+        }
+    }
+
+    /// The window server ID of a window element
+    pub(crate) fn window_id(&self) -> Option<u32> {
+        let mut window_id = 0u32;
+        let error = unsafe { _AXUIElementGetWindow(self.0.as_ptr(), &mut window_id) };
+        (error == 0).then_some(window_id)
+    }
+}
+
+/// A window's window server tags; None if they cannot be read
+pub(crate) fn window_tags(window_id: u32) -> Option<[u32; 2]> {
+    let mut tags = [0u32; 2];
+    let error = unsafe {
+        CGSGetWindowTags(
+            CGSMainConnectionID(),
+            window_id,
+            tags.as_mut_ptr(),
+            (tags.len() * 32) as i32,
+        )
+    };
+    (error == 0).then_some(tags)
+}
+
+/// Read one value through a `CGSGetWindow*` call; None on a CGError
+#[cfg(feature = "private-cgs")]
+fn read_window_value<T: Default>(
+    window_id: u32,
+    call: unsafe extern "C" fn(i32, u32, *mut T) -> i32,
+) -> Option<T> {
+    let mut value = T::default();
+    let error = unsafe { call(CGSMainConnectionID(), window_id, &mut value) };
+    (error == 0).then_some(value)
+}
+
+/// The window server's sharing state for a window
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_sharing_state(window_id: u32) -> Option<i32> {
+    read_window_value(window_id, CGSGetWindowSharingState)
+}
+
+/// The window server's level for a window
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_level(window_id: u32) -> Option<i32> {
+    read_window_value(window_id, CGSGetWindowLevel)
+}
+
+/// The window server's alpha for a window
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_alpha(window_id: u32) -> Option<f32> {
+    read_window_value(window_id, CGSGetWindowAlpha)
+}
+
+/// The process behind the window server connection that owns a window
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_owner_pid(window_id: u32) -> Option<i32> {
+    let connection = read_window_value(window_id, CGSGetWindowOwner)?;
+    let mut pid = 0;
+    let error = unsafe { CGSConnectionGetPID(connection, &mut pid) };
+    (error == 0).then_some(pid)
+}
+
+/// Every process ID on the system
+pub(crate) fn list_pids() -> Vec<i32> {
+    unsafe {
+        let count = proc_listallpids(ptr::null_mut(), 0);
+        if count <= 0 {
+            return Vec::new();
+        }
+
+        // Leave room for processes started between the two calls
+        let mut pids = vec![0i32; count as usize + 64];
+        let count = proc_listallpids(
+            pids.as_mut_ptr() as *mut c_void,
+            (pids.len() * std::mem::size_of::<i32>()) as c_int,
+        );
+        pids.truncate(count.max(0) as usize);
+        pids
+    }
+}
+
+fn buffer_to_string(buffer: &[u8]) -> String {
+    CStr::from_bytes_until_nul(buffer)
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The executable path of `pid`; None if it exited or belongs to another
+/// user
+pub(crate) fn pid_path(pid: i32) -> Option<String> {
+    let mut path = vec![0u8; PROC_PIDPATHINFO_MAXSIZE];
+    let length = unsafe { proc_pidpath(pid, path.as_mut_ptr() as *mut c_void, path.len() as u32) };
+    (length > 0).then(|| buffer_to_string(&path))
+}
+
+/// The name of `pid`; None if it exited or belongs to another user
+pub(crate) fn pid_name(pid: i32) -> Option<String> {
+    let mut name = vec![0u8; PROC_NAME_MAXSIZE];
+    let length = unsafe { proc_name(pid, name.as_mut_ptr() as *mut c_void, name.len() as u32) };
+    (length > 0).then(|| buffer_to_string(&name))
+}
+
+/// One `proc_pidinfo` flavor, whose struct is `T`
+fn pid_info<T>(pid: i32, flavor: c_int) -> Option<T> {
+    let mut info = std::mem::MaybeUninit::<T>::zeroed();
+    let size = std::mem::size_of::<T>() as c_int;
+    let written = unsafe { proc_pidinfo(pid, flavor, 0, info.as_mut_ptr() as *mut c_void, size) };
+    // A short read means the call failed part-way
+    (written == size).then(|| unsafe { info.assume_init() })
+}
+
+/// The BSD info of `pid`; None if it exited or belongs to another user
+fn pid_bsd_info(pid: i32) -> Option<ProcBsdInfo> {
+    pid_info(pid, PROC_PIDTBSDINFO)
+}
+
+/// CPU time `pid` has used so far, user and system; None if it exited or
+/// belongs to another user
+pub(crate) fn pid_cpu_time(pid: i32) -> Option<std::time::Duration> {
+    static TIMEBASE: OnceLock<(u64, u64)> = OnceLock::new();
+    let info: ProcTaskInfo = pid_info(pid, PROC_PIDTASKINFO)?;
+    // Task times are in Mach ticks, which are only nanoseconds on Intel
+    let &(numer, denom) = TIMEBASE.get_or_init(|| {
+        let mut timebase = MachTimebaseInfo::default();
+        match unsafe { mach_timebase_info(&mut timebase) } {
+            0 if timebase.denom != 0 => (timebase.numer as u64, timebase.denom as u64),
+            _ => (1, 1),
+        }
+    });
+    let ticks = info.total_user.saturating_add(info.total_system);
+    let nanos = (ticks as u128 * numer as u128 / denom as u128) as u64;
+    Some(std::time::Duration::from_nanos(nanos))
+}
+
+/// The parent of `pid`; None if it exited or belongs to another user
+pub(crate) fn pid_parent(pid: i32) -> Option<i32> {
+    pid_bsd_info(pid).map(|info| info.ppid as i32)
+}
+
+/// The environment `pid` was started with, as `KEY=VALUE` strings. Only
+/// readable for this user's processes, or any as root.
+pub(crate) fn pid_environment(pid: i32) -> Option<Vec<String>> {
+    let mut arg_max: c_int = 0;
+    let mut size = std::mem::size_of::<c_int>();
+    let mut name = [CTL_KERN, KERN_ARGMAX];
+    let status = unsafe {
+        sysctl(
+            name.as_mut_ptr(),
+            name.len() as u32,
+            &mut arg_max as *mut c_int as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if status != 0 || arg_max <= 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u8; arg_max as usize];
+    let mut size = buffer.len();
+    let mut name = [CTL_KERN, KERN_PROCARGS2, pid];
+    let status = unsafe {
+        sysctl(
+            name.as_mut_ptr(),
+            name.len() as u32,
+            buffer.as_mut_ptr() as *mut c_void,
+            &mut size,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if status != 0 {
+        return None;
+    }
+    buffer.truncate(size);
+
+    // argc, the executable path padded with NULs, argc arguments, then the
+    // environment, each string NUL-terminated
+    let argc = i32::from_ne_bytes(buffer.get(..4)?.try_into().ok()?).max(0) as usize;
+    let mut strings = buffer[4..]
+        .split(|&byte| byte == 0)
+        .filter(|string| !string.is_empty());
+    strings.next()?;
+    Some(
+        strings
+            .skip(argc)
+            .map(|string| String::from_utf8_lossy(string).into_owned())
+            .collect(),
+    )
+}
+
+/// A file or shared memory object a process has open
+pub(crate) enum OpenFile {
+    File(String),
+    /// Named with `shm_open`; the name is not a path
+    SharedMemory(String),
+}
+
+/// One `proc_pidfdinfo` flavor for descriptor `fd`, whose struct is `T`
+fn fd_info<T>(pid: i32, fd: i32, flavor: c_int) -> Option<T> {
+    let mut info = std::mem::MaybeUninit::<T>::zeroed();
+    let size = std::mem::size_of::<T>() as c_int;
+    let written =
+        unsafe { proc_pidfdinfo(pid, fd, flavor, info.as_mut_ptr() as *mut c_void, size) };
+    (written == size).then(|| unsafe { info.assume_init() })
+}
+
+fn c_chars_to_string(chars: &[c_char]) -> String {
+    let bytes: Vec<u8> = chars.iter().map(|&c| c as u8).collect();
+    buffer_to_string(&bytes)
+}
+
+/// Files and shared memory `pid` has open, skipping sockets, pipes and
+/// descriptors closed while they were read. None if it exited or belongs to
+/// another user.
+pub(crate) fn pid_open_files(pid: i32) -> Option<Vec<OpenFile>> {
+    let entry_size = std::mem::size_of::<ProcFdInfo>() as c_int;
+    let size = unsafe { proc_pidinfo(pid, PROC_PIDLISTFDS, 0, ptr::null_mut(), 0) };
+    if size <= 0 {
+        return None;
+    }
+
+    // Leave room for descriptors opened between the two calls
+    let mut fds = vec![ProcFdInfo::default(); (size / entry_size) as usize + 16];
+    let written = unsafe {
+        proc_pidinfo(
+            pid,
+            PROC_PIDLISTFDS,
+            0,
+            fds.as_mut_ptr() as *mut c_void,
+            fds.len() as c_int * entry_size,
+        )
+    };
+    if written <= 0 {
+        return None;
+    }
+    fds.truncate((written / entry_size) as usize);
+
+    Some(
+        fds.into_iter()
+            .filter_map(|fd| match fd.fd_type {
+                PROX_FDTYPE_VNODE => {
+                    let info: VnodeFdInfoWithPath = fd_info(pid, fd.fd, PROC_PIDFDVNODEPATHINFO)?;
+                    Some(OpenFile::File(c_chars_to_string(&info.path)))
+                }
+                PROX_FDTYPE_PSHM => {
+                    let info: PshmFdInfo = fd_info(pid, fd.fd, PROC_PIDFDPSHMINFO)?;
+                    Some(OpenFile::SharedMemory(c_chars_to_string(&info.name)))
+                }
+                _ => None,
+            })
+            .filter(|file| match file {
+                OpenFile::File(path) | OpenFile::SharedMemory(path) => !path.is_empty(),
+            })
+            .collect(),
+    )
+}
+
+/// The user ID `pid` runs as; None if it exited or belongs to another user
+pub(crate) fn pid_uid(pid: i32) -> Option<u32> {
+    pid_bsd_info(pid).map(|info| info.uid)
+}
+
+/// The effective user ID of this process
+pub(crate) fn effective_uid() -> u32 {
+    unsafe { geteuid() }
+}
+
+/// The login name of `uid`, if the account exists
+pub(crate) fn user_name(uid: u32) -> Option<String> {
+    let mut passwd = std::mem::MaybeUninit::<Passwd>::zeroed();
+    let mut buffer = vec![0 as c_char; 4096];
+    let mut result: *mut Passwd = ptr::null_mut();
+    unsafe {
+        let error = getpwuid_r(
+            uid,
+            passwd.as_mut_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if error != 0 || result.is_null() || (*result).pw_name.is_null() {
+            return None;
+        }
+        Some(
+            CStr::from_ptr((*result).pw_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// The user whose GUI session is on screen, from the owner of /dev/console
+pub(crate) fn console_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata("/dev/console")
+        .ok()
+        .map(|metadata| metadata.uid())
+}
+
+/// `kSecCodeInfoTeamIdentifier`, the signing Team ID in signing information
+static CODE_INFO_TEAM_ID: CFKey = CFKey::new("teamid");
+/// `kSecCodeInfoIdentifier`, the signing identifier
+static CODE_INFO_IDENTIFIER: CFKey = CFKey::new("identifier");
+/// `kSecCodeInfoUnique`, the CDHash of the code directory
+static CODE_INFO_UNIQUE: CFKey = CFKey::new("unique");
+
+/// How code is signed; every field is None for unsigned code
+pub(crate) struct CodeSignature {
+    /// None if ad-hoc signed or signed by Apple
+    pub team_id: Option<String>,
+    pub identifier: Option<String>,
+    pub cdhash: Option<Vec<u8>>,
+}
+
+/// How the code at `path` (an executable or a bundle) is signed; None if
+/// it cannot be read
+pub(crate) fn code_signature(path: &str) -> Option<CodeSignature> {
+    unsafe {
+        let url = Owned::from_create(CFURLCreateFromFileSystemRepresentation(
+            ptr::null(),
+            path.as_ptr(),
+            path.len() as isize,
+            path.ends_with(".app"),
+        ))?;
+        let mut code: CFTypeRef = ptr::null();
+        if SecStaticCodeCreateWithPath(url.as_ptr(), 0, &mut code) != 0 {
+            return None;
+        }
+        let code = Owned::from_create(code)?;
+        let mut information: CFTypeRef = ptr::null();
+        if SecCodeCopySigningInformation(
+            code.as_ptr(),
+            K_SEC_CS_SIGNING_INFORMATION,
+            &mut information,
+        ) != 0
+        {
+            return None;
+        }
+        let information = Owned::from_create(information)?;
+
+        let string = |key: &CFKey| {
+            let value = CFDictionaryGetValue(information.as_ptr(), key.get()?);
+            if !has_type(value, CFStringGetTypeID()) {
+                return None;
+            }
+            cfstring_to_string(value).filter(|value| !value.is_empty())
+        };
+        let cdhash = CODE_INFO_UNIQUE.get().and_then(|key| {
+            let value = CFDictionaryGetValue(information.as_ptr(), key);
+            if !has_type(value, CFDataGetTypeID()) || CFDataGetBytePtr(value).is_null() {
+                return None;
+            }
+            let length = usize::try_from(CFDataGetLength(value)).ok()?;
+            Some(std::slice::from_raw_parts(CFDataGetBytePtr(value), length).to_vec())
+        });
+        Some(CodeSignature {
+            team_id: string(&CODE_INFO_TEAM_ID),
+            identifier: string(&CODE_INFO_IDENTIFIER),
+            cdhash,
+        })
+    }
+}
+
+/// `errSecCSUnsigned`
+const ERR_SEC_CS_UNSIGNED: i32 = -67062;
+/// `kSecCSCheckAllArchitectures`
+const K_SEC_CS_CHECK_ALL_ARCHITECTURES: u32 = 1 << 0;
+
+/// Whether code still matches its signature
+pub(crate) enum SignatureCheck {
+    Valid,
+    Unsigned,
+    /// The OSStatus the check failed with
+    Invalid(i32),
+}
+
+impl SignatureCheck {
+    fn from_status(status: i32) -> Self {
+        match status {
+            0 => SignatureCheck::Valid,
+            ERR_SEC_CS_UNSIGNED => SignatureCheck::Unsigned,
+            status => SignatureCheck::Invalid(status),
+        }
+    }
+}
+
+/// Whether the file at `path` still matches its code signature; None if
+/// it cannot be read
+pub(crate) fn check_signature(path: &str) -> Option<SignatureCheck> {
+    unsafe {
+        let url = Owned::from_create(CFURLCreateFromFileSystemRepresentation(
+            ptr::null(),
+            path.as_ptr(),
+            path.len() as isize,
+            path.ends_with(".app"),
+        ))?;
+        let mut code: CFTypeRef = ptr::null();
+        if SecStaticCodeCreateWithPath(url.as_ptr(), 0, &mut code) != 0 {
+            return None;
+        }
+        let code = Owned::from_create(code)?;
+        Some(SignatureCheck::from_status(SecStaticCodeCheckValidity(
+            code.as_ptr(),
+            K_SEC_CS_CHECK_ALL_ARCHITECTURES,
+            ptr::null(),
+        )))
+    }
+}
+
+/// Whether this process's code, as loaded and as the kernel has seen it
+/// paged in since, still matches its signature
+pub(crate) fn check_own_signature() -> Option<SignatureCheck> {
+    unsafe {
+        let mut code: CFTypeRef = ptr::null();
+        if SecCodeCopySelf(0, &mut code) != 0 {
+            return None;
+        }
+        let code = Owned::from_create(code)?;
+        Some(SignatureCheck::from_status(SecCodeCheckValidity(
+            code.as_ptr(),
+            0,
+            ptr::null(),
+        )))
+    }
+}
+
+/// SHA-256 of `data`; None if it is too large for one call
+pub(crate) fn sha256(data: &[u8]) -> Option<[u8; 32]> {
+    let length = u32::try_from(data.len()).ok()?;
+    let mut digest = [0u8; 32];
+    unsafe {
+        CC_SHA256(data.as_ptr().cast(), length, digest.as_mut_ptr());
+    }
+    Some(digest)
+}
+
+/// The file the code at `address` was loaded from: the executable, or the
+/// library it is in
+pub(crate) fn image_path(address: *const c_void) -> Option<String> {
+    unsafe {
+        let mut info: DlInfo = std::mem::zeroed();
+        if dladdr(address, &mut info) == 0 || info.dli_fname.is_null() {
+            return None;
+        }
+        Some(
+            CStr::from_ptr(info.dli_fname)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// The Team ID of the certificate the code at `path` (an executable or a
+/// bundle) is signed with; None if it is unsigned, ad-hoc signed or signed
+/// by Apple
+pub(crate) fn team_identifier(path: &str) -> Option<String> {
+    code_signature(path)?.team_id
+}
+
+/// The identifier of the bundle at `bundle_path`, if it has one
+pub(crate) fn bundle_identifier(bundle_path: &str) -> Option<String> {
+    unsafe {
+        let url = Owned::from_create(CFURLCreateFromFileSystemRepresentation(
+            ptr::null(),
+            bundle_path.as_ptr(),
+            bundle_path.len() as isize,
+            true,
+        ))?;
+        let bundle = Owned::from_create(CFBundleCreate(ptr::null(), url.as_ptr()))?;
+
+        // The identifier belongs to the bundle, so it is read before the
+        // bundle is released
+        let identifier = CFBundleGetIdentifier(bundle.as_ptr());
+        if !has_type(identifier, CFStringGetTypeID()) {
+            return None;
+        }
+        cfstring_to_string(identifier).filter(|id| !id.is_empty())
+    }
+}
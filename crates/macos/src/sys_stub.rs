@@ -0,0 +1,281 @@
+//! Stand-in for `sys` on platforms without a backend yet.
+//!
+//! Mirrors the API of `sys.rs` so the rest of the crate compiles unchanged,
+//! but nothing can be read: the window list is never available, and the
+//! display, process and file lookups come back empty. Scans report
+//! `DetectionError::PlatformUnsupported` (see `window_list.rs`).
+
+use std::convert::Infallible;
+use std::marker::PhantomData;
+
+/// Whether this build can read anything from the running system
+pub(crate) const PLATFORM_SUPPORTED: bool = false;
+
+#[derive(Clone, Copy)]
+pub(crate) struct CGPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct CGSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct CGRect {
+    pub origin: CGPoint,
+    pub size: CGSize,
+}
+
+/// A dictionary key. There are no dictionaries to look it up in.
+pub(crate) struct CFKey;
+
+impl CFKey {
+    pub(crate) const fn new(_name: &'static str) -> Self {
+        Self
+    }
+}
+
+/// A dictionary inside a `WindowList`; it cannot be constructed here
+#[derive(Clone, Copy)]
+pub(crate) struct Dictionary<'a> {
+    never: Infallible,
+    _list: PhantomData<&'a WindowList>,
+}
+
+impl<'a> Dictionary<'a> {
+    pub(crate) fn string(&self, _key: &CFKey) -> Option<String> {
+        match self.never {}
+    }
+
+    pub(crate) fn int(&self, _key: &CFKey) -> Option<i32> {
+        match self.never {}
+    }
+
+    pub(crate) fn float(&self, _key: &CFKey) -> Option<f64> {
+        match self.never {}
+    }
+
+    pub(crate) fn bool(&self, _key: &CFKey) -> Option<bool> {
+        match self.never {}
+    }
+
+    pub(crate) fn dictionary(&self, _key: &CFKey) -> Option<Dictionary<'a>> {
+        match self.never {}
+    }
+}
+
+/// A snapshot of the window list; it cannot be constructed here
+pub(crate) struct WindowList {
+    never: Infallible,
+}
+
+impl WindowList {
+    pub(crate) fn copy(_option: u32, _relative_window_id: u32) -> Option<Self> {
+        None
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self.never {}
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        match self.never {}
+    }
+
+    pub(crate) fn windows(&self) -> impl Iterator<Item = Dictionary<'_>> {
+        match self.never {}
+        #[allow(unreachable_code)]
+        std::iter::empty()
+    }
+}
+
+/// The pixel data of a 32-bit window image
+pub(crate) struct ImageData {
+    never: Infallible,
+    pub width: usize,
+    pub height: usize,
+    pub bytes_per_row: usize,
+    pub little_endian: bool,
+}
+
+impl ImageData {
+    pub(crate) fn bytes(&self) -> &[u8] {
+        match self.never {}
+    }
+}
+
+pub(crate) fn create_window_image(
+    _window_id: u32,
+    _list_option: u32,
+    _image_option: u32,
+) -> Option<ImageData> {
+    None
+}
+
+pub(crate) fn active_display_ids(_max: usize) -> Result<Vec<u32>, i32> {
+    Ok(Vec::new())
+}
+
+pub(crate) fn main_display_id() -> u32 {
+    0
+}
+
+pub(crate) fn display_bounds(_display_id: u32) -> CGRect {
+    CGRect {
+        origin: CGPoint { x: 0.0, y: 0.0 },
+        size: CGSize {
+            width: 0.0,
+            height: 0.0,
+        },
+    }
+}
+
+pub(crate) fn screen_capture_access() -> bool {
+    false
+}
+
+pub(crate) fn accessibility_access() -> bool {
+    false
+}
+
+/// An element in another app's accessibility tree; it cannot be constructed here
+pub(crate) struct AXElement {
+    never: Infallible,
+}
+
+impl AXElement {
+    pub(crate) fn application(_pid: i32, _timeout_seconds: f32) -> Option<Self> {
+        None
+    }
+
+    pub(crate) fn string(&self, _attribute: &CFKey) -> Option<String> {
+        match self.never {}
+    }
+
+    pub(crate) fn elements(&self, _attribute: &CFKey) -> Vec<AXElement> {
+        match self.never {}
+    }
+
+    pub(crate) fn window_id(&self) -> Option<u32> {
+        match self.never {}
+    }
+}
+
+pub(crate) fn window_tags(_window_id: u32) -> Option<[u32; 2]> {
+    None
+}
+
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_sharing_state(_window_id: u32) -> Option<i32> {
+    None
+}
+
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_level(_window_id: u32) -> Option<i32> {
+    None
+}
+
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_alpha(_window_id: u32) -> Option<f32> {
+    None
+}
+
+#[cfg(feature = "private-cgs")]
+pub(crate) fn window_owner_pid(_window_id: u32) -> Option<i32> {
+    None
+}
+
+pub(crate) fn list_pids() -> Vec<i32> {
+    Vec::new()
+}
+
+pub(crate) fn pid_path(_pid: i32) -> Option<String> {
+    None
+}
+
+pub(crate) fn pid_name(_pid: i32) -> Option<String> {
+    None
+}
+
+pub(crate) fn bundle_identifier(_bundle_path: &str) -> Option<String> {
+    None
+}
+
+#[allow(dead_code)] // Never constructed: no code signature can be read
+pub(crate) struct CodeSignature {
+    pub team_id: Option<String>,
+    pub identifier: Option<String>,
+    pub cdhash: Option<Vec<u8>>,
+}
+
+pub(crate) fn code_signature(_path: &str) -> Option<CodeSignature> {
+    None
+}
+
+pub(crate) fn team_identifier(_path: &str) -> Option<String> {
+    None
+}
+
+#[allow(dead_code)] // Never constructed: no signature can be checked
+pub(crate) enum SignatureCheck {
+    Valid,
+    Unsigned,
+    Invalid(i32),
+}
+
+pub(crate) fn check_signature(_path: &str) -> Option<SignatureCheck> {
+    None
+}
+
+pub(crate) fn check_own_signature() -> Option<SignatureCheck> {
+    None
+}
+
+pub(crate) fn sha256(_data: &[u8]) -> Option<[u8; 32]> {
+    None
+}
+
+pub(crate) fn image_path(_address: *const std::os::raw::c_void) -> Option<String> {
+    None
+}
+
+pub(crate) fn pid_uid(_pid: i32) -> Option<u32> {
+    None
+}
+
+pub(crate) fn pid_parent(_pid: i32) -> Option<i32> {
+    None
+}
+
+pub(crate) fn pid_environment(_pid: i32) -> Option<Vec<String>> {
+    None
+}
+
+pub(crate) fn pid_cpu_time(_pid: i32) -> Option<std::time::Duration> {
+    None
+}
+
+#[allow(dead_code)] // Never constructed: no process can be read
+pub(crate) enum OpenFile {
+    File(String),
+    SharedMemory(String),
+}
+
+pub(crate) fn pid_open_files(_pid: i32) -> Option<Vec<OpenFile>> {
+    None
+}
+
+pub(crate) fn effective_uid() -> u32 {
+    u32::MAX
+}
+
+pub(crate) fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+pub(crate) fn console_uid() -> Option<u32> {
+    None
+}
@@ -0,0 +1,236 @@
+//! How Cluely's windows change between scans.
+//!
+//! A single scan shows where a window is; a series of them shows what it
+//! does. An overlay that jumps above the meeting window whenever the
+//! meeting app comes to the front is acting on purpose, which says far more
+//! than any one position could.
+//!
+//! The window list is ordered front to back, so a window's place in it is
+//! its z-order.
+
+use std::collections::HashMap;
+
+use no_cluely_core::{meeting_window, DetectionError, DetectorConfig, ScannedWindow, WindowBounds};
+
+use crate::window_list;
+
+/// A change in a Cluely window seen between two scans
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WindowBehavior {
+    LayerChanged {
+        window_id: i32,
+        owner: String,
+        from: i32,
+        to: i32,
+    },
+    /// Moved, resized, or both
+    BoundsChanged {
+        window_id: i32,
+        owner: String,
+        from: WindowBounds,
+        to: WindowBounds,
+    },
+    /// Now in front of the meeting window it was behind
+    RaisedAboveMeeting {
+        window_id: i32,
+        owner: String,
+        meeting_app: String,
+        /// Owner of the frontmost normal window at the time
+        frontmost_app: Option<String>,
+    },
+    /// Now behind the meeting window it was in front of
+    LoweredBelowMeeting {
+        window_id: i32,
+        owner: String,
+        meeting_app: String,
+    },
+}
+
+impl WindowBehavior {
+    pub fn window_id(&self) -> i32 {
+        match self {
+            WindowBehavior::LayerChanged { window_id, .. }
+            | WindowBehavior::BoundsChanged { window_id, .. }
+            | WindowBehavior::RaisedAboveMeeting { window_id, .. }
+            | WindowBehavior::LoweredBelowMeeting { window_id, .. } => *window_id,
+        }
+    }
+
+    /// One-line summary for logs and alerts
+    pub fn describe(&self) -> String {
+        match self {
+            WindowBehavior::LayerChanged {
+                window_id,
+                owner,
+                from,
+                to,
+            } => format!(
+                "{} window {} changed layer from {} to {}",
+                owner, window_id, from, to
+            ),
+            WindowBehavior::BoundsChanged {
+                window_id,
+                owner,
+                from,
+                to,
+            } => {
+                let moved = (from.x, from.y) != (to.x, to.y);
+                let resized = (from.width, from.height) != (to.width, to.height);
+                let change = match (moved, resized) {
+                    (true, false) => format!(
+                        "moved from ({}, {}) to ({}, {})",
+                        from.x, from.y, to.x, to.y
+                    ),
+                    (false, true) => format!(
+                        "resized from {}x{} to {}x{}",
+                        from.width, from.height, to.width, to.height
+                    ),
+                    _ => format!(
+                        "moved and resized to {}x{} at ({}, {})",
+                        to.width, to.height, to.x, to.y
+                    ),
+                };
+                format!("{} window {} {}", owner, window_id, change)
+            }
+            WindowBehavior::RaisedAboveMeeting {
+                window_id,
+                owner,
+                meeting_app,
+                frontmost_app,
+            } => {
+                let mut description = format!(
+                    "{} window {} moved above the {} meeting window",
+                    owner, window_id, meeting_app
+                );
+                if let Some(frontmost_app) = frontmost_app {
+                    description.push_str(&format!(" while {} was frontmost", frontmost_app));
+                }
+                description
+            }
+            WindowBehavior::LoweredBelowMeeting {
+                window_id,
+                owner,
+                meeting_app,
+            } => format!(
+                "{} window {} dropped below the {} meeting window",
+                owner, window_id, meeting_app
+            ),
+        }
+    }
+}
+
+/// What was last seen of a Cluely window
+#[derive(Debug, Clone)]
+struct TrackedWindow {
+    layer: i32,
+    bounds: Option<WindowBounds>,
+    /// In front of the meeting window; None without a meeting on screen
+    above_meeting: Option<bool>,
+}
+
+/// Follows Cluely's windows from scan to scan and reports how they change.
+/// Windows that appear or close between scans are not reported.
+#[derive(Debug, Default)]
+pub struct WindowTracker {
+    known: HashMap<i32, TrackedWindow>,
+}
+
+impl WindowTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan every window and report what changed since the last call
+    pub fn poll(&mut self) -> Result<Vec<WindowBehavior>, DetectionError> {
+        let windows = window_list::scan_all_windows(&DetectorConfig::default(), false)?;
+        Ok(self.observe(&windows))
+    }
+
+    /// Report what changed since the last call, given windows in window
+    /// list order (front to back)
+    pub fn observe(&mut self, windows: &[ScannedWindow]) -> Vec<WindowBehavior> {
+        let onscreen: Vec<&ScannedWindow> =
+            windows.iter().filter(|window| window.is_onscreen).collect();
+        let rank_of = |window_id: i32| {
+            onscreen
+                .iter()
+                .position(|window| window.window_id == window_id)
+        };
+        let meeting = meeting_window(windows)
+            .and_then(|(window, app)| Some((rank_of(window.window_id)?, app)));
+        let frontmost_app = onscreen
+            .iter()
+            .find(|window| window.layer == 0)
+            .map(|window| window.owner.clone());
+
+        let mut behaviors = Vec::new();
+        let mut seen = HashMap::with_capacity(self.known.len());
+        for window in windows.iter().filter(|window| window.is_cluely_related()) {
+            let current = TrackedWindow {
+                layer: window.layer,
+                bounds: window.bounds,
+                above_meeting: meeting
+                    .and_then(|(meeting_rank, _)| Some(rank_of(window.window_id)? < meeting_rank)),
+            };
+
+            if let Some(previous) = self.known.get(&window.window_id) {
+                let window_id = window.window_id;
+                let owner = || window.owner.clone();
+                if previous.layer != current.layer {
+                    behaviors.push(WindowBehavior::LayerChanged {
+                        window_id,
+                        owner: owner(),
+                        from: previous.layer,
+                        to: current.layer,
+                    });
+                }
+                if let (Some(from), Some(to)) = (previous.bounds, current.bounds) {
+                    if from != to {
+                        behaviors.push(WindowBehavior::BoundsChanged {
+                            window_id,
+                            owner: owner(),
+                            from,
+                            to,
+                        });
+                    }
+                }
+                if let (Some((_, meeting_app)), Some(was_above), Some(is_above)) =
+                    (meeting, previous.above_meeting, current.above_meeting)
+                {
+                    if is_above && !was_above {
+                        behaviors.push(WindowBehavior::RaisedAboveMeeting {
+                            window_id,
+                            owner: owner(),
+                            meeting_app: meeting_app.to_string(),
+                            frontmost_app: frontmost_app.clone(),
+                        });
+                    } else if was_above && !is_above {
+                        behaviors.push(WindowBehavior::LoweredBelowMeeting {
+                            window_id,
+                            owner: owner(),
+                            meeting_app: meeting_app.to_string(),
+                        });
+                    }
+                }
+            }
+            seen.insert(window.window_id, current);
+        }
+        self.known = seen;
+
+        for behavior in &behaviors {
+            tracing::debug!(
+                window_id = behavior.window_id(),
+                behavior = %behavior.describe(),
+                "window behavior"
+            );
+        }
+        behaviors
+    }
+
+    /// Forget every window, so the next scan sets a fresh starting point
+    pub fn reset(&mut self) {
+        self.known.clear();
+    }
+}
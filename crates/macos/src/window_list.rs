@@ -0,0 +1,176 @@
+//! Reading the window list from the window server.
+
+use std::time::Instant;
+
+use no_cluely_core::{
+    parse_window, DetectionError, DetectorConfig, ScannedWindow, WindowDictionary, WindowKey,
+    WindowOrder,
+};
+
+use crate::matcher::TargetMatcher;
+use crate::process;
+use crate::sys::{self, CFKey, Dictionary, WindowList};
+
+// CGWindowListOption flags, as defined in CGWindow.h
+pub(crate) const K_CG_WINDOW_LIST_OPTION_ALL: u32 = 0;
+pub(crate) const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY: u32 = 1 << 0;
+pub(crate) const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ABOVE_WINDOW: u32 = 1 << 1;
+pub(crate) const K_CG_WINDOW_LIST_OPTION_ON_SCREEN_BELOW_WINDOW: u32 = 1 << 2;
+pub(crate) const K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+pub(crate) const K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS: u32 = 1 << 4;
+pub(crate) const K_CG_NULL_WINDOW_ID: u32 = 0;
+
+// Window property keys, for `WindowKey` and for the fields read directly
+pub(crate) static WINDOW_OWNER_NAME: CFKey = CFKey::new("kCGWindowOwnerName");
+pub(crate) static WINDOW_OWNER_PID: CFKey = CFKey::new("kCGWindowOwnerPID");
+pub(crate) static WINDOW_SHARING_STATE: CFKey = CFKey::new("kCGWindowSharingState");
+pub(crate) static WINDOW_LAYER: CFKey = CFKey::new("kCGWindowLayer");
+pub(crate) static WINDOW_NUMBER: CFKey = CFKey::new("kCGWindowNumber");
+pub(crate) static WINDOW_NAME: CFKey = CFKey::new("kCGWindowName");
+pub(crate) static WINDOW_ALPHA: CFKey = CFKey::new("kCGWindowAlpha");
+static WINDOW_IS_ONSCREEN: CFKey = CFKey::new("kCGWindowIsOnscreen");
+static WINDOW_BOUNDS: CFKey = CFKey::new("kCGWindowBounds");
+static WINDOW_STORE_TYPE: CFKey = CFKey::new("kCGWindowStoreType");
+static WINDOW_BACKING_TYPE: CFKey = CFKey::new("kCGWindowBackingType");
+
+// Bounds dictionary keys
+static BOUNDS_X: CFKey = CFKey::new("X");
+static BOUNDS_Y: CFKey = CFKey::new("Y");
+static BOUNDS_WIDTH: CFKey = CFKey::new("Width");
+static BOUNDS_HEIGHT: CFKey = CFKey::new("Height");
+
+fn cf_key(key: WindowKey) -> &'static CFKey {
+    match key {
+        WindowKey::OwnerName => &WINDOW_OWNER_NAME,
+        WindowKey::OwnerPid => &WINDOW_OWNER_PID,
+        WindowKey::SharingState => &WINDOW_SHARING_STATE,
+        WindowKey::Layer => &WINDOW_LAYER,
+        WindowKey::Number => &WINDOW_NUMBER,
+        WindowKey::Name => &WINDOW_NAME,
+        WindowKey::Alpha => &WINDOW_ALPHA,
+        WindowKey::IsOnscreen => &WINDOW_IS_ONSCREEN,
+        WindowKey::Bounds => &WINDOW_BOUNDS,
+        WindowKey::StoreType => &WINDOW_STORE_TYPE,
+        WindowKey::BackingType => &WINDOW_BACKING_TYPE,
+        WindowKey::BoundsX => &BOUNDS_X,
+        WindowKey::BoundsY => &BOUNDS_Y,
+        WindowKey::BoundsWidth => &BOUNDS_WIDTH,
+        WindowKey::BoundsHeight => &BOUNDS_HEIGHT,
+    }
+}
+
+/// Arguments for `CGWindowListCopyWindowInfo`: the option flags and the
+/// reference window
+fn window_list_options(config: &DetectorConfig) -> (u32, u32) {
+    let mut option = K_CG_WINDOW_LIST_OPTION_ALL;
+    if config.on_screen_only {
+        option |= K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY;
+    }
+    if config.exclude_desktop_elements {
+        option |= K_CG_WINDOW_LIST_EXCLUDE_DESKTOP_ELEMENTS;
+    }
+
+    let Some(relative) = config.relative_to else {
+        return (option, K_CG_NULL_WINDOW_ID);
+    };
+    // The above/below options replace on-screen-only rather than combine with it
+    option &= !K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ONLY;
+    option |= match relative.order {
+        WindowOrder::Above => K_CG_WINDOW_LIST_OPTION_ON_SCREEN_ABOVE_WINDOW,
+        WindowOrder::Below => K_CG_WINDOW_LIST_OPTION_ON_SCREEN_BELOW_WINDOW,
+    };
+    if relative.including {
+        option |= K_CG_WINDOW_LIST_OPTION_INCLUDING_WINDOW;
+    }
+    (option, relative.window_id)
+}
+
+/// Copy the window list that `config` asks for
+pub(crate) fn copy_window_list(config: &DetectorConfig) -> Result<WindowList, DetectionError> {
+    if !sys::PLATFORM_SUPPORTED {
+        return Err(DetectionError::PlatformUnsupported);
+    }
+
+    let (option, relative_window_id) = window_list_options(config);
+    let Some(window_list) = WindowList::copy(option, relative_window_id) else {
+        tracing::warn!("window server returned no window list");
+        return Err(DetectionError::WindowListUnavailable);
+    };
+
+    // A GUI session always has windows (menu bar, Dock), so an empty
+    // list means there is no window server to ask. When the list is
+    // relative to another window, empty is a valid answer.
+    if window_list.is_empty() && config.relative_to.is_none() {
+        tracing::warn!("window list is empty; no GUI session");
+        return Err(DetectionError::WindowListUnavailable);
+    }
+
+    Ok(window_list)
+}
+
+impl<'a> WindowDictionary for Dictionary<'a> {
+    fn string(&self, key: WindowKey) -> Option<String> {
+        self.string(cf_key(key))
+    }
+
+    fn int(&self, key: WindowKey) -> Option<i32> {
+        self.int(cf_key(key))
+    }
+
+    fn float(&self, key: WindowKey) -> Option<f64> {
+        self.float(cf_key(key))
+    }
+
+    fn bool(&self, key: WindowKey) -> Option<bool> {
+        self.bool(cf_key(key))
+    }
+
+    fn dictionary(&self, key: WindowKey) -> Option<Self> {
+        self.dictionary(cf_key(key))
+    }
+}
+
+/// Parse window dictionaries, across the rayon thread pool if `parallel`
+fn parse_windows(window_dicts: &[Dictionary], parallel: bool) -> Vec<ScannedWindow> {
+    #[cfg(feature = "parallel")]
+    if parallel {
+        use rayon::prelude::*;
+        return window_dicts.par_iter().map(parse_window).collect();
+    }
+    #[cfg(not(feature = "parallel"))]
+    debug_assert!(!parallel, "parallel parsing needs the `parallel` feature");
+
+    window_dicts.iter().map(parse_window).collect()
+}
+
+/// Read the windows `config` selects from the window server
+pub(crate) fn scan_all_windows(
+    config: &DetectorConfig,
+    parallel: bool,
+) -> Result<Vec<ScannedWindow>, DetectionError> {
+    let started = Instant::now();
+
+    let window_list = copy_window_list(config)?;
+    let window_dicts: Vec<Dictionary> = window_list.windows().collect();
+    let mut all_windows = parse_windows(&window_dicts, parallel);
+    // The detector's own windows, such as the menu bar item, look like
+    // Cluely's by name
+    all_windows
+        .retain(|window| !(window.is_cluely_related() && process::is_detector(window.owner_pid)));
+    let mut targets = TargetMatcher::new(&config.targets);
+    for window in all_windows.iter_mut() {
+        window.target = targets
+            .target(window.owner_pid, &window.owner)
+            .map(str::to_string);
+    }
+    #[cfg(feature = "private-cgs")]
+    let all_windows = crate::cgs::apply_sharing_state(all_windows);
+
+    tracing::debug!(
+        windows = all_windows.len(),
+        parallel,
+        duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+        "read window list"
+    );
+    Ok(all_windows)
+}
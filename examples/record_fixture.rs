@@ -0,0 +1,53 @@
+//! Record the current window list as a benchmark fixture.
+//!
+//! ```text
+//! cargo run --example record_fixture > benches/fixtures/my-desktop.tsv
+//! ```
+//!
+//! One tab-separated line per window, in the column order of the header.
+//! Window titles need Screen Recording permission; without it they are empty.
+
+use no_cluely_driver::scan_all_rust;
+
+fn main() {
+    let scan = match scan_all_rust(true) {
+        Ok(scan) => scan,
+        Err(e) => {
+            eprintln!("Failed to get window list: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    println!("# window_id\towner_pid\tlayer\tsharing_state\tstore_type\tbacking_type\tis_onscreen\talpha\tx\ty\twidth\theight\towner\tname");
+    for window in &scan.windows {
+        let bounds = match window.bounds {
+            Some(b) => format!("{}\t{}\t{}\t{}", b.x, b.y, b.width, b.height),
+            None => "\t\t\t".to_string(),
+        };
+        // The scanner fills in a placeholder for untitled windows
+        let name = if window.name == "<No Title>" {
+            ""
+        } else {
+            &window.name
+        };
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            window.window_id,
+            window.owner_pid,
+            window.layer,
+            window.sharing_state,
+            window.store_type,
+            window.backing_type,
+            u8::from(window.is_onscreen),
+            window.alpha,
+            bounds,
+            clean(&window.owner),
+            clean(name),
+        );
+    }
+}
+
+/// Keep names on one line and in one column
+fn clean(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
@@ -0,0 +1,56 @@
+//! Arbitrary signature patterns matched against arbitrary window text.
+//!
+//! Patterns will come from more places than the built-in list (config
+//! files, downloaded rule sets), so compiling and matching must cope with
+//! anything.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_cluely_core::{
+    is_cluely_process, is_cluely_url, normalize, Signature, SignatureSet, Subject,
+};
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    owner: Option<String>,
+    title: Option<String>,
+    bundle_id: Option<String>,
+    subjects: Vec<(String, Option<String>, Option<String>)>,
+}
+
+fuzz_target!(|input: Input| {
+    let mut signature = Signature::new("fuzz");
+    if let Some(pattern) = &input.owner {
+        let Ok(with_owner) = signature.owner(pattern) else {
+            return;
+        };
+        signature = with_owner;
+    }
+    if let Some(pattern) = &input.title {
+        let Ok(with_title) = signature.title(pattern) else {
+            return;
+        };
+        signature = with_title;
+    }
+    if let Some(pattern) = &input.bundle_id {
+        let Ok(with_bundle_id) = signature.bundle_id(pattern) else {
+            return;
+        };
+        signature = with_bundle_id;
+    }
+
+    let mut signatures = SignatureSet::builtin();
+    signatures.push(signature);
+    for (owner, title, bundle_id) in &input.subjects {
+        is_cluely_process(owner);
+        is_cluely_url(owner);
+        let subject = Subject {
+            owner: normalize::lower(owner),
+            title: title.as_deref().map(normalize::lower),
+            bundle_id: bundle_id.as_deref().map(normalize::lower),
+        };
+        signatures.matching(&subject);
+    }
+});
@@ -0,0 +1,130 @@
+//! Arbitrary window dictionaries through parsing and every detection rule.
+//!
+//! Values are typed the way Core Foundation types them, and read back with
+//! the same conversions the macOS backend applies, so any key can hold a
+//! string, a number, a boolean or another dictionary.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use no_cluely_core::{
+    analyze_displays, analyze_windows, capture_evasion, detect_cluely_in_rust, parse_window,
+    placement, suspicious_processes, DetectorConfig, Display, ScannedWindow, WindowBounds,
+    WindowDictionary, WindowKey,
+};
+
+#[derive(Debug, Arbitrary)]
+enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Dictionary(Vec<(u8, Value)>),
+}
+
+/// A window dictionary; the `u8` picks the key from `WindowKey::ALL`
+#[derive(Clone, Copy)]
+struct Entries<'a>(&'a [(u8, Value)]);
+
+impl<'a> Entries<'a> {
+    fn get(&self, key: WindowKey) -> Option<&'a Value> {
+        // Like a CFDictionary, the last value stored under a key wins
+        self.0
+            .iter()
+            .rev()
+            .find(|(index, _)| WindowKey::ALL[*index as usize % WindowKey::ALL.len()] == key)
+            .map(|(_, value)| value)
+    }
+}
+
+impl WindowDictionary for Entries<'_> {
+    fn string(&self, key: WindowKey) -> Option<String> {
+        match self.get(key)? {
+            Value::String(text) => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    // CFNumberGetValue refuses a lossy conversion
+    fn int(&self, key: WindowKey) -> Option<i32> {
+        match *self.get(key)? {
+            Value::Int(number) => i32::try_from(number).ok(),
+            Value::Float(number) => {
+                let truncated = number as i32;
+                (truncated as f64 == number).then_some(truncated)
+            }
+            _ => None,
+        }
+    }
+
+    fn float(&self, key: WindowKey) -> Option<f64> {
+        match *self.get(key)? {
+            Value::Int(number) => Some(number as f64),
+            Value::Float(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    fn bool(&self, key: WindowKey) -> Option<bool> {
+        match *self.get(key)? {
+            Value::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn dictionary(&self, key: WindowKey) -> Option<Self> {
+        match self.get(key)? {
+            Value::Dictionary(entries) => Some(Entries(entries)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    windows: Vec<Vec<(u8, Value)>>,
+    displays: Vec<(u32, [f64; 4])>,
+    include_system: bool,
+    shared_display: Option<u32>,
+    fuzzy_max_distance: Option<u8>,
+}
+
+fuzz_target!(|input: Input| {
+    let windows: Vec<ScannedWindow> = input
+        .windows
+        .iter()
+        .map(|entries| parse_window(&Entries(entries)))
+        .collect();
+    let displays: Vec<Display> = input
+        .displays
+        .iter()
+        .enumerate()
+        .map(|(i, &(id, [x, y, width, height]))| Display {
+            id,
+            bounds: WindowBounds {
+                x,
+                y,
+                width,
+                height,
+            },
+            is_main: i == 0,
+        })
+        .collect();
+    let config = DetectorConfig {
+        fuzzy_max_distance: input.fuzzy_max_distance.map(usize::from),
+        ..Default::default()
+    };
+
+    for window in &windows {
+        window.layer_class();
+        if let Some(bounds) = &window.bounds {
+            placement(bounds, &displays);
+        }
+    }
+    detect_cluely_in_rust(&windows);
+    capture_evasion(&windows);
+    analyze_displays(&windows, &displays, input.shared_display);
+    suspicious_processes(windows.clone(), input.include_system);
+    analyze_windows(windows, &displays, input.include_system, &config);
+});
@@ -15,12 +15,37 @@ typedef struct {
     uint32_t screen_capture_evasion_count;   // Windows avoiding screen capture
     uint32_t elevated_layer_count;           // Windows using elevated layers
     int32_t max_layer_detected;              // Highest layer number found
+    uint64_t last_scan_duration_us;          // Time the scan took, in microseconds
+    uint32_t ruleset_version;                // Version of the detection rules used
+    uint32_t rules_fired;                    // ClueLyRule bits of the rules that fired
+    uint64_t signatures_version;             // Signature bundle version; 0 for built-in only
 } ClueLyDetectionResult;
 
+/// Bits of ClueLyDetectionResult.rules_fired
+typedef enum {
+    CLUELY_RULE_CLUELY_SIGNATURE = 1 << 0,       // A window matched a Cluely signature
+    CLUELY_RULE_SCREEN_CAPTURE_EVASION = 1 << 1, // A window is hidden from screen capture
+    CLUELY_RULE_ELEVATED_LAYER = 1 << 2,         // A window sits above normal windows
+} ClueLyRule;
+
+/// Outcome of the try_* functions
+typedef enum {
+    CLUELY_OK = 0,
+    CLUELY_WINDOW_LIST_UNAVAILABLE = 1,      // No window list (no GUI session)
+    CLUELY_INVALID_ARGUMENT = 2,             // A required pointer was NULL
+    CLUELY_PANICKED = 3,                     // The library hit a bug; the call was abandoned
+    CLUELY_PLATFORM_UNSUPPORTED = 4,         // Not built for macOS; nothing can be scanned
+    CLUELY_PERMISSION_DENIED = 5,            // The scan needs root
+} ClueLyStatus;
+
 /// Main detection function - returns detailed result with evasion analysis
 /// Returns a structure with detection status, window count, and evasion techniques
 ClueLyDetectionResult detect_cluely(void);
 
+/// Like detect_cluely(), but says why a scan failed
+/// Writes the result to out only when it returns CLUELY_OK
+ClueLyStatus try_detect_cluely(ClueLyDetectionResult* out);
+
 /// Simple boolean check - returns 1 if Cluely detected, 0 otherwise
 /// This is the simplest function to use from Swift
 int is_cluely_running(void);
@@ -31,12 +56,71 @@ uint32_t get_cluely_window_count(void);
 /// Generate a detailed text report of Cluely detection and evasion techniques
 /// Returns a pointer to a C string that must be freed with free_cluely_report()
 /// The report includes specific details about detected evasion techniques
+/// Returns NULL if the report could not be built
 char* get_cluely_report(void);
 
+/// Like get_cluely_report(), but says why a scan failed
+/// On CLUELY_OK, *out must be freed with free_cluely_report(); otherwise it is NULL
+ClueLyStatus try_get_cluely_report(char** out);
+
 /// Free memory allocated by get_cluely_report()
 /// MUST be called to free memory returned by get_cluely_report()
 void free_cluely_report(char* report);
 
+/// How the detector's binary matches its code signature
+typedef enum {
+    CLUELY_SIGNATURE_VALID = 0,
+    CLUELY_SIGNATURE_UNSIGNED = 1,           // Not signed; nothing to check against
+    CLUELY_SIGNATURE_INVALID = 2,            // The file no longer matches its signature
+    CLUELY_SIGNATURE_UNCHECKED = 3,          // The signature could not be checked
+} ClueLySignatureStatus;
+
+/// Result of verify_self_integrity()
+typedef struct {
+    bool is_intact;                          // No sign the detector was patched or replaced
+    ClueLySignatureStatus signature;         // Code signature of the binary on disk
+    int8_t running_code_valid;               // 1 matches its signature, 0 not, -1 unsigned
+    bool modified;                           // Binary changed since the first check in this process
+    char sha256[65];                         // SHA-256 of the binary as hex; empty if unreadable
+} ClueLyIntegrityResult;
+
+/// Check the detector's own binary (the executable or this library) for
+/// tampering; call once at startup so later calls can spot a replaced file
+/// Writes the result to out only when it returns CLUELY_OK
+ClueLyStatus verify_self_integrity(ClueLyIntegrityResult* out);
+
+/// Settings for cluely_monitor_create()
+typedef struct {
+    bool on_screen_only;                     // Only scan windows currently on screen
+    bool exclude_desktop_elements;           // Skip desktop picture and icon windows
+    bool incremental;                        // Only parse windows new since the last poll
+    const char* const* owner_patterns;       // Extra owner name patterns; "^..." is a regex
+    uint32_t owner_pattern_count;            // Number of entries in owner_patterns
+} ClueLyMonitorConfig;
+
+/// Long-lived monitor that keeps its configuration and window cache
+/// between polls. Safe to poll from any thread.
+typedef struct ClueLyMonitorHandle ClueLyMonitorHandle;
+
+/// Create a monitor; pass NULL for the default configuration
+/// Returns NULL if an owner pattern is invalid
+/// MUST be freed with cluely_monitor_destroy()
+ClueLyMonitorHandle* cluely_monitor_create(const ClueLyMonitorConfig* config);
+
+/// Scan with the monitor's configuration and return the result
+ClueLyDetectionResult cluely_monitor_poll(const ClueLyMonitorHandle* monitor);
+
+/// Like cluely_monitor_poll(), but says why a scan failed
+/// Writes the result to out only when it returns CLUELY_OK
+ClueLyStatus cluely_monitor_try_poll(const ClueLyMonitorHandle* monitor, ClueLyDetectionResult* out);
+
+/// Polls in a row that failed to scan; 0 after a successful one
+/// While above 0, cluely_monitor_poll() is returning a stale result
+uint32_t cluely_monitor_consecutive_failures(const ClueLyMonitorHandle* monitor);
+
+/// Free a monitor created by cluely_monitor_create()
+void cluely_monitor_destroy(ClueLyMonitorHandle* monitor);
+
 #ifdef __cplusplus
 }
 #endif
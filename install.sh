@@ -6,7 +6,7 @@ echo ""
 
 # Build the library
 echo "📦 Building library..."
-cargo build --lib --release
+cargo build --lib --release --features ffi
 
 if [ $? -ne 0 ]; then
     echo "❌ Build failed!"